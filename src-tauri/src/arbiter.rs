@@ -0,0 +1,102 @@
+use crate::busylight::PulseState;
+
+// Decides what a light shows when several things want it at once. Each source that wants a
+// light makes a claim at its priority and the highest one wins; equal claims go to the one made
+// first. The pipeline in `lib.rs` gathers the claims for every output and shows the winner, so a
+// new feature only has to pick its place in `Priority` instead of special-casing the others.
+
+/// Who wants the light, lowest first so the derived order ranks them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// The plain weather color
+    Temperature,
+    /// The precipitation pulse and the other weather rules, or the pipeline status
+    Weather,
+    /// Night mode turning the light off, or the `quietHours` schedule
+    Quiet,
+    /// The `wakeUp` sunrise, which lights up through night mode
+    WakeUp,
+    /// A call, meeting, huddle or calendar event going on, Do Not Disturb, or the camera or
+    /// microphone in use
+    Busy,
+    /// Outside `workHours`, when the light stays off
+    OffHours,
+    /// Someone asked for it: manual mode, snooze or paused updates
+    Manual,
+    /// An official severe weather warning
+    SevereAlert,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Claim {
+    Show(PulseState),
+    /// Leave the light as it is; something outside the pipeline drives it
+    Hold,
+}
+
+#[derive(Debug, Default)]
+pub struct Arbiter {
+    claims: Vec<(Priority, Claim)>,
+}
+
+impl Arbiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn claim(&mut self, priority: Priority, claim: Claim) -> &mut Self {
+        self.claims.push((priority, claim));
+        self
+    }
+
+    /// The highest priority claimed so far
+    pub fn highest(&self) -> Option<Priority> {
+        self.claims.iter().map(|(priority, _)| *priority).max()
+    }
+
+    /// The claim that gets the light, None when nobody asked
+    pub fn winner(self) -> Option<(Priority, Claim)> {
+        // `max_by_key` keeps the last of equal keys, so walk the claims backwards
+        self.claims.into_iter().rev().max_by_key(|(priority, _)| *priority)
+    }
+
+    /// What to show, None when nobody asked or the winner holds the light
+    pub fn plan(self) -> Option<PulseState> {
+        match self.winner()? {
+            (_, Claim::Show(plan)) => Some(plan),
+            (_, Claim::Hold) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::busylight::Effect;
+
+    fn steady(color: (u8, u8, u8), pct: u8) -> Claim {
+        Claim::Show(PulseState { active: false, color_srgb: color, pct_high: pct, pct_low: pct, speed_ms: 0, sequence: Vec::new(), effect: Effect::Breathe })
+    }
+
+    #[test]
+    fn test_winner() {
+        assert_eq!(Arbiter::new().winner(), None);
+
+        let mut arbiter = Arbiter::new();
+        arbiter.claim(Priority::Temperature, steady((255, 128, 0), 60))
+            .claim(Priority::Quiet, steady((0, 0, 0), 0))
+            .claim(Priority::Weather, steady((0, 0, 255), 60));
+        assert_eq!(arbiter.highest(), Some(Priority::Quiet));
+        assert_eq!(arbiter.winner(), Some((Priority::Quiet, steady((0, 0, 0), 0))));
+
+        // A severe alert outranks manual mode, and the first of equal claims wins
+        let mut arbiter = Arbiter::new();
+        arbiter.claim(Priority::Manual, Claim::Hold)
+            .claim(Priority::Manual, steady((0, 0, 0), 0))
+            .claim(Priority::Weather, steady((0, 0, 255), 60));
+        assert_eq!(arbiter.winner(), Some((Priority::Manual, Claim::Hold)));
+        let mut arbiter = Arbiter::new();
+        arbiter.claim(Priority::Manual, Claim::Hold).claim(Priority::SevereAlert, steady((255, 0, 0), 60));
+        assert_eq!(arbiter.winner(), Some((Priority::SevereAlert, steady((255, 0, 0), 60))));
+    }
+}
@@ -1,8 +1,12 @@
+use chrono::{DateTime, Utc};
 use hidapi::{HidApi, HidDevice};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use crate::sink::{LightSink, SinkStatus};
+use crate::watchdog::Heartbeat;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceInfo {
@@ -12,15 +16,187 @@ pub struct DeviceInfo {
     pub vendor_id: u16,
     #[serde(rename = "productId")]
     pub product_id: u16,
+    #[serde(default)]
+    pub serial: Option<String>,
 }
 
+impl DeviceInfo {
+    fn from_hid(device_info: &hidapi::DeviceInfo) -> Self {
+        Self {
+            product: device_info.product_string().map(|s| s.to_string()),
+            path: Some(device_info.path().to_string_lossy().into_owned()),
+            vendor_id: device_info.vendor_id(),
+            product_id: device_info.product_id(),
+            serial: device_info.serial_number().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()),
+        }
+    }
+
+    /// Stable name for the physical device in `busylightRoles`: the serial number, or the USB
+    /// path for units without one
+    pub fn key(&self) -> String {
+        self.serial.clone()
+            .or_else(|| self.path.clone())
+            .unwrap_or_else(|| format!("{:04x}:{:04x}", self.vendor_id, self.product_id))
+    }
+}
+
+/// Why the Busylight is (or isn't) usable, so the UI can tell "unplugged" from "no access"
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceStatus {
+    Connected,
+    NotFound,
+    /// Enumerated but the device node couldn't be opened (Linux without a udev rule)
+    PermissionDenied,
+    /// The HID backend itself failed to initialize
+    HidUnavailable,
+}
+
+// Decimal 10171 is 0x27bb (Kuando); 0x04d8 covers the older Microchip-based units
+pub const SUPPORTED_VIDS: [u16; 2] = [0x27bb, 0x04d8];
+
+/// Built-in ringtones of the Omega/Alpha, numbered as in the audio byte
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ringtone {
+    OpenOffice = 1,
+    Quiet = 2,
+    Funky = 3,
+    FairyTale = 4,
+    KuandoTrain = 5,
+    TelephoneNordic = 6,
+    TelephoneOriginal = 7,
+    TelephonePickMeUp = 8,
+    Buzz = 11,
+}
+
+impl Ringtone {
+    /// Config names, in menu order
+    pub const NAMES: [(&'static str, Ringtone); 9] = [
+        ("open_office", Ringtone::OpenOffice),
+        ("quiet", Ringtone::Quiet),
+        ("funky", Ringtone::Funky),
+        ("fairy_tale", Ringtone::FairyTale),
+        ("kuando_train", Ringtone::KuandoTrain),
+        ("telephone_nordic", Ringtone::TelephoneNordic),
+        ("telephone_original", Ringtone::TelephoneOriginal),
+        ("telephone_pick_me_up", Ringtone::TelephonePickMeUp),
+        ("buzz", Ringtone::Buzz),
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES.iter().find(|(n, _)| *n == name).map(|(_, tone)| *tone)
+    }
+}
+
+// Last byte of a step: bit 7 applies the audio setting, bits 3-6 pick the tone (0 is silence)
+// and bits 0-2 the volume
+fn audio_byte(tone: Option<Ringtone>, volume: u8) -> u8 {
+    match tone {
+        Some(tone) => 0x80 | ((tone as u8) << 3) | volume.min(7),
+        None => 0x80,
+    }
+}
+
+/// Per-device correction for units whose LEDs don't match the built-in curves (some clones run
+/// visibly green or blue, or jump from off to bright). Kept in config by `DeviceInfo::key()`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Calibration {
+    /// Exponent of the brightness curve behind `light_pct()` and the pulse frames
+    pub gamma: f32,
+    /// Exponent `light()` decodes colors with; 0 uses the standard sRGB curve
+    pub color_gamma: f32,
+    /// Per-channel scaling (0-1) applied to every frame, to take the tint out of white
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self { gamma: crate::color::DEFAULT_GAMMA, color_gamma: 0.0, red: 1.0, green: 1.0, blue: 1.0 }
+    }
+}
+
+impl Calibration {
+    /// Drive levels for `rgb` at a perceived brightness (0..=1)
+    pub fn level(&self, rgb: (u8, u8, u8), perceived: f32) -> (u8, u8, u8) {
+        let gamma = if self.gamma > 0.0 { self.gamma } else { crate::color::DEFAULT_GAMMA };
+        crate::color::apply_brightness_curve(rgb, perceived, gamma)
+    }
+
+    // Applies degamma correction (naive standard sRGB approximation used in original codebase),
+    // or a plain power curve with `color_gamma`
+    fn degamma(&self, val: u8) -> u8 {
+        let v = val as f32 / 255.0;
+        let corrected = if self.color_gamma > 0.0 {
+            v.powf(self.color_gamma)
+        } else if v <= 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        };
+        (corrected * 255.0).clamp(0.0, 255.0).round() as u8
+    }
+
+    fn balance(&self, rgb: (u8, u8, u8)) -> (u8, u8, u8) {
+        let scale = |c: u8, k: f32| (c as f32 * k.clamp(0.0, 1.0)).round() as u8;
+        (scale(rgb.0, self.red), scale(rgb.1, self.green), scale(rgb.2, self.blue))
+    }
+}
+
+/// Test colors for calibrating a light by eye, with what to look for
+pub const TEST_COLORS: [(&str, (u8, u8, u8), u8); 7] = [
+    ("White: should look neutral, without a tint", (255, 255, 255), 100),
+    ("Red", (255, 0, 0), 100),
+    ("Green", (0, 255, 0), 100),
+    ("Blue", (0, 0, 255), 100),
+    ("Warm white: should look like candlelight, not pink or green", (255, 147, 41), 100),
+    ("White at 50%: should look about half as bright", (255, 255, 255), 50),
+    ("White at 5%: should be dim but clearly on", (255, 255, 255), 5),
+];
+
+// Omega/Alpha report, after the report ID: seven 8-byte steps (command, repeat, red, green, blue,
+// on time, off time, audio), then sensitivity, timeout and trigger bytes, 3 bytes of 0xFF padding
+// and a 2-byte checksum
+const FOOTER: usize = 57;
+const PADDING: usize = FOOTER + 3;
+const CHECKSUM: usize = 63;
+// Step commands: jump (to the step in the low nibble) and keep-alive (timeout in seconds, 0-15)
+const JUMP: u8 = 0x10;
+const KEEP_ALIVE: u8 = 0x80;
+/// Longest keep-alive timeout a device accepts; the interval has to stay under it
+pub const MAX_KEEP_ALIVE_SECS: u8 = 15;
+const DEFAULT_KEEP_ALIVE_MS: u64 = 5000;
+
+// A report that only resets the device's watchdog, leaving the running step program alone
+fn keep_alive_report(timeout_secs: u8) -> [u8; 65] {
+    let mut report = [0u8; 65];
+    report[1] = KEEP_ALIVE | timeout_secs.min(MAX_KEEP_ALIVE_SECS);
+    report[PADDING..CHECKSUM].fill(0xFF);
+    with_checksum(report)
+}
+
+// Sum of bytes 0..=62, big-endian in the last two
+fn with_checksum(mut report: [u8; 65]) -> [u8; 65] {
+    let sum: u32 = report[..CHECKSUM].iter().map(|&b| b as u32).sum();
+    report[CHECKSUM] = ((sum >> 8) & 0xff) as u8;
+    report[CHECKSUM + 1] = (sum % 256) as u8;
+    report
+}
+
+pub const UDEV_RULE_PATH: &str = "/etc/udev/rules.d/50-weatherlight-busylight.rules";
+
 pub struct Busylight {
     device: Option<HidDevice>,
     info: Option<DeviceInfo>,
+    status: DeviceStatus,
     is_new_protocol: bool,
     buffer: [u8; 65], // Maximum buffer size we might need
     api: Option<HidApi>,
-    last_reconnect: std::time::Instant,
+    // Only this device (by `DeviceInfo::key()`); None takes the first one found
+    target: Option<String>,
+    pub calibration: Calibration,
 }
 
 impl Busylight {
@@ -29,10 +205,12 @@ impl Busylight {
         let mut bl = Self {
             device: None,
             info: None,
+            status: DeviceStatus::NotFound,
             is_new_protocol: false,
             buffer: [0; 65],
             api,
-            last_reconnect: std::time::Instant::now(),
+            target: None,
+            calibration: Calibration::default(),
         };
         
         // Initialize basic buffer
@@ -49,84 +227,118 @@ impl Busylight {
         bl
     }
 
+    /// A driver bound to one physical device, for lights after the first
+    pub fn for_device(key: &str) -> Self {
+        Self { target: Some(key.to_string()), ..Self::new() }
+    }
+
     pub fn connect(&mut self) -> Result<(), String> {
+        let mut denied: Option<DeviceInfo> = None;
+
         if let Some(api) = &mut self.api {
             api.refresh_devices().map_err(|e| e.to_string())?;
             
-            // Supported Vendor IDs for Kuando Busylight
-            let supported_vids = vec![10171, 0x27bb, 0x04d8]; // Decimal 10171 is 0x27bb
-            
             for device_info in api.device_list() {
-                println!("DEBUG HID: VID={}, PID={}, Product={:?}", 
-                    device_info.vendor_id(), device_info.product_id(), device_info.product_string());
-                if supported_vids.contains(&device_info.vendor_id()) {
+                if SUPPORTED_VIDS.contains(&device_info.vendor_id()) {
+                    let info = DeviceInfo::from_hid(device_info);
+                    if self.target.as_ref().is_some_and(|key| *key != info.key()) {
+                        continue;
+                    }
                     let path = device_info.path();
-                    if let Ok(dev) = api.open_path(path) {
-                        self.device = Some(dev);
-                        
-                        let is_new = device_info.vendor_id() == 10171 || device_info.vendor_id() == 0x27bb;
-                        println!("Found Busylight: VID={}, PID={}, UsagePage={}, Interface={}", 
-                            device_info.vendor_id(), device_info.product_id(), device_info.usage_page(), device_info.interface_number());
-                        self.is_new_protocol = is_new;
-                        
-                        self.info = Some(DeviceInfo {
-                            product: device_info.product_string().map(|s| s.to_string()),
-                            path: Some(path.to_string_lossy().into_owned()),
-                            vendor_id: device_info.vendor_id(),
-                            product_id: device_info.product_id(),
-                        });
-
-                        // Setup buffer for new protocol
-                        if is_new {
-                            self.buffer[1] = 16;
-                            // bytes 9..=58 are already 0 from init
-                            self.buffer[59] = 255;
-                            self.buffer[60] = 255;
-                            self.buffer[61] = 255;
-                            self.buffer[62] = 255;
-                            // 63 and 64 will be overwritten by checksum on send
-                        } else {
-                            self.buffer[1] = 0;
+                    let dev = match api.open_path(path) {
+                        Ok(dev) => dev,
+                        Err(e) => {
+                            println!("Failed to open Busylight at {:?}: {}", path, e);
+                            if denied.is_none() && is_permission_denied(path) {
+                                denied = Some(info);
+                            }
+                            continue;
                         }
-                        
-                        return Ok(());
+                    };
+
+                    self.device = Some(dev);
+                    self.status = DeviceStatus::Connected;
+                    
+                    let is_new = device_info.vendor_id() == 10171 || device_info.vendor_id() == 0x27bb;
+                    println!("Found Busylight: VID={}, PID={}, UsagePage={}, Interface={}", 
+                        device_info.vendor_id(), device_info.product_id(), device_info.usage_page(), device_info.interface_number());
+                    self.is_new_protocol = is_new;
+                    
+                    self.info = Some(info);
+
+                    // Setup buffer for new protocol: step 0 shows the color and jumps to itself.
+                    // Sensitivity, timeout and trigger stay 0 (the watchdog is set by keep-alive
+                    // steps instead); the checksum is filled in on send.
+                    if is_new {
+                        self.buffer[1] = JUMP;
+                        self.buffer[FOOTER..PADDING].fill(0);
+                        self.buffer[PADDING..CHECKSUM].fill(0xFF);
+                    } else {
+                        self.buffer[1] = 0;
                     }
+                    
+                    return Ok(());
                 }
             }
         }
         
+        self.device = None;
+        if self.api.is_none() {
+            self.info = None;
+            self.status = DeviceStatus::HidUnavailable;
+            return Err("HID API failed to initialize".into());
+        }
+        if let Some(info) = denied {
+            // Keep the enumerated details so diagnostics can show which device needs access
+            self.info = Some(info);
+            self.status = DeviceStatus::PermissionDenied;
+            return Err("Busylight found but access was denied".into());
+        }
+        self.info = None;
+        self.status = DeviceStatus::NotFound;
+        Err("No Busylight device found".into())
+    }
+
+    /// Opens one specific device without binding to it for good
+    fn connect_key(&mut self, key: &str) -> Result<(), String> {
+        let bound = self.target.replace(key.to_string());
+        let result = self.connect();
+        self.target = bound;
+        result
+    }
+
+    /// Drops the handle of a device that was unplugged (or stopped accepting writes)
+    pub fn disconnect(&mut self) {
         self.device = None;
         self.info = None;
-        Err("No Busylight device found or HID API failed".into())
+        self.status = DeviceStatus::NotFound;
     }
 
     pub fn is_connected(&self) -> bool {
         self.device.is_some()
     }
 
+    pub fn status(&self) -> DeviceStatus {
+        self.status
+    }
+
     pub fn get_info(&self) -> Option<DeviceInfo> {
         self.info.clone()
     }
 
-    pub fn off(&mut self) {
-        self.light(0, 0, 0);
+    /// `DeviceInfo::key()` of the open device
+    pub fn key(&self) -> Option<String> {
+        self.info.as_ref().filter(|_| self.device.is_some()).map(|i| i.key())
     }
 
-    // Applies degamma correction (naive standard sRGB approximation used in original codebase)
-    fn degamma(val: u8) -> u8 {
-        let v = val as f32 / 255.0;
-        let corrected = if v <= 0.04045 {
-            v / 12.92
-        } else {
-            ((v + 0.055) / 1.055).powf(2.4)
-        };
-        (corrected * 255.0).clamp(0.0, 255.0).round() as u8
+    pub fn off(&mut self) {
+        self.light(0, 0, 0);
     }
 
     pub fn light(&mut self, mut r: u8, mut g: u8, mut b: u8) {
-        r = Self::degamma(r);
-        g = Self::degamma(g);
-        b = Self::degamma(b);
+        r = self.calibration.degamma(r);
+        g = self.calibration.degamma(g);
+        b = self.calibration.degamma(b);
 
         self.buffer[3] = r;
         self.buffer[4] = g;
@@ -144,188 +356,471 @@ impl Busylight {
     }
 
     pub fn light_pct(&mut self, r: u8, g: u8, b: u8, pct: u8) {
-        let pct_perceived = (pct as f32 / 100.0).clamp(0.0, 1.0);
-        // Convert perceived brightness slider to linear hardware power multiplier (Gamma 2.8)
-        let power_factor = pct_perceived.powf(2.8);
-        
-        self.buffer[3] = (r as f32 * power_factor) as u8;
-        self.buffer[4] = (g as f32 * power_factor) as u8;
-        self.buffer[5] = (b as f32 * power_factor) as u8;
-        
+        // Perceived brightness slider to linear hardware power
+        let (r, g, b) = self.calibration.level((r, g, b), pct as f32 / 100.0);
+        self.light_raw(r, g, b);
+    }
+
+    /// Starts `tone` looping at `volume` (0-7), or silences the device with `None`
+    pub fn set_tone(&mut self, tone: Option<Ringtone>, volume: u8) {
+        self.buffer[8] = audio_byte(tone, volume);
         self.send();
+        if tone.is_some() {
+            // Without the apply bit, later light frames leave the tone playing instead of restarting it
+            self.buffer[8] &= 0x7f;
+        }
     }
 
-    fn tween_rgb(start: (u8, u8, u8), end: (u8, u8, u8), value: f32) -> (u8, u8, u8) {
-        (
-            (start.0 as f32 + (end.0 as f32 - start.0 as f32) * value) as u8,
-            (start.1 as f32 + (end.1 as f32 - start.1 as f32) * value) as u8,
-            (start.2 as f32 + (end.2 as f32 - start.2 as f32) * value) as u8,
-        )
+    /// Resets the watchdog of an Omega/Alpha, which blanks the LEDs when nothing arrives within
+    /// `timeout_secs` of the last keep-alive. Older units have no watchdog and get the frame again.
+    pub fn keep_alive(&mut self, timeout_secs: u8) {
+        if self.is_new_protocol {
+            self.write(keep_alive_report(timeout_secs));
+        } else {
+            self.send();
+        }
     }
 
     fn send(&mut self) {
-        let mut should_reconnect = false;
-        
-        if let Some(dev) = &self.device {
-            let mut send_buf = self.buffer;
-            
-            let result = if self.is_new_protocol {
-                // Calculate Checksum for new protocol (bytes 0..62)
-                // Note: node-hid writes index 0 as report ID on Windows implicitly
-                // On Windows hidapi, we need to send 65 bytes including native report ID 0
-                let sum: u32 = send_buf[0..63].iter().map(|&b| b as u32).sum();
-                send_buf[63] = ((sum >> 8) & 0xff) as u8;
-                send_buf[64] = (sum % 256) as u8;
-                dev.write(&send_buf[..65])
-            } else {
-                dev.write(&send_buf[..9])
-            };
+        let mut send_buf = self.buffer;
+        // White balance goes on the report only, so the buffer keeps the frame as asked for
+        let (r, g, b) = self.calibration.balance((send_buf[3], send_buf[4], send_buf[5]));
+        send_buf[3..6].copy_from_slice(&[r, g, b]);
+        self.write(send_buf);
+    }
 
-            if let Err(e) = result {
-                println!("Busylight write error. Connection likely stale: {}", e);
-                should_reconnect = true;
-            }
+    fn write(&mut self, report: [u8; 65]) {
+        let Some(dev) = &self.device else { return };
+
+        let result = if self.is_new_protocol {
+            // Note: node-hid writes index 0 as report ID on Windows implicitly
+            // On Windows hidapi, we need to send 65 bytes including native report ID 0
+            dev.write(&with_checksum(report))
+        } else {
+            dev.write(&report[..9])
+        };
+
+        if let Err(e) = result {
+            // The hotplug scan reopens it and replays the buffer if it's still there
+            println!("Busylight write failed, treating it as unplugged: {}", e);
+            self.disconnect();
         }
+    }
+}
 
-        if should_reconnect && self.last_reconnect.elapsed() > std::time::Duration::from_secs(2) {
-            self.last_reconnect = std::time::Instant::now();
-            self.device = None;
-            // Attempt to reconnect once. If it succeeds, resend the buffer.
-            if self.connect().is_ok() {
-                if let Some(dev) = &self.device {
-                     let mut send_buf = self.buffer;
-                     if self.is_new_protocol {
-                        let sum: u32 = send_buf[0..63].iter().map(|&b| b as u32).sum();
-                        send_buf[63] = ((sum >> 8) & 0xff) as u8;
-                        send_buf[64] = (sum % 256) as u8;
-                        let _ = dev.write(&send_buf[..65]);
-                    } else {
-                        let _ = dev.write(&send_buf[..9]);
-                    }
-                }
-            }
+/// Every supported device currently plugged in, once each even when it exposes several HID
+/// interfaces
+pub fn enumerate() -> Vec<DeviceInfo> {
+    let Ok(api) = HidApi::new() else { return Vec::new() };
+    let mut devices: Vec<DeviceInfo> = Vec::new();
+    for device_info in api.device_list().filter(|d| SUPPORTED_VIDS.contains(&d.vendor_id())) {
+        let info = DeviceInfo::from_hid(device_info);
+        if !devices.iter().any(|d| d.key() == info.key()) {
+            devices.push(info);
         }
     }
+    devices
 }
 
-// Controller allows holding the lock to update state across threads
+// hidapi's error text differs between backends, so check the device node itself
+#[cfg(target_os = "linux")]
+fn is_permission_denied(path: &std::ffi::CStr) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::path::Path::new(std::ffi::OsStr::from_bytes(path.to_bytes()));
+    matches!(
+        std::fs::OpenOptions::new().read(true).write(true).open(path),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_permission_denied(_path: &std::ffi::CStr) -> bool {
+    false
+}
+
+/// udev rule granting the logged-in user access to supported Busylights
+pub fn udev_rule() -> String {
+    let mut rule = String::from("# Kuando Busylight access for WeatherLight\n");
+    for vid in SUPPORTED_VIDS {
+        rule.push_str(&format!("SUBSYSTEM==\"hidraw\", ATTRS{{idVendor}}==\"{:04x}\", MODE=\"0660\", TAG+=\"uaccess\"\n", vid));
+        rule.push_str(&format!("SUBSYSTEM==\"usb\", ATTRS{{idVendor}}==\"{:04x}\", MODE=\"0660\", TAG+=\"uaccess\"\n", vid));
+    }
+    rule
+}
+
+/// Writes `udev_rule()` to `UDEV_RULE_PATH` through pkexec and re-triggers udev so the
+/// already-plugged device picks up the new permissions.
+#[cfg(target_os = "linux")]
+pub fn install_udev_rule() -> Result<(), String> {
+    let tmp = std::env::temp_dir().join("weatherlight-busylight.rules");
+    std::fs::write(&tmp, udev_rule()).map_err(|e| e.to_string())?;
+
+    let script = format!(
+        "install -m 0644 \"$0\" {} && udevadm control --reload-rules && udevadm trigger --subsystem-match=hidraw --subsystem-match=usb",
+        UDEV_RULE_PATH
+    );
+    let status = std::process::Command::new("pkexec")
+        .args(["sh", "-c", &script])
+        .arg(&tmp)
+        .status()
+        .map_err(|e| format!("Failed to run pkexec: {}", e));
+    let _ = std::fs::remove_file(&tmp);
+
+    match status? {
+        s if s.success() => Ok(()),
+        _ => Err("Installing the udev rule was cancelled or failed".into()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn install_udev_rule() -> Result<(), String> {
+    Err("udev rules only apply to Linux".into())
+}
+
+// Controller allows holding the lock to update state across threads; one per physical light
 pub struct BusylightController {
     pub bl: Mutex<Busylight>,
-    pub manual_mode: Mutex<bool>,
     // Shared state for the pulse thread to read
     pub pulse_state: Arc<Mutex<PulseState>>,
+    pub pulse_heartbeat: Arc<Heartbeat>,
+    /// Crossfade length for plan changes through `show()`; 0 switches instantly
+    pub fade_ms: AtomicU64,
+    /// How often the pulse thread resets the device's watchdog while nothing else is being sent
+    pub keep_alive_ms: AtomicU64,
+    // The crossfade in progress, rendered by the pulse thread
+    fade: Mutex<Option<Fade>>,
+}
+
+// A crossfade from the last frame the device showed (drive levels) to whatever the pulse state asks for
+#[derive(Clone, Copy)]
+struct Fade {
+    from: (u8, u8, u8),
+    started: Instant,
+    duration: Duration,
+}
+
+impl Fade {
+    /// The frame to send on the way to `target`, and whether the fade is over
+    fn frame(&self, target: (u8, u8, u8)) -> ((u8, u8, u8), bool) {
+        fade_frame(self.from, target, self.started.elapsed(), self.duration)
+    }
+}
+
+// One frame of an active pulse, in drive levels, `elapsed_ms` into the cycle
+fn pulse_frame(state: &PulseState, elapsed_ms: u64, calibration: &Calibration) -> (u8, u8, u8) {
+    let t = (elapsed_ms % state.speed_ms) as f32 / state.speed_ms as f32;
+    let max_pct = state.pct_high as f32 / 100.0;
+    let min_pct = state.pct_low as f32 / 100.0;
+    let current_pct_perceived = min_pct + (max_pct - min_pct) * state.effect.level(t);
+    calibration.level(state.color_srgb, current_pct_perceived)
+}
+
+fn fade_frame(from: (u8, u8, u8), to: (u8, u8, u8), elapsed: Duration, duration: Duration) -> ((u8, u8, u8), bool) {
+    if elapsed >= duration {
+        return (to, true);
+    }
+    let t = elapsed.as_secs_f32() / duration.as_secs_f32();
+    // Smoothstep, so the fade neither starts nor lands with a jump in speed
+    let eased = t * t * (3.0 - 2.0 * t);
+    (crate::color::mix(from, to, eased, crate::color::Interpolation::Rgb), false)
+}
+
+/// Waveform of an active pulse, between `pct_high` and `pct_low`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Effect {
+    /// Sine-eased, the original breathe
+    #[default]
+    Breathe,
+    Triangle,
+    /// High for the first half of each cycle, low for the second
+    Blink,
+    DoubleBlink,
+    /// A short flash at the start of each cycle
+    Strobe,
+    /// A one-off rise instead of a cycle; not selectable
+    Sunrise(Ramp),
 }
 
-#[derive(Clone, PartialEq)]
+impl Effect {
+    /// Config names, in menu order
+    pub const NAMES: [(&'static str, Effect); 5] = [
+        ("breathe", Effect::Breathe),
+        ("triangle", Effect::Triangle),
+        ("blink", Effect::Blink),
+        ("double_blink", Effect::DoubleBlink),
+        ("strobe", Effect::Strobe),
+    ];
+
+    /// Unknown names breathe
+    pub fn from_name(name: &str) -> Self {
+        Self::NAMES.iter().find(|(n, _)| *n == name).map(|(_, effect)| *effect).unwrap_or_default()
+    }
+
+    // How far up from `pct_low` toward `pct_high` (0..=1) the light is at `t` (0..1) through a
+    // cycle. Every waveform starts the cycle high.
+    fn level(self, t: f32) -> f32 {
+        // High to Low phase, then Low to High phase
+        let falling = t < 0.5;
+        let linear_progress = (if falling { t * 2.0 } else { (t - 0.5) * 2.0 }).clamp(0.0, 1.0);
+        let ramp = |eased: f32| if falling { 1.0 - eased } else { eased };
+        let on = |lit: bool| if lit { 1.0 } else { 0.0 };
+        match self {
+            // Sine easing mathematically stretches the top/bottom curves to hide PWM jumps
+            // and drastically reduces perceived hardware flashing at absolute turnaround points
+            Effect::Breathe => ramp((std::f32::consts::PI * linear_progress - std::f32::consts::FRAC_PI_2).sin() * 0.5 + 0.5),
+            Effect::Triangle => ramp(linear_progress),
+            Effect::Blink => on(falling),
+            Effect::DoubleBlink => on(t < 0.15 || (0.3..0.45).contains(&t)),
+            Effect::Strobe => on(t < 0.1),
+            // Drawn from the wall clock by `Ramp::at()`
+            Effect::Sunrise(_) => 1.0,
+        }
+    }
+}
+
+// Where a sunrise starts, as a perceived brightness
+const RAMP_GLIMMER: f32 = 0.01;
+
+/// A wake-up sunrise from a glimmer of `from` to the plan's color at `pct_high`. Timed on the
+/// wall clock, so every refresh during it (and a replugged light) lands at the same point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ramp {
+    pub from: (u8, u8, u8),
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Ramp {
+    /// Color and perceived brightness (0..=1) at `now`; past the end it holds `to` at `pct_high`
+    pub fn at(&self, now: DateTime<Utc>, to: (u8, u8, u8), pct_high: u8) -> ((u8, u8, u8), f32) {
+        let total = (self.end - self.start).num_milliseconds().max(1) as f32;
+        let t = ((now - self.start).num_milliseconds() as f32 / total).clamp(0.0, 1.0);
+        let high = pct_high as f32 / 100.0;
+        let color = crate::color::mix(self.from, to, t, crate::color::Interpolation::Rgb);
+        (color, RAMP_GLIMMER + (high - RAMP_GLIMMER).max(0.0) * t)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct PulseState {
     pub active: bool,
     pub color_srgb: (u8, u8, u8),
     pub pct_high: u8,
     pub pct_low: u8,
     pub speed_ms: u64,
+    // When non-empty, step through these colors at pct_high every speed_ms instead of pulsing
+    pub sequence: Vec<(u8, u8, u8)>,
+    pub effect: Effect,
 }
 
 impl BusylightController {
-    pub fn new() -> Result<Arc<Self>, String> {
-        let mut bl = Busylight::new();
+    /// Controller for the first device found
+    pub fn new() -> Arc<Self> {
+        Self::start(Busylight::new())
+    }
+
+    /// Controller for one specific device
+    pub fn for_device(key: &str) -> Arc<Self> {
+        Self::start(Busylight::for_device(key))
+    }
+
+    fn start(mut bl: Busylight) -> Arc<Self> {
         let _ = bl.connect(); // Try initial connect
-        
+
         let controller = Arc::new(Self {
             bl: Mutex::new(bl),
-            manual_mode: Mutex::new(false),
             pulse_state: Arc::new(Mutex::new(PulseState {
                 active: false,
                 color_srgb: (0,0,0),
                 pct_high: 100,
                 pct_low: 50,
-                speed_ms: 1000
+                speed_ms: 1000,
+                sequence: Vec::new(),
+                effect: Effect::Breathe,
             })),
+            pulse_heartbeat: Heartbeat::new(),
+            fade_ms: AtomicU64::new(0),
+            keep_alive_ms: AtomicU64::new(DEFAULT_KEEP_ALIVE_MS),
+            fade: Mutex::new(None),
         });
 
-        // Spawn pulse worker thread
-        let pulse_ctrl = Arc::clone(&controller);
+        Self::spawn_pulse_worker(&controller);
+
+        controller
+    }
+
+    /// Stops the pulse thread and turns the light off, for a controller that's being dropped
+    fn shutdown(&self) {
+        self.pulse_heartbeat.next_generation();
+        if let Ok(mut bl) = self.bl.lock() {
+            bl.off();
+        }
+    }
+
+    /// Starts a pulse worker thread. Any previous worker exits on its next tick.
+    pub fn spawn_pulse_worker(controller: &Arc<Self>) {
+        let pulse_ctrl = Arc::clone(controller);
+        let generation = pulse_ctrl.pulse_heartbeat.next_generation();
         thread::spawn(move || {
-            let mut idle_ticks = 0;
+            let mut last_write = Instant::now();
             let refresh_rate_ms = 33; // ~30FPS timing
             let mut cycle_start_time = std::time::Instant::now();
             let mut was_active = false;
 
             loop {
-                // Read state
+                if pulse_ctrl.pulse_heartbeat.generation() != generation {
+                    break; // Replaced by the watchdog
+                }
+                pulse_ctrl.pulse_heartbeat.beat();
+
+                // Read state (a panic elsewhere must not stop pulsing for good)
                 let state = {
-                    let s = pulse_ctrl.pulse_state.lock().unwrap();
+                    let s = pulse_ctrl.pulse_state.lock().unwrap_or_else(|e| e.into_inner());
                     s.clone()
                 };
 
-                if state.active {
+                let fade = *pulse_ctrl.fade.lock().unwrap_or_else(|e| e.into_inner());
+                let calibration = pulse_ctrl.bl.lock().map(|bl| bl.calibration).unwrap_or_default();
+
+                // The frame the plan asks for now, in drive levels, and how long to hold it
+                let (target, hold_ms) = if state.active {
                     if !was_active {
                         cycle_start_time = std::time::Instant::now();
                         was_active = true;
                     }
-                    idle_ticks = 0;
-                    
+
                     if state.speed_ms == 0 {
                         // Fallback if speed is too fast (prevent div by zero)
                         thread::sleep(Duration::from_millis(100));
                         continue;
                     }
 
-                    let elapsed = cycle_start_time.elapsed().as_millis() as u64;
-                    let position = elapsed % state.speed_ms;
-                    let half_speed = state.speed_ms / 2;
-
-                    let mut linear_progress = if position < half_speed {
-                        // High to Low phase
-                        position as f32 / half_speed as f32
+                    if let Effect::Sunrise(ramp) = state.effect {
+                        let (color, perceived) = ramp.at(Utc::now(), state.color_srgb, state.pct_high);
+                        (calibration.level(color, perceived), 100)
+                    } else if !state.sequence.is_empty() {
+                        let step = (cycle_start_time.elapsed().as_millis() as u64 / state.speed_ms) as usize;
+                        let c = state.sequence[step % state.sequence.len()];
+                        (calibration.level(c, state.pct_high as f32 / 100.0), 100)
                     } else {
-                        // Low to High phase
-                        (position - half_speed) as f32 / half_speed as f32
-                    };
-                    
-                    linear_progress = linear_progress.clamp(0.0, 1.0);
+                        (pulse_frame(&state, cycle_start_time.elapsed().as_millis() as u64, &calibration), refresh_rate_ms)
+                    }
+                } else if fade.is_some() {
+                    // Fading into a steady color (or off), which the worker otherwise leaves alone
+                    was_active = false;
+                    (calibration.level(state.color_srgb, state.pct_high as f32 / 100.0), refresh_rate_ms)
+                } else {
+                    was_active = false;
+                    let interval = pulse_ctrl.keep_alive_ms.load(Ordering::Relaxed).clamp(1000, (MAX_KEEP_ALIVE_SECS as u64 - 1) * 1000);
+                    if last_write.elapsed() >= Duration::from_millis(interval) {
+                        last_write = Instant::now();
+                        if let Ok(mut bl) = pulse_ctrl.bl.lock() {
+                            bl.keep_alive(MAX_KEEP_ALIVE_SECS); // Keep-alive to prevent hardware watchdog timeout
+                        }
+                    }
+                    thread::sleep(Duration::from_millis(100)); // Idle
+                    continue;
+                };
 
-                    // Sine easing mathematically stretches the top/bottom curves to hide PWM jumps 
-                    // and drastically reduces perceived hardware flashing at absolute turnaround points
-                    let easing = (std::f32::consts::PI * linear_progress - std::f32::consts::FRAC_PI_2).sin() * 0.5 + 0.5;
+                let frame = match fade {
+                    Some(fade) => {
+                        let (frame, done) = fade.frame(target);
+                        if done {
+                            *pulse_ctrl.fade.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                        }
+                        frame
+                    }
+                    None => target,
+                };
+                if let Ok(mut bl) = pulse_ctrl.bl.lock() {
+                    bl.light_raw(frame.0, frame.1, frame.2);
+                }
+                last_write = Instant::now();
+                // Fades run at the full frame rate, even under a slow color sequence
+                thread::sleep(Duration::from_millis(if fade.is_some() { refresh_rate_ms } else { hold_ms }));
+            }
+        });
+    }
 
-                    let max_pct = state.pct_high as f32 / 100.0;
-                    let min_pct = state.pct_low as f32 / 100.0;
+    // Starts a crossfade from whatever the device shows now, unless fading is off
+    fn begin_fade(&self) {
+        let ms = self.fade_ms.load(Ordering::Relaxed);
+        if ms == 0 {
+            return;
+        }
+        let from = self.bl.lock().map(|bl| (bl.buffer[3], bl.buffer[4], bl.buffer[5])).unwrap_or((0, 0, 0));
+        *self.fade.lock().unwrap_or_else(|e| e.into_inner()) = Some(Fade { from, started: Instant::now(), duration: Duration::from_millis(ms) });
+    }
 
-                    let current_pct_perceived = if position < half_speed {
-                        max_pct - (max_pct - min_pct) * easing
-                    } else {
-                        min_pct + (max_pct - min_pct) * easing
-                    };
+    fn is_fading(&self) -> bool {
+        self.fade.lock().map(|f| f.is_some()).unwrap_or(false)
+    }
 
-                    let power_factor = current_pct_perceived.powf(2.8);
+    /// Blinks `rgb` at full brightness `count` times, then restores whatever was showing.
+    pub fn flash(self: &Arc<Self>, rgb: (u8, u8, u8), count: u32) {
+        let frames = (0..count).flat_map(|_| [(rgb, 250), ((0, 0, 0), 250)]).collect();
+        self.play_frames(frames);
+    }
 
-                    let frame_voltage = (
-                        (state.color_srgb.0 as f32 * power_factor) as u8,
-                        (state.color_srgb.1 as f32 * power_factor) as u8,
-                        (state.color_srgb.2 as f32 * power_factor) as u8
-                    );
+    /// Sweeps red, green and blue twice, then restores whatever was showing, so the unit can be
+    /// told apart from the others
+    pub fn identify(self: &Arc<Self>) {
+        let sweep = [(255, 0, 0), (0, 255, 0), (0, 0, 255)];
+        let frames = (0..2).flat_map(|_| sweep.map(|rgb| (rgb, 300))).collect();
+        self.play_frames(frames);
+    }
 
-                    if let Ok(mut bl) = pulse_ctrl.bl.lock() {
-                        bl.light_raw(frame_voltage.0, frame_voltage.1, frame_voltage.2);
-                    }
+    // Shows each color at full brightness for its milliseconds, then restores the light
+    fn play_frames(self: &Arc<Self>, frames: Vec<((u8, u8, u8), u64)>) {
+        let ctrl = Arc::clone(self);
+        thread::spawn(move || {
+            // The plan as the flash leaves it, paused; `show()` may replace it meanwhile
+            let paused = ctrl.pulse_state.lock().map(|mut p| {
+                let was_pulsing = p.active;
+                p.active = false;
+                (p.clone(), was_pulsing)
+            }).ok();
+            // A flash is meant to be abrupt; the plan underneath simply resumes
+            if let Ok(mut fade) = ctrl.fade.lock() { *fade = None; }
+            let saved = ctrl.bl.lock().map(|bl| (bl.buffer[3], bl.buffer[4], bl.buffer[5])).unwrap_or((0, 0, 0));
 
-                    thread::sleep(Duration::from_millis(refresh_rate_ms));
+            for (rgb, ms) in frames {
+                if let Ok(mut bl) = ctrl.bl.lock() { bl.light_pct(rgb.0, rgb.1, rgb.2, 100); }
+                thread::sleep(Duration::from_millis(ms));
+            }
 
-                } else {
-                    was_active = false;
-                    idle_ticks += 1;
-                    if idle_ticks >= 20 { // 2 seconds at 100ms intervals
-                        idle_ticks = 0;
-                        if let Ok(mut bl) = pulse_ctrl.bl.lock() {
-                            bl.send(); // Keep-alive to prevent hardware watchdog timeout
-                        }
+            // What to put back: the frame from before, or a steady plan that arrived mid-flash
+            // (written under the frames). The pulse thread picks up a pulsing plan or a fade.
+            enum Restore { Saved, Steady(PulseState), Nothing }
+            let restore = {
+                let Ok(mut plan) = ctrl.pulse_state.lock() else { return };
+                match paused {
+                    Some((paused, was_pulsing)) if *plan == paused => {
+                        plan.active = was_pulsing;
+                        Restore::Saved
                     }
-                    thread::sleep(Duration::from_millis(100)); // Idle
+                    _ if !plan.active && !ctrl.is_fading() => Restore::Steady(plan.clone()),
+                    _ => Restore::Nothing,
                 }
+            };
+            let Ok(mut bl) = ctrl.bl.lock() else { return };
+            match restore {
+                Restore::Saved => bl.light_raw(saved.0, saved.1, saved.2),
+                Restore::Steady(plan) if plan.pct_high == 0 => bl.off(),
+                Restore::Steady(plan) => bl.light_pct(plan.color_srgb.0, plan.color_srgb.1, plan.color_srgb.2, plan.pct_high),
+                Restore::Nothing => {}
             }
         });
-        
-        Ok(controller)
+    }
+
+    /// Plays `tone` for `duration`, then silences it; the light carries on unchanged
+    pub fn play_jingle(self: &Arc<Self>, tone: Ringtone, volume: u8, duration: Duration) {
+        let ctrl = Arc::clone(self);
+        thread::spawn(move || {
+            if let Ok(mut bl) = ctrl.bl.lock() { bl.set_tone(Some(tone), volume); }
+            thread::sleep(duration);
+            if let Ok(mut bl) = ctrl.bl.lock() { bl.set_tone(None, 0); }
+        });
     }
 
     pub fn set_solid(&self, r: u8, g: u8, b: u8) {
@@ -343,6 +838,8 @@ impl BusylightController {
             pct_high,
             pct_low,
             speed_ms,
+            sequence: Vec::new(),
+            effect: Effect::Breathe,
         };
         
         {
@@ -356,8 +853,316 @@ impl BusylightController {
         
     }
 
+    /// Stops pulsing and any crossfade, leaving the device to direct writes
     pub fn stop_pulse(&self) {
         let mut state = self.pulse_state.lock().unwrap();
         state.active = false;
+        *self.fade.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+impl LightSink for BusylightController {
+    fn kind(&self) -> &'static str {
+        "busylight"
+    }
+
+    fn set_color(&self, rgb: (u8, u8, u8), brightness: u8) {
+        self.show(&PulseState { active: false, color_srgb: rgb, pct_high: brightness, pct_low: brightness, speed_ms: 0, sequence: Vec::new(), effect: Effect::Breathe });
+    }
+
+    fn pulse(&self, rgb: (u8, u8, u8), high: u8, low: u8, speed_ms: u64) {
+        self.show(&PulseState { active: true, color_srgb: rgb, pct_high: high, pct_low: low, speed_ms, sequence: Vec::new(), effect: Effect::Breathe });
+    }
+
+    fn off(&self) {
+        self.set_color((0, 0, 0), 0);
+    }
+
+    fn status(&self) -> SinkStatus {
+        match self.bl.lock().map(|bl| bl.is_connected()) {
+            Ok(true) => SinkStatus::Connected,
+            _ => SinkStatus::Disconnected,
+        }
+    }
+
+    // The pulse thread runs color sequences and crossfades itself, so the plan goes to it whole;
+    // only a steady plan with no fade is written straight to the device
+    fn show(&self, plan: &PulseState) {
+        let changed = self.pulse_state.lock().map(|p| *p != *plan).unwrap_or(true);
+        if changed {
+            self.begin_fade();
+        }
+        if let Ok(mut p) = self.pulse_state.lock() { *p = plan.clone(); }
+        if plan.active || self.is_fading() {
+            return;
+        }
+        if let Ok(mut bl) = self.bl.lock() {
+            if plan.pct_high == 0 {
+                bl.off();
+            } else {
+                bl.light_pct(plan.color_srgb.0, plan.color_srgb.1, plan.color_srgb.2, plan.pct_high);
+            }
+        }
+    }
+}
+
+/// All connected Busylights. `primary` takes the first device found and keeps everything that
+/// was single-light before (diagnostics, jingles, `"busylight"` in `deviceLocations`); `sync()`
+/// gives every further device its own controller and pulse thread.
+pub struct BusylightManager {
+    pub primary: Arc<BusylightController>,
+    /// The diagnostics view drives the lights; the pipeline leaves them alone
+    pub manual_mode: Mutex<bool>,
+    extras: Mutex<Vec<(String, Arc<BusylightController>)>>,
+    // `BusylightController::fade_ms` and `keep_alive_ms` for every light, including ones plugged in later
+    fade_ms: AtomicU64,
+    keep_alive_ms: AtomicU64,
+    // `Busylight::calibration` by device key
+    calibrations: Mutex<Vec<(String, Calibration)>>,
+    // Keys of devices that refused access, left alone until the device list changes (a replug, a
+    // new light) or `reconnect` runs, so a missing udev rule doesn't retry every scan
+    denied: Mutex<Vec<String>>,
+    // The device list of the last scan
+    present: Mutex<Vec<String>>,
+}
+
+impl BusylightManager {
+    pub fn new() -> Arc<Self> {
+        let manager = Arc::new(Self {
+            primary: BusylightController::new(),
+            manual_mode: Mutex::new(false),
+            extras: Mutex::new(Vec::new()),
+            fade_ms: AtomicU64::new(0),
+            keep_alive_ms: AtomicU64::new(DEFAULT_KEEP_ALIVE_MS),
+            calibrations: Mutex::new(Vec::new()),
+            denied: Mutex::new(Vec::new()),
+            present: Mutex::new(Vec::new()),
+        });
+        manager.sync();
+        manager
+    }
+
+    /// Hotplug scan, run every couple of seconds: drops the handles of unplugged devices, reopens
+    /// replugged ones and replays their last frame, and starts a controller for each new device.
+    /// Returns whether a new light was added, which has nothing to show yet.
+    pub fn sync(&self) -> bool {
+        let present: Vec<String> = enumerate().into_iter().map(|d| d.key()).collect();
+        let mut denied = self.denied.lock().unwrap_or_else(|e| e.into_inner());
+        {
+            let mut seen = self.present.lock().unwrap_or_else(|e| e.into_inner());
+            if *seen != present {
+                denied.clear();
+                seen.clone_from(&present);
+            }
+        }
+        let mut extras = self.extras.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Extras go first so the primary never takes over a device another controller owns
+        extras.retain(|(key, ctrl)| {
+            let plugged = present.contains(key);
+            if !plugged {
+                println!("Busylight {} unplugged", key);
+                ctrl.shutdown();
+            }
+            plugged
+        });
+        for (key, ctrl) in extras.iter() {
+            if let Ok(mut bl) = ctrl.bl.lock() {
+                if !bl.is_connected() && !denied.contains(key) {
+                    if bl.connect().is_ok() {
+                        bl.send();
+                    } else if bl.status() == DeviceStatus::PermissionDenied {
+                        denied.push(key.clone());
+                    }
+                }
+            }
+            // Only the primary's pulse thread is on the watchdog's list
+            if ctrl.pulse_heartbeat.age() > Duration::from_secs(10) {
+                ctrl.pulse_state.clear_poison();
+                ctrl.bl.clear_poison();
+                BusylightController::spawn_pulse_worker(ctrl);
+            }
+        }
+
+        let primary_key = {
+            let mut bl = self.primary.bl.lock().unwrap_or_else(|e| e.into_inner());
+            if bl.key().is_some_and(|key| !present.contains(&key)) {
+                println!("Busylight unplugged");
+                bl.disconnect();
+            }
+            if !bl.is_connected() {
+                let owned: Vec<&String> = extras.iter().map(|(k, _)| k).collect();
+                if let Some(key) = present.iter().find(|k| !owned.contains(k) && !denied.contains(k)) {
+                    if bl.connect_key(key).is_ok() {
+                        println!("Busylight {} connected", key);
+                        bl.calibration = self.calibration_for(key);
+                        bl.send();
+                    } else if bl.status() == DeviceStatus::PermissionDenied {
+                        denied.push(key.clone());
+                    }
+                }
+            }
+            bl.key()
+        };
+
+        let mut added = false;
+        for key in present {
+            if Some(&key) != primary_key.as_ref() && !extras.iter().any(|(k, _)| *k == key) {
+                println!("Busylight {} added", key);
+                let ctrl = BusylightController::for_device(&key);
+                ctrl.fade_ms.store(self.fade_ms.load(Ordering::Relaxed), Ordering::Relaxed);
+                ctrl.keep_alive_ms.store(self.keep_alive_ms.load(Ordering::Relaxed), Ordering::Relaxed);
+                if let Ok(mut bl) = ctrl.bl.lock() {
+                    bl.calibration = self.calibration_for(&key);
+                }
+                extras.push((key.clone(), ctrl));
+                added = true;
+            }
+        }
+        added
+    }
+
+    /// Reopens every device, e.g. after a resume left the handles stale or a udev rule changed
+    /// the permissions
+    pub fn reconnect(&self) {
+        for (_, light) in self.all() {
+            if let Ok(mut bl) = light.bl.lock() {
+                bl.disconnect();
+            }
+        }
+        self.denied.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        self.sync();
+    }
+
+    /// Sets the crossfade length of every light, now and after hotplug
+    pub fn set_fade(&self, ms: u64) {
+        self.fade_ms.store(ms, Ordering::Relaxed);
+        for (_, light) in self.all() {
+            light.fade_ms.store(ms, Ordering::Relaxed);
+        }
+    }
+
+    /// Sets the keep-alive interval of every light, now and after hotplug
+    pub fn set_keep_alive(&self, ms: u64) {
+        self.keep_alive_ms.store(ms, Ordering::Relaxed);
+        for (_, light) in self.all() {
+            light.keep_alive_ms.store(ms, Ordering::Relaxed);
+        }
+    }
+
+    /// Sets each light's calibration by device key, now and after hotplug; lights without an
+    /// entry get the defaults
+    pub fn set_calibrations(&self, calibrations: Vec<(String, Calibration)>) {
+        *self.calibrations.lock().unwrap_or_else(|e| e.into_inner()) = calibrations;
+        for (key, light) in self.all() {
+            let calibration = key.map(|key| self.calibration_for(&key)).unwrap_or_default();
+            if let Ok(mut bl) = light.bl.lock() {
+                bl.calibration = calibration;
+            }
+        }
+    }
+
+    fn calibration_for(&self, key: &str) -> Calibration {
+        self.calibrations.lock().unwrap_or_else(|e| e.into_inner()).iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, c)| *c)
+            .unwrap_or_default()
+    }
+
+    /// Clears poison from manual mode and every light's locks, for a restarted orchestrator
+    pub fn clear_poison(&self) {
+        self.manual_mode.clear_poison();
+        for (_, light) in self.all() {
+            light.pulse_state.clear_poison();
+            light.bl.clear_poison();
+        }
+    }
+
+    /// Every light with its device key, primary first (its key is None while it's disconnected)
+    pub fn all(&self) -> Vec<(Option<String>, Arc<BusylightController>)> {
+        let primary_key = self.primary.bl.lock().ok().and_then(|bl| bl.key());
+        let extras = self.extras.lock().unwrap_or_else(|e| e.into_inner());
+        std::iter::once((primary_key, self.primary.clone()))
+            .chain(extras.iter().map(|(key, ctrl)| (Some(key.clone()), ctrl.clone())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fade_frame() {
+        let (from, to) = ((0, 0, 0), (200, 100, 0));
+        let second = Duration::from_secs(1);
+        assert_eq!(fade_frame(from, to, Duration::ZERO, second), ((0, 0, 0), false));
+        assert_eq!(fade_frame(from, to, Duration::from_millis(500), second), ((100, 50, 0), false));
+        // Smoothstep is slow at the ends
+        assert_eq!(fade_frame(from, to, Duration::from_millis(100), second), ((5, 2, 0), false));
+        assert_eq!(fade_frame(from, to, second, second), (to, true));
+    }
+
+    #[test]
+    fn test_effect_levels() {
+        // Breathe and triangle fall to the low point mid-cycle and climb back
+        for effect in [Effect::Breathe, Effect::Triangle] {
+            assert_eq!(effect.level(0.0), 1.0);
+            assert!(effect.level(0.5) < 0.001);
+            assert!(effect.level(0.999) > 0.99);
+        }
+        // Sine easing lingers near the turning points
+        assert!(Effect::Breathe.level(0.1) > Effect::Triangle.level(0.1));
+        assert_eq!((Effect::Blink.level(0.4), Effect::Blink.level(0.6)), (1.0, 0.0));
+        let double: Vec<f32> = [0.1, 0.2, 0.35, 0.6].iter().map(|&t| Effect::DoubleBlink.level(t)).collect();
+        assert_eq!(double, vec![1.0, 0.0, 1.0, 0.0]);
+        assert_eq!((Effect::Strobe.level(0.05), Effect::Strobe.level(0.2)), (1.0, 0.0));
+        assert_eq!(Effect::from_name("double_blink"), Effect::DoubleBlink);
+        assert_eq!(Effect::from_name("pulse"), Effect::Breathe);
+    }
+
+    #[test]
+    fn test_sunrise_ramp() {
+        let start = Utc::now();
+        let ramp = Ramp { from: (200, 0, 0), start, end: start + chrono::Duration::minutes(30) };
+        assert_eq!(ramp.at(start, (0, 0, 200), 60), ((200, 0, 0), RAMP_GLIMMER));
+        let (color, perceived) = ramp.at(start + chrono::Duration::minutes(15), (0, 0, 200), 60);
+        assert_eq!(color, (100, 0, 100));
+        assert!((perceived - 0.305).abs() < 0.001);
+        let (color, perceived) = ramp.at(start + chrono::Duration::hours(1), (0, 0, 200), 60);
+        assert_eq!(color, (0, 0, 200));
+        assert!((perceived - 0.6).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_calibration() {
+        let default = Calibration::default();
+        assert_eq!(default.balance((200, 100, 50)), (200, 100, 50));
+        assert_eq!(default.level((255, 255, 255), 0.5), crate::color::apply_brightness((255, 255, 255), 0.5));
+        assert_eq!(default.degamma(128), 55);
+
+        let clone = Calibration { gamma: 2.0, color_gamma: 2.0, red: 1.0, green: 0.5, blue: 1.5 };
+        assert_eq!(clone.balance((200, 100, 50)), (200, 50, 50));
+        assert_eq!(clone.level((200, 200, 200), 0.5), (50, 50, 50));
+        assert_eq!(clone.degamma(128), 64);
+    }
+
+    #[test]
+    fn test_keep_alive_report() {
+        let report = keep_alive_report(99);
+        assert_eq!(report[0..3], [0, 0x8f, 0]);
+        assert_eq!(report[57..63], [0, 0, 0, 0xff, 0xff, 0xff]);
+        // 0x8f + 3 * 0xff
+        assert_eq!(report[63..65], [0x03, 0x8c]);
+    }
+
+    #[test]
+    fn test_audio_byte() {
+        assert_eq!(audio_byte(None, 5), 128);
+        assert_eq!(audio_byte(Some(Ringtone::OpenOffice), 0), 136);
+        assert_eq!(audio_byte(Some(Ringtone::Funky), 3), 155);
+        assert_eq!(audio_byte(Some(Ringtone::Buzz), 99), 223);
+        assert_eq!(Ringtone::from_name("kuando_train"), Some(Ringtone::KuandoTrain));
+        assert_eq!(Ringtone::from_name("Funky"), None);
     }
 }
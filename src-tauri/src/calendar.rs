@@ -0,0 +1,529 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use reqwest::Client;
+use crate::config::CalendarConfig;
+
+// A calendar feed (ICS) as a busy source: during an event marked busy the arbiter shows
+// `calendar.meetingColor`. The feed is fetched every `calendar.refreshMinutes`, and whether an
+// event is on is checked every `CHECK_INTERVAL` so the light follows meetings to the minute.
+// The parser covers what calendar exports use: folded lines, UTC or local times (a TZID is read
+// as the computer's own zone), DTEND or DURATION, TRANSP, and RRULE with DAILY, WEEKLY (BYDAY),
+// MONTHLY or YEARLY on the start date, INTERVAL, COUNT, UNTIL, EXDATE and moved occurrences.
+// Rules with other BY parts only count their first occurrence.
+
+/// How often the poller checks for an event starting or ending
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+// Bounds the occurrences walked for one rule, about 27 years of a daily meeting
+const MAX_OCCURRENCES: usize = 10_000;
+
+// Bounds a rule's INTERVAL, which comes from the feed; a thousand years is already never
+const MAX_INTERVAL: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<DateTime<Utc>>,
+    by_day: Vec<Weekday>,
+}
+
+impl Recurrence {
+    // Occurrence starts from `first` on, in the event's own wall-clock time; they end where the
+    // calendar does
+    fn starts(&self, first: NaiveDateTime) -> Box<dyn Iterator<Item = NaiveDateTime> + '_> {
+        let interval = self.interval.clamp(1, MAX_INTERVAL);
+        let every_days = move |days: u64| (0u64..).map_while(move |n| n.checked_mul(days).and_then(|d| first.checked_add_days(Days::new(d))));
+        match self.frequency {
+            Frequency::Daily => Box::new(every_days(interval as u64)),
+            Frequency::Weekly if !self.by_day.is_empty() => {
+                // Weeks start on Monday
+                let week_start = first.date() - chrono::Duration::days(first.weekday().num_days_from_monday() as i64);
+                Box::new(every_days(1)
+                    .filter(move |start| ((start.date() - week_start).num_days() / 7) % interval as i64 == 0 && self.by_day.contains(&start.weekday())))
+            }
+            Frequency::Weekly => Box::new(every_days(interval as u64 * 7)),
+            Frequency::Monthly | Frequency::Yearly => {
+                let months = if self.frequency == Frequency::Monthly { interval } else { interval * 12 };
+                // Months without the start's day (a 31st, February 29th) are skipped, as RFC 5545 does
+                Box::new((0u32..).map_while(move |n| n.checked_mul(months).and_then(|m| first.checked_add_months(Months::new(m))))
+                    .filter(move |start| start.day() == first.day()))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub summary: String,
+    uid: String,
+    // Wall-clock start; floating ones are in the computer's zone
+    start: NaiveDateTime,
+    floating: bool,
+    length: chrono::Duration,
+    pub all_day: bool,
+    /// Opaque rather than transparent (free)
+    pub busy: bool,
+    recurrence: Option<Recurrence>,
+    // Set on an occurrence that was moved or edited; it replaces that start of the series
+    recurrence_id: Option<DateTime<Utc>>,
+    exdates: Vec<DateTime<Utc>>,
+}
+
+impl CalendarEvent {
+    /// Whether an occurrence of the event is going on at `now`
+    pub fn covers(&self, now: DateTime<Utc>) -> bool {
+        self.occurrences()
+            .take_while(|start| *start <= now)
+            .any(|start| now < start + self.length && !self.exdates.contains(&start))
+    }
+
+    fn occurrences(&self) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        let rule = self.recurrence.as_ref();
+        let starts: Box<dyn Iterator<Item = NaiveDateTime> + '_> = match rule {
+            Some(rule) => rule.starts(self.start),
+            None => Box::new(std::iter::once(self.start)),
+        };
+        starts.take(rule.and_then(|r| r.count).unwrap_or(MAX_OCCURRENCES).min(MAX_OCCURRENCES))
+            .filter_map(move |start| to_utc(start, self.floating))
+            .take_while(move |start| rule.and_then(|r| r.until).is_none_or(|until| *start <= until))
+    }
+}
+
+/// The first busy event going on at `now`
+pub fn busy_event(events: &[CalendarEvent], now: DateTime<Utc>, include_all_day: bool) -> Option<&CalendarEvent> {
+    events.iter().find(|e| e.busy && (include_all_day || !e.all_day) && e.covers(now))
+}
+
+// The events of the feed last fetched
+struct FetchedFeed {
+    url: String,
+    // When it was last fetched, or tried
+    fetched_at: DateTime<Utc>,
+    events: Vec<CalendarEvent>,
+}
+
+pub struct CalendarFeed {
+    client: Client,
+    events: Mutex<Option<FetchedFeed>>,
+    // Whether the last check found a busy event
+    busy: AtomicBool,
+}
+
+impl Default for CalendarFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CalendarFeed {
+    pub fn new() -> Self {
+        Self {
+            client: Client::builder().timeout(Duration::from_secs(20)).build().unwrap_or_default(),
+            events: Mutex::new(None),
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Relaxed)
+    }
+
+    /// Forgets the feed, when the calendar is turned off; returns whether it was busy
+    pub fn reset(&self) -> bool {
+        *self.events.lock().unwrap() = None;
+        self.busy.swap(false, Ordering::Relaxed)
+    }
+
+    /// Fetches the feed when it's due, then checks for a busy event; returns whether busy changed.
+    /// A failed fetch keeps the events it already has.
+    pub async fn poll(&self, config: &CalendarConfig) -> Result<bool, String> {
+        let url = config.feed_url();
+        let due = match &*self.events.lock().unwrap() {
+            Some(feed) => feed.url != url || Utc::now() - feed.fetched_at >= config.refresh_interval(),
+            None => true,
+        };
+        if due {
+            let fetched = self.fetch(&url).await;
+            let mut cache = self.events.lock().unwrap();
+            let kept = cache.take().filter(|feed| feed.url == url).map(|feed| feed.events);
+            match (fetched, kept) {
+                (Ok(events), _) => *cache = Some(FetchedFeed { url, fetched_at: Utc::now(), events }),
+                (Err(e), Some(events)) => {
+                    println!("Calendar fetch failed, keeping the last events: {}", e);
+                    *cache = Some(FetchedFeed { url, fetched_at: Utc::now(), events });
+                }
+                (Err(e), None) => return Err(e),
+            }
+        }
+
+        let busy = self.events.lock().unwrap().as_ref()
+            .is_some_and(|feed| busy_event(&feed.events, Utc::now(), config.include_all_day).is_some());
+        Ok(self.busy.swap(busy, Ordering::Relaxed) != busy)
+    }
+
+    async fn fetch(&self, url: &str) -> Result<Vec<CalendarEvent>, String> {
+        let resp = self.client.get(url).send().await.map_err(|e| e.to_string())?;
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {} from the calendar", resp.status()));
+        }
+        let text = resp.text().await.map_err(|e| e.to_string())?;
+        if !text.contains("BEGIN:VCALENDAR") {
+            return Err("Not an iCalendar feed".to_string());
+        }
+        Ok(parse_ics(&text))
+    }
+}
+
+// A DTSTART/DTEND/EXDATE value
+#[derive(Debug, Clone, Copy)]
+struct Stamp {
+    at: NaiveDateTime,
+    floating: bool,
+    date_only: bool,
+}
+
+impl Stamp {
+    fn utc(&self) -> Option<DateTime<Utc>> {
+        to_utc(self.at, self.floating)
+    }
+}
+
+#[derive(Default)]
+struct Draft {
+    uid: String,
+    summary: String,
+    start: Option<Stamp>,
+    end: Option<Stamp>,
+    duration: Option<chrono::Duration>,
+    transparent: bool,
+    free: bool,
+    cancelled: bool,
+    rule: Option<Recurrence>,
+    unsupported_rule: bool,
+    recurrence_id: Option<DateTime<Utc>>,
+    exdates: Vec<DateTime<Utc>>,
+}
+
+impl Draft {
+    fn finish(self) -> Option<CalendarEvent> {
+        let start = self.start.filter(|_| !self.cancelled)?;
+        let length = match (self.end, self.duration) {
+            (Some(end), _) => end.at - start.at,
+            (None, Some(duration)) => duration,
+            // An all-day event without an end is one day
+            (None, None) if start.date_only => chrono::Duration::days(1),
+            (None, None) => chrono::Duration::zero(),
+        };
+        if length <= chrono::Duration::zero() {
+            return None;
+        }
+        Some(CalendarEvent {
+            summary: self.summary,
+            uid: self.uid,
+            start: start.at,
+            floating: start.floating,
+            length,
+            all_day: start.date_only,
+            busy: !self.transparent && !self.free,
+            recurrence: self.rule.filter(|_| !self.unsupported_rule),
+            recurrence_id: self.recurrence_id,
+            exdates: self.exdates,
+        })
+    }
+}
+
+/// The events of an iCalendar document; cancelled and zero-length ones are left out
+pub fn parse_ics(text: &str) -> Vec<CalendarEvent> {
+    let mut events = Vec::new();
+    let mut draft: Option<Draft> = None;
+    // Components inside the event (alarms), whose properties aren't the event's
+    let mut nested = 0;
+
+    for line in unfold(text) {
+        // Parameters (TZID, VALUE=DATE) aren't needed: the value's own form tells them apart
+        let Some((name, value)) = split_property(&line) else { continue };
+        match (name.as_str(), value.trim().to_ascii_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") => draft = Some(Draft::default()),
+            ("BEGIN", _) if draft.is_some() => nested += 1,
+            ("END", "VEVENT") => {
+                events.extend(draft.take().and_then(Draft::finish));
+                nested = 0;
+            }
+            ("END", _) if nested > 0 => nested -= 1,
+            _ => {}
+        }
+        let Some(event) = draft.as_mut().filter(|_| nested == 0) else { continue };
+        let value = value.trim();
+        match name.as_str() {
+            "UID" => event.uid = value.to_string(),
+            "SUMMARY" => event.summary = unescape(value),
+            "DTSTART" => event.start = parse_stamp(value),
+            "DTEND" => event.end = parse_stamp(value),
+            "DURATION" => event.duration = parse_duration(value),
+            "TRANSP" => event.transparent = value.eq_ignore_ascii_case("TRANSPARENT"),
+            "X-MICROSOFT-CDO-BUSYSTATUS" => event.free = value.eq_ignore_ascii_case("FREE"),
+            "STATUS" => event.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            "RRULE" => {
+                event.rule = parse_rule(value);
+                event.unsupported_rule = event.rule.is_none() || unsupported_parts(value);
+            }
+            "RECURRENCE-ID" => event.recurrence_id = parse_stamp(value).and_then(|s| s.utc()),
+            "EXDATE" => event.exdates.extend(value.split(',').filter_map(|v| parse_stamp(v)?.utc())),
+            _ => {}
+        }
+    }
+
+    // A moved occurrence stands on its own and takes its original start out of the series
+    let moved: Vec<(String, DateTime<Utc>)> = events.iter()
+        .filter_map(|e| Some((e.uid.clone(), e.recurrence_id?)))
+        .collect();
+    for event in events.iter_mut().filter(|e| e.recurrence_id.is_none() && e.recurrence.is_some()) {
+        event.exdates.extend(moved.iter().filter(|(uid, _)| *uid == event.uid).map(|(_, start)| *start));
+    }
+    events
+}
+
+// Continuation lines start with a space or tab
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.lines() {
+        match raw.strip_prefix(' ').or_else(|| raw.strip_prefix('\t')) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+// "DTSTART;TZID=Europe/Berlin:20240105T090000" -> ("DTSTART", "20240105T090000")
+fn split_property(line: &str) -> Option<(String, &str)> {
+    let mut quoted = false;
+    let (colon, _) = line.char_indices().find(|&(_, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ':' && !quoted
+    })?;
+    let head = &line[..colon];
+    let name = head.split(';').next().unwrap_or(head);
+    Some((name.to_ascii_uppercase(), &line[colon + 1..]))
+}
+
+// "20240105T090000Z" (UTC), "20240105T090000" (local) or "20240105" (a date)
+fn parse_stamp(value: &str) -> Option<Stamp> {
+    let value = value.trim();
+    if value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some(Stamp { at: date.and_hms_opt(0, 0, 0)?, floating: true, date_only: true });
+    }
+    let (text, floating) = match value.strip_suffix('Z') {
+        Some(text) => (text, false),
+        None => (value, true),
+    };
+    let at = NaiveDateTime::parse_from_str(text, "%Y%m%dT%H%M%S").ok()?;
+    Some(Stamp { at, floating, date_only: false })
+}
+
+fn to_utc(at: NaiveDateTime, floating: bool) -> Option<DateTime<Utc>> {
+    if floating {
+        // A time skipped by a DST change doesn't exist locally
+        Local.from_local_datetime(&at).earliest().map(|t| t.with_timezone(&Utc))
+    } else {
+        Some(Utc.from_utc_datetime(&at))
+    }
+}
+
+// "PT1H30M", "P1D", "P2W"
+fn parse_duration(value: &str) -> Option<chrono::Duration> {
+    let body = value.trim_start_matches('+').strip_prefix('P')?;
+    let mut total = chrono::Duration::zero();
+    let mut number = String::new();
+    let mut time = false;
+    for c in body.chars() {
+        match c {
+            'T' => time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += match (unit, time) {
+                    ('W', false) => chrono::Duration::weeks(n),
+                    ('D', false) => chrono::Duration::days(n),
+                    ('H', true) => chrono::Duration::hours(n),
+                    ('M', true) => chrono::Duration::minutes(n),
+                    ('S', true) => chrono::Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(total)
+}
+
+// "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20241231T000000Z"
+fn parse_rule(value: &str) -> Option<Recurrence> {
+    let mut frequency = None;
+    let mut rule = Recurrence { frequency: Frequency::Daily, interval: 1, count: None, until: None, by_day: Vec::new() };
+    for part in value.split(';') {
+        let Some((key, val)) = part.split_once('=') else { continue };
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => frequency = match val.to_ascii_uppercase().as_str() {
+                "DAILY" => Some(Frequency::Daily),
+                "WEEKLY" => Some(Frequency::Weekly),
+                "MONTHLY" => Some(Frequency::Monthly),
+                "YEARLY" => Some(Frequency::Yearly),
+                _ => None,
+            },
+            "INTERVAL" => rule.interval = val.parse().ok().filter(|&n| n > 0).unwrap_or(1).min(MAX_INTERVAL),
+            "COUNT" => rule.count = val.parse().ok(),
+            "UNTIL" => rule.until = parse_stamp(val).and_then(|s| s.utc()),
+            "BYDAY" => rule.by_day = val.split(',').filter_map(weekday).collect(),
+            _ => {}
+        }
+    }
+    rule.frequency = frequency?;
+    Some(rule)
+}
+
+// BY parts the walk in `Recurrence::starts` doesn't follow; BYDAY only works weekly
+fn unsupported_parts(value: &str) -> bool {
+    let value = value.to_ascii_uppercase();
+    let weekly = value.contains("FREQ=WEEKLY");
+    value.split(';').any(|part| {
+        let key = part.split('=').next().unwrap_or("");
+        key.starts_with("BY") && !(weekly && key == "BYDAY")
+    })
+}
+
+// "MO", or "2TU" from a monthly rule
+fn weekday(code: &str) -> Option<Weekday> {
+    match code.trim().get(code.trim().len().checked_sub(2)?..)?.to_ascii_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", " ").replace("\\N", " ").replace("\\,", ",").replace("\\;", ";").replace("\\\\", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    const FEED: &str = "BEGIN:VCALENDAR\r\n\
+BEGIN:VEVENT\r\n\
+UID:standup\r\n\
+SUMMARY:Stand\r\n up\r\n\
+DTSTART:20240101T090000Z\r\n\
+DURATION:PT15M\r\n\
+RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=6\r\n\
+EXDATE:20240103T090000Z\r\n\
+BEGIN:VALARM\r\n\
+DURATION:PT1H\r\n\
+END:VALARM\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:standup\r\n\
+RECURRENCE-ID:20240108T090000Z\r\n\
+DTSTART:20240108T100000Z\r\n\
+DTEND:20240108T101500Z\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:lunch\r\n\
+DTSTART:20240101T120000Z\r\n\
+DTEND:20240101T130000Z\r\n\
+TRANSP:TRANSPARENT\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:holiday\r\n\
+DTSTART;VALUE=DATE:20240102\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:cancelled\r\n\
+DTSTART:20240101T150000Z\r\n\
+DTEND:20240101T160000Z\r\n\
+STATUS:CANCELLED\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parse_ics() {
+        let events = parse_ics(FEED);
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].summary, "Standup");
+        assert_eq!(events[0].length, chrono::Duration::minutes(15));
+        assert!(!events[2].busy);
+        assert!(events[3].all_day);
+        assert_eq!(events[3].length, chrono::Duration::days(1));
+    }
+
+    #[test]
+    fn test_busy_event() {
+        let events = parse_ics(FEED);
+        let busy = |t: &str| busy_event(&events, at(t), false).map(|e| e.uid.as_str());
+        assert_eq!(busy("2024-01-01T09:10:00Z"), Some("standup"));
+        assert_eq!(busy("2024-01-01T09:15:00Z"), None);
+        // Excluded, then moved to 10:00
+        assert_eq!(busy("2024-01-03T09:05:00Z"), None);
+        assert_eq!(busy("2024-01-08T09:05:00Z"), None);
+        assert_eq!(busy("2024-01-08T10:05:00Z"), Some("standup"));
+        assert_eq!(busy("2024-01-10T09:05:00Z"), Some("standup"));
+        // COUNT=6 ends the series after Wednesday the 17th
+        assert_eq!(busy("2024-01-17T09:05:00Z"), Some("standup"));
+        assert_eq!(busy("2024-01-22T09:05:00Z"), None);
+        // Free and cancelled events don't count
+        assert_eq!(busy("2024-01-01T12:30:00Z"), None);
+        assert_eq!(busy("2024-01-01T15:30:00Z"), None);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("PT1H30M"), Some(chrono::Duration::minutes(90)));
+        assert_eq!(parse_duration("P1DT2H"), Some(chrono::Duration::hours(26)));
+        assert_eq!(parse_duration("P2W"), Some(chrono::Duration::weeks(2)));
+        assert_eq!(parse_duration("1H"), None);
+    }
+
+    #[test]
+    fn test_huge_interval() {
+        let rule = parse_rule("FREQ=DAILY;INTERVAL=4000000000").unwrap();
+        assert_eq!(rule.interval, MAX_INTERVAL);
+        let first = at("2024-01-01T09:00:00Z").naive_utc();
+        // Runs out at the end of the calendar instead of overflowing
+        for frequency in [Frequency::Daily, Frequency::Weekly, Frequency::Monthly, Frequency::Yearly] {
+            let rule = Recurrence { frequency, interval: u32::MAX, ..rule.clone() };
+            assert_eq!(rule.starts(first).next(), Some(first));
+            assert!(rule.starts(first).count() > 1);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_rules() {
+        assert!(!unsupported_parts("FREQ=WEEKLY;BYDAY=MO,WE"));
+        assert!(unsupported_parts("FREQ=MONTHLY;BYDAY=2TU"));
+        assert!(unsupported_parts("FREQ=YEARLY;BYMONTH=3"));
+    }
+}
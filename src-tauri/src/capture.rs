@@ -0,0 +1,241 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::config::CaptureConfig;
+
+// Whether any app has the camera or microphone open, as a busy source that needs no accounts.
+// Windows records it in the privacy settings' consent store (an entry whose last use has started
+// but not stopped), macOS reports it per device ("is running somewhere" in CoreAudio and
+// CoreMediaIO), and on Linux a capture PCM in the RUNNING state tells. Linux has no camera
+// check: PipeWire keeps every /dev/video* open, so an open node says nothing.
+// Checks are blocking and cheap; the poller runs them every `CHECK_INTERVAL`.
+
+/// How often the poller looks at the devices
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Device {
+    Camera,
+    Microphone,
+}
+
+pub struct CaptureMonitor {
+    // Whether the last check found a device in use
+    busy: AtomicBool,
+}
+
+impl Default for CaptureMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CaptureMonitor {
+    pub fn new() -> Self {
+        Self { busy: AtomicBool::new(false) }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Relaxed)
+    }
+
+    /// Forgets the last check, when detection is turned off; returns whether it was busy
+    pub fn reset(&self) -> bool {
+        self.busy.swap(false, Ordering::Relaxed)
+    }
+
+    /// Looks at the devices `config` watches; returns whether busy changed. Blocking.
+    pub fn check(&self, config: &CaptureConfig) -> bool {
+        let busy = (config.camera && in_use(Device::Camera)) || (config.microphone && in_use(Device::Microphone));
+        self.busy.swap(busy, Ordering::Relaxed) != busy
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn in_use(device: Device) -> bool {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let store = match device {
+        Device::Camera => "webcam",
+        Device::Microphone => "microphone",
+    };
+    let key = format!(r"HKCU\Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\{}", store);
+    Command::new("reg")
+        .args(["query", &key, "/s"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map(|output| consent_store_in_use(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "macos")]
+pub fn in_use(device: Device) -> bool {
+    match device {
+        Device::Camera => mac::camera_in_use(),
+        Device::Microphone => mac::microphone_in_use(),
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn in_use(device: Device) -> bool {
+    use std::fs;
+    use std::path::Path;
+
+    if device == Device::Camera {
+        return false;
+    }
+
+    // Entries of `dir` whose names pass `keep`
+    fn entries(dir: &Path, keep: impl Fn(&str) -> bool) -> Vec<std::path::PathBuf> {
+        fs::read_dir(dir).map(|entries| {
+            entries.flatten()
+                .filter(|e| keep(&e.file_name().to_string_lossy()))
+                .map(|e| e.path())
+                .collect()
+        }).unwrap_or_default()
+    }
+
+    // /proc/asound/card*/pcm*c/sub*/status
+    entries(Path::new("/proc/asound"), |n| n.starts_with("card")).iter()
+        .flat_map(|card| entries(card, |n| n.starts_with("pcm") && n.ends_with('c')))
+        .flat_map(|pcm| entries(&pcm, |n| n.starts_with("sub")))
+        .any(|sub| fs::read_to_string(sub.join("status")).is_ok_and(|status| capture_running(&status)))
+}
+
+/// `reg query ... /s` output of a ConsentStore key: an app is using the device while its
+/// LastUsedTimeStart is set and its LastUsedTimeStop is still 0
+pub fn consent_store_in_use(text: &str) -> bool {
+    let mut entry: Option<(u64, u64)> = None;
+    let active = |entry: Option<(u64, u64)>| matches!(entry, Some((start, 0)) if start > 0);
+    for line in text.lines() {
+        if line.starts_with("HKEY_") {
+            if active(entry.take()) {
+                return true;
+            }
+            entry = Some((0, 0));
+            continue;
+        }
+        // "    LastUsedTimeStop    REG_QWORD    0x0"
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (Some(entry), [name, "REG_QWORD", value]) = (entry.as_mut(), fields.as_slice()) else { continue };
+        let Ok(value) = u64::from_str_radix(value.trim_start_matches("0x"), 16) else { continue };
+        match *name {
+            "LastUsedTimeStart" => entry.0 = value,
+            "LastUsedTimeStop" => entry.1 = value,
+            _ => {}
+        }
+    }
+    active(entry)
+}
+
+/// A capture substream's /proc/asound status while something records from it
+pub fn capture_running(status: &str) -> bool {
+    status.lines().any(|line| line.trim() == "state: RUNNING")
+}
+
+// CoreAudio and CoreMediaIO share the property-address calling convention
+#[cfg(target_os = "macos")]
+mod mac {
+    use std::ffi::c_void;
+    use std::ptr;
+
+    #[repr(C)]
+    struct PropertyAddress {
+        selector: u32,
+        scope: u32,
+        element: u32,
+    }
+
+    const fn fourcc(code: &[u8; 4]) -> u32 {
+        u32::from_be_bytes(*code)
+    }
+
+    const SYSTEM_OBJECT: u32 = 1;
+    const SCOPE_GLOBAL: u32 = fourcc(b"glob");
+    const ELEMENT_MAIN: u32 = 0;
+    const DEFAULT_INPUT_DEVICE: u32 = fourcc(b"dIn ");
+    const HARDWARE_DEVICES: u32 = fourcc(b"dev#");
+    const IS_RUNNING_SOMEWHERE: u32 = fourcc(b"gone");
+
+    #[link(name = "CoreAudio", kind = "framework")]
+    extern "C" {
+        fn AudioObjectGetPropertyData(object: u32, address: *const PropertyAddress, qualifier_size: u32, qualifier: *const c_void, data_size: *mut u32, data: *mut c_void) -> i32;
+    }
+
+    #[link(name = "CoreMediaIO", kind = "framework")]
+    extern "C" {
+        fn CMIOObjectGetPropertyDataSize(object: u32, address: *const PropertyAddress, qualifier_size: u32, qualifier: *const c_void, data_size: *mut u32) -> i32;
+        fn CMIOObjectGetPropertyData(object: u32, address: *const PropertyAddress, qualifier_size: u32, qualifier: *const c_void, data_size: u32, data_used: *mut u32, data: *mut c_void) -> i32;
+    }
+
+    fn address(selector: u32) -> PropertyAddress {
+        PropertyAddress { selector, scope: SCOPE_GLOBAL, element: ELEMENT_MAIN }
+    }
+
+    // The default input device, running for any process
+    pub fn microphone_in_use() -> bool {
+        let mut device: u32 = 0;
+        let mut size = 4;
+        let status = unsafe {
+            AudioObjectGetPropertyData(SYSTEM_OBJECT, &address(DEFAULT_INPUT_DEVICE), 0, ptr::null(), &mut size, &mut device as *mut u32 as *mut c_void)
+        };
+        if status != 0 || device == 0 {
+            return false;
+        }
+        let mut running: u32 = 0;
+        let mut size = 4;
+        let status = unsafe {
+            AudioObjectGetPropertyData(device, &address(IS_RUNNING_SOMEWHERE), 0, ptr::null(), &mut size, &mut running as *mut u32 as *mut c_void)
+        };
+        status == 0 && running != 0
+    }
+
+    // Any camera, running for any process
+    pub fn camera_in_use() -> bool {
+        let devices_address = address(HARDWARE_DEVICES);
+        let mut size: u32 = 0;
+        if unsafe { CMIOObjectGetPropertyDataSize(SYSTEM_OBJECT, &devices_address, 0, ptr::null(), &mut size) } != 0 {
+            return false;
+        }
+        let mut devices = vec![0u32; size as usize / 4];
+        let mut used: u32 = 0;
+        let status = unsafe {
+            CMIOObjectGetPropertyData(SYSTEM_OBJECT, &devices_address, 0, ptr::null(), size, &mut used, devices.as_mut_ptr() as *mut c_void)
+        };
+        if status != 0 {
+            return false;
+        }
+        devices.truncate(used as usize / 4);
+        devices.iter().any(|&device| {
+            let mut running: u32 = 0;
+            let mut used: u32 = 0;
+            let status = unsafe {
+                CMIOObjectGetPropertyData(device, &address(IS_RUNNING_SOMEWHERE), 0, ptr::null(), 4, &mut used, &mut running as *mut u32 as *mut c_void)
+            };
+            status == 0 && running != 0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consent_store_in_use() {
+        let key = r"HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\webcam";
+        let idle = format!("\r\n{key}\r\n    Value    REG_SZ    Allow\r\n\r\n{key}\\NonPackaged\\C:#Apps#zoom.exe\r\n    LastUsedTimeStart    REG_QWORD    0x1d9f00000000000\r\n    LastUsedTimeStop    REG_QWORD    0x1d9f00000001000\r\n");
+        assert!(!consent_store_in_use(&idle));
+        let busy = format!("{idle}\r\n{key}\\MicrosoftTeams_8wekyb3d8bbwe\r\n    LastUsedTimeStart    REG_QWORD    0x1d9f00000002000\r\n    LastUsedTimeStop    REG_QWORD    0x0\r\n");
+        assert!(consent_store_in_use(&busy));
+        assert!(!consent_store_in_use(""));
+    }
+
+    #[test]
+    fn test_capture_running() {
+        assert!(capture_running("state: RUNNING\nowner_pid   : 1234\n"));
+        assert!(!capture_running("closed\n"));
+        assert!(!capture_running("state: PREPARED\n"));
+    }
+}
@@ -0,0 +1,341 @@
+use crate::config::AppConfig;
+use crate::models::WeatherState;
+
+// Everything that turns weather into a color: hex parsing, the temperature scale, blending, and
+// the perceived-brightness curve the LEDs are driven with.
+//
+// Blending between two sRGB colors: "rgb" is a straight per-channel blend (the original
+// behavior); "oklab" blends in the OKLab perceptual space (Ottosson 2020), which keeps in-between
+// colors as bright and saturated as their ends instead of passing through muddy greys, most
+// visibly between blue and green.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    Rgb,
+    Oklab,
+}
+
+impl Interpolation {
+    /// Config value to mode; anything unrecognised is plain RGB
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "oklab" => Self::Oklab,
+            _ => Self::Rgb,
+        }
+    }
+}
+
+pub(crate) fn to_linear(c: u8) -> f32 {
+    let v = c as f32 / 255.0;
+    if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+}
+
+pub(crate) fn to_srgb(v: f32) -> u8 {
+    let v = v.clamp(0.0, 1.0);
+    let encoded = if v <= 0.0031308 { v * 12.92 } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 };
+    (encoded * 255.0).round() as u8
+}
+
+#[allow(clippy::excessive_precision)] // published matrix constants, kept verbatim
+fn to_oklab(rgb: (u8, u8, u8)) -> [f32; 3] {
+    let (r, g, b) = (to_linear(rgb.0), to_linear(rgb.1), to_linear(rgb.2));
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+#[allow(clippy::excessive_precision)] // published matrix constants, kept verbatim
+fn from_oklab(lab: [f32; 3]) -> (u8, u8, u8) {
+    let l = (lab[0] + 0.3963377774 * lab[1] + 0.2158037573 * lab[2]).powi(3);
+    let m = (lab[0] - 0.1055613458 * lab[1] - 0.0638541728 * lab[2]).powi(3);
+    let s = (lab[0] - 0.0894841775 * lab[1] - 1.2914855480 * lab[2]).powi(3);
+    (
+        to_srgb(4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s),
+        to_srgb(-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s),
+        to_srgb(-0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s),
+    )
+}
+
+/// The color a fraction `t` (0..=1) of the way from `start` to `end`
+pub fn mix(start: (u8, u8, u8), end: (u8, u8, u8), t: f32, mode: Interpolation) -> (u8, u8, u8) {
+    let t = t.clamp(0.0, 1.0);
+    match mode {
+        Interpolation::Rgb => {
+            let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+            (lerp(start.0, end.0), lerp(start.1, end.1), lerp(start.2, end.2))
+        }
+        Interpolation::Oklab => {
+            let (a, b) = (to_oklab(start), to_oklab(end));
+            from_oklab([a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t])
+        }
+    }
+}
+
+/// A temperature scale: sorted °C stops, blended with one interpolation mode and clamped at
+/// the ends. Built with `ColorScale::builder()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorScale {
+    stops: Vec<(f64, (u8, u8, u8))>,
+    mode: Interpolation,
+}
+
+#[derive(Debug, Clone)]
+pub struct ColorScaleBuilder {
+    stops: Vec<(f64, String)>,
+    mode: Interpolation,
+}
+
+impl ColorScale {
+    pub fn builder() -> ColorScaleBuilder {
+        ColorScaleBuilder { stops: Vec::new(), mode: Interpolation::Rgb }
+    }
+
+    /// Color at a temperature in °C
+    pub fn color_at(&self, temp_c: f64) -> (u8, u8, u8) {
+        let (first, last) = (self.stops[0], self.stops[self.stops.len() - 1]);
+        if temp_c <= first.0 { return first.1; }
+        if temp_c >= last.0 { return last.1; }
+
+        let i = self.stops.iter().position(|s| temp_c <= s.0).unwrap_or(self.stops.len() - 1);
+        let (start, end) = (self.stops[i - 1], self.stops[i]);
+        let range = end.0 - start.0;
+        let t = if range == 0.0 { 0.0 } else { (temp_c - start.0) / range };
+        mix(start.1, end.1, t as f32, self.mode)
+    }
+
+    /// `color_at` as `#rrggbb`
+    pub fn hex_at(&self, temp_c: f64) -> String {
+        to_hex(self.color_at(temp_c))
+    }
+}
+
+impl ColorScaleBuilder {
+    /// Adds a stop; order doesn't matter
+    pub fn stop(mut self, temp_c: f64, hex: &str) -> Self {
+        self.stops.push((temp_c, hex.to_string()));
+        self
+    }
+
+    pub fn interpolation(mut self, mode: Interpolation) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sorts the stops; fails without any, or on a color that isn't `#rrggbb` or a
+    /// temperature that isn't a number
+    pub fn build(self) -> Result<ColorScale, String> {
+        if self.stops.is_empty() {
+            return Err("Color scale needs at least one stop".to_string());
+        }
+        let mut stops = Vec::with_capacity(self.stops.len());
+        for (temp, hex) in self.stops {
+            if !temp.is_finite() {
+                return Err(format!("Invalid stop temperature {}", temp));
+            }
+            let rgb = hex_to_rgb(&hex).ok_or_else(|| format!("Invalid color {} (expected #rrggbb)", hex))?;
+            stops.push((temp, rgb));
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(ColorScale { stops, mode: self.mode })
+    }
+}
+
+pub fn hex_to_rgb(hex: &str) -> Option<(u8, u8, u8)> {
+    if hex.len() != 7 || !hex.starts_with('#') { return None; }
+    let r = u8::from_str_radix(&hex[1..3], 16).ok()?;
+    let g = u8::from_str_radix(&hex[3..5], 16).ok()?;
+    let b = u8::from_str_radix(&hex[5..7], 16).ok()?;
+    Some((r, g, b))
+}
+
+pub fn to_hex(rgb: (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb.0, rgb.1, rgb.2)
+}
+
+/// Exponent of the brightness curve unless a Busylight is calibrated otherwise
+pub const DEFAULT_GAMMA: f32 = 2.8;
+
+/// Drive levels for `rgb` at a perceived brightness (0..=1). The LEDs are linear, so the
+/// brightness is raised to gamma 2.8 to make equal slider steps look equal.
+pub fn apply_brightness(rgb: (u8, u8, u8), perceived: f32) -> (u8, u8, u8) {
+    apply_brightness_curve(rgb, perceived, DEFAULT_GAMMA)
+}
+
+/// `apply_brightness()` with another exponent, for LEDs that respond differently
+pub fn apply_brightness_curve(rgb: (u8, u8, u8), perceived: f32, gamma: f32) -> (u8, u8, u8) {
+    let power_factor = perceived.clamp(0.0, 1.0).powf(gamma);
+    let scale = |c: u8| (c as f32 * power_factor) as u8;
+    (scale(rgb.0), scale(rgb.1), scale(rgb.2))
+}
+
+/// `rgb` moved a fraction `amount` (0..=1) of the way to the grey of the same luminance
+pub fn desaturate(rgb: (u8, u8, u8), amount: f32) -> (u8, u8, u8) {
+    let luma = (0.2126 * rgb.0 as f32 + 0.7152 * rgb.1 as f32 + 0.0722 * rgb.2 as f32).round() as u8;
+    mix(rgb, (luma, luma, luma), amount, Interpolation::Rgb)
+}
+
+// Share of the saturation a certain chance of precipitation takes away; the rest keeps the hue readable
+const MAX_WASHOUT: f32 = 0.8;
+
+// US EPA AQI categories: (upper bound, color, label)
+const AQI_BANDS: &[(f64, &str, &str)] = &[
+    (50.0, "#00e400", "Good"),
+    (100.0, "#ffff00", "Moderate"),
+    (150.0, "#ff7e00", "Unhealthy for Sensitive Groups"),
+    (200.0, "#ff0000", "Unhealthy"),
+    (300.0, "#8f3f97", "Very Unhealthy"),
+    (f64::MAX, "#7e0023", "Hazardous"),
+];
+
+pub(crate) fn aqi_band(aqi: f64) -> (&'static str, &'static str) {
+    let band = AQI_BANDS.iter().find(|(upper, _, _)| aqi <= *upper).unwrap_or(&AQI_BANDS[AQI_BANDS.len() - 1]);
+    (band.1, band.2)
+}
+
+/// The light's base color for the current conditions, before night and alert rules
+pub fn calculate_weather_color(weather: &WeatherState, config: &AppConfig) -> String {
+    let mut base = basis_color(weather, config);
+    // Dual encoding: hue is temperature, saturation the chance of staying dry
+    if config.precip_saturation {
+        if let Some(rgb) = hex_to_rgb(&base) {
+            let chance = (weather.precip_probability / 100.0).clamp(0.0, 1.0) as f32;
+            base = to_hex(desaturate(rgb, chance * MAX_WASHOUT));
+        }
+    }
+    // Allergy tint: halfway between the weather color and the pollen color
+    if config.pollen.mode == "tint" && config.pollen.is_triggered(weather) {
+        if let (Some(a), Some(b)) = (hex_to_rgb(&base), hex_to_rgb(&config.pollen.color)) {
+            return to_hex(mix(a, b, 0.5, Interpolation::from_name(&config.interpolation)));
+        }
+    }
+    base
+}
+
+fn basis_color(weather: &WeatherState, config: &AppConfig) -> String {
+    // AQI mode uses the standard bands; without a reading it falls back to temperature
+    if config.light_basis == "aqi" {
+        if let Some(aq) = &weather.air_quality {
+            return aqi_band(aq.us_aqi).0.to_string();
+        }
+    }
+
+    // Anomaly mode compares today's high with the normal; without a normal it falls back to temperature
+    if config.light_basis == "anomaly" {
+        if let Some(normal) = weather.normal_high {
+            let high = weather.today_high.unwrap_or(weather.temperature);
+            return crate::themes::anomaly_color(high - normal, Interpolation::from_name(&config.interpolation));
+        }
+    }
+
+    let temp_c = if config.light_basis == "apparent" {
+        weather.apparent_temperature.unwrap_or(weather.temperature)
+    } else {
+        weather.temperature
+    };
+    crate::themes::active(config).color_at(config.banded_temp(temp_c), Interpolation::from_name(&config.interpolation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mix() {
+        let (blue, green) = ((0, 0, 255), (0, 255, 0));
+        for mode in [Interpolation::Rgb, Interpolation::Oklab] {
+            assert_eq!(mix(blue, green, 0.0, mode), blue);
+            assert_eq!(mix(blue, green, 1.0, mode), green);
+        }
+        assert_eq!(mix(blue, green, 0.5, Interpolation::Rgb), (0, 127, 127));
+
+        // The OKLab midpoint stays brighter than the muddy RGB one
+        let luminance = |c: (u8, u8, u8)| 0.2126 * to_linear(c.0) + 0.7152 * to_linear(c.1) + 0.0722 * to_linear(c.2);
+        let perceptual = mix(blue, green, 0.5, Interpolation::Oklab);
+        assert!(luminance(perceptual) > luminance((0, 127, 127)), "{:?}", perceptual);
+
+        // Round trip through OKLab is lossless to the byte
+        assert_eq!(from_oklab(to_oklab((200, 120, 30))), (200, 120, 30));
+        assert_eq!(Interpolation::from_name("hsluv"), Interpolation::Rgb);
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        assert_eq!(hex_to_rgb("#FF8000"), Some((255, 128, 0)));
+        assert_eq!(to_hex((255, 128, 0)), "#ff8000");
+        assert_eq!(hex_to_rgb("FF8000"), None);
+        assert_eq!(hex_to_rgb("#FF80"), None);
+        assert_eq!(hex_to_rgb("#GG8000"), None);
+    }
+
+    #[test]
+    fn test_apply_brightness() {
+        assert_eq!(apply_brightness((255, 128, 10), 1.0), (255, 128, 10));
+        assert_eq!(apply_brightness((255, 128, 10), 0.0), (0, 0, 0));
+        // Half the slider is well under half the power
+        assert_eq!(apply_brightness((255, 255, 255), 0.5), (36, 36, 36));
+        assert_eq!(apply_brightness((255, 255, 255), 1.5), (255, 255, 255));
+    }
+
+    #[test]
+    fn test_desaturate() {
+        assert_eq!(desaturate((255, 0, 0), 0.0), (255, 0, 0));
+        assert_eq!(desaturate((255, 0, 0), 1.0), (54, 54, 54));
+        let washed = desaturate((255, 80, 0), 0.5);
+        // Still leaning red, but the channels have closed in
+        assert!(washed.0 > washed.1 && washed.1 > washed.2);
+        assert!(washed.0 - washed.2 < 255);
+    }
+
+    #[test]
+    fn test_scale_builder() {
+        let scale = ColorScale::builder().stop(10.0, "#ff0080").stop(0.0, "#000000").build().unwrap();
+        assert_eq!(scale.hex_at(5.0), "#7f0040");
+        assert_eq!(scale.hex_at(-40.0), "#000000");
+        assert_eq!(scale.hex_at(40.0), "#ff0080");
+        assert_eq!(scale.hex_at(10.0), "#ff0080");
+
+        let single = ColorScale::builder().stop(0.0, "#123456").build().unwrap();
+        assert_eq!(single.hex_at(99.0), "#123456");
+
+        assert!(ColorScale::builder().build().is_err());
+        assert!(ColorScale::builder().stop(0.0, "red").build().is_err());
+        assert!(ColorScale::builder().stop(f64::NAN, "#000000").build().is_err());
+    }
+
+    // Between any two neighbouring stops, every RGB channel moves one way only and OKLab
+    // lightness never doubles back, across every built-in theme
+    #[test]
+    fn test_interpolation_is_monotonic() {
+        let lightness = |c: (u8, u8, u8)| to_oklab(c)[0];
+        for theme in crate::themes::all(&AppConfig::default()) {
+            for pair in theme.stops.windows(2) {
+                let (start, end) = (&pair[0], &pair[1]);
+                let rgb = ColorScale::builder().stop(start.temp, &start.color).stop(end.temp, &end.color).build().unwrap();
+                let oklab = ColorScale::builder().stop(start.temp, &start.color).stop(end.temp, &end.color)
+                    .interpolation(Interpolation::Oklab).build().unwrap();
+
+                let steps: Vec<f64> = (0..=50).map(|i| start.temp + (end.temp - start.temp) * i as f64 / 50.0).collect();
+                let channels: Vec<(u8, u8, u8)> = steps.iter().map(|&t| rgb.color_at(t)).collect();
+                for (channel, pick) in [("red", 0), ("green", 1), ("blue", 2)] {
+                    let values: Vec<i32> = channels.iter().map(|c| [c.0, c.1, c.2][pick] as i32).collect();
+                    let rising = values.windows(2).all(|w| w[1] >= w[0]);
+                    let falling = values.windows(2).all(|w| w[1] <= w[0]);
+                    assert!(rising || falling, "{} {} wobbles between {} and {}: {:?}", theme.name, channel, start.temp, end.temp, values);
+                }
+
+                let (l_start, l_end) = (lightness(oklab.color_at(start.temp)), lightness(oklab.color_at(end.temp)));
+                let direction = (l_end - l_start).signum();
+                for w in steps.windows(2) {
+                    let delta = lightness(oklab.color_at(w[1])) - lightness(oklab.color_at(w[0]));
+                    // Rounding to bytes leaves a little jitter on near-flat segments
+                    assert!(delta * direction >= -0.005, "{} lightness doubles back near {}", theme.name, w[0]);
+                }
+            }
+        }
+    }
+}
@@ -1,45 +1,1344 @@
 use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use std::fs;
 use std::path::PathBuf;
+use crate::busylight::Calibration;
+use crate::models::{ForecastItem, WeatherState};
+use crate::themes::Theme;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase", default)]
 pub struct AppConfig {
     pub provider: String,
+    /// Tried in order when `provider` fails
+    pub fallback_providers: Vec<String>,
+    /// How often the weather is fetched; see `refresh_interval` for the limits applied
+    pub refresh_interval_minutes: u32,
     pub unit: String,
+    /// Added to every temperature (°C) the provider reports, for a spot that runs warmer or colder
+    /// than the forecast grid point
+    pub temp_offset: f64,
+    /// What the light color follows: "temperature", "apparent" (feels-like, falling back to the
+    /// air temperature), "aqi" or "anomaly" (today's high against the normal for the date)
+    pub light_basis: String,
+    /// Name of the color theme, built-in or one of `custom_themes`; see `themes`
+    pub theme: String,
+    /// Themes imported from the settings window
+    pub custom_themes: Vec<Theme>,
+    /// How colors between two theme stops are blended: "rgb" or "oklab" (perceptual)
+    pub interpolation: String,
+    /// Width of fixed color bands in the display unit (e.g. 5 for 10-15°, 15-20°, ...); 0 follows
+    /// the temperature continuously
+    pub band_size: f64,
+    /// Washes the color out toward grey as the chance of precipitation rises, so the hue still
+    /// reads as temperature and a vivid light means a dry day
+    pub precip_saturation: bool,
+    /// The tray icon: "color" (the weather color), "number" (the rounded temperature on it) or
+    /// "numberOnly" (the temperature underlined in the color)
+    pub tray_icon: String,
+    /// What the weather color is drawn as: "square", "circle", "thermometer" (a bar filled to the
+    /// temperature) or "split" (the color beside the chance of precipitation)
+    pub tray_icon_shape: String,
+    /// Rain, snow or a thunderstorm in the forecast drawn along the bottom of the tray icon
+    pub tray_conditions: bool,
     pub auto_location: bool,
     pub auto_start: bool,
     pub location: String,
+    /// Places to switch `location` between from the tray, e.g. home and the office
+    pub saved_locations: Vec<String>,
     pub api_key: String,
     pub pulse: bool,
     pub pulse_speed: u64,
+    /// Waveform of the precipitation pulse, one of `busylight::Effect::NAMES`
+    pub pulse_effect: String,
     pub max_brightness: u8,
+    /// How long a Busylight takes to blend into a new color, in ms; 0 snaps straight to it
+    pub crossfade_ms: u64,
+    /// How often an idle Busylight gets a keep-alive, in seconds (1-14); the Omega and Alpha
+    /// blank themselves 15 seconds after the last one
+    pub keep_alive_seconds: u64,
     pub sunset_sunrise: bool,
+    /// Where `sunset_sunrise` draws the line: "sunset" or "civil" (night from the end of evening
+    /// civil twilight to the start of morning twilight)
+    pub night_boundary: String,
     pub temp_horizon: String,
     pub precip_horizon: String,
+    /// An hour counts as wet at this probability of precipitation (percent) ...
+    pub precip_probability_threshold: f64,
+    /// ... or at this forecast amount (mm)
+    pub precip_amount_threshold: f64,
+    pub date_overrides: Vec<DateOverride>,
+    pub sound_alerts: SoundAlertConfig,
+    pub jingle: JingleConfig,
+    pub device_locations: Vec<DeviceLocation>,
+    pub busylight_roles: Vec<BusylightRole>,
+    pub busylight_calibrations: Vec<BusylightCalibration>,
+    pub network_profiles: Vec<NetworkProfile>,
+    pub flash_before_precip: bool,
+    pub history_retention_days: u32,
+    pub heat_warning: HeatWarningConfig,
+    pub custom_provider: CustomProviderConfig,
+    pub pws: PwsConfig,
+    pub mqtt: MqttConfig,
+    pub hue: HueConfig,
+    pub wled: WledConfig,
+    pub home_assistant: HomeAssistantConfig,
+    pub aurora: AuroraConfig,
+    pub severe_alerts: SevereAlertConfig,
+    pub high_wind: HighWindConfig,
+    pub thunder: ThunderConfig,
+    pub storm_watch: StormWatchConfig,
+    pub precip_styles: PrecipStylesConfig,
+    pub pollen: PollenConfig,
+    pub frost: FrostConfig,
+    pub fog: FogConfig,
+    pub night_dim: NightDimConfig,
+    pub status_light: StatusLightConfig,
+    pub wake_up: WakeUpConfig,
+    pub quiet_hours: QuietHoursConfig,
+    pub work_hours: WorkHoursConfig,
+    pub manual_presets: Vec<ManualPreset>,
+    pub teams: TeamsConfig,
+    pub slack: SlackConfig,
+    pub calendar: CalendarConfig,
+    pub capture: CaptureConfig,
+}
+
+/// Settings applied while connected to a given Wi-Fi network. `settings` holds any subset of the
+/// config keys (e.g. `{"location": "Wellington", "autoLocation": false, "maxBrightness": 30}`).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NetworkProfile {
+    pub name: String,
+    pub ssid: String,
+    pub settings: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Binds a light ("busylight" or a device plugin name) to its own location instead of the main one.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeviceLocation {
+    pub device: String,
+    pub location: String,
+}
+
+/// What one Busylight shows when several are connected. `device` is its `DeviceInfo::key()`
+/// (serial number, or USB path without one). `role` is "weather" (every light rule, the
+/// default), "temperature" (the steady weather color only), "precipitation" (the rain or snow
+/// color, brighter as it gets likelier) or "tomorrow" (the color of tomorrow's high).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BusylightRole {
+    pub device: String,
+    pub role: String,
+}
+
+/// Gamma and white balance for one Busylight (`device` is its `DeviceInfo::key()`), e.g.
+/// `{"device": "1234", "gamma": 2.2, "green": 0.8}`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BusylightCalibration {
+    pub device: String,
+    #[serde(flatten)]
+    pub calibration: Calibration,
+}
+
+/// Sound files played for selected events; an empty path leaves that event silent.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SoundAlertConfig {
+    pub enabled: bool,
+    pub rain_soon: String,
+    pub severe_alert: String,
+    pub device_disconnect: String,
+}
+
+/// A Busylight ringtone played alongside `sound_alerts` for the same events. `tone` is one of
+/// `busylight::Ringtone::NAMES`, `volume` 1-7.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JingleConfig {
+    pub enabled: bool,
+    pub tone: String,
+    pub volume: u8,
+    pub duration_ms: u64,
+    pub on_severe_alert: bool,
+    pub on_precip_onset: bool,
+}
+
+impl Default for JingleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tone: "funky".to_string(),
+            volume: 3,
+            duration_ms: 3000,
+            on_severe_alert: true,
+            on_precip_onset: true,
+        }
+    }
+}
+
+/// Philips Hue output through the bridge's local API. `targets` are "light:<id>" or "group:<id>"
+/// (a room or zone); `app_key` is handed out by the bridge when pairing with its link button.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HueConfig {
+    pub enabled: bool,
+    /// IP address or host name of the bridge
+    pub bridge: String,
+    pub app_key: String,
+    pub targets: Vec<String>,
+}
+
+impl HueConfig {
+    /// The bridge address as a URL; plain HTTP unless a scheme is given
+    pub fn base_url(&self) -> String {
+        let bridge = self.bridge.trim().trim_end_matches('/');
+        if bridge.contains("://") { bridge.to_string() } else { format!("http://{}", bridge) }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.enabled && !self.bridge.trim().is_empty() && !self.app_key.is_empty() && !self.targets.is_empty()
+    }
+}
+
+/// Microsoft Teams presence. `client_id` is an app registration in Microsoft Entra with public
+/// client flows allowed and the delegated Presence.Read permission; `refresh_token` comes from
+/// signing in with a device code. During a call or meeting the light shows `busy_color`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TeamsConfig {
+    pub enabled: bool,
+    pub client_id: String,
+    /// "organizations" (any work or school account), "common" or a tenant id
+    pub tenant: String,
+    pub refresh_token: String,
+    pub busy_color: String,
+    pub poll_seconds: u64,
+}
+
+impl Default for TeamsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            client_id: String::new(),
+            tenant: "organizations".to_string(),
+            refresh_token: String::new(),
+            busy_color: "#FF0000".to_string(),
+            poll_seconds: 30,
+        }
+    }
+}
+
+impl TeamsConfig {
+    pub fn is_ready(&self) -> bool {
+        self.enabled && !self.client_id.trim().is_empty() && !self.refresh_token.is_empty()
+    }
+
+    /// An OAuth endpoint ("devicecode" or "token") of the sign-in tenant
+    pub fn login_url(&self, endpoint: &str) -> String {
+        let tenant = if self.tenant.trim().is_empty() { "organizations" } else { self.tenant.trim() };
+        format!("https://login.microsoftonline.com/{}/oauth2/v2.0/{}", tenant, endpoint)
+    }
+
+    pub fn poll_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.poll_seconds.clamp(10, 600))
+    }
+}
+
+/// Slack huddles and Do Not Disturb. `token` is a user token (`xoxp-`) with the
+/// users.profile:read and dnd:read scopes. While busy the light shows `busy_color`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SlackConfig {
+    pub enabled: bool,
+    pub token: String,
+    pub on_huddle: bool,
+    pub on_dnd: bool,
+    pub busy_color: String,
+    pub poll_seconds: u64,
+}
+
+impl Default for SlackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token: String::new(),
+            on_huddle: true,
+            on_dnd: true,
+            busy_color: "#FF0000".to_string(),
+            poll_seconds: 30,
+        }
+    }
+}
+
+impl SlackConfig {
+    pub fn is_ready(&self) -> bool {
+        self.enabled && !self.token.trim().is_empty() && (self.on_huddle || self.on_dnd)
+    }
+
+    pub fn poll_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.poll_seconds.clamp(10, 600))
+    }
+}
+
+/// A calendar feed (an ICS URL; `webcal://` works too). During events marked busy the light
+/// shows `meeting_color`. Free and cancelled events don't count, nor do all-day ones unless
+/// `include_all_day`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CalendarConfig {
+    pub enabled: bool,
+    pub url: String,
+    pub meeting_color: String,
+    pub include_all_day: bool,
+    pub refresh_minutes: u32,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            meeting_color: "#FF0000".to_string(),
+            include_all_day: false,
+            refresh_minutes: 15,
+        }
+    }
+}
+
+impl CalendarConfig {
+    pub fn is_ready(&self) -> bool {
+        self.enabled && !self.url.trim().is_empty()
+    }
+
+    /// The feed as an HTTPS URL
+    pub fn feed_url(&self) -> String {
+        let url = self.url.trim();
+        match url.strip_prefix("webcal://") {
+            Some(rest) => format!("https://{}", rest),
+            None => url.to_string(),
+        }
+    }
+
+    pub fn refresh_interval(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.refresh_minutes.clamp(5, 1440) as i64)
+    }
+}
+
+/// Any app using the camera or microphone, as read from the operating system; no accounts needed.
+/// While either watched device is in use the light shows `busy_color`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CaptureConfig {
+    pub enabled: bool,
+    pub camera: bool,
+    pub microphone: bool,
+    pub busy_color: String,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            camera: true,
+            microphone: true,
+            busy_color: "#FF0000".to_string(),
+        }
+    }
+}
+
+impl CaptureConfig {
+    pub fn is_ready(&self) -> bool {
+        self.enabled && (self.camera || self.microphone)
+    }
+}
+
+/// A WLED LED strip. `protocol` is "json" (the HTTP JSON API, which can breathe for a pulse)
+/// or "udp" (DRGB realtime). With `forecast_hours` above 0 the ordinary weather color is replaced
+/// by that many hours of forecast colors spread along the strip, now at the first LED.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WledConfig {
+    pub enabled: bool,
+    /// IP address or host name of the controller
+    pub host: String,
+    pub protocol: String,
+    pub forecast_hours: usize,
+}
+
+impl Default for WledConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            protocol: "json".to_string(),
+            forecast_hours: 0,
+        }
+    }
+}
+
+impl WledConfig {
+    /// The controller's host without a scheme or trailing slash
+    pub fn host_name(&self) -> &str {
+        let host = self.host.trim().trim_end_matches('/');
+        host.split_once("://").map(|(_, rest)| rest).unwrap_or(host)
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.host_name())
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.enabled && !self.host_name().is_empty()
+    }
+}
+
+/// Settings for `provider: "pws"`. `source` is "wunderground" (uses `station_id` and `api_key`)
+/// or "ecowitt" (polls `gateway_url` on the local network).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PwsConfig {
+    pub source: String,
+    pub station_id: String,
+    pub gateway_url: String,
+}
+
+impl Default for PwsConfig {
+    fn default() -> Self {
+        Self {
+            source: "wunderground".to_string(),
+            station_id: String::new(),
+            gateway_url: String::new(),
+        }
+    }
+}
+
+/// Settings for `provider: "homeassistant"`. `token` is a long-lived access token from the HA profile page.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HomeAssistantConfig {
+    pub url: String,
+    pub token: String,
+    pub entity_id: String,
+}
+
+impl Default for HomeAssistantConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://homeassistant.local:8123".to_string(),
+            token: String::new(),
+            entity_id: "weather.home".to_string(),
+        }
+    }
+}
+
+/// Settings for `provider: "mqtt"`. Payloads are plain values ("21.4", "on") or JSON, in which case
+/// the matching `*_path` picks the value out (same path syntax as `customProvider`).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub temperature_topic: String,
+    pub temperature_path: String,
+    /// "C", "F" or "K"
+    pub temperature_unit: String,
+    pub precipitation_topic: String,
+    pub precipitation_path: String,
+    /// "percent", "fraction" (0-1), "mm" or "bool"
+    pub precipitation_unit: String,
+    pub humidity_topic: String,
+    pub humidity_path: String,
+    /// Readings older than this are treated as missing
+    pub max_age_minutes: u32,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 1883,
+            username: String::new(),
+            password: String::new(),
+            temperature_topic: String::new(),
+            temperature_path: String::new(),
+            temperature_unit: "C".to_string(),
+            precipitation_topic: String::new(),
+            precipitation_path: String::new(),
+            precipitation_unit: "bool".to_string(),
+            humidity_topic: String::new(),
+            humidity_path: String::new(),
+            max_age_minutes: 60,
+        }
+    }
+}
+
+/// Settings for `provider: "custom"`. `url` may contain `{lat}`, `{lon}` and `{apiKey}`;
+/// paths are JSON pointers ("/current/temp") or dotted ("current.temp").
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CustomProviderConfig {
+    pub url: String,
+    pub temperature_path: String,
+    /// "C", "F" or "K"
+    pub temperature_unit: String,
+    pub precipitation_path: String,
+    /// "percent", "fraction" (0-1), "mm" or "bool"
+    pub precipitation_unit: String,
+    pub humidity_path: String,
+}
+
+impl Default for CustomProviderConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            temperature_path: String::new(),
+            temperature_unit: "C".to_string(),
+            precipitation_path: String::new(),
+            precipitation_unit: "percent".to_string(),
+            humidity_path: String::new(),
+        }
+    }
+}
+
+/// Switches the light to an urgent pattern when the temperature named by `basis` stays at or above
+/// `threshold` (°C) for `hours` consecutive forecast hours within the next day.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HeatWarningConfig {
+    pub enabled: bool,
+    pub threshold: f64,
+    /// "apparent" (the provider's feels-like), "heat_index" (NWS heat index from temperature and
+    /// humidity) or "air"; the first two fall back to the air temperature when the data is missing
+    pub basis: String,
+    pub hours: u32,
+    /// Hex colors alternated every `interval_ms`
+    pub colors: Vec<String>,
+    pub interval_ms: u64,
+}
+
+impl Default for HeatWarningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 35.0,
+            basis: "apparent".to_string(),
+            hours: 3,
+            colors: vec!["#FF0000".to_string(), "#FFFFFF".to_string()],
+            interval_ms: 400,
+        }
+    }
+}
+
+/// Pulses `color` instead of the temperature color while an official warning is in effect.
+/// Warnings come from the provider (Environment Canada) or, for the others, the NWS alerts feed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SevereAlertConfig {
+    pub enabled: bool,
+    pub color: String,
+    pub pulse_speed_ms: u64,
+    /// One of `busylight::Effect::NAMES`; "strobe" is the hardest to miss
+    pub effect: String,
+}
+
+impl Default for SevereAlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: "#FF00FF".to_string(),
+            pulse_speed_ms: 800,
+            effect: "breathe".to_string(),
+        }
+    }
+}
+
+impl SevereAlertConfig {
+    pub fn is_active(&self, weather: &WeatherState) -> bool {
+        self.enabled && !weather.alerts.is_empty()
+    }
+}
+
+/// Pulses `color` while gusts (or the sustained wind, when a provider has no gusts) reach
+/// `gust_threshold` km/h.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HighWindConfig {
+    pub enabled: bool,
+    pub gust_threshold: f64,
+    pub color: String,
+    pub pulse_speed_ms: u64,
+}
+
+impl Default for HighWindConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            gust_threshold: 60.0,
+            color: "#00FFFF".to_string(),
+            pulse_speed_ms: 600,
+        }
+    }
+}
+
+impl HighWindConfig {
+    pub fn is_triggered(&self, weather: &WeatherState) -> bool {
+        self.enabled && weather.wind_gust.or(weather.wind_speed).is_some_and(|w| w >= self.gust_threshold)
+    }
+}
+
+/// Flashes through `colors` instead of the ordinary rain pulse while a thunderstorm is forecast
+/// within the precipitation horizon.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ThunderConfig {
+    pub enabled: bool,
+    pub colors: Vec<String>,
+    pub interval_ms: u64,
+}
+
+impl Default for ThunderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            colors: vec!["#FFFF00".to_string(), "#FFFFFF".to_string()],
+            interval_ms: 250,
+        }
+    }
+}
+
+impl ThunderConfig {
+    pub fn is_triggered(&self, weather: &WeatherState) -> bool {
+        self.enabled && weather.has_thunder
+    }
+}
+
+/// Pulses `color` while the 3-hour pressure tendency falls by `drop_threshold` hPa or more, the
+/// classic sign of an approaching storm. Needs a couple of hours of recorded history first.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StormWatchConfig {
+    pub enabled: bool,
+    pub drop_threshold: f64,
+    pub color: String,
+    pub pulse_speed_ms: u64,
+}
+
+impl Default for StormWatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            drop_threshold: 4.0,
+            color: "#8A2BE2".to_string(),
+            pulse_speed_ms: 1500,
+        }
+    }
+}
+
+impl StormWatchConfig {
+    pub fn is_triggered(&self, weather: &WeatherState) -> bool {
+        self.enabled && weather.pressure_tendency.is_some_and(|t| t <= -self.drop_threshold)
+    }
+}
+
+/// How the precipitation pulse looks for one type. An empty `color` keeps the weather color and a
+/// `pulse_speed_ms` of 0 keeps the probability-scaled speed.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PrecipStyle {
+    pub color: String,
+    pub pulse_speed_ms: u64,
+}
+
+/// Separate pulse styles for rain and snow, used when `enabled`. Precipitation of unknown type
+/// counts as rain.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PrecipStylesConfig {
+    pub enabled: bool,
+    pub rain: PrecipStyle,
+    pub snow: PrecipStyle,
+}
+
+impl Default for PrecipStylesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rain: PrecipStyle { color: "#0050FF".to_string(), pulse_speed_ms: 0 },
+            snow: PrecipStyle { color: "#FFFFFF".to_string(), pulse_speed_ms: 3000 },
+        }
+    }
+}
+
+impl PrecipStylesConfig {
+    pub fn style_for(&self, weather: &WeatherState) -> Option<&PrecipStyle> {
+        if !self.enabled || !weather.has_precipitation {
+            return None;
+        }
+        Some(if weather.precip_type.as_deref() == Some("Snow") { &self.snow } else { &self.rain })
+    }
+}
+
+/// Allergy mode: once today's peak count of a pollen type reaches its threshold (grains/m³), the
+/// light either blends toward `color` ("tint") or pulses it ("pulse"). Defaults are roughly the
+/// "high" bands used by European pollen services.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PollenConfig {
+    pub enabled: bool,
+    pub mode: String,
+    pub birch_threshold: f64,
+    pub grass_threshold: f64,
+    pub ragweed_threshold: f64,
+    pub color: String,
+    pub pulse_speed_ms: u64,
+}
+
+impl Default for PollenConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: "tint".to_string(),
+            birch_threshold: 100.0,
+            grass_threshold: 50.0,
+            ragweed_threshold: 20.0,
+            color: "#FFD700".to_string(),
+            pulse_speed_ms: 2000,
+        }
+    }
+}
+
+impl PollenConfig {
+    /// Names of the pollen types at or above their threshold
+    pub fn high_counts(&self, weather: &WeatherState) -> Vec<&'static str> {
+        let Some(pollen) = weather.pollen.as_ref().filter(|_| self.enabled) else {
+            return Vec::new();
+        };
+        [("birch", pollen.birch, self.birch_threshold), ("grass", pollen.grass, self.grass_threshold), ("ragweed", pollen.ragweed, self.ragweed_threshold)]
+            .into_iter()
+            .filter(|(_, count, threshold)| count.is_some_and(|c| c >= *threshold))
+            .map(|(name, _, _)| name)
+            .collect()
+    }
+
+    pub fn is_triggered(&self, weather: &WeatherState) -> bool {
+        !self.high_counts(weather).is_empty()
+    }
+
+    pub fn pulses(&self, weather: &WeatherState) -> bool {
+        self.mode == "pulse" && self.is_triggered(weather)
+    }
+}
+
+/// Overnight frost: `WeatherState.frost_warning` is set whenever the forecast low within the next
+/// `window_hours` drops below `threshold` °C. With `enabled` the light also flashes through `colors`
+/// between `evening_start_hour` and `evening_end_hour` (local time), while there's still time to
+/// cover plants or the car.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FrostConfig {
+    pub enabled: bool,
+    pub threshold: f64,
+    pub window_hours: u32,
+    pub colors: Vec<String>,
+    pub interval_ms: u64,
+    pub evening_start_hour: u32,
+    pub evening_end_hour: u32,
+}
+
+impl Default for FrostConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 0.0,
+            window_hours: 12,
+            colors: vec!["#A0E8FF".to_string(), "#FFFFFF".to_string()],
+            interval_ms: 700,
+            evening_start_hour: 17,
+            evening_end_hour: 23,
+        }
+    }
+}
+
+impl FrostConfig {
+    /// Lowest forecast temperature from the current hour to `window_hours` ahead
+    pub fn forecast_low(&self, forecast: &[ForecastItem], now: DateTime<Utc>) -> Option<f64> {
+        let window_end = now + chrono::Duration::hours(self.window_hours as i64);
+        forecast.iter()
+            .filter(|item| item.time + chrono::Duration::hours(1) > now && item.time < window_end)
+            .map(|item| item.temp)
+            .reduce(f64::min)
+    }
+
+    pub fn expects_frost(&self, forecast: &[ForecastItem], now: DateTime<Utc>) -> bool {
+        self.forecast_low(forecast, now).is_some_and(|low| low < self.threshold)
+    }
+
+    /// Whether the warning pattern should show at `local_hour`
+    pub fn is_triggered(&self, weather: &WeatherState, local_hour: u32) -> bool {
+        self.enabled && weather.frost_warning && (self.evening_start_hour..self.evening_end_hour).contains(&local_hour)
+    }
+}
+
+/// Fog or visibility below `visibility_km` now or within the next `hours` sets
+/// `WeatherState.has_fog`. With `enabled`, a steady light (no precipitation pulse or warning
+/// showing) turns to a dim `color` at `brightness` percent instead of the temperature color.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FogConfig {
+    pub enabled: bool,
+    pub visibility_km: f64,
+    pub hours: u32,
+    pub color: String,
+    pub brightness: u8,
+}
+
+impl Default for FogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            visibility_km: 1.0,
+            hours: 3,
+            color: "#C8C8C8".to_string(),
+            brightness: 25,
+        }
+    }
+}
+
+impl FogConfig {
+    /// Whether a visibility reading (km) counts as fog
+    pub fn is_low(&self, visibility: Option<f64>) -> bool {
+        visibility.is_some_and(|v| v < self.visibility_km)
+    }
+
+    pub fn is_triggered(&self, weather: &WeatherState) -> bool {
+        self.enabled && weather.has_fog
+    }
+}
+
+/// What the light shows when there's no weather to show, so a broken setup can be told apart
+/// from a dark night. Off by default, which leaves the light off on errors as before.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StatusLightConfig {
+    pub enabled: bool,
+    /// Fetch failed and there is no recent cached weather
+    pub error_color: String,
+    /// No location or a missing API key
+    pub setup_color: String,
+    pub brightness: u8,
+    pub pulse_speed_ms: u64,
+    /// Until the first weather arrives (setup incomplete, first fetch under way or failed), breathe
+    /// a very dim white so a new user sees the device working. `enabled`'s colors win.
+    pub standby: bool,
+    pub standby_brightness: u8,
+    pub standby_pulse_speed_ms: u64,
+}
+
+impl Default for StatusLightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            error_color: "#FFA000".to_string(),
+            setup_color: "#FFFFFF".to_string(),
+            brightness: 30,
+            pulse_speed_ms: 3000,
+            standby: false,
+            standby_brightness: 8,
+            standby_pulse_speed_ms: 6000,
+        }
+    }
+}
+
+/// A wake-up light: over the `minutes` before `time` ("HH:MM", local), or before sunrise when
+/// `time` is empty, the light rises from a glimmer of `color` (deep red) to the day's weather
+/// color. It outranks night mode; snooze and manual mode still win.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WakeUpConfig {
+    pub enabled: bool,
+    pub time: String,
+    pub minutes: u32,
+    pub color: String,
+}
+
+impl Default for WakeUpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            time: "07:00".to_string(),
+            minutes: 30,
+            color: "#8B0000".to_string(),
+        }
+    }
+}
+
+impl WakeUpConfig {
+    /// Start and end of the ramp under way at `now`, if any
+    pub fn window(&self, sunrise: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if !self.enabled || self.minutes == 0 {
+            return None;
+        }
+        let ends: Vec<DateTime<Utc>> = if self.time.trim().is_empty() {
+            sunrise.into_iter().collect()
+        } else {
+            // Tomorrow's too, for a ramp that starts before midnight
+            let time = NaiveTime::parse_from_str(self.time.trim(), "%H:%M").ok()?;
+            let today = now.with_timezone(&Local).date_naive();
+            [Some(today), today.succ_opt()].into_iter().flatten()
+                .filter_map(|date| date.and_time(time).and_local_timezone(Local).earliest())
+                .map(|end| end.with_timezone(&Utc))
+                .collect()
+        };
+        let ramp = chrono::Duration::minutes(self.minutes as i64);
+        ends.into_iter().find(|end| *end - ramp <= now && now < *end).map(|end| (end - ramp, end))
+    }
+}
+
+/// Quiet hours on the clock, whatever the weather and apart from `sunset_sunrise`. A quiet period
+/// starts at `start` ("HH:MM") on each of `days` ("mon" to "sun") and runs until `end`, the next
+/// morning when `end` is earlier. The light is off meanwhile, or dimmed to `brightness` percent
+/// when that's above 0.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QuietHoursConfig {
+    pub enabled: bool,
+    pub days: Vec<String>,
+    pub start: String,
+    pub end: String,
+    pub brightness: u8,
+}
+
+impl Default for QuietHoursConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            days: ["mon", "tue", "wed", "thu", "fri", "sat", "sun"].iter().map(|d| d.to_string()).collect(),
+            start: "22:00".to_string(),
+            end: "07:00".to_string(),
+            brightness: 0,
+        }
+    }
+}
+
+impl QuietHoursConfig {
+    /// Whether `now` (local time) is inside a quiet period
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        self.enabled && in_schedule(&self.days, &self.start, &self.end, now)
+    }
+}
+
+/// For offices: the light only runs on `days` ("mon" to "sun") from `start` to `end` ("HH:MM").
+/// Outside those hours it stays off, the weather is fetched hourly at most and the presence
+/// sources (Teams, Slack, the calendar, the camera and microphone) aren't checked.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorkHoursConfig {
+    pub enabled: bool,
+    pub days: Vec<String>,
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for WorkHoursConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            days: ["mon", "tue", "wed", "thu", "fri"].iter().map(|d| d.to_string()).collect(),
+            start: "08:00".to_string(),
+            end: "18:00".to_string(),
+        }
+    }
+}
+
+impl WorkHoursConfig {
+    /// Whether `now` (local time) is outside the working hours
+    pub fn is_off_hours(&self, now: NaiveDateTime) -> bool {
+        self.enabled && !in_schedule(&self.days, &self.start, &self.end, now)
+    }
+}
+
+// Whether `now` falls in a period starting at `start` on one of `days` and running to `end`, which
+// is the next day when it isn't after `start` (equal times make a whole day)
+fn in_schedule(days: &[String], start: &str, end: &str, now: NaiveDateTime) -> bool {
+    let (Ok(start), Ok(end)) = (NaiveTime::parse_from_str(start.trim(), "%H:%M"), NaiveTime::parse_from_str(end.trim(), "%H:%M")) else {
+        return false;
+    };
+    // A period that started yesterday may still be running
+    let today = now.date();
+    [Some(today), today.pred_opt()].into_iter().flatten().any(|date| {
+        let day = date.weekday().to_string().to_lowercase();
+        if !days.iter().any(|d| d.trim().eq_ignore_ascii_case(&day)) {
+            return false;
+        }
+        let from = date.and_time(start);
+        let until = if end > start { date.and_time(end) } else { date.and_time(end) + chrono::Duration::days(1) };
+        from <= now && now < until
+    })
+}
+
+/// With `sunset_sunrise`, keeps the light on at night as a nightlight instead of turning it off:
+/// every light rule still applies, capped at `brightness` percent. A `tint` color (e.g. "#FF9329",
+/// candlelight) pulls the weather color halfway toward it; empty leaves it as is.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NightDimConfig {
+    pub enabled: bool,
+    pub brightness: u8,
+    pub tint: String,
+}
+
+impl Default for NightDimConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            brightness: 10,
+            tint: String::new(),
+        }
+    }
+}
+
+/// Pulses `color` at night while NOAA's Kp index is at or above `kp_threshold`. Roughly, Kp 5
+/// reaches 60° latitude, Kp 7 about 50°.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuroraConfig {
+    pub enabled: bool,
+    pub kp_threshold: f64,
+    pub color: String,
+    pub pulse_speed_ms: u64,
+}
+
+impl Default for AuroraConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kp_threshold: 5.0,
+            color: "#00FF7F".to_string(),
+            pulse_speed_ms: 3000,
+        }
+    }
+}
+
+impl AuroraConfig {
+    pub fn is_triggered(&self, weather: &WeatherState) -> bool {
+        self.enabled && weather.is_night && weather.kp_index.is_some_and(|kp| kp >= self.kp_threshold)
+    }
+}
+
+impl HeatWarningConfig {
+    /// The temperature `basis` compares against `threshold` for one forecast hour
+    pub fn reading(&self, item: &ForecastItem) -> f64 {
+        match self.basis.as_str() {
+            "air" => item.temp,
+            "heat_index" => item.humidity.map_or(item.temp, |rh| crate::weather::heat_index(item.temp, rh)),
+            _ => item.apparent_temp.unwrap_or(item.temp),
+        }
+    }
+
+    pub fn is_triggered(&self, forecast: &[ForecastItem], now: DateTime<Utc>) -> bool {
+        if !self.enabled || self.hours == 0 {
+            return false;
+        }
+
+        let window_end = now + chrono::Duration::hours(24);
+        let mut run_hours = 0.0;
+        for (i, item) in forecast.iter().enumerate() {
+            if item.time >= window_end {
+                break;
+            }
+            // Blocks can span several hours (OpenWeatherMap uses 3h steps)
+            let block_hours = forecast.get(i + 1)
+                .map(|next| (next.time - item.time).num_minutes() as f64 / 60.0)
+                .unwrap_or(1.0);
+
+            if self.reading(item) >= self.threshold {
+                run_hours += block_hours;
+                if run_hours >= self.hours as f64 {
+                    return true;
+                }
+            } else {
+                run_hours = 0.0;
+            }
+        }
+        false
+    }
+}
+
+/// A named manual-mode scene for the Busylights ("Focus", "In a call"), applied from the tray or
+/// the diagnostics view: `color` at `brightness` percent, steady or pulsing with `effect`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ManualPreset {
+    pub name: String,
+    pub color: String,
+    pub brightness: u8,
+    pub pulse: bool,
+    pub pulse_speed_ms: u64,
+    pub effect: String,
+}
+
+impl Default for ManualPreset {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            color: "#FF0000".to_string(),
+            brightness: 100,
+            pulse: false,
+            pulse_speed_ms: 1500,
+            effect: "breathe".to_string(),
+        }
+    }
+}
+
+impl ManualPreset {
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self { name: "Focus".to_string(), color: "#8000FF".to_string(), brightness: 60, ..Default::default() },
+            Self { name: "In a call".to_string(), ..Default::default() },
+            Self { name: "Back in 5".to_string(), color: "#FFBF00".to_string(), pulse: true, pulse_speed_ms: 3000, ..Default::default() },
+        ]
+    }
+}
+
+/// A recurring calendar rule that replaces the weather color on matching days.
+/// `start`/`end` are "MM-DD"; an empty `end` means a single day, and ranges may wrap the new year.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DateOverride {
+    pub name: String,
+    pub start: String,
+    pub end: String,
+    /// Hex colors; more than one alternates every `interval_ms`
+    pub colors: Vec<String>,
+    pub interval_ms: u64,
+}
+
+fn parse_month_day(s: &str) -> Option<(u32, u32)> {
+    let (m, d) = s.trim().split_once('-')?;
+    let m: u32 = m.parse().ok()?;
+    let d: u32 = d.parse().ok()?;
+    if (1..=12).contains(&m) && (1..=31).contains(&d) { Some((m, d)) } else { None }
+}
+
+impl DateOverride {
+    pub fn is_active(&self, date: NaiveDate) -> bool {
+        let Some(start) = parse_month_day(&self.start) else { return false; };
+        let end = if self.end.trim().is_empty() { Some(start) } else { parse_month_day(&self.end) };
+        let Some(end) = end else { return false; };
+        let today = (date.month(), date.day());
+
+        if start <= end {
+            start <= today && today <= end
+        } else {
+            // Wraps the new year, e.g. 12-31 -> 01-01
+            today >= start || today <= end
+        }
+    }
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             provider: "open-meteo".to_string(),
+            fallback_providers: Vec::new(),
+            refresh_interval_minutes: 15,
             unit: "C".to_string(),
+            temp_offset: 0.0,
+            light_basis: "temperature".to_string(),
+            theme: crate::themes::DEFAULT_THEME.to_string(),
+            custom_themes: Vec::new(),
+            interpolation: "rgb".to_string(),
+            band_size: 0.0,
+            precip_saturation: false,
+            tray_icon: "color".to_string(),
+            tray_icon_shape: "square".to_string(),
+            tray_conditions: true,
             auto_location: true,
             auto_start: false,
             location: "".to_string(),
+            saved_locations: Vec::new(),
             api_key: "".to_string(),
             pulse: true,
             pulse_speed: 5000,
+            pulse_effect: "breathe".to_string(),
             max_brightness: 60,
+            crossfade_ms: 2000,
+            keep_alive_seconds: 5,
             sunset_sunrise: false,
+            night_boundary: "sunset".to_string(),
             temp_horizon: "current".to_string(),
             precip_horizon: "immediate".to_string(),
+            precip_probability_threshold: 35.0,
+            precip_amount_threshold: 0.5,
+            date_overrides: Vec::new(),
+            sound_alerts: SoundAlertConfig::default(),
+            jingle: JingleConfig::default(),
+            device_locations: Vec::new(),
+            busylight_roles: Vec::new(),
+            busylight_calibrations: Vec::new(),
+            network_profiles: Vec::new(),
+            flash_before_precip: false,
+            history_retention_days: 30,
+            heat_warning: HeatWarningConfig::default(),
+            custom_provider: CustomProviderConfig::default(),
+            pws: PwsConfig::default(),
+            mqtt: MqttConfig::default(),
+            hue: HueConfig::default(),
+            wled: WledConfig::default(),
+            home_assistant: HomeAssistantConfig::default(),
+            aurora: AuroraConfig::default(),
+            severe_alerts: SevereAlertConfig::default(),
+            high_wind: HighWindConfig::default(),
+            thunder: ThunderConfig::default(),
+            storm_watch: StormWatchConfig::default(),
+            precip_styles: PrecipStylesConfig::default(),
+            pollen: PollenConfig::default(),
+            frost: FrostConfig::default(),
+            fog: FogConfig::default(),
+            night_dim: NightDimConfig::default(),
+            status_light: StatusLightConfig::default(),
+            wake_up: WakeUpConfig::default(),
+            quiet_hours: QuietHoursConfig::default(),
+            work_hours: WorkHoursConfig::default(),
+            manual_presets: ManualPreset::defaults(),
+            teams: TeamsConfig::default(),
+            slack: SlackConfig::default(),
+            calendar: CalendarConfig::default(),
+            capture: CaptureConfig::default(),
         }
     }
 }
 
+const MAX_REFRESH_MINUTES: u32 = 180;
+
 // Helper functions removed as rename_all handles this natively
 impl AppConfig {
+    /// Whether no provider in the chain can fetch anything without `api_key`
+    pub fn requires_api_key(&self) -> bool {
+        let registry = crate::weather::ProviderRegistry::builtin();
+        self.provider_chain().iter().all(|id| registry.get(id).is_some_and(|p| p.requires_api_key(self)))
+    }
+
+    /// Shifts a fetched state's temperatures, including the forecast, by `temp_offset`. Dew points
+    /// are left alone: they measure moisture, not the local warmth.
+    pub fn apply_temp_offset(&self, weather: &mut WeatherState) {
+        let offset = self.temp_offset;
+        if offset == 0.0 {
+            return;
+        }
+        weather.temperature += offset;
+        for t in [&mut weather.apparent_temperature, &mut weather.today_high, &mut weather.today_low].into_iter().flatten() {
+            *t += offset;
+        }
+        for item in &mut weather.debug_forecast {
+            item.temp += offset;
+            if let Some(t) = item.apparent_temp.as_mut() {
+                *t += offset;
+            }
+        }
+    }
+
+    /// A °C reading in the display `unit`
+    pub fn display_temp(&self, celsius: f64) -> f64 {
+        if self.unit == "F" { celsius * 9.0 / 5.0 + 32.0 } else { celsius }
+    }
+
+    /// `celsius` moved to the middle of its `band_size` band, so every reading in the band gets
+    /// one color. Bands are counted in the display unit.
+    pub fn banded_temp(&self, celsius: f64) -> f64 {
+        if self.band_size <= 0.0 {
+            return celsius;
+        }
+        let shown = self.display_temp(celsius);
+        let middle = (shown / self.band_size).floor() * self.band_size + self.band_size / 2.0;
+        if self.unit == "F" { (middle - 32.0) * 5.0 / 9.0 } else { middle }
+    }
+
+    /// Whether a forecast period with this probability (percent) and amount (mm) counts as precipitation
+    pub fn is_wet(&self, probability: f64, amount: f64) -> bool {
+        probability >= self.precip_probability_threshold || amount >= self.precip_amount_threshold
+    }
+
+    /// `provider` followed by `fallback_providers`, without repeats
+    pub fn provider_chain(&self) -> Vec<&str> {
+        let mut chain = vec![self.provider.as_str()];
+        for id in &self.fallback_providers {
+            let id = id.trim();
+            if !id.is_empty() && !chain.contains(&id) {
+                chain.push(id);
+            }
+        }
+        chain
+    }
+
+    /// `refresh_interval_minutes`, kept above the strictest quota in the provider chain and at most 3 hours
+    pub fn refresh_interval(&self) -> std::time::Duration {
+        let registry = crate::weather::ProviderRegistry::builtin();
+        let min = self.provider_chain().iter()
+            // Plugins and unknown ids get the same floor as the keyless APIs
+            .map(|id| registry.get(id).map(|p| p.info().min_refresh_minutes).unwrap_or(5))
+            .max()
+            .unwrap_or(5);
+        let minutes = self.refresh_interval_minutes.clamp(min, MAX_REFRESH_MINUTES.max(min));
+        std::time::Duration::from_secs(minutes as u64 * 60)
+    }
+
+    pub fn profile_for_network(&self, ssid: Option<&str>) -> Option<&NetworkProfile> {
+        let ssid = ssid?;
+        self.network_profiles.iter().find(|p| p.ssid == ssid)
+    }
+
+    /// This config with the matching network profile's settings layered on top
+    pub fn for_network(&self, ssid: Option<&str>) -> AppConfig {
+        let Some(profile) = self.profile_for_network(ssid) else { return self.clone(); };
+        let Ok(mut value) = serde_json::to_value(self) else { return self.clone(); };
+
+        if let Some(obj) = value.as_object_mut() {
+            for (key, v) in &profile.settings {
+                // Profiles can't nest further profiles
+                if key != "networkProfiles" {
+                    obj.insert(key.clone(), v.clone());
+                }
+            }
+        }
+        serde_json::from_value(value).unwrap_or_else(|_| self.clone())
+    }
+
+    /// Location bound to `device`, if it has one
+    pub fn location_for_device(&self, device: &str) -> Option<&str> {
+        self.device_locations.iter()
+            .find(|d| d.device == device && !d.location.trim().is_empty())
+            .map(|d| d.location.as_str())
+    }
+
+    /// Role of the Busylight with this key; "weather" unless one is assigned
+    pub fn busylight_role(&self, key: Option<&str>) -> &str {
+        key.and_then(|key| self.busylight_roles.iter().find(|r| r.device == key))
+            .map(|r| r.role.as_str())
+            .filter(|role| !role.is_empty())
+            .unwrap_or("weather")
+    }
+
+    /// `busylightCalibrations` by device key, for `BusylightManager::set_calibrations()`
+    pub fn busylight_calibrations(&self) -> Vec<(String, Calibration)> {
+        self.busylight_calibrations.iter().map(|c| (c.device.clone(), c.calibration)).collect()
+    }
+
+    /// Whether any light needs tomorrow's forecast
+    pub fn wants_tomorrow(&self) -> bool {
+        self.busylight_roles.iter().any(|r| r.role == "tomorrow")
+    }
+
+    /// First date override matching `date` that has at least one color
+    pub fn active_date_override(&self, date: NaiveDate) -> Option<&DateOverride> {
+        self.date_overrides.iter().find(|o| !o.colors.is_empty() && o.is_active(date))
+    }
+
     pub fn save(&self) -> Result<(), String> {
         let path = crate::config::get_config_path();
         let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
@@ -73,3 +1372,249 @@ pub fn load_config() -> AppConfig {
 pub fn save_config(config: &AppConfig) -> Result<(), String> {
     config.save()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Pollen;
+
+    #[test]
+    fn test_date_override_ranges() {
+        let xmas = DateOverride { start: "12-24".into(), end: "12-26".into(), ..Default::default() };
+        assert!(xmas.is_active(NaiveDate::from_ymd_opt(2026, 12, 25).unwrap()));
+        assert!(!xmas.is_active(NaiveDate::from_ymd_opt(2026, 12, 27).unwrap()));
+
+        let nye = DateOverride { start: "12-31".into(), end: "01-01".into(), ..Default::default() };
+        assert!(nye.is_active(NaiveDate::from_ymd_opt(2027, 1, 1).unwrap()));
+        assert!(!nye.is_active(NaiveDate::from_ymd_opt(2027, 1, 2).unwrap()));
+
+        let single = DateOverride { start: "02-06".into(), ..Default::default() };
+        assert!(single.is_active(NaiveDate::from_ymd_opt(2026, 2, 6).unwrap()));
+    }
+
+    #[test]
+    fn test_wake_up_window() {
+        let sunrise = Utc::now() + chrono::Duration::minutes(10);
+        let rule = WakeUpConfig { enabled: true, time: String::new(), ..Default::default() };
+        assert_eq!(rule.window(Some(sunrise), Utc::now()), Some((sunrise - chrono::Duration::minutes(30), sunrise)));
+        assert_eq!(rule.window(Some(sunrise), sunrise), None);
+        assert_eq!(rule.window(None, Utc::now()), None);
+
+        // 23:50 local; a 07:00 alarm is hours away, a 00:10 one starts now
+        let late = Local::now().date_naive().and_hms_opt(23, 50, 0).unwrap().and_local_timezone(Local).earliest().unwrap().with_timezone(&Utc);
+        let alarm = WakeUpConfig { enabled: true, ..Default::default() };
+        assert_eq!(alarm.window(None, late), None);
+        let (start, end) = WakeUpConfig { time: "00:10".into(), ..alarm }.window(None, late).unwrap();
+        assert_eq!(end - late, chrono::Duration::minutes(20));
+        assert_eq!(end - start, chrono::Duration::minutes(30));
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        // 2026-10-16 is a Friday
+        let at = |day: u32, h: u32, m: u32| NaiveDate::from_ymd_opt(2026, 10, day).unwrap().and_hms_opt(h, m, 0).unwrap();
+        let rule = QuietHoursConfig { enabled: true, ..Default::default() };
+        assert!(rule.is_active(at(16, 23, 0)));
+        assert!(rule.is_active(at(17, 6, 59)));
+        assert!(!rule.is_active(at(17, 7, 0)));
+        assert!(!rule.is_active(at(16, 21, 59)));
+        assert!(!QuietHoursConfig { enabled: false, ..rule.clone() }.is_active(at(16, 23, 0)));
+
+        // Weeknights only: Friday night counts into Saturday morning, Saturday night doesn't
+        let weeknights = QuietHoursConfig { days: vec!["mon".into(), "tue".into(), "wed".into(), "thu".into(), "fri".into()], ..rule.clone() };
+        assert!(weeknights.is_active(at(17, 3, 0)));
+        assert!(!weeknights.is_active(at(17, 23, 0)));
+        assert!(!weeknights.is_active(at(18, 3, 0)));
+
+        // A daytime period stays on its own day
+        let afternoon = QuietHoursConfig { start: "13:00".into(), end: "14:30".into(), ..rule };
+        assert!(afternoon.is_active(at(16, 14, 0)));
+        assert!(!afternoon.is_active(at(16, 14, 30)));
+        assert!(!afternoon.is_active(at(17, 0, 30)));
+    }
+
+    #[test]
+    fn test_work_hours() {
+        // 2026-10-16 is a Friday
+        let at = |day: u32, h: u32, m: u32| NaiveDate::from_ymd_opt(2026, 10, day).unwrap().and_hms_opt(h, m, 0).unwrap();
+        let office = WorkHoursConfig { enabled: true, ..Default::default() };
+        assert!(!office.is_off_hours(at(16, 8, 0)));
+        assert!(!office.is_off_hours(at(16, 17, 59)));
+        assert!(office.is_off_hours(at(16, 18, 0)));
+        assert!(office.is_off_hours(at(16, 7, 59)));
+        assert!(office.is_off_hours(at(17, 12, 0)));
+        assert!(!WorkHoursConfig::default().is_off_hours(at(17, 12, 0)));
+    }
+
+    #[test]
+    fn test_heat_warning_run_length() {
+        let now = Utc::now();
+        let hourly = |temps: &[f64]| -> Vec<ForecastItem> {
+            temps.iter().enumerate().map(|(i, t)| ForecastItem {
+                time: now + chrono::Duration::hours(i as i64),
+                temp: 30.0,
+                apparent_temp: Some(*t),
+                precip_prob: 0.0,
+                precip_type: "None".into(),
+                humidity: None,
+                dew_point: None,
+            }).collect()
+        };
+        let rule = HeatWarningConfig { enabled: true, ..Default::default() };
+
+        assert!(rule.is_triggered(&hourly(&[33.0, 36.0, 37.0, 36.0, 30.0]), now));
+        // Three hot hours, but not consecutive
+        assert!(!rule.is_triggered(&hourly(&[36.0, 36.0, 30.0, 36.0, 30.0]), now));
+        assert!(!HeatWarningConfig::default().is_triggered(&hourly(&[40.0, 40.0, 40.0]), now));
+
+        // A single 3h block counts as three hours
+        let block = vec![
+            ForecastItem { time: now, temp: 36.0, apparent_temp: None, precip_prob: 0.0, precip_type: "None".into(), humidity: None, dew_point: None },
+            ForecastItem { time: now + chrono::Duration::hours(3), temp: 20.0, apparent_temp: None, precip_prob: 0.0, precip_type: "None".into(), humidity: None, dew_point: None },
+        ];
+        assert!(rule.is_triggered(&block, now));
+        // 33°C at 60% humidity is a heat index near 40°C, but only 33°C of air
+        let humid = hourly(&[33.0, 33.0, 33.0]).into_iter()
+            .map(|item| ForecastItem { temp: 33.0, humidity: Some(60.0), ..item })
+            .collect::<Vec<_>>();
+        assert!(HeatWarningConfig { basis: "heat_index".into(), ..rule.clone() }.is_triggered(&humid, now));
+        assert!(!HeatWarningConfig { basis: "air".into(), ..rule.clone() }.is_triggered(&humid, now));
+    }
+
+    #[test]
+    fn test_temp_offset() {
+        let now = Utc::now();
+        let mut weather = WeatherState {
+            temperature: 20.0,
+            apparent_temperature: Some(19.0),
+            today_high: Some(24.0),
+            dew_point: Some(12.0),
+            debug_forecast: vec![ForecastItem {
+                time: now, temp: 20.0, apparent_temp: None, precip_prob: 0.0, precip_type: "None".into(), humidity: None, dew_point: Some(12.0),
+            }],
+            ..Default::default()
+        };
+        AppConfig { temp_offset: -2.5, ..Default::default() }.apply_temp_offset(&mut weather);
+
+        assert_eq!((weather.temperature, weather.apparent_temperature, weather.today_high, weather.today_low), (17.5, Some(16.5), Some(21.5), None));
+        assert_eq!(weather.debug_forecast[0].temp, 17.5);
+        assert_eq!(weather.dew_point, Some(12.0));
+    }
+
+    #[test]
+    fn test_banded_temp() {
+        let config = AppConfig { band_size: 5.0, ..Default::default() };
+        assert_eq!(config.banded_temp(11.0), 12.5);
+        assert_eq!(config.banded_temp(14.9), 12.5);
+        assert_eq!(config.banded_temp(15.0), 17.5);
+        assert_eq!(config.banded_temp(-0.5), -2.5);
+        assert_eq!(AppConfig::default().banded_temp(11.0), 11.0);
+
+        // 60-70°F is one band in Fahrenheit: 65°F
+        let fahrenheit = AppConfig { unit: "F".into(), band_size: 10.0, ..Default::default() };
+        assert!((fahrenheit.banded_temp(17.0) - 18.333333333333332).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_frost_window() {
+        let now = Utc::now();
+        let hourly = |temps: &[f64]| -> Vec<ForecastItem> {
+            temps.iter().enumerate().map(|(i, t)| ForecastItem {
+                time: now + chrono::Duration::hours(i as i64 * 3),
+                temp: *t,
+                apparent_temp: None,
+                precip_prob: 0.0,
+                precip_type: "None".into(),
+                humidity: None,
+                dew_point: None,
+            }).collect()
+        };
+        let rule = FrostConfig::default();
+
+        assert_eq!(rule.forecast_low(&hourly(&[6.0, 2.0, -1.5, 3.0]), now), Some(-1.5));
+        assert!(rule.expects_frost(&hourly(&[6.0, 2.0, -1.5, 3.0]), now));
+        // Frost 15 hours out is past the default 12h window
+        assert!(!rule.expects_frost(&hourly(&[6.0, 2.0, 1.0, 0.5, 1.0, -3.0]), now));
+        assert!(FrostConfig { threshold: 2.0, ..rule.clone() }.expects_frost(&hourly(&[6.0, 1.5]), now));
+
+        let frosty = WeatherState { frost_warning: true, ..Default::default() };
+        let lit = FrostConfig { enabled: true, ..rule.clone() };
+        assert!(lit.is_triggered(&frosty, 19));
+        assert!(!lit.is_triggered(&frosty, 8));
+        assert!(!rule.is_triggered(&frosty, 19));
+    }
+
+    #[test]
+    fn test_precip_styles() {
+        let styles = PrecipStylesConfig { enabled: true, ..Default::default() };
+        let wet = |kind: Option<&str>| WeatherState { has_precipitation: true, precip_type: kind.map(String::from), ..Default::default() };
+
+        assert_eq!(styles.style_for(&wet(Some("Snow"))).unwrap().color, "#FFFFFF");
+        // Unknown types pulse like rain
+        assert_eq!(styles.style_for(&wet(None)).unwrap().color, "#0050FF");
+        assert!(styles.style_for(&WeatherState::default()).is_none());
+        assert!(PrecipStylesConfig::default().style_for(&wet(Some("Snow"))).is_none());
+    }
+
+    #[test]
+    fn test_pollen_thresholds() {
+        let rule = PollenConfig { enabled: true, ..Default::default() };
+        let weather = |pollen: Pollen| WeatherState { pollen: Some(pollen), ..Default::default() };
+
+        let spring = weather(Pollen { birch: Some(240.0), grass: Some(12.0), ragweed: None });
+        assert_eq!(rule.high_counts(&spring), vec!["birch"]);
+        assert!(!rule.pulses(&spring));
+        assert!(PollenConfig { mode: "pulse".into(), ..rule.clone() }.pulses(&spring));
+
+        // Outside the model's coverage every series is null
+        assert!(!rule.is_triggered(&weather(Pollen::default())));
+        assert!(!PollenConfig::default().is_triggered(&spring));
+    }
+
+    #[test]
+    fn test_network_profile_overlay() {
+        let mut settings = serde_json::Map::new();
+        settings.insert("location".into(), "Wellington".into());
+        settings.insert("maxBrightness".into(), 30.into());
+        let config = AppConfig {
+            location: "Auckland".into(),
+            network_profiles: vec![NetworkProfile { name: "Office".into(), ssid: "CorpWiFi".into(), settings }],
+            ..Default::default()
+        };
+
+        let office = config.for_network(Some("CorpWiFi"));
+        assert_eq!(office.location, "Wellington");
+        assert_eq!(office.max_brightness, 30);
+        assert_eq!(office.unit, config.unit);
+
+        assert_eq!(config.for_network(Some("HomeNet")).location, "Auckland");
+        assert_eq!(config.for_network(None).location, "Auckland");
+    }
+
+    #[test]
+    fn test_provider_chain_api_key() {
+        let mut config = AppConfig { provider: "openweathermap".into(), ..Default::default() };
+        assert!(config.requires_api_key());
+
+        config.fallback_providers = vec!["openweathermap".into(), " ".into(), "open-meteo".into()];
+        assert_eq!(config.provider_chain(), vec!["openweathermap", "open-meteo"]);
+        // Open-Meteo can still answer without a key
+        assert!(!config.requires_api_key());
+    }
+
+    #[test]
+    fn test_refresh_interval_clamping() {
+        let mut config = AppConfig::default();
+        assert_eq!(config.refresh_interval().as_secs(), 15 * 60);
+
+        config.refresh_interval_minutes = 1;
+        assert_eq!(config.refresh_interval().as_secs(), 5 * 60);
+        config.refresh_interval_minutes = 24 * 60;
+        assert_eq!(config.refresh_interval().as_secs(), 180 * 60);
+
+        // AccuWeather's daily quota raises the floor even as a fallback
+        config.refresh_interval_minutes = 10;
+        config.fallback_providers = vec!["accuweather".into()];
+        assert_eq!(config.refresh_interval().as_secs(), 30 * 60);
+    }
+}
@@ -0,0 +1,209 @@
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use crate::models::{HistoryStats, WeatherSnapshot};
+
+// One row per weather refresh, kept for `historyRetentionDays`. Backs the history/statistics
+// commands and answers "why was the light red at noon" after the fact. The recorded pressure
+// also gives the 3-hour pressure tendency, which no provider reports directly.
+
+// The tendency needs a reading at least this old, and uses none older than `TENDENCY_HOURS`
+const MIN_TENDENCY_SPAN: chrono::Duration = chrono::Duration::hours(2);
+const TENDENCY_HOURS: i64 = 3;
+
+pub struct HistoryStore {
+    conn: Mutex<Connection>,
+}
+
+pub fn get_history_path() -> PathBuf {
+    let mut path = crate::config::get_config_path();
+    path.set_file_name("history.db");
+    path
+}
+
+impl HistoryStore {
+    pub fn open(path: &PathBuf) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        Self::init(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self, String> {
+        let conn = Connection::open_in_memory().map_err(|e| e.to_string())?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> Result<Self, String> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS snapshots (
+                timestamp INTEGER NOT NULL,
+                temperature REAL NOT NULL,
+                has_precipitation INTEGER NOT NULL,
+                precip_probability REAL NOT NULL,
+                color TEXT NOT NULL,
+                device_connected INTEGER NOT NULL,
+                location_name TEXT NOT NULL,
+                provider TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_snapshots_timestamp ON snapshots(timestamp);",
+        ).map_err(|e| e.to_string())?;
+
+        // Databases from before pressure was recorded
+        let has_pressure = conn.prepare("SELECT pressure FROM snapshots LIMIT 0").is_ok();
+        if !has_pressure {
+            conn.execute("ALTER TABLE snapshots ADD COLUMN pressure REAL", []).map_err(|e| e.to_string())?;
+        }
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    pub fn record(&self, snapshot: &WeatherSnapshot) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO snapshots (timestamp, temperature, has_precipitation, precip_probability, color, device_connected, location_name, provider, pressure)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                snapshot.timestamp.timestamp(),
+                snapshot.temperature,
+                snapshot.has_precipitation,
+                snapshot.precip_probability,
+                snapshot.color,
+                snapshot.device_connected,
+                snapshot.location_name,
+                snapshot.provider,
+                snapshot.pressure,
+            ],
+        ).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Drops rows older than `retention_days`
+    pub fn prune(&self, retention_days: u32) -> Result<usize, String> {
+        let cutoff = Utc::now() - chrono::Duration::days(retention_days as i64);
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM snapshots WHERE timestamp < ?1", params![cutoff.timestamp()])
+            .map_err(|e| e.to_string())
+    }
+
+    /// Snapshots since `since`, oldest first
+    pub fn query(&self, since: DateTime<Utc>) -> Result<Vec<WeatherSnapshot>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, temperature, has_precipitation, precip_probability, color, device_connected, location_name, provider, pressure
+             FROM snapshots WHERE timestamp >= ?1 ORDER BY timestamp ASC",
+        ).map_err(|e| e.to_string())?;
+
+        let rows = stmt.query_map(params![since.timestamp()], |row| {
+            Ok(WeatherSnapshot {
+                timestamp: Utc.timestamp_opt(row.get(0)?, 0).single().unwrap_or_default(),
+                temperature: row.get(1)?,
+                has_precipitation: row.get(2)?,
+                precip_probability: row.get(3)?,
+                color: row.get(4)?,
+                device_connected: row.get(5)?,
+                location_name: row.get(6)?,
+                provider: row.get(7)?,
+                pressure: row.get(8)?,
+            })
+        }).map_err(|e| e.to_string())?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    }
+
+    /// Change in hPa from the oldest reading at `location` in the last three hours to `current`,
+    /// scaled to three hours. `None` until the history spans at least two hours.
+    pub fn pressure_tendency(&self, location: &str, current: f64, now: DateTime<Utc>) -> Result<Option<f64>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let since = now - chrono::Duration::hours(TENDENCY_HOURS);
+        let oldest: Option<(i64, f64)> = conn.query_row(
+            "SELECT timestamp, pressure FROM snapshots
+             WHERE pressure IS NOT NULL AND location_name = ?1 AND timestamp >= ?2 AND timestamp <= ?3
+             ORDER BY timestamp ASC LIMIT 1",
+            params![location, since.timestamp(), (now - MIN_TENDENCY_SPAN).timestamp()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional().map_err(|e| e.to_string())?;
+
+        Ok(oldest.map(|(timestamp, pressure)| {
+            let hours = (now.timestamp() - timestamp) as f64 / 3600.0;
+            (current - pressure) * TENDENCY_HOURS as f64 / hours
+        }))
+    }
+
+    pub fn stats(&self, since: DateTime<Utc>) -> Result<HistoryStats, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT COUNT(*), MIN(temperature), MAX(temperature), AVG(temperature),
+                    AVG(has_precipitation), AVG(device_connected)
+             FROM snapshots WHERE timestamp >= ?1",
+            params![since.timestamp()],
+            |row| Ok(HistoryStats {
+                count: row.get::<_, i64>(0)? as u32,
+                min_temperature: row.get(1)?,
+                max_temperature: row.get(2)?,
+                avg_temperature: row.get(3)?,
+                precipitation_share: row.get::<_, Option<f64>>(4)?.unwrap_or(0.0),
+                device_uptime_share: row.get::<_, Option<f64>>(5)?.unwrap_or(0.0),
+            }),
+        ).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(hours_ago: i64, temperature: f64, wet: bool) -> WeatherSnapshot {
+        WeatherSnapshot {
+            timestamp: Utc::now() - chrono::Duration::hours(hours_ago),
+            temperature,
+            has_precipitation: wet,
+            precip_probability: if wet { 80.0 } else { 0.0 },
+            color: "#ff0000".to_string(),
+            device_connected: true,
+            location_name: "Auckland".to_string(),
+            provider: "Open-Meteo".to_string(),
+            pressure: None,
+        }
+    }
+
+    #[test]
+    fn test_record_query_prune() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store.record(&snapshot(72, 10.0, false)).unwrap();
+        store.record(&snapshot(2, 20.0, true)).unwrap();
+        store.record(&snapshot(1, 30.0, false)).unwrap();
+
+        let day = Utc::now() - chrono::Duration::hours(24);
+        let recent = store.query(day).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].temperature, 20.0);
+
+        let stats = store.stats(day).unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.max_temperature, Some(30.0));
+        assert_eq!(stats.precipitation_share, 0.5);
+
+        assert_eq!(store.prune(2).unwrap(), 1);
+        assert_eq!(store.query(Utc::now() - chrono::Duration::days(30)).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_pressure_tendency() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        assert_eq!(store.pressure_tendency("Auckland", 1000.0, Utc::now()).unwrap(), None);
+
+        let reading = |hours_ago: i64, pressure: f64, location: &str| WeatherSnapshot {
+            pressure: Some(pressure),
+            location_name: location.to_string(),
+            ..snapshot(hours_ago, 15.0, false)
+        };
+        store.record(&reading(5, 990.0, "Auckland")).unwrap();
+        store.record(&reading(3, 1012.0, "Wellington")).unwrap();
+        store.record(&reading(2, 1004.0, "Auckland")).unwrap();
+        store.record(&reading(1, 1002.0, "Auckland")).unwrap();
+        let now = Utc::now();
+
+        // Only the two-hour-old Auckland reading qualifies: -4 hPa in 2 h is -6 per 3 h
+        let tendency = store.pressure_tendency("Auckland", 1000.0, now).unwrap().unwrap();
+        assert!((tendency + 6.0).abs() < 0.1);
+    }
+}
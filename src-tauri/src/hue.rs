@@ -0,0 +1,229 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use reqwest::Client;
+use serde_json::{json, Value};
+use crate::config::HueConfig;
+use crate::models::HueTarget;
+use crate::sink::{LightSink, SinkStatus};
+
+// Philips Hue lights and rooms, driven through the bridge's local REST API (v1, plain HTTP on the
+// LAN). Pairing asks the bridge for an app key, which it only hands out within 30 seconds of its
+// link button being pressed. Bridges take about ten commands a second, so unchanged states are
+// not re-sent, and a pulse is the bulbs' own 15-second breathe ("lselect") rather than a loop.
+
+/// Device name used in `deviceLocations` for the Hue output
+pub const HUE_DEVICE: &str = "hue";
+
+// What the bridge records as the app's name when pairing
+const DEVICE_TYPE: &str = "weatherlight#desktop";
+
+pub struct HueOutput {
+    client: Client,
+    // Targets and body of the last state sent
+    last: Mutex<Option<(Vec<String>, Value)>>,
+    // Whether the last command got through
+    reachable: AtomicBool,
+}
+
+impl Default for HueOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HueOutput {
+    pub fn new() -> Self {
+        Self {
+            client: Client::builder().timeout(Duration::from_secs(5)).build().unwrap_or_default(),
+            last: Mutex::new(None),
+            reachable: AtomicBool::new(true),
+        }
+    }
+
+    /// Asks the bridge at `base` for an app key; the link button has to have been pressed first
+    pub async fn pair(&self, base: &str) -> Result<String, String> {
+        let url = format!("{}/api", base.trim_end_matches('/'));
+        let resp = self.client.post(&url).json(&json!({ "devicetype": DEVICE_TYPE })).send().await.map_err(|e| e.to_string())?;
+        let body: Value = resp.json().await.map_err(|e| e.to_string())?;
+        let entry = body.as_array().and_then(|a| a.first()).ok_or_else(|| "Unexpected reply from the Hue bridge".to_string())?;
+
+        if let Some(key) = entry.pointer("/success/username").and_then(|v| v.as_str()) {
+            return Ok(key.to_string());
+        }
+        match entry.pointer("/error/type").and_then(|v| v.as_i64()) {
+            Some(101) => Err("Press the link button on the Hue bridge, then pair again".to_string()),
+            _ => Err(bridge_error(&body).unwrap_or_else(|| "Unexpected reply from the Hue bridge".to_string())),
+        }
+    }
+
+    /// Lights first, then rooms and zones, each sorted by name
+    pub async fn list_targets(&self, config: &HueConfig) -> Result<Vec<HueTarget>, String> {
+        let mut targets = Vec::new();
+        for (path, prefix) in [("lights", "light"), ("groups", "group")] {
+            let url = format!("{}/api/{}/{}", config.base_url(), config.app_key, path);
+            let body: Value = self.client.get(&url).send().await.map_err(|e| e.to_string())?
+                .json().await.map_err(|e| e.to_string())?;
+            if let Some(err) = bridge_error(&body) {
+                return Err(err);
+            }
+            let mut found: Vec<HueTarget> = body.as_object().map(|entries| {
+                entries.iter().map(|(id, entry)| HueTarget {
+                    id: format!("{}:{}", prefix, id),
+                    name: entry.get("name").and_then(|v| v.as_str()).unwrap_or(id).to_string(),
+                    kind: entry.get("type").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                }).collect()
+            }).unwrap_or_default();
+            found.sort_by(|a, b| a.name.cmp(&b.name));
+            targets.extend(found);
+        }
+        Ok(targets)
+    }
+
+    /// Sends one light state to every configured target, unless it's what they already show.
+    /// `brightness` is a percentage; black or 0% turns the targets off.
+    pub async fn apply(&self, config: &HueConfig, rgb: (u8, u8, u8), brightness: u8, pulse: bool) -> Result<(), String> {
+        if !config.is_ready() {
+            return Ok(());
+        }
+        let result = self.send_state(config, rgb, brightness, pulse).await;
+        self.reachable.store(result.is_ok(), Ordering::Relaxed);
+        result
+    }
+
+    async fn send_state(&self, config: &HueConfig, rgb: (u8, u8, u8), brightness: u8, pulse: bool) -> Result<(), String> {
+        let body = state_body(rgb, brightness, pulse);
+        // A breathe ends by itself, so a pulse is always re-sent
+        let sent = (config.targets.clone(), body.clone());
+        if !pulse && self.last.lock().unwrap().as_ref() == Some(&sent) {
+            return Ok(());
+        }
+
+        for target in &config.targets {
+            let path = match target.split_once(':') {
+                Some(("light", id)) => format!("lights/{}/state", id),
+                Some(("group", id)) => format!("groups/{}/action", id),
+                _ => return Err(format!("Unknown Hue target {}", target)),
+            };
+            let url = format!("{}/api/{}/{}", config.base_url(), config.app_key, path);
+            let reply: Value = self.client.put(&url).json(&body).send().await.map_err(|e| e.to_string())?
+                .json().await.map_err(|e| e.to_string())?;
+            if let Some(err) = bridge_error(&reply) {
+                *self.last.lock().unwrap() = None;
+                return Err(format!("Hue {}: {}", target, err));
+            }
+        }
+        *self.last.lock().unwrap() = Some(sent);
+        Ok(())
+    }
+}
+
+/// The Hue output with the settings it was built from, as a light sink
+pub struct HueSink {
+    output: Arc<HueOutput>,
+    config: HueConfig,
+}
+
+impl HueSink {
+    pub fn new(output: Arc<HueOutput>, config: HueConfig) -> Self {
+        Self { output, config }
+    }
+
+    fn send(&self, rgb: (u8, u8, u8), brightness: u8, pulse: bool) {
+        let (output, config) = (self.output.clone(), self.config.clone());
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = output.apply(&config, rgb, brightness, pulse).await {
+                println!("Hue update failed: {}", e);
+            }
+        });
+    }
+}
+
+impl LightSink for HueSink {
+    fn kind(&self) -> &'static str {
+        "hue"
+    }
+
+    fn set_color(&self, rgb: (u8, u8, u8), brightness: u8) {
+        self.send(rgb, brightness, false);
+    }
+
+    // Bulbs only have the one breathe, so depth and speed are dropped
+    fn pulse(&self, rgb: (u8, u8, u8), high: u8, _low: u8, _speed_ms: u64) {
+        self.send(rgb, high, true);
+    }
+
+    fn off(&self) {
+        self.send((0, 0, 0), 0, false);
+    }
+
+    fn status(&self) -> SinkStatus {
+        if self.output.reachable.load(Ordering::Relaxed) { SinkStatus::Connected } else { SinkStatus::Disconnected }
+    }
+}
+
+// The bridge answers 200 even for failures, with [{"error": {"type", "address", "description"}}]
+fn bridge_error(body: &Value) -> Option<String> {
+    body.as_array()?.iter()
+        .find_map(|entry| entry.pointer("/error/description").and_then(|v| v.as_str()))
+        .map(|d| d.to_string())
+}
+
+fn state_body(rgb: (u8, u8, u8), brightness: u8, pulse: bool) -> Value {
+    if rgb == (0, 0, 0) || brightness == 0 {
+        return json!({ "on": false });
+    }
+    let (x, y) = rgb_to_xy(rgb);
+    let bri = ((brightness.min(100) as u32 * 254 + 50) / 100).max(1);
+    json!({
+        "on": true,
+        "bri": bri,
+        "xy": [x, y],
+        "alert": if pulse { "lselect" } else { "none" },
+        // Tenths of a second
+        "transitiontime": 4,
+    })
+}
+
+/// CIE xy chromaticity of an sRGB color, as the bridge wants it (Philips' wide-gamut D65
+/// conversion, rounded to 4 places). The bulb clamps it to its own gamut.
+pub fn rgb_to_xy(rgb: (u8, u8, u8)) -> (f64, f64) {
+    let linear = |c: u8| {
+        let v = c as f64 / 255.0;
+        if v > 0.04045 { ((v + 0.055) / 1.055).powf(2.4) } else { v / 12.92 }
+    };
+    let (r, g, b) = (linear(rgb.0), linear(rgb.1), linear(rgb.2));
+    let x = r * 0.664511 + g * 0.154324 + b * 0.162028;
+    let y = r * 0.283881 + g * 0.668433 + b * 0.047685;
+    let z = r * 0.000088 + g * 0.072310 + b * 0.986039;
+    let sum = x + y + z;
+    if sum == 0.0 {
+        // White point, for black
+        return (0.3227, 0.329);
+    }
+    let round = |v: f64| (v * 10_000.0).round() / 10_000.0;
+    (round(x / sum), round(y / sum))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_to_xy() {
+        assert_eq!(rgb_to_xy((255, 0, 0)), (0.7006, 0.2993));
+        assert_eq!(rgb_to_xy((0, 0, 255)), (0.1355, 0.0399));
+        let (x, y) = rgb_to_xy((255, 255, 255));
+        assert!((x - 0.3227).abs() < 0.001 && (y - 0.329).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_state_body() {
+        assert_eq!(state_body((0, 0, 0), 60, false), json!({ "on": false }));
+        assert_eq!(state_body((255, 0, 0), 0, true), json!({ "on": false }));
+        let body = state_body((255, 0, 0), 60, true);
+        assert_eq!(body["bri"], 152);
+        assert_eq!(body["alert"], "lselect");
+        assert_eq!(state_body((255, 0, 0), 1, false)["bri"], 3);
+    }
+}
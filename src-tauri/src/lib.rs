@@ -3,24 +3,143 @@ pub mod config;
 pub mod busylight;
 pub mod weather;
 pub mod tray;
+pub mod vision;
+pub mod plugins;
+pub mod sound;
+pub mod watchdog;
+pub mod network;
+pub mod history;
+pub mod weather_cache;
+pub mod sun;
+pub mod color;
+pub mod themes;
+pub mod hue;
+pub mod wled;
+pub mod sink;
+pub mod arbiter;
+pub mod teams;
+pub mod slack;
+pub mod calendar;
+pub mod capture;
 
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tauri::{Manager, AppHandle, State, Listener};
+use tauri::{Manager, AppHandle, State, Listener, Emitter};
+use chrono::{DateTime, Timelike, Utc};
 use tauri_plugin_autostart::MacosLauncher;
 use tauri_plugin_autostart::ManagerExt;
 use tauri_plugin_opener::OpenerExt;
+use tauri_plugin_notification::NotificationExt;
 
-use crate::models::{WeatherState, LocationDetectResult, LocationValidationResult, DeviceInfoResult};
-use crate::config::{AppConfig, load_config, save_config};
-use crate::busylight::BusylightController;
-use crate::weather::WeatherService;
+use crate::models::{WeatherState, DailyForecast, LocationDetectResult, LocationValidationResult, DeviceInfoResult, BusylightListing, CalibrationStep, HueTarget, TeamsDeviceCode, BusylightStatusResult, WeatherUpdated, ColorPreview, PaletteSwatch, WeatherSnapshot, HistoryStats, ProviderHealth};
+use crate::config::{AppConfig, DateOverride, ManualPreset, load_config, save_config};
+use crate::busylight::{BusylightController, BusylightManager, Calibration, DeviceStatus, Effect, PulseState, Ramp, Ringtone, TEST_COLORS};
+use crate::hue::{HueOutput, HueSink, HUE_DEVICE};
+use crate::wled::{WledOutput, WledSink, WLED_DEVICE};
+use crate::teams::TeamsPresence;
+use crate::slack::SlackPresence;
+use crate::calendar::CalendarFeed;
+use crate::capture::CaptureMonitor;
+use crate::sink::LightSink;
+use crate::arbiter::{Arbiter, Claim, Priority};
+use crate::weather::{ProviderInfo, ProviderRegistry, WeatherService};
+use crate::plugins::{PluginManifest, PluginRegistry, PluginSink};
+use crate::watchdog::Heartbeat;
+use crate::history::HistoryStore;
+use crate::themes::Theme;
+use crate::color::{hex_to_rgb, to_hex, Interpolation};
+
+pub use crate::color::calculate_weather_color;
+
+/// What each Busylight showed, by device key
+pub type ShownPlans = Vec<(Option<String>, PulseState)>;
 
 pub struct AppState {
     pub config: Mutex<AppConfig>,
     pub weather_state: Mutex<Option<WeatherState>>,
-    pub busylight: Arc<BusylightController>,
+    // Weather of each light bound in `deviceLocations` (by device name), from its last fetch
+    pub bound_weather: Mutex<Vec<(String, WeatherState)>>,
+    pub busylight: Arc<BusylightManager>,
     pub weather_svc: Arc<WeatherService>,
+    pub plugins: Arc<PluginRegistry>,
+    pub hue: Arc<HueOutput>,
+    pub wled: Arc<WledOutput>,
+    pub teams: Arc<TeamsPresence>,
+    pub slack: Arc<SlackPresence>,
+    pub calendar: Arc<CalendarFeed>,
+    pub capture: Arc<CaptureMonitor>,
+    // Light stays off until this instant; cleared by the background loop when it passes
+    pub snooze_until: Mutex<Option<DateTime<Utc>>>,
+    // Manual mode ends by itself at this instant; None holds it until it's turned off
+    pub manual_until: Mutex<Option<DateTime<Utc>>>,
+    // Some while updates are paused from the tray, with what each Busylight showed (by device
+    // key) to put back on resume
+    pub paused: Mutex<Option<ShownPlans>>,
+    pub orchestrator_heartbeat: Arc<Heartbeat>,
+    // Wi-Fi network last seen by the orchestrator, selects a network profile
+    pub current_ssid: Mutex<Option<String>>,
+    // None when the database couldn't be opened; history is then simply not recorded
+    pub history: Option<Arc<HistoryStore>>,
+}
+
+impl AppState {
+    /// Clears poison from every lock the orchestrator takes, so a loop restarted after a panic
+    /// doesn't panic again on its first pass
+    fn clear_poison(&self) {
+        self.config.clear_poison();
+        self.weather_state.clear_poison();
+        self.bound_weather.clear_poison();
+        self.snooze_until.clear_poison();
+        self.manual_until.clear_poison();
+        self.paused.clear_poison();
+        self.current_ssid.clear_poison();
+        self.busylight.clear_poison();
+        self.weather_svc.clear_poison();
+    }
+}
+
+/// Device name used in `deviceLocations` for the built-in Busylight (the first one found); any
+/// further Busylights are "busylight:<key>"
+pub const BUSYLIGHT_DEVICE: &str = "busylight";
+
+// Every connected Busylight with its `deviceLocations` name and device key
+fn busylights(state: &AppState) -> Vec<(String, Option<String>, Arc<BusylightController>)> {
+    state.busylight.all().into_iter().enumerate()
+        .map(|(i, (key, light))| {
+            let name = match (i, &key) {
+                (0, _) | (_, None) => BUSYLIGHT_DEVICE.to_string(),
+                (_, Some(key)) => format!("{}:{}", BUSYLIGHT_DEVICE, key),
+            };
+            (name, key, light)
+        })
+        .collect()
+}
+
+/// A light output with its `deviceLocations` name and role (see `BusylightRole`)
+struct NamedSink {
+    name: String,
+    role: String,
+    output: Arc<dyn LightSink>,
+}
+
+// Everything the pipeline drives: each Busylight, each running device plugin, and Hue and WLED
+// when they're set up
+fn light_sinks(state: &AppState, config: &AppConfig) -> Vec<NamedSink> {
+    let mut sinks: Vec<NamedSink> = busylights(state).into_iter()
+        .map(|(name, key, light)| NamedSink { name, role: config.busylight_role(key.as_deref()).to_string(), output: light })
+        .collect();
+    let weather_sink = |name: &str, output: Arc<dyn LightSink>| NamedSink { name: name.to_string(), role: "weather".to_string(), output };
+    for name in state.plugins.device_names() {
+        sinks.push(weather_sink(&name, Arc::new(PluginSink::new(state.plugins.clone(), name.clone()))));
+    }
+    if config.hue.is_ready() {
+        sinks.push(weather_sink(HUE_DEVICE, Arc::new(HueSink::new(state.hue.clone(), config.hue.clone()))));
+    }
+    if config.wled.is_ready() {
+        let interpolation = Interpolation::from_name(&config.interpolation);
+        sinks.push(weather_sink(WLED_DEVICE, Arc::new(WledSink::new(state.wled.clone(), config.wled.clone(), interpolation))));
+    }
+    sinks
 }
 
 // --- Tauri Commands (API bridge) ---
@@ -38,6 +157,8 @@ async fn set_settings(app: AppHandle, state: State<'_, AppState>, settings: AppC
         *cfg = settings.clone();
     }
     save_config(&settings)?;
+    // A fixed API key or a new provider deserves a try right away
+    state.weather_svc.reset_provider_health();
     
     // Apply autostart logic
     let autostart_manager = app.autolaunch();
@@ -47,6 +168,8 @@ async fn set_settings(app: AppHandle, state: State<'_, AppState>, settings: AppC
         let _ = autostart_manager.disable();
     }
 
+    crate::tray::refresh_tray_menu(&app);
+
     // Refresh weather pipeline so light updates immediately upon changing settings
     tauri::async_runtime::spawn(async move {
         update_weather_pipeline(&app).await;
@@ -79,15 +202,32 @@ async fn get_weather_state(state: State<'_, AppState>) -> Result<Option<WeatherS
     Ok(ws)
 }
 
+/// Daily summaries for the coming week, fetched on demand rather than by the refresh loop
+#[tauri::command]
+async fn get_forecast(state: State<'_, AppState>) -> Result<Vec<DailyForecast>, String> {
+    let config = {
+        let ssid = state.current_ssid.lock().unwrap().clone();
+        state.config.lock().unwrap().for_network(ssid.as_deref())
+    };
+    state.weather_svc.fetch_forecast(&config).await
+}
+
+/// Providers that have been failing, including those the circuit breaker is skipping
+#[tauri::command]
+async fn get_provider_health(state: State<'_, AppState>) -> Result<Vec<ProviderHealth>, String> {
+    Ok(state.weather_svc.provider_health())
+}
+
 #[tauri::command]
 async fn get_device_info(state: State<'_, AppState>) -> Result<Option<DeviceInfoResult>, String> {
-    if let Ok(bl) = state.busylight.bl.lock() {
+    if let Ok(bl) = state.busylight.primary.bl.lock() {
         if let Some(info) = bl.get_info() {
             return Ok(Some(DeviceInfoResult {
                 product: info.product,
                 path: info.path,
                 vendor_id: info.vendor_id,
                 product_id: info.product_id,
+                accessible: bl.is_connected(),
             }));
         }
     }
@@ -95,28 +235,303 @@ async fn get_device_info(state: State<'_, AppState>) -> Result<Option<DeviceInfo
 }
 
 #[tauri::command]
-async fn get_busylight_status(state: State<'_, AppState>) -> Result<bool, String> {
-    let connected = if let Ok(bl) = state.busylight.bl.lock() {
-        bl.is_connected()
+async fn get_busylight_status(state: State<'_, AppState>) -> Result<BusylightStatusResult, String> {
+    Ok(busylight_status(&state))
+}
+
+// The primary Busylight's connection, with what to do about it
+fn busylight_status(state: &AppState) -> BusylightStatusResult {
+    let (connected, status, path) = if let Ok(bl) = state.busylight.primary.bl.lock() {
+        (bl.is_connected(), bl.status(), bl.get_info().and_then(|i| i.path))
     } else {
-        false
+        (false, DeviceStatus::NotFound, None)
+    };
+
+    let message = match status {
+        DeviceStatus::Connected => None,
+        DeviceStatus::NotFound => Some("No Busylight device found".to_string()),
+        DeviceStatus::PermissionDenied => Some(format!(
+            "Busylight found at {} but access was denied. Install the udev rule from Diagnostics, then replug the device.",
+            path.unwrap_or_else(|| "an unknown path".to_string())
+        )),
+        DeviceStatus::HidUnavailable => Some("The system HID library could not be initialized".to_string()),
+    };
+    BusylightStatusResult { connected, status, message }
+}
+
+/// Returns the udev rule for supported Busylights; with `install` it is also written to
+/// /etc/udev/rules.d via pkexec and the device is reopened.
+#[tauri::command]
+async fn busylight_udev_rule(install: bool, state: State<'_, AppState>) -> Result<String, String> {
+    let rule = crate::busylight::udev_rule();
+    if install {
+        tauri::async_runtime::spawn_blocking(crate::busylight::install_udev_rule)
+            .await
+            .map_err(|e| e.to_string())??;
+
+        // udev applies the new permissions asynchronously after the trigger
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let lights = state.busylight.clone();
+        let _ = tokio::task::spawn_blocking(move || lights.reconnect()).await;
+    }
+    Ok(rule)
+}
+
+/// Connected Busylights with their configured roles, the first one (the diagnostics device) first
+#[tauri::command]
+async fn list_busylights(state: State<'_, AppState>) -> Result<Vec<BusylightListing>, String> {
+    let config = state.config.lock().unwrap().clone();
+    Ok(busylights(&state).into_iter()
+        .filter_map(|(name, key, light)| {
+            let key = key?;
+            let product = light.bl.lock().ok().and_then(|bl| bl.get_info()).and_then(|i| i.product);
+            let calibration = config.busylight_calibrations.iter().find(|c| c.device == key).map(|c| c.calibration).unwrap_or_default();
+            Some(BusylightListing { role: config.busylight_role(Some(&key)).to_string(), calibration, key, name, product })
+        })
+        .collect())
+}
+
+/// Runs a short RGB sweep on the Busylight with `key` (every Busylight without one), to check
+/// it's connected and find which unit is which
+#[tauri::command]
+async fn test_device(key: Option<String>, state: State<'_, AppState>) -> Result<(), String> {
+    identify_busylights(&state, key.as_deref())
+}
+
+pub(crate) fn identify_busylights(state: &AppState, key: Option<&str>) -> Result<(), String> {
+    let lights: Vec<Arc<BusylightController>> = busylights(state).into_iter()
+        .filter(|(_, k, light)| match key {
+            Some(key) => k.as_deref() == Some(key),
+            None => light.bl.lock().map(|bl| bl.is_connected()).unwrap_or(false),
+        })
+        .map(|(_, _, light)| light)
+        .collect();
+    if lights.is_empty() {
+        return Err(match key {
+            Some(key) => format!("Busylight {} isn't connected", key),
+            None => "No Busylight device found".to_string(),
+        });
+    }
+    for light in lights {
+        light.identify();
+    }
+    Ok(())
+}
+
+/// Shows test color `step` (wrapping) of `TEST_COLORS` on the Busylight with `key`, through
+/// `calibration` instead of the saved one, so the settings window can tune it by eye. Without
+/// `step` the saved calibration comes back and the light returns to the weather.
+#[tauri::command]
+async fn calibrate_busylight(key: String, step: Option<usize>, calibration: Calibration, app: AppHandle, state: State<'_, AppState>) -> Result<Option<CalibrationStep>, String> {
+    let Some(step) = step else {
+        let calibrations = state.config.lock().unwrap().busylight_calibrations();
+        state.busylight.set_calibrations(calibrations);
+        tauri::async_runtime::spawn(async move {
+            update_weather_pipeline(&app).await;
+        });
+        return Ok(None);
     };
-    Ok(connected)
+
+    let light = busylights(&state).into_iter()
+        .find(|(_, k, _)| k.as_deref() == Some(key.as_str()))
+        .map(|(_, _, light)| light)
+        .ok_or_else(|| format!("Busylight {} isn't connected", key))?;
+    let step = step % TEST_COLORS.len();
+    let (label, rgb, pct) = TEST_COLORS[step];
+    light.stop_pulse();
+    if let Ok(mut bl) = light.bl.lock() {
+        bl.calibration = calibration;
+        bl.light_pct(rgb.0, rgb.1, rgb.2, pct);
+    }
+    Ok(Some(CalibrationStep { step, total: TEST_COLORS.len(), label: label.to_string() }))
 }
 
+/// Pairs with the Hue bridge at `bridge` (IP or host name) and returns the app key to save
 #[tauri::command]
-async fn set_manual_mode(enabled: bool, state: State<'_, AppState>, app: AppHandle) -> Result<(), String> {
+async fn pair_hue_bridge(bridge: String, state: State<'_, AppState>) -> Result<String, String> {
+    let config = crate::config::HueConfig { bridge, ..Default::default() };
+    state.hue.pair(&config.base_url()).await
+}
+
+/// Lights and rooms on the bridge, for picking `hue.targets`
+#[tauri::command]
+async fn list_hue_targets(bridge: String, app_key: String, state: State<'_, AppState>) -> Result<Vec<HueTarget>, String> {
+    let config = crate::config::HueConfig { bridge, app_key, ..Default::default() };
+    state.hue.list_targets(&config).await
+}
+
+/// Turns manual mode on or off. With `minutes`, it turns itself off after that long and the
+/// weather comes back; returns when that happens.
+#[tauri::command]
+async fn set_manual_mode(enabled: bool, minutes: Option<u64>, state: State<'_, AppState>, app: AppHandle) -> Result<Option<DateTime<Utc>>, String> {
     if let Ok(mut mode) = state.busylight.manual_mode.lock() {
         *mode = enabled;
     }
+    let until = minutes.filter(|&m| enabled && m > 0).map(|m| Utc::now() + chrono::Duration::minutes(m as i64));
+    if let Ok(mut u) = state.manual_until.lock() {
+        *u = until;
+    }
+    crate::tray::refresh_tray_menu(&app);
     if !enabled {
         tauri::async_runtime::spawn(async move {
             update_weather_pipeline(&app).await;
         });
     }
+    Ok(until)
+}
+
+/// Starts a Microsoft sign-in for Teams presence; show the code, then call `teams_sign_in_finish`
+#[tauri::command]
+async fn teams_sign_in_start(client_id: String, tenant: String, state: State<'_, AppState>) -> Result<TeamsDeviceCode, String> {
+    let config = crate::config::TeamsConfig { client_id, tenant, ..Default::default() };
+    state.teams.start_sign_in(&config).await
+}
+
+/// Waits for the user to enter the code; resolves to the refresh token for `teams.refreshToken`
+#[tauri::command]
+async fn teams_sign_in_finish(client_id: String, tenant: String, code: TeamsDeviceCode, state: State<'_, AppState>) -> Result<String, String> {
+    let config = crate::config::TeamsConfig { client_id, tenant, ..Default::default() };
+    state.teams.finish_sign_in(&config, &code).await
+}
+
+/// Turns manual mode on with the named preset on the Busylights, for `minutes` if given;
+/// returns when it ends
+#[tauri::command]
+async fn apply_manual_preset(name: String, minutes: Option<u64>, app: AppHandle) -> Result<Option<DateTime<Utc>>, String> {
+    apply_preset(&app, &name, minutes)
+}
+
+/// Adds `preset` to `manualPresets`, replacing one with the same name
+#[tauri::command]
+async fn save_manual_preset(preset: ManualPreset, app: AppHandle, state: State<'_, AppState>) -> Result<Vec<ManualPreset>, String> {
+    if preset.name.trim().is_empty() {
+        return Err("A preset needs a name".to_string());
+    }
+    hex_to_rgb(&preset.color).ok_or_else(|| format!("Invalid color {}", preset.color))?;
+    let presets = {
+        let mut config = state.config.lock().unwrap();
+        let preset = ManualPreset { name: preset.name.trim().to_string(), ..preset };
+        match config.manual_presets.iter_mut().find(|p| p.name.eq_ignore_ascii_case(&preset.name)) {
+            Some(existing) => *existing = preset,
+            None => config.manual_presets.push(preset),
+        }
+        save_config(&config)?;
+        config.manual_presets.clone()
+    };
+    crate::tray::refresh_tray_menu(&app);
+    Ok(presets)
+}
+
+#[tauri::command]
+async fn delete_manual_preset(name: String, app: AppHandle, state: State<'_, AppState>) -> Result<Vec<ManualPreset>, String> {
+    let presets = {
+        let mut config = state.config.lock().unwrap();
+        config.manual_presets.retain(|p| !p.name.eq_ignore_ascii_case(name.trim()));
+        save_config(&config)?;
+        config.manual_presets.clone()
+    };
+    crate::tray::refresh_tray_menu(&app);
+    Ok(presets)
+}
+
+pub(crate) fn apply_preset(app: &AppHandle, name: &str, minutes: Option<u64>) -> Result<Option<DateTime<Utc>>, String> {
+    let state: State<'_, AppState> = app.state();
+    let preset = state.config.lock().unwrap().manual_presets.iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+        .cloned()
+        .ok_or_else(|| format!("No preset named {}", name))?;
+    let plan = preset_plan(&preset)?;
+    *state.busylight.manual_mode.lock().unwrap() = true;
+    let until = minutes.filter(|&m| m > 0).map(|m| Utc::now() + chrono::Duration::minutes(m as i64));
+    *state.manual_until.lock().unwrap() = until;
+    for (_, _, light) in busylights(&state) {
+        light.show(&plan);
+    }
+    crate::tray::refresh_tray_menu(app);
+    let _ = app.emit("manual-preset-applied", &preset.name);
+    Ok(until)
+}
+
+/// Turns manual mode on from the tray, holding whatever the lights show; the diagnostics view
+/// hears it as `manual-mode-started`
+pub(crate) fn start_manual_mode(app: &AppHandle) {
+    let state: State<'_, AppState> = app.state();
+    *state.busylight.manual_mode.lock().unwrap() = true;
+    *state.manual_until.lock().unwrap() = None;
+    crate::tray::refresh_tray_menu(app);
+    let _ = app.emit("manual-mode-started", ());
+}
+
+/// Ends manual mode from outside the diagnostics view, which hears it as `manual-mode-expired`
+pub(crate) fn end_manual_mode(app: &AppHandle) {
+    let state: State<'_, AppState> = app.state();
+    *state.busylight.manual_mode.lock().unwrap() = false;
+    *state.manual_until.lock().unwrap() = None;
+    crate::tray::refresh_tray_menu(app);
+    let _ = app.emit("manual-mode-expired", ());
+    let _ = app.emit("refresh_weather", ());
+}
+
+/// Whether the light pulses for precipitation: the settings window's "None (No Pulse)" horizon
+/// turns it off as well as `pulse`
+pub(crate) fn precip_pulse_enabled(config: &AppConfig) -> bool {
+    config.pulse && config.precip_horizon != "none"
+}
+
+/// Switches the main location from the tray to `location`, or back to detecting it with None, and
+/// refreshes; the settings window hears it as `location-changed`
+pub(crate) fn switch_location(app: &AppHandle, location: Option<String>) -> Result<(), String> {
+    let state: State<'_, AppState> = app.state();
+    let (auto_location, location) = {
+        let mut config = state.config.lock().unwrap();
+        config.auto_location = location.is_none();
+        if let Some(location) = location {
+            config.location = location;
+        }
+        save_config(&config)?;
+        (config.auto_location, config.location.clone())
+    };
+    crate::tray::refresh_tray_menu(app);
+    let _ = app.emit("location-changed", serde_json::json!({ "autoLocation": auto_location, "location": location }));
+    let _ = app.emit("refresh_weather", ());
+    Ok(())
+}
+
+/// Flips the precipitation pulse from the tray and saves it; the settings window hears the
+/// horizon it should show as `pulse-changed`
+pub(crate) fn set_precip_pulse(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let state: State<'_, AppState> = app.state();
+    let horizon = {
+        let mut config = state.config.lock().unwrap();
+        config.pulse = enabled;
+        // Nothing to pulse for without a horizon to look at
+        if enabled && config.precip_horizon == "none" {
+            config.precip_horizon = AppConfig::default().precip_horizon;
+        }
+        save_config(&config)?;
+        if enabled { config.precip_horizon.clone() } else { "none".to_string() }
+    };
+    crate::tray::refresh_tray_menu(app);
+    let _ = app.emit("pulse-changed", horizon);
+    let _ = app.emit("refresh_weather", ());
     Ok(())
 }
 
+// A preset's color at its brightness, steady or pulsing down to a fifth of it
+fn preset_plan(preset: &ManualPreset) -> Result<PulseState, String> {
+    let color = hex_to_rgb(&preset.color).ok_or_else(|| format!("Invalid color {}", preset.color))?;
+    let pct = preset.brightness.min(100);
+    Ok(PulseState {
+        active: preset.pulse,
+        color_srgb: color,
+        pct_high: pct,
+        pct_low: if preset.pulse { pct / 5 } else { pct },
+        speed_ms: preset.pulse_speed_ms.max(200),
+        sequence: Vec::new(),
+        effect: Effect::from_name(&preset.effect),
+    })
+}
+
 #[tauri::command]
 async fn open_external(url: String, app: AppHandle) -> Result<(), String> {
     let _ = app.opener().open_url(url, None::<&str>);
@@ -126,51 +541,275 @@ async fn open_external(url: String, app: AppHandle) -> Result<(), String> {
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ManualState {
+    #[serde(default)]
     temp: f64,
     pulse: bool,
     pulse_speed: u64,
-    max_brightness: u8
+    max_brightness: u8,
+    // Waveform of the test pulse; the precipitation one when empty
+    #[serde(default)]
+    effect: String,
+    // A fixed "#RRGGBB" instead of the color for `temp`
+    #[serde(default)]
+    color: Option<String>,
+    // Bottom of the pulse in percent; 0 when not given
+    #[serde(default)]
+    pulse_low: Option<u8>,
+}
+
+impl ManualState {
+    /// The plan for an explicit `color`, bypassing the weather rules entirely. None without one.
+    fn color_plan(&self) -> Option<Result<PulseState, String>> {
+        let hex = self.color.as_deref()?;
+        Some(hex_to_rgb(hex).ok_or_else(|| format!("Invalid color {}", hex)).map(|rgb| PulseState {
+            active: self.pulse,
+            color_srgb: rgb,
+            pct_high: self.max_brightness.min(100),
+            pct_low: self.pulse_low.unwrap_or(0).min(self.max_brightness.min(100)),
+            speed_ms: self.pulse_speed.max(200),
+            sequence: Vec::new(),
+            effect: Effect::from_name(&self.effect),
+        }))
+    }
 }
 
 #[tauri::command]
 async fn apply_manual_state(state_payload: ManualState, state: State<'_, AppState>) -> Result<(), String> {
     let is_manual = *state.busylight.manual_mode.lock().unwrap();
     if is_manual {
-        let mock_weather = WeatherState {
-            temperature: state_payload.temp,
-            has_precipitation: state_payload.pulse,
-            location_name: String::new(),
-            sun_times: crate::models::SunTimes { sunrise: None, sunset: None },
-            is_night: false,
-            provider: String::new(),
-            last_updated: chrono::Utc::now(),
-            debug_forecast: Vec::new()
-        };
-        // Note: Our manual config from UI doesn't have a unit toggle, but the
-        // slider assumes Celsius by default inside diag. Let's create a minimal config.
-        let mock_config = AppConfig { unit: "C".to_string(), ..Default::default() };
-        let hex_color = calculate_weather_color(&mock_weather, &mock_config);
-
-        if let Some(rgba) = hex_to_rgb(&hex_color) {
-            if state_payload.pulse {
-                if let Ok(mut p) = state.busylight.pulse_state.lock() {
-                    p.active = true;
-                    p.color_srgb = rgba;
-                    p.pct_high = state_payload.max_brightness;
-                    p.pct_low = state_payload.max_brightness / 2;
-                    p.speed_ms = state_payload.pulse_speed;
-                }
-            } else {
-                if let Ok(mut p) = state.busylight.pulse_state.lock() { p.active = false; }
-                if let Ok(mut bl) = state.busylight.bl.lock() {
-                    bl.light_pct(rgba.0, rgba.1, rgba.2, state_payload.max_brightness);
-                }
+        if let Some(plan) = state_payload.color_plan() {
+            let plan = plan?;
+            for (_, _, light) in busylights(&state) {
+                light.show(&plan);
             }
+            return Ok(());
+        }
+        // The diagnostics slider overrides speed and brightness, everything else is the real config
+        let config = AppConfig {
+            pulse: true,
+            pulse_speed: state_payload.pulse_speed,
+            max_brightness: state_payload.max_brightness,
+            ..state.config.lock().unwrap().clone()
+        };
+        let config = if state_payload.effect.is_empty() { config } else { AppConfig { pulse_effect: state_payload.effect, ..config } };
+        let (_, plan) = preview_plan(state_payload.temp, state_payload.pulse, false, &config);
+        for (_, _, light) in busylights(&state) {
+            light.show(&plan);
+        }
+    }
+    Ok(())
+}
+
+/// Runs a hypothetical reading (°C) through the pipeline's color, brightness and night rules.
+/// With `flash`, the Busylight shows the result for 2 seconds before returning to the weather.
+#[tauri::command]
+async fn preview_color(temp: f64, precip: bool, night: bool, flash: bool, app: AppHandle, state: State<'_, AppState>) -> Result<ColorPreview, String> {
+    let config = {
+        let ssid = state.current_ssid.lock().unwrap().clone();
+        state.config.lock().unwrap().for_network(ssid.as_deref())
+    };
+    let (color, plan) = preview_plan(temp, precip, night, &config);
+    let preview = ColorPreview {
+        color,
+        light_color: to_hex(plan.color_srgb),
+        brightness: plan.pct_high,
+        pulse: plan.active,
+        pulse_low: plan.pct_low,
+        speed_ms: plan.speed_ms,
+        night_mode: config.sunset_sunrise && night,
+    };
+
+    if flash && !busylights_claimed(&state, &config) {
+        for (_, _, light) in busylights(&state) {
+            light.show(&plan);
         }
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            update_weather_pipeline(&app).await;
+        });
+    }
+    Ok(preview)
+}
+
+// A made-up reading through the same color and light rules as `show_weather`; returns the
+// weather color and the Busylight plan
+fn preview_plan(temp: f64, precip: bool, night: bool, config: &AppConfig) -> (String, PulseState) {
+    let weather = WeatherState {
+        temperature: temp,
+        has_precipitation: precip,
+        precip_probability: if precip { 100.0 } else { 0.0 },
+        is_night: night,
+        last_updated: chrono::Utc::now(),
+        ..Default::default()
+    };
+    let hex_color = calculate_weather_color(&weather, config);
+    let (light_config, rgba) = night_adjusted(config, &weather, hex_to_rgb(&hex_color).unwrap_or((0, 0, 0)));
+    let plan = if quiet_hours(config, &weather) { lights_out() } else { busylight_plan(rgba, &weather, &light_config, None) };
+    (hex_color, plan)
+}
+
+#[tauri::command]
+async fn simulate_palette(vision_type: String, state: State<'_, AppState>) -> Result<Vec<PaletteSwatch>, String> {
+    let (theme, config) = {
+        let config = state.config.lock().unwrap();
+        (crate::themes::active(&config), config.clone())
+    };
+    let mut swatches = Vec::with_capacity(theme.stops.len());
+    for stop in &theme.stops {
+        let rgb = hex_to_rgb(&stop.color).ok_or_else(|| format!("Invalid scale color {}", stop.color))?;
+        let sim = crate::vision::simulate(rgb, &vision_type)
+            .ok_or_else(|| format!("Unknown vision type: {}", vision_type))?;
+        swatches.push(PaletteSwatch {
+            temp: config.display_temp(stop.temp),
+            color: stop.color.clone(),
+            simulated: to_hex(sim),
+        });
     }
+    Ok(swatches)
+}
+
+/// Built-in color themes followed by imported ones, with stops in the display unit
+#[tauri::command]
+async fn list_themes(state: State<'_, AppState>) -> Result<Vec<Theme>, String> {
+    let config = state.config.lock().unwrap();
+    Ok(crate::themes::all(&config).iter().map(|t| t.in_unit(&config.unit)).collect())
+}
+
+#[tauri::command]
+async fn apply_theme(name: String, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let settings = {
+        let mut cfg = state.config.lock().unwrap();
+        let theme = crate::themes::find(&cfg, &name).ok_or_else(|| format!("Unknown theme: {}", name))?;
+        cfg.theme = theme.name;
+        cfg.clone()
+    };
+    save_config(&settings)?;
+    tauri::async_runtime::spawn(async move {
+        update_weather_pipeline(&app).await;
+    });
     Ok(())
 }
 
+/// Adds a theme from its exported JSON and returns it as stored
+#[tauri::command]
+async fn import_theme(json: String, state: State<'_, AppState>) -> Result<Theme, String> {
+    let (theme, settings) = {
+        let mut cfg = state.config.lock().unwrap();
+        let theme = crate::themes::import(&mut cfg, &json)?;
+        (theme, cfg.clone())
+    };
+    save_config(&settings)?;
+    Ok(theme)
+}
+
+#[tauri::command]
+async fn export_theme(name: String, state: State<'_, AppState>) -> Result<String, String> {
+    let theme = {
+        let config = state.config.lock().unwrap();
+        crate::themes::find(&config, &name).ok_or_else(|| format!("Unknown theme: {}", name))?.in_unit(&config.unit)
+    };
+    serde_json::to_string_pretty(&theme).map_err(|e| e.to_string())
+}
+
+/// Built-in weather providers, in settings list order
+#[tauri::command]
+async fn list_providers() -> Result<Vec<ProviderInfo>, String> {
+    Ok(ProviderRegistry::builtin().list())
+}
+
+#[tauri::command]
+async fn list_plugins(state: State<'_, AppState>) -> Result<Vec<PluginManifest>, String> {
+    Ok(state.plugins.manifests())
+}
+
+#[tauri::command]
+async fn reload_plugins(state: State<'_, AppState>) -> Result<Vec<PluginManifest>, String> {
+    Ok(state.plugins.reload())
+}
+
+/// Snoozes the light for `minutes` (0 resumes immediately). Returns when the snooze ends.
+#[tauri::command]
+async fn snooze(minutes: u64, app: AppHandle) -> Result<Option<DateTime<Utc>>, String> {
+    let until = if minutes == 0 {
+        None
+    } else {
+        Some(Utc::now() + chrono::Duration::minutes(minutes as i64))
+    };
+    set_snooze(&app, until);
+    Ok(until)
+}
+
+pub(crate) fn set_snooze(app: &AppHandle, until: Option<DateTime<Utc>>) {
+    let state: State<'_, AppState> = app.state();
+    if let Ok(mut s) = state.snooze_until.lock() {
+        *s = until;
+    }
+    let _ = app.emit("refresh_weather", ());
+}
+
+pub(crate) fn is_paused(state: &AppState) -> bool {
+    state.paused.lock().map(|p| p.is_some()).unwrap_or(false)
+}
+
+/// Pauses updates (no fetching, no presence polling, every light off) or resumes them, putting
+/// back what the Busylights showed so a manual override carries on where it was
+pub(crate) fn set_paused(app: &AppHandle, paused: bool) {
+    let state: State<'_, AppState> = app.state();
+    if paused == is_paused(&state) {
+        return;
+    }
+    if paused {
+        let shown = busylights(&state).into_iter()
+            .map(|(_, key, light)| (key, light.pulse_state.lock().map(|p| p.clone()).unwrap_or_else(|_| lights_out())))
+            .collect();
+        *state.paused.lock().unwrap() = Some(shown);
+        let config = state.config.lock().unwrap().clone();
+        for sink in light_sinks(&state, &config) {
+            sink.output.off();
+        }
+        crate::tray::update_tray_tooltip(app, "WeatherLight - Paused");
+    } else {
+        let shown = state.paused.lock().unwrap().take().unwrap_or_default();
+        for (_, key, light) in busylights(&state) {
+            if let Some((_, plan)) = shown.iter().find(|(k, _)| *k == key) {
+                light.show(plan);
+            }
+        }
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            update_weather_pipeline(&app).await;
+        });
+    }
+    crate::tray::refresh_tray_menu(app);
+}
+
+fn snoozed_until(state: &AppState) -> Option<DateTime<Utc>> {
+    state.snooze_until.lock().ok().and_then(|s| *s).filter(|t| *t > Utc::now())
+}
+
+/// Current Wi-Fi network and the name of the profile it selects, if any
+#[tauri::command]
+async fn get_network_profile(state: State<'_, AppState>) -> Result<(Option<String>, Option<String>), String> {
+    let ssid = state.current_ssid.lock().unwrap().clone();
+    let profile = state.config.lock().unwrap()
+        .profile_for_network(ssid.as_deref())
+        .map(|p| p.name.clone());
+    Ok((ssid, profile))
+}
+
+/// Recorded refreshes from the last `hours`, oldest first
+#[tauri::command]
+async fn get_history(hours: u32, state: State<'_, AppState>) -> Result<Vec<WeatherSnapshot>, String> {
+    let store = state.history.as_ref().ok_or("History is unavailable")?;
+    store.query(Utc::now() - chrono::Duration::hours(hours as i64))
+}
+
+#[tauri::command]
+async fn get_history_stats(hours: u32, state: State<'_, AppState>) -> Result<HistoryStats, String> {
+    let store = state.history.as_ref().ok_or("History is unavailable")?;
+    store.stats(Utc::now() - chrono::Duration::hours(hours as i64))
+}
+
 #[tauri::command]
 async fn resize_settings(height: f64, app: AppHandle) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("main") {
@@ -192,37 +831,50 @@ pub fn run() {
         }))
         .plugin(tauri_plugin_autostart::init(tauri_plugin_autostart::MacosLauncher::LaunchAgent, Some(vec!["--minimized"])))
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .on_window_event(|window, event| match event {
             tauri::WindowEvent::CloseRequested { api, .. } => {
                 let _ = window.hide();
                 api.prevent_close();
             }
+            tauri::WindowEvent::ThemeChanged(_) => crate::tray::redraw_tray_icon(window.app_handle()),
             _ => {}
         })
         .setup(|app| {
             // Initialize App State
             let config = load_config();
             
-            let busylight = BusylightController::new().unwrap_or_else(|_e| {
-                Arc::new(BusylightController {
-                    bl: Mutex::new(crate::busylight::Busylight::new()),
-                    manual_mode: Mutex::new(false),
-                    pulse_state: Arc::new(Mutex::new(crate::busylight::PulseState {
-                        active: false,
-                        color_srgb: (0,0,0),
-                        pct_high: 100,
-                        pct_low: 50,
-                        speed_ms: 1000,
-                    })),
-                })
-            });
-            let weather_svc = Arc::new(WeatherService::new());
+            let busylight = BusylightManager::new();
+            let orchestrator_heartbeat = Heartbeat::new();
+            let history = match HistoryStore::open(&crate::history::get_history_path()) {
+                Ok(store) => Some(Arc::new(store)),
+                Err(e) => {
+                    println!("Weather history disabled: {}", e);
+                    None
+                }
+            };
+            let plugins = Arc::new(PluginRegistry::load());
+            let weather_svc = Arc::new(WeatherService::new(plugins.clone()));
 
             app.manage(AppState {
                 config: Mutex::new(config.clone()),
                 weather_state: Mutex::new(None),
+                bound_weather: Mutex::new(Vec::new()),
                 busylight: busylight.clone(),
                 weather_svc: weather_svc.clone(),
+                plugins: plugins.clone(),
+                hue: Arc::new(HueOutput::new()),
+                wled: Arc::new(WledOutput::new()),
+                teams: Arc::new(TeamsPresence::new()),
+                slack: Arc::new(SlackPresence::new()),
+                calendar: Arc::new(CalendarFeed::new()),
+                capture: Arc::new(CaptureMonitor::new()),
+                snooze_until: Mutex::new(None),
+                paused: Mutex::new(None),
+                manual_until: Mutex::new(None),
+                orchestrator_heartbeat: orchestrator_heartbeat.clone(),
+                current_ssid: Mutex::new(None),
+                history,
             });
 
             // Enforce OS autostart state matching config
@@ -237,19 +889,153 @@ pub fn run() {
             crate::tray::create_tray(app.handle())?;
 
             // Spawn background orchestrator thread
-            let app_handle = app.handle().clone();
+            spawn_orchestrator(app.handle().clone());
+
+            // Restart the pulse worker or orchestrator if either stops making progress
+            let pulse_ctrl = busylight.primary.clone();
+            let orchestrator_app = app.handle().clone();
+            crate::watchdog::spawn(vec![
+                crate::watchdog::Watched {
+                    name: "pulse worker",
+                    heartbeat: busylight.primary.pulse_heartbeat.clone(),
+                    stale_after: Duration::from_secs(10),
+                    restart: Box::new(move || {
+                        pulse_ctrl.pulse_state.clear_poison();
+                        pulse_ctrl.bl.clear_poison();
+                        BusylightController::spawn_pulse_worker(&pulse_ctrl);
+                    }),
+                },
+                crate::watchdog::Watched {
+                    name: "orchestrator",
+                    heartbeat: orchestrator_heartbeat.clone(),
+                    // A full pipeline run is several HTTP requests with 10s timeouts each, retried with backoff
+                    stale_after: Duration::from_secs(300),
+                    restart: Box::new(move || {
+                        orchestrator_app.state::<AppState>().clear_poison();
+                        spawn_orchestrator(orchestrator_app.clone());
+                    }),
+                },
+            ]);
+
+            // Optional one-off flash when precipitation is about to start
+            let flash_app = app.handle().clone();
+            app.listen("precip-imminent", move |_| {
+                let state = flash_app.state::<AppState>();
+                let config = state.config.lock().unwrap().clone();
+                let is_night_mode = config.sunset_sunrise
+                    && state.weather_state.lock().unwrap().as_ref().map(|w| w.is_night).unwrap_or(false);
+                let quiet = is_night_mode || config.quiet_hours.is_active(chrono::Local::now().naive_local());
+                if config.flash_before_precip && !quiet && !busylights_claimed(&state, &config) {
+                    for (_, _, light) in busylights(&state) {
+                        light.flash((255, 255, 255), 3);
+                    }
+                }
+            });
+
+            // MQTT readings arrive on their own schedule, refresh as they come in
+            let mqtt_app = app.handle().clone();
+            let mqtt_updates = weather_svc.mqtt_updates();
             tauri::async_runtime::spawn(async move {
-                let mut last_fetch = std::time::Instant::now();
-                let mut first_run = true;
-                    
                 loop {
-                    // 15 minute interval, or first run
-                    if first_run || last_fetch.elapsed() >= Duration::from_secs(15 * 60) {
-                        first_run = false;
-                        last_fetch = std::time::Instant::now();
-                        update_weather_pipeline(&app_handle).await;
+                    mqtt_updates.notified().await;
+                    // Temperature and precipitation topics are often published back to back
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    update_weather_pipeline(&mqtt_app).await;
+                }
+            });
+
+            // Teams presence on its own schedule; a call starting or ending decides the lights again
+            let teams_app = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    let state = teams_app.state::<AppState>();
+                    let config = state.config.lock().unwrap().teams.clone();
+                    let changed = if config.is_ready() && !off_hours(&state.config.lock().unwrap()) && !is_paused(&state) {
+                        match state.teams.poll(&config).await {
+                            Ok((changed, rotated)) => {
+                                if let Some(token) = rotated {
+                                    let mut app_config = state.config.lock().unwrap();
+                                    app_config.teams.refresh_token = token;
+                                    if let Err(e) = save_config(&app_config) {
+                                        println!("Failed to save the Teams sign-in: {}", e);
+                                    }
+                                }
+                                changed
+                            }
+                            Err(e) => {
+                                // Unknown presence goes back to the weather
+                                println!("Teams presence failed: {}", e);
+                                state.teams.reset()
+                            }
+                        }
+                    } else {
+                        state.teams.reset()
+                    };
+                    if changed {
+                        redecide_lights(&teams_app);
+                    }
+                    tokio::time::sleep(config.poll_interval()).await;
+                }
+            });
+
+            // Slack the same way, without a sign-in to keep fresh
+            let slack_app = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    let state = slack_app.state::<AppState>();
+                    let config = state.config.lock().unwrap().slack.clone();
+                    let changed = if config.is_ready() && !off_hours(&state.config.lock().unwrap()) && !is_paused(&state) {
+                        state.slack.poll(&config).await.unwrap_or_else(|e| {
+                            println!("Slack presence failed: {}", e);
+                            state.slack.reset()
+                        })
+                    } else {
+                        state.slack.reset()
+                    };
+                    if changed {
+                        redecide_lights(&slack_app);
+                    }
+                    tokio::time::sleep(config.poll_interval()).await;
+                }
+            });
+
+            // The calendar is fetched every `refreshMinutes` but checked often, so meetings start on time
+            let calendar_app = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    let state = calendar_app.state::<AppState>();
+                    let config = state.config.lock().unwrap().calendar.clone();
+                    let changed = if config.is_ready() && !off_hours(&state.config.lock().unwrap()) && !is_paused(&state) {
+                        state.calendar.poll(&config).await.unwrap_or_else(|e| {
+                            println!("Calendar failed: {}", e);
+                            state.calendar.reset()
+                        })
+                    } else {
+                        state.calendar.reset()
+                    };
+                    if changed {
+                        redecide_lights(&calendar_app);
+                    }
+                    tokio::time::sleep(crate::calendar::CHECK_INTERVAL).await;
+                }
+            });
+
+            // Camera and microphone use is checked on a blocking thread; the OS calls can be slow
+            let capture_app = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    let state = capture_app.state::<AppState>();
+                    let config = state.config.lock().unwrap().capture.clone();
+                    let changed = if config.is_ready() && !off_hours(&state.config.lock().unwrap()) && !is_paused(&state) {
+                        let monitor = state.capture.clone();
+                        tokio::task::spawn_blocking(move || monitor.check(&config)).await.unwrap_or(false)
+                    } else {
+                        state.capture.reset()
+                    };
+                    if changed {
+                        redecide_lights(&capture_app);
                     }
-                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    tokio::time::sleep(crate::capture::CHECK_INTERVAL).await;
                 }
             });
 
@@ -271,12 +1057,38 @@ pub fn run() {
             detect_location,
             validate_location,
             get_weather_state,
+            get_forecast,
+            get_provider_health,
             get_device_info,
             get_busylight_status,
+            busylight_udev_rule,
+            list_busylights,
+            calibrate_busylight,
+            test_device,
+            pair_hue_bridge,
+            list_hue_targets,
+            teams_sign_in_start,
+            teams_sign_in_finish,
             set_manual_mode,
             apply_manual_state,
+            apply_manual_preset,
+            save_manual_preset,
+            delete_manual_preset,
+            preview_color,
             open_external,
-            resize_settings
+            resize_settings,
+            simulate_palette,
+            list_themes,
+            apply_theme,
+            import_theme,
+            export_theme,
+            list_providers,
+            list_plugins,
+            reload_plugins,
+            snooze,
+            get_network_profile,
+            get_history,
+            get_history_stats
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
@@ -289,150 +1101,985 @@ pub fn run() {
         });
 }
 
+// Slowest weather refresh outside `workHours`
+const OFF_HOURS_REFRESH: Duration = Duration::from_secs(60 * 60);
+
+// Background loop: periodic weather refresh, snooze and manual mode expiry and disconnect detection.
+// Each call starts a new generation; a replaced loop exits on its next tick.
+fn spawn_orchestrator(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let heartbeat = state.orchestrator_heartbeat.clone();
+    let busylight = state.busylight.clone();
+    let generation = heartbeat.next_generation();
+
+    tauri::async_runtime::spawn(async move {
+        let mut last_fetch = std::time::Instant::now();
+        let mut last_network_check: Option<std::time::Instant> = None;
+        let mut local_address = crate::network::local_address();
+        let mut last_address_check = std::time::Instant::now();
+        let mut last_precip_alert: Option<DateTime<Utc>> = None;
+        let mut first_run = true;
+        // Random extra wait per cycle so installs don't all hit the APIs on the same minute
+        let mut poll_offset = Duration::ZERO;
+        let mut was_connected = busylight.primary.bl.lock().map(|bl| bl.is_connected()).unwrap_or(false);
+        let mut last_device_scan = std::time::Instant::now();
+        let mut was_waking = false;
+        let mut was_quiet = false;
+        let mut was_resting = off_hours(&app_handle.state::<AppState>().config.lock().unwrap());
+            
+        loop {
+            if heartbeat.generation() != generation {
+                break;
+            }
+            heartbeat.beat();
+
+            // Switch network profile when the Wi-Fi network changes (checked every minute)
+            let has_profiles = !app_handle.state::<AppState>().config.lock().unwrap().network_profiles.is_empty();
+            if has_profiles && last_network_check.is_none_or(|t| t.elapsed() >= Duration::from_secs(60)) {
+                last_network_check = Some(std::time::Instant::now());
+                let ssid = tokio::task::spawn_blocking(crate::network::current_ssid).await.ok().flatten();
+                let changed = {
+                    let state = app_handle.state::<AppState>();
+                    let mut current = state.current_ssid.lock().unwrap();
+                    if *current != ssid {
+                        *current = ssid;
+                        true
+                    } else {
+                        false
+                    }
+                };
+                if changed && !first_run {
+                    update_weather_pipeline(&app_handle).await;
+                }
+            }
+
+            // Refresh right away when connectivity comes back or the machine joins another network,
+            // rather than leaving a stale (or dark) light until the next scheduled fetch (checked every 10s)
+            if last_address_check.elapsed() >= Duration::from_secs(10) {
+                last_address_check = std::time::Instant::now();
+                let address = crate::network::local_address();
+                if address != local_address {
+                    println!("Network changed: {:?} -> {:?}", local_address, address);
+                    local_address = address;
+                    if address.is_some() && !first_run {
+                        // Failures while offline say nothing about the providers
+                        app_handle.state::<AppState>().weather_svc.reset_provider_health();
+                        last_fetch = std::time::Instant::now();
+                        update_weather_pipeline(&app_handle).await;
+                    }
+                }
+            }
+
+            // Configured interval (re-read every tick so settings apply without a restart), or first
+            // run. Outside working hours the lights are off, so the weather can wait an hour.
+            let (refresh_interval, resting) = {
+                let config = app_handle.state::<AppState>().config.lock().unwrap().clone();
+                (config.refresh_interval(), off_hours(&config))
+            };
+            let refresh_interval = if resting { refresh_interval.max(OFF_HOURS_REFRESH) } else { refresh_interval };
+            if first_run || last_fetch.elapsed() >= refresh_interval + poll_offset {
+                first_run = false;
+                last_fetch = std::time::Instant::now();
+                poll_offset = weather::jitter(refresh_interval / 10);
+                update_weather_pipeline(&app_handle).await;
+            }
+
+            // The tray's "Updated 14:32 · next in 9 min", which only changes by the minute
+            let (updated, paused) = {
+                let state = app_handle.state::<AppState>();
+                let updated = state.weather_state.lock().unwrap().as_ref().map(|w| w.last_updated);
+                (updated, is_paused(&state))
+            };
+            let next_in = (refresh_interval + poll_offset).saturating_sub(last_fetch.elapsed());
+            crate::tray::update_tray_status(updated, (!paused).then_some(next_in));
+
+            // Resume weather display once a snooze runs out
+            let snooze_expired = {
+                let state = app_handle.state::<AppState>();
+                let mut until = state.snooze_until.lock().unwrap();
+                if matches!(*until, Some(t) if t <= Utc::now()) {
+                    *until = None;
+                    true
+                } else {
+                    false
+                }
+            };
+            if snooze_expired {
+                update_weather_pipeline(&app_handle).await;
+            }
+
+            // Same for a timed manual override, which people tend to forget is on
+            let manual_expired = {
+                let state = app_handle.state::<AppState>();
+                let mut until = state.manual_until.lock().unwrap();
+                if matches!(*until, Some(t) if t <= Utc::now()) {
+                    *until = None;
+                    *busylight.manual_mode.lock().unwrap() = false;
+                    true
+                } else {
+                    false
+                }
+            };
+            if manual_expired {
+                crate::tray::refresh_tray_menu(&app_handle);
+                let _ = app_handle.emit("manual-mode-expired", ());
+                update_weather_pipeline(&app_handle).await;
+            }
+
+            // A wake-up sunrise starts and ends on the clock, not with a refresh
+            let waking = {
+                let state = app_handle.state::<AppState>();
+                let sunrise = state.weather_state.lock().unwrap().as_ref().and_then(|w| w.sun_times.sunrise);
+                let wake_up = state.config.lock().unwrap().wake_up.clone();
+                wake_up.window(sunrise, Utc::now()).is_some()
+            };
+            if waking != was_waking {
+                was_waking = waking;
+                if !first_run {
+                    update_weather_pipeline(&app_handle).await;
+                }
+            }
+
+            // Likewise quiet hours
+            let quiet = app_handle.state::<AppState>().config.lock().unwrap().quiet_hours.is_active(chrono::Local::now().naive_local());
+            if quiet != was_quiet {
+                was_quiet = quiet;
+                if !first_run {
+                    update_weather_pipeline(&app_handle).await;
+                }
+            }
+
+            // And working hours, which start with fresh weather
+            if resting != was_resting {
+                was_resting = resting;
+                if !first_run {
+                    last_fetch = std::time::Instant::now();
+                    update_weather_pipeline(&app_handle).await;
+                }
+            }
+
+            // Heads-up 15 minutes before forecast precipitation starts, once per start time
+            let imminent = {
+                let state = app_handle.state::<AppState>();
+                let starts = state.weather_state.lock().unwrap().as_ref().and_then(|w| w.precip_starts_at);
+                starts.filter(|_| !is_paused(&state)).filter(|t| {
+                    let until = *t - Utc::now();
+                    until > chrono::Duration::zero() && until <= chrono::Duration::minutes(15) && last_precip_alert != Some(*t)
+                })
+            };
+            if let Some(starts) = imminent {
+                last_precip_alert = Some(starts);
+                let _ = app_handle.emit("precip-imminent", starts);
+            }
+
+            // Hotplug: lights coming and going are noticed within 2 seconds. Replugged ones get
+            // their last frame back; a new one has nothing to show until the pipeline runs.
+            if last_device_scan.elapsed() >= Duration::from_secs(2) {
+                last_device_scan = std::time::Instant::now();
+                let lights = busylight.clone();
+                let added = tokio::task::spawn_blocking(move || lights.sync()).await.unwrap_or(false);
+                if added && !first_run {
+                    update_weather_pipeline(&app_handle).await;
+                }
+            }
+
+            let connected = busylight.primary.bl.lock().map(|bl| bl.is_connected()).unwrap_or(was_connected);
+            if was_connected && !connected {
+                let config = app_handle.state::<AppState>().config.lock().unwrap().clone();
+                crate::sound::play_event(&config, crate::sound::SoundEvent::DeviceDisconnect);
+            }
+            was_connected = connected;
+
+            let before_sleep = std::time::SystemTime::now();
+            tokio::time::sleep(Duration::from_secs(1)).await;
+
+            // The monotonic sleep doesn't count time spent suspended but the wall clock does, so a
+            // big jump means the machine just woke up: reopen the (now stale) HID handle and refresh
+            if before_sleep.elapsed().is_ok_and(|gap| gap >= Duration::from_secs(30)) {
+                println!("Resumed from sleep, refreshing");
+                let lights = busylight.clone();
+                let _ = tokio::task::spawn_blocking(move || lights.reconnect()).await;
+                app_handle.state::<AppState>().weather_svc.reset_provider_health();
+                last_fetch = std::time::Instant::now();
+                update_weather_pipeline(&app_handle).await;
+            }
+        }
+    });
+}
+
 // Orchestrator logic
 async fn update_weather_pipeline(app: &AppHandle) {
     let state: State<'_, AppState> = app.state();
     
-    let config = { state.config.lock().unwrap().clone() };
+    let config = {
+        let ssid = state.current_ssid.lock().unwrap().clone();
+        state.config.lock().unwrap().for_network(ssid.as_deref())
+    };
+    state.busylight.set_fade(config.crossfade_ms);
+    state.busylight.set_keep_alive(config.keep_alive_seconds * 1000);
+    state.busylight.set_calibrations(config.busylight_calibrations());
+
+    // Nothing to fetch or show until `set_paused` resumes
+    if is_paused(&state) {
+        return;
+    }
     
     // Validate minimally
-    if (config.location.is_empty() && !config.auto_location) || (config.provider == "openweathermap" && config.api_key.is_empty()) {
+    if (config.location.is_empty() && !config.auto_location) || (config.requires_api_key() && config.api_key.is_empty()) {
         crate::tray::update_tray_tooltip(app, "WeatherLight - Setup Required");
+        show_status(&state, &config, PipelineStatus::SetupRequired, |name| config.location_for_device(name).is_none());
         return;
     }
 
-    match state.weather_svc.fetch(&config).await {
-        Ok(weather) => {
-            let is_night_mode = config.sunset_sunrise && weather.is_night;
-            
-            // Generate tooltip string
-            let display_temp = if config.unit == "F" {
-                (weather.temperature * 9.0 / 5.0) + 32.0
-            } else {
-                weather.temperature
-            }.round();
-            let is_night_mode = config.sunset_sunrise && weather.is_night;
-            
-            // Tooltip string
-            let short_location = weather.location_name.split(',').next().unwrap_or(&weather.location_name);
-            let mut tooltip = format!("{}: {}°{}", short_location, weather.temperature.round(), config.unit);
-            if weather.has_precipitation { tooltip.push_str(" (Precip)"); }
-            if is_night_mode { tooltip.push_str(" (Night)"); }
-            
-            crate::tray::update_tray_tooltip(app, &tooltip);
-
-            // Calculate color
-            let hex_color = calculate_weather_color(&weather, &config);
-
-            // Update Tray Icon
-            crate::tray::update_tray_icon(app, &hex_color, is_night_mode);
-
-            // Update Busylight if not in manual mode
-            let is_manual = *state.busylight.manual_mode.lock().unwrap();
-            if !is_manual {
-                if let Some(rgba) = hex_to_rgb(&hex_color) {
-                    if is_night_mode || rgba == (0,0,0) {
-                        if let Ok(mut bl) = state.busylight.bl.lock() { bl.off(); }
-                        if let Ok(mut p) = state.busylight.pulse_state.lock() { p.active = false; }
-                    } else if weather.has_precipitation && config.pulse {
-                        if let Ok(mut p) = state.busylight.pulse_state.lock() {
-                            p.active = true;
-                            p.color_srgb = rgba;
-                            p.pct_high = config.max_brightness;
-                            p.pct_low = config.max_brightness / 2;
-                            p.speed_ms = config.pulse_speed;
-                        }
-                    } else {
-                        if let Ok(mut p) = state.busylight.pulse_state.lock() { p.active = false; }
-                        if let Ok(mut bl) = state.busylight.bl.lock() { 
-                            bl.light_pct(rgba.0, rgba.1, rgba.2, config.max_brightness);
-                        }
-                    }
+    if config.status_light.standby && state.weather_state.lock().unwrap().is_none() {
+        show_status(&state, &config, PipelineStatus::Waiting, |name| config.location_for_device(name).is_none());
+    }
+
+    let weather = match state.weather_svc.fetch(&config).await {
+        Ok(mut weather) => {
+            if config.aurora.enabled {
+                match state.weather_svc.fetch_kp_index().await {
+                    Ok(kp) => weather.kp_index = Some(kp),
+                    Err(e) => println!("Kp index fetch failed: {}", e),
                 }
             }
-            
-            // Store state
-            if let Ok(mut ws) = state.weather_state.lock() {
-                *ws = Some(weather);
+            if config.wants_tomorrow() {
+                match state.weather_svc.fetch_forecast(&config).await {
+                    Ok(days) => weather.tomorrow_high = days.get(1).map(|d| d.high),
+                    Err(e) => println!("Tomorrow's forecast unavailable: {}", e),
+                }
+            }
+            // Compared against earlier refreshes before this one is recorded
+            if let (Some(store), Some(pressure)) = (state.history.as_ref(), weather.pressure) {
+                match store.pressure_tendency(&weather.location_name, pressure, Utc::now()) {
+                    Ok(tendency) => weather.pressure_tendency = tendency,
+                    Err(e) => println!("Pressure tendency unavailable: {}", e),
+                }
+            }
+            if let Err(e) = crate::weather_cache::save(&crate::weather_cache::get_cache_path(), &weather) {
+                println!("Failed to cache weather: {}", e);
+            }
+            Some(weather)
+        }
+        Err(e) => {
+            // Keep the light on the last good data through outages, including right after an offline start
+            let now = Utc::now();
+            let cached = state.weather_state.lock().unwrap().clone()
+                .filter(|w| crate::weather_cache::is_usable(w, now))
+                .or_else(|| crate::weather_cache::load(&crate::weather_cache::get_cache_path(), now));
+            if cached.is_some() {
+                println!("Weather fetch failed, showing cached data: {}", e);
             }
-        },
-        Err(_) => {
+            cached.map(|mut weather| {
+                weather.stale = true;
+                weather.is_night = state.weather_svc.is_night_for(&weather.sun_times, &config);
+                weather
+            })
+        }
+    };
+
+    match weather {
+        Some(weather) => {
+            show_weather(app, &state, &config, weather.clone());
+            // An open settings window follows along instead of polling `get_weather_state`
+            let _ = app.emit("weather_updated", WeatherUpdated { weather, busylight: busylight_status(&state) });
+        }
+        None => {
             crate::tray::update_tray_tooltip(app, "Error fetching weather");
-            if let Ok(mut p) = state.busylight.pulse_state.lock() { p.active = false; }
-            if let Ok(mut bl) = state.busylight.bl.lock() { bl.off(); }
-        }
-    }
-}
-
-const COLOR_SCALE: &[(f64, &str)] = &[
-    (-50.0, "#e1e1ff"), (-49.0, "#dfdfff"), (-48.0, "#dfdfff"), (-47.0, "#dcdcff"), (-46.0, "#dcdcff"),
-    (-45.0, "#dadaff"), (-44.0, "#dadaff"), (-43.0, "#d7d7ff"), (-42.0, "#d2d2ff"), (-41.0, "#cbcbff"),
-    (-40.0, "#c4c4ff"), (-39.0, "#bdbdff"), (-38.0, "#b6b6ff"), (-37.0, "#afafff"), (-36.0, "#a9a9ff"),
-    (-35.0, "#a4a4ff"), (-34.0, "#9e9eff"), (-33.0, "#9898ff"), (-32.0, "#9292ff"), (-31.0, "#8c8cff"),
-    (-30.0, "#8787ff"), (-29.0, "#8181ff"), (-28.0, "#7373f4"), (-27.0, "#6565e7"), (-26.0, "#5757da"),
-    (-25.0, "#4b4bcd"), (-24.0, "#4040c1"), (-23.0, "#3737b6"), (-22.0, "#2e2eab"), (-21.0, "#2626a0"),
-    (-20.0, "#1f1f96"), (-19.0, "#19198c"), (-18.0, "#141483"), (-17.0, "#11127e"), (-16.0, "#0e1078"),
-    (-15.0, "#0b0f73"), (-14.0, "#0a0d6e"), (-13.0, "#080d6b"), (-12.0, "#060b66"), (-11.0, "#050a62"),
-    (-10.0, "#04095d"), (-9.0,  "#030859"), (-8.0,  "#020856"), (-7.0,  "#010752"), (-6.0,  "#01064e"),
-    (-5.0,  "#01054a"), (-4.0,  "#000546"), (-3.0,  "#000443"), (-2.0,  "#000440"), (-1.0,  "#00033d"),
-    (0.0,   "#00033a"), (1.0,   "#000b57"), (2.0,   "#001d7c"), (3.0,   "#003bab"), (4.0,   "#0068e4"),
-    (5.0,   "#008cd7"), (6.0,   "#009e98"), (7.0,   "#00b466"), (8.0,   "#00cb40"), (9.0,   "#00e425"),
-    (10.0,  "#00ff13"), (11.0,  "#01ff0b"), (12.0,  "#07ff05"), (13.0,  "#17ff02"), (14.0,  "#33ff01"),
-    (15.0,  "#60ff00"), (16.0,  "#89f400"), (17.0,  "#9cda00"), (18.0,  "#b1c100"), (19.0,  "#c8ab00"),
-    (20.0,  "#e19600"), (21.0,  "#fc8300"), (22.0,  "#ff7300"), (23.0,  "#ff6600"), (24.0,  "#ff5900"),
-    (25.0,  "#ff4d00"), (26.0,  "#ff4300"), (27.0,  "#ff3900"), (28.0,  "#ff3000"), (29.0,  "#ff2800"),
-    (30.0,  "#ff2100"), (31.0,  "#ff1b00"), (32.0,  "#ff1500"), (33.0,  "#ff1000"), (34.0,  "#ff0c00"),
-    (35.0,  "#ff0900"), (36.0,  "#ff0600"), (37.0,  "#ff0400"), (38.0,  "#ff0300"), (39.0,  "#ff0100"),
-    (40.0,  "#ff0101"), (41.0,  "#ff0003"), (42.0,  "#ff0006"), (43.0,  "#ff000a"), (44.0,  "#f1000b"),
-    (45.0,  "#dc000a"), (46.0,  "#cb000a"), (47.0,  "#b80009"), (48.0,  "#a90008"), (49.0,  "#980008"),
-    (50.0,  "#8a0007"), (51.0,  "#7c0006"), (52.0,  "#6e0006"), (53.0,  "#630005"), (54.0,  "#570005"),
-    (55.0,  "#4e0004"), (56.0,  "#440004"), (57.0,  "#3c0003"), (58.0,  "#330003"), (59.0,  "#2d0003"),
-    (60.0,  "#260003"), (61.0,  "#200002"), (62.0,  "#1b0002"), (63.0,  "#160002"), (64.0,  "#120001"),
-    (65.0,  "#0e0001"), (66.0,  "#0d0001"), (67.0,  "#0d0001"), (68.0,  "#0d0001"), (69.0,  "#0d0001"),
-    (70.0,  "#0d0001")
-];
-
-fn calculate_weather_color(weather: &WeatherState, config: &AppConfig) -> String {
+            show_status(&state, &config, PipelineStatus::FetchFailed, |name| config.location_for_device(name).is_none());
+        }
+    }
 
+    update_bound_locations(app, &config).await;
+}
+
+// Tray, lights, history and alerts for a fetched (or replayed) weather state
+fn show_weather(app: &AppHandle, state: &AppState, config: &AppConfig, weather: WeatherState) {
+    let is_night_mode = config.sunset_sunrise && weather.is_night;
     
-    // Convert current temperature to match gradient steps (gradient is in F in electron version originally but colorScale.js is in C)
-    // Wait, colorScale.js says `{ temp: 0, color: '00033a' }, // 32°F`, meaning the primary `temp` lookup is in Celsius!
-    let temp_c = weather.temperature;
-    
-    // Clamp to mapping array bounds
-    if temp_c <= COLOR_SCALE[0].0 { return COLOR_SCALE[0].1.to_string(); }
-    let last = COLOR_SCALE.len() - 1;
-    if temp_c >= COLOR_SCALE[last].0 { return COLOR_SCALE[last].1.to_string(); }
+    let date_override = config.active_date_override(chrono::Local::now().date_naive());
+    let snoozed = snoozed_until(state);
+
+    // Tooltip string
+    let mut tooltip = build_tooltip(&weather, config, is_night_mode);
+    if let Some(rule) = date_override {
+        if !rule.name.is_empty() {
+            tooltip.push_str(&format!("\n{}", rule.name));
+        }
+    }
+    if let Some(until) = snoozed {
+        tooltip.push_str(&format!("\nSnoozed until {}", until.with_timezone(&chrono::Local).format("%H:%M")));
+    }
+    // Calculate color
+    let hex_color = calculate_weather_color(&weather, config);
+
+    // Update tray tooltip and icon in one go
+    let icon = crate::tray::IconSpec {
+        hex_color: hex_color.clone(),
+        is_night_mode,
+        label: crate::tray::IconLabel::new(&config.tray_icon, (config.display_temp(weather.temperature).round() as i64).to_string()),
+        condition: crate::tray::Condition::of(&weather).filter(|_| config.tray_conditions),
+        shape: crate::tray::IconShape::from_name(&config.tray_icon_shape),
+        temperature: weather.temperature,
+        precip_color: (if weather.precip_type.as_deref() == Some("Snow") { &config.precip_styles.snow } else { &config.precip_styles.rain }).color.clone(),
+        precip_probability: weather.precip_probability,
+    };
+    crate::tray::update_tray(app, &tooltip, &icon);
+    crate::tray::update_tray_forecast(app, &weather, config);
+
+    drive_lights(state, config, &weather);
     
-    // Find interpolation bracket
-    for i in 1..COLOR_SCALE.len() {
-        if temp_c <= COLOR_SCALE[i].0 {
-            let start_node = &COLOR_SCALE[i - 1];
-            let end_node = &COLOR_SCALE[i];
-            
-            // Linear interpolate value between the two gradient stops
-            let range = end_node.0 - start_node.0;
-            let value = if range == 0.0 { 0.0 } else { (temp_c - start_node.0) / range };
-            
-            if let (Some(mut start), Some(end)) = (hex_to_rgb(start_node.1), hex_to_rgb(end_node.1)) {
-                let r = (start.0 as f32 + (end.0 as f32 - start.0 as f32) * value as f32) as u8;
-                let g = (start.1 as f32 + (end.1 as f32 - start.1 as f32) * value as f32) as u8;
-                let b = (start.2 as f32 + (end.2 as f32 - start.2 as f32) * value as f32) as u8;
-                return format!("#{val:02x}{val2:02x}{val3:02x}", val=r, val2=g, val3=b);
+    // Replayed data was already recorded when it was fresh
+    if let Some(store) = state.history.as_ref().filter(|_| !weather.stale) {
+        let snapshot = WeatherSnapshot {
+            timestamp: weather.last_updated,
+            temperature: weather.temperature,
+            has_precipitation: weather.has_precipitation,
+            precip_probability: weather.precip_probability,
+            color: hex_color.clone(),
+            device_connected: state.busylight.primary.bl.lock().map(|bl| bl.is_connected()).unwrap_or(false),
+            location_name: weather.location_name.clone(),
+            provider: weather.provider.clone(),
+            pressure: weather.pressure,
+        };
+        if let Err(e) = store.record(&snapshot).and_then(|_| store.prune(config.history_retention_days)) {
+            println!("Failed to record weather history: {}", e);
+        }
+    }
+
+    // Store state
+    if let Ok(mut ws) = state.weather_state.lock() {
+        let heat_now = config.heat_warning.is_triggered(&weather.debug_forecast, Utc::now());
+        let heat_before = ws.as_ref().map(|prev| config.heat_warning.is_triggered(&prev.debug_forecast, Utc::now())).unwrap_or(false);
+        if heat_now && !heat_before && !weather.stale {
+            notify_heat_warning(app, &weather, config);
+        }
+        // Only chime on the transition, and never for the first fetch after launch
+        if let Some(previous) = ws.as_ref().filter(|_| !weather.stale) {
+            if rain_within_hour(&weather) && !rain_within_hour(previous) {
+                crate::sound::play_event(config, crate::sound::SoundEvent::RainSoon);
+                if config.jingle.on_precip_onset {
+                    play_jingle(state, config, &weather);
+                }
+            }
+            if config.severe_alerts.is_active(&weather) && !config.severe_alerts.is_active(previous) {
+                crate::sound::play_event(config, crate::sound::SoundEvent::SevereAlert);
+                if config.jingle.on_severe_alert {
+                    play_jingle(state, config, &weather);
+                }
             }
-            return start_node.1.to_string();
         }
+        *ws = Some(weather);
+    }
+}
+
+// Updates every light output not bound to another location with whatever claims it (see
+// `arbiter.rs`); bound ones are driven by `drive_bound_light`
+fn drive_lights(state: &AppState, config: &AppConfig, weather: &WeatherState) {
+    let Some(rgba) = hex_to_rgb(&calculate_weather_color(weather, config)) else { return };
+    let date_override = config.active_date_override(chrono::Local::now().date_naive());
+    let wake_up = wake_up_plan(config, weather, rgba, Utc::now());
+    let (light_config, rgba) = night_adjusted(config, weather, rgba);
+    for sink in light_sinks(state, config) {
+        if config.location_for_device(&sink.name).is_none() {
+            let mut arbiter = weather_claims(state, &sink, rgba, weather, &light_config, date_override);
+            if let Some(plan) = &wake_up {
+                arbiter.claim(Priority::WakeUp, Claim::Show(plan.clone()));
+            }
+            drive_sink(&sink, arbiter, rgba, weather, &light_config);
+        }
+    }
+}
+
+/// Decides every light again on the weather already fetched, when only a claim changed (a call
+/// starting, a meeting ending). Fetching stays on the orchestrator's interval, so this never
+/// spends a provider's quota.
+fn redecide_lights(app: &AppHandle) {
+    let state: State<'_, AppState> = app.state();
+    let config = {
+        let ssid = state.current_ssid.lock().unwrap().clone();
+        state.config.lock().unwrap().for_network(ssid.as_deref())
+    };
+    if is_paused(&state) {
+        return;
+    }
+
+    let unbound = |name: &str| config.location_for_device(name).is_none();
+    let weather = state.weather_state.lock().unwrap().clone();
+    match weather {
+        _ if (config.location.is_empty() && !config.auto_location) || (config.requires_api_key() && config.api_key.is_empty()) => {
+            show_status(&state, &config, PipelineStatus::SetupRequired, unbound);
+        }
+        // Night may have fallen since the fetch
+        Some(mut weather) => {
+            weather.is_night = state.weather_svc.is_night_for(&weather.sun_times, &config);
+            drive_lights(&state, &config, &weather);
+        }
+        None => show_status(&state, &config, PipelineStatus::Waiting, unbound),
+    }
+
+    let bound = state.bound_weather.lock().unwrap().clone();
+    for binding in config.device_locations.iter().filter(|b| !b.location.trim().is_empty()) {
+        let Some(sink) = light_sinks(&state, &config).into_iter().find(|s| s.name == binding.device) else { continue };
+        let loc_config = location_config(&config, &binding.location);
+        match bound.iter().find(|(device, _)| *device == binding.device) {
+            Some((_, weather)) => {
+                let weather = WeatherState { is_night: state.weather_svc.is_night_for(&weather.sun_times, &loc_config), ..weather.clone() };
+                drive_bound_light(&state, &loc_config, &sink, &weather);
+            }
+            None => show_status(&state, &config, PipelineStatus::FetchFailed, |name| name == binding.device),
+        }
+    }
+}
+
+// Busylight ringtone for an alert; never at night or in quiet hours, while snoozed or in manual mode
+fn play_jingle(state: &AppState, config: &AppConfig, weather: &WeatherState) {
+    let jingle = &config.jingle;
+    let quiet = weather.is_night || config.quiet_hours.is_active(chrono::Local::now().naive_local()) || busylights_claimed(state, config);
+    if !jingle.enabled || quiet {
+        return;
+    }
+    match Ringtone::from_name(&jingle.tone) {
+        Some(tone) => state.busylight.primary.play_jingle(tone, jingle.volume, Duration::from_millis(jingle.duration_ms.clamp(500, 10_000))),
+        None => println!("Unknown Busylight ringtone {}", jingle.tone),
+    }
+}
+
+fn notify_heat_warning(app: &AppHandle, weather: &WeatherState, config: &AppConfig) {
+    let threshold = config.display_temp(config.heat_warning.threshold).round();
+    let short_location = weather.location_name.split(',').next().unwrap_or(&weather.location_name);
+    let body = format!(
+        "{}: {} at or above {}°{} for {}+ hours",
+        short_location, heat_basis_label(&config.heat_warning.basis), threshold, config.unit, config.heat_warning.hours
+    );
+    if let Err(e) = app.notification().builder().title("Extreme heat warning").body(body).show() {
+        println!("Failed to show heat warning notification: {}", e);
+    }
+}
+
+fn heat_basis_label(basis: &str) -> &'static str {
+    match basis {
+        "air" => "temperature",
+        "heat_index" => "heat index",
+        _ => "feels-like temperature",
+    }
+}
+
+// Lights bound to their own location get a separate fetch and color, independent of the tray
+async fn update_bound_locations(app: &AppHandle, config: &AppConfig) {
+    let state: State<'_, AppState> = app.state();
+
+    for binding in &config.device_locations {
+        if binding.location.trim().is_empty() { continue; }
+
+        let loc_config = location_config(config, &binding.location);
+        let is_target = |name: &str| name == binding.device;
+        let Some(sink) = light_sinks(&state, config).into_iter().find(|s| s.name == binding.device) else { continue };
+
+        let fetched = match state.weather_svc.fetch(&loc_config).await {
+            Ok(weather) => {
+                drive_bound_light(&state, &loc_config, &sink, &weather);
+                Some(weather)
+            }
+            Err(e) => {
+                println!("Weather fetch for {} ({}) failed: {}", binding.device, binding.location, e);
+                show_status(&state, config, PipelineStatus::FetchFailed, is_target);
+                None
+            }
+        };
+        // Kept for `redecide_lights`
+        let mut bound = state.bound_weather.lock().unwrap();
+        bound.retain(|(device, _)| *device != binding.device);
+        bound.extend(fetched.map(|weather| (binding.device.clone(), weather)));
     }
-    
-    "#FFFFFF".to_string()
 }
 
-fn hex_to_rgb(hex: &str) -> Option<(u8, u8, u8)> {
-    if hex.len() != 7 || !hex.starts_with('#') { return None; }
-    let r = u8::from_str_radix(&hex[1..3], 16).ok()?;
-    let g = u8::from_str_radix(&hex[3..5], 16).ok()?;
-    let b = u8::from_str_radix(&hex[5..7], 16).ok()?;
-    Some((r, g, b))
+// The config a light bound to `location` runs its rules with
+fn location_config(config: &AppConfig, location: &str) -> AppConfig {
+    AppConfig {
+        auto_location: false,
+        location: location.to_string(),
+        ..config.clone()
+    }
+}
+
+// A light bound to its own location, on that location's weather
+fn drive_bound_light(state: &AppState, loc_config: &AppConfig, sink: &NamedSink, weather: &WeatherState) {
+    let Some(rgba) = hex_to_rgb(&calculate_weather_color(weather, loc_config)) else { return };
+    let date_override = loc_config.active_date_override(chrono::Local::now().date_naive());
+    // The sunrise follows the bound location's own
+    let wake_up = wake_up_plan(loc_config, weather, rgba, Utc::now());
+    let (light_config, rgba) = night_adjusted(loc_config, weather, rgba);
+    let mut arbiter = weather_claims(state, sink, rgba, weather, &light_config, date_override);
+    if let Some(plan) = wake_up {
+        arbiter.claim(Priority::WakeUp, Claim::Show(plan));
+    }
+    drive_sink(sink, arbiter, rgba, weather, &light_config);
 }
 
+/// Why the pipeline has no weather for a light
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PipelineStatus {
+    SetupRequired,
+    FetchFailed,
+    /// The first fetch since launch is under way
+    Waiting,
+}
+
+const STANDBY_COLOR: &str = "#FFFFFF";
+
+// Shows a pipeline status on the light outputs that `matches` selects: a slow pulse of the status
+// color with `statusLight`, a dim white breathe before any weather with `statusLight.standby`,
+// otherwise off. Manual mode and snooze win, as they do over the weather.
+fn show_status<F: Fn(&str) -> bool>(state: &AppState, config: &AppConfig, status: PipelineStatus, matches: F) {
+    let rule = &config.status_light;
+    let no_weather = state.weather_state.lock().unwrap().is_none();
+    let style = match status {
+        PipelineStatus::SetupRequired if rule.enabled => Some((rule.setup_color.as_str(), rule.brightness, rule.pulse_speed_ms)),
+        PipelineStatus::FetchFailed if rule.enabled => Some((rule.error_color.as_str(), rule.brightness, rule.pulse_speed_ms)),
+        _ if rule.standby && no_weather => Some((STANDBY_COLOR, rule.standby_brightness, rule.standby_pulse_speed_ms)),
+        _ => None,
+    };
+    let pulse = style
+        .and_then(|(color, brightness, speed_ms)| Some((hex_to_rgb(color)?, brightness.min(config.max_brightness), speed_ms.max(200))));
+
+    let plan = match pulse {
+        Some((rgb, brightness, speed_ms)) => PulseState { active: true, color_srgb: rgb, pct_high: brightness, pct_low: 0, speed_ms, sequence: Vec::new(), effect: Effect::Breathe },
+        None => lights_out(),
+    };
+    for sink in light_sinks(state, config) {
+        if matches(&sink.name) {
+            let mut arbiter = user_claims(state, config, sink.output.kind());
+            if let Some(quiet) = scheduled_quiet(config, &plan) {
+                arbiter.claim(Priority::Quiet, Claim::Show(quiet));
+            }
+            arbiter.claim(Priority::Weather, Claim::Show(plan.clone()));
+            if let Some(plan) = arbiter.plan() {
+                sink.output.show(&plan);
+            }
+        }
+    }
+}
+
+fn lights_out() -> PulseState {
+    PulseState { active: false, color_srgb: (0, 0, 0), pct_high: 0, pct_low: 0, speed_ms: 0, sequence: Vec::new(), effect: Effect::Breathe }
+}
+
+// The claims that have nothing to do with the weather: manual mode holds the Busylights for the
+// diagnostics view, snooze, paused updates and the hours outside `workHours` turn every output off, and a Teams
+// call, a Slack huddle or Do Not Disturb, a busy calendar event, or the camera or microphone in
+// use shows its busy color
+fn user_claims(state: &AppState, config: &AppConfig, kind: &str) -> Arbiter {
+    let mut arbiter = Arbiter::new();
+    if kind == "busylight" && *state.busylight.manual_mode.lock().unwrap() {
+        arbiter.claim(Priority::Manual, Claim::Hold);
+    }
+    if snoozed_until(state).is_some() || is_paused(state) {
+        arbiter.claim(Priority::Manual, Claim::Show(lights_out()));
+    }
+    if off_hours(config) {
+        arbiter.claim(Priority::OffHours, Claim::Show(lights_out()));
+    }
+    let busy = |color: &str| Claim::Show(PulseState {
+        active: false, color_srgb: hex_to_rgb(color).unwrap_or((255, 0, 0)), pct_high: config.max_brightness, pct_low: config.max_brightness, speed_ms: 0, sequence: Vec::new(), effect: Effect::Breathe,
+    });
+    if config.teams.enabled && state.teams.is_busy() {
+        arbiter.claim(Priority::Busy, busy(&config.teams.busy_color));
+    }
+    if config.slack.enabled && state.slack.is_busy() {
+        arbiter.claim(Priority::Busy, busy(&config.slack.busy_color));
+    }
+    if config.calendar.enabled && state.calendar.is_busy() {
+        arbiter.claim(Priority::Busy, busy(&config.calendar.meeting_color));
+    }
+    if config.capture.enabled && state.capture.is_busy() {
+        arbiter.claim(Priority::Busy, busy(&config.capture.busy_color));
+    }
+    arbiter
+}
+
+// Outside `workHours`, when the lights stay off and polling slows down
+fn off_hours(config: &AppConfig) -> bool {
+    config.work_hours.is_off_hours(chrono::Local::now().naive_local())
+}
+
+// Flashes, previews and jingles only interrupt Busylights nobody has claimed
+fn busylights_claimed(state: &AppState, config: &AppConfig) -> bool {
+    user_claims(state, config, "busylight").highest().is_some()
+}
+
+// Everything that wants `sink` for a weather state, from a severe alert down to its role
+fn weather_claims(state: &AppState, sink: &NamedSink, rgba: (u8, u8, u8), weather: &WeatherState, config: &AppConfig, date_override: Option<&DateOverride>) -> Arbiter {
+    let mut arbiter = user_claims(state, config, sink.output.kind());
+    if let Some(plan) = severe_alert_plan(rgba, weather, config) {
+        arbiter.claim(Priority::SevereAlert, Claim::Show(plan));
+    }
+    if quiet_hours(config, weather) {
+        arbiter.claim(Priority::Quiet, Claim::Show(lights_out()));
+    }
+    let plan = role_plan(&sink.role, rgba, weather, config, date_override);
+    if let Some(quiet) = scheduled_quiet(config, &plan) {
+        arbiter.claim(Priority::Quiet, Claim::Show(quiet));
+    }
+    arbiter.claim(if plan.active { Priority::Weather } else { Priority::Temperature }, Claim::Show(plan));
+    arbiter
+}
+
+fn quiet_hours(config: &AppConfig, weather: &WeatherState) -> bool {
+    // An aurora is the one reason to light up during night mode, unless the light only dims
+    let night_off = config.sunset_sunrise && weather.is_night && !config.night_dim.enabled;
+    night_off && !config.aurora.is_triggered(weather)
+}
+
+// Scheduled quiet hours turn `plan` off, or cap it at their brightness
+fn scheduled_quiet(config: &AppConfig, plan: &PulseState) -> Option<PulseState> {
+    let quiet = &config.quiet_hours;
+    if !quiet.is_active(chrono::Local::now().naive_local()) {
+        return None;
+    }
+    if quiet.brightness == 0 {
+        return Some(lights_out());
+    }
+    let cap = quiet.brightness.min(config.max_brightness);
+    Some(PulseState { pct_high: plan.pct_high.min(cap), pct_low: plan.pct_low.min(cap), ..plan.clone() })
+}
+
+// Official warnings pulse on every output, over everything else
+fn severe_alert_plan(rgba: (u8, u8, u8), weather: &WeatherState, config: &AppConfig) -> Option<PulseState> {
+    let rule = &config.severe_alerts;
+    rule.is_active(weather).then(|| PulseState {
+        active: true,
+        color_srgb: hex_to_rgb(&rule.color).unwrap_or(rgba),
+        pct_high: config.max_brightness,
+        pct_low: config.max_brightness / 5,
+        speed_ms: rule.pulse_speed_ms.max(200),
+        sequence: Vec::new(),
+        effect: Effect::from_name(&rule.effect),
+    })
+}
+
+// With night dimming, the light rules run at the night brightness on the tinted weather color
+fn night_adjusted(config: &AppConfig, weather: &WeatherState, rgba: (u8, u8, u8)) -> (AppConfig, (u8, u8, u8)) {
+    let dim = &config.night_dim;
+    if !(dim.enabled && config.sunset_sunrise && weather.is_night) {
+        return (config.clone(), rgba);
+    }
+    let tinted = match hex_to_rgb(&dim.tint) {
+        Some(tint) => crate::color::mix(rgba, tint, 0.5, Interpolation::from_name(&config.interpolation)),
+        None => rgba,
+    };
+    (AppConfig { max_brightness: dim.brightness.min(config.max_brightness), ..config.clone() }, tinted)
+}
+
+// The sunrise while `wakeUp` is ramping, toward the weather color at full `maxBrightness`
+fn wake_up_plan(config: &AppConfig, weather: &WeatherState, rgba: (u8, u8, u8), now: DateTime<Utc>) -> Option<PulseState> {
+    let (start, end) = config.wake_up.window(weather.sun_times.sunrise, now)?;
+    let from = hex_to_rgb(&config.wake_up.color).unwrap_or((139, 0, 0));
+    Some(PulseState {
+        active: true,
+        color_srgb: rgba,
+        pct_high: config.max_brightness,
+        pct_low: 0,
+        // Unused by the ramp, but the pulse thread skips plans without a speed
+        speed_ms: 1000,
+        sequence: Vec::new(),
+        effect: Effect::Sunrise(Ramp { from, start, end }),
+    })
+}
+
+// Shows the claim that won the sink. Where that's just the weather color, a sink that can show a
+// gradient gets the coming hours instead (WLED with `forecastHours`).
+fn drive_sink(sink: &NamedSink, arbiter: Arbiter, rgba: (u8, u8, u8), weather: &WeatherState, config: &AppConfig) {
+    let Some((priority, Claim::Show(plan))) = arbiter.winner() else { return };
+    let plain = priority == Priority::Temperature && sink.role == "weather" && plan.color_srgb == rgba && plan.pct_high == config.max_brightness;
+    if plain && config.wled.forecast_hours > 0 && sink.output.show_gradient(&forecast_colors(weather, config), config.max_brightness) {
+        return;
+    }
+    sink.output.show(&plan);
+}
+
+// What a light with `role` (see `BusylightRole`) shows; "weather" is the full rule set
+fn role_plan(role: &str, rgba: (u8, u8, u8), weather: &WeatherState, config: &AppConfig, date_override: Option<&DateOverride>) -> PulseState {
+    let steady = |color: (u8, u8, u8), pct: u8| PulseState {
+        active: false, color_srgb: color, pct_high: pct, pct_low: pct, speed_ms: 0, sequence: Vec::new(), effect: Effect::Breathe,
+    };
+
+    match role {
+        "temperature" => steady(rgba, config.max_brightness),
+        "precipitation" => {
+            let style = if weather.precip_type.as_deref() == Some("Snow") { &config.precip_styles.snow } else { &config.precip_styles.rain };
+            let color = hex_to_rgb(&style.color).unwrap_or((0, 80, 255));
+            if weather.has_precipitation && config.pulse {
+                let (pct_low, speed_ms) = pulse_params(weather.precip_probability, config);
+                PulseState { active: true, color_srgb: color, pct_high: config.max_brightness, pct_low, speed_ms, sequence: Vec::new(), effect: Effect::from_name(&config.pulse_effect) }
+            } else {
+                // Dark when it's dry, full brightness when it's certain
+                let share = (weather.precip_probability / 100.0).clamp(0.0, 1.0);
+                steady(color, (config.max_brightness as f64 * share).round() as u8)
+            }
+        }
+        "tomorrow" => {
+            let tomorrow = weather.tomorrow_high
+                .map(|high| crate::themes::active(config).color_at(config.banded_temp(high), Interpolation::from_name(&config.interpolation)))
+                .and_then(|hex| hex_to_rgb(&hex));
+            steady(tomorrow.unwrap_or(rgba), config.max_brightness)
+        }
+        _ => busylight_plan(rgba, weather, config, date_override),
+    }
+}
+
+// The weather rules in priority order, shared by the pipeline and `preview_color`. Night mode,
+// snooze, manual mode and severe alerts sit above them in the arbiter.
+fn busylight_plan(rgba: (u8, u8, u8), weather: &WeatherState, config: &AppConfig, date_override: Option<&DateOverride>) -> PulseState {
+    let (pulse_low, pulse_speed) = pulse_params(weather.precip_probability, config);
+    let pulse = |color: (u8, u8, u8), pct_low: u8, speed_ms: u64| PulseState {
+        active: true, color_srgb: color, pct_high: config.max_brightness, pct_low, speed_ms, sequence: Vec::new(), effect: Effect::Breathe,
+    };
+    // Steps through the colors at full brightness instead of breathing
+    let sequence = |colors: &[String], speed_ms: u64| {
+        let colors: Vec<(u8, u8, u8)> = colors.iter().filter_map(|c| hex_to_rgb(c)).collect();
+        PulseState { sequence: colors.clone(), ..pulse(colors.first().copied().unwrap_or(rgba), config.max_brightness, speed_ms) }
+    };
+    let steady = |color: (u8, u8, u8), pct: u8| PulseState {
+        active: false, color_srgb: color, pct_high: pct, pct_low: pct, speed_ms: 0, sequence: Vec::new(), effect: Effect::Breathe,
+    };
+
+    if rgba == (0,0,0) {
+        steady((0, 0, 0), 0)
+    } else if config.high_wind.is_triggered(weather) {
+        pulse(hex_to_rgb(&config.high_wind.color).unwrap_or(rgba), config.max_brightness / 3, config.high_wind.pulse_speed_ms.max(200))
+    } else if config.thunder.is_triggered(weather) {
+        sequence(&config.thunder.colors, config.thunder.interval_ms.max(100))
+    } else if config.storm_watch.is_triggered(weather) {
+        pulse(hex_to_rgb(&config.storm_watch.color).unwrap_or(rgba), config.max_brightness / 4, config.storm_watch.pulse_speed_ms.max(200))
+    } else if config.aurora.is_triggered(weather) {
+        // Slow, deep breathe so it reads as something other than rain
+        pulse(hex_to_rgb(&config.aurora.color).unwrap_or(rgba), config.max_brightness / 5, config.aurora.pulse_speed_ms.max(500))
+    } else if config.heat_warning.is_triggered(&weather.debug_forecast, Utc::now()) {
+        // Heat warning outranks the rules below it
+        let rule = &config.heat_warning;
+        sequence(&rule.colors, if rule.interval_ms > 0 { rule.interval_ms } else { 400 })
+    } else if config.frost.is_triggered(weather, chrono::Local::now().hour()) {
+        sequence(&config.frost.colors, config.frost.interval_ms.max(200))
+    } else if config.pollen.pulses(weather) {
+        pulse(hex_to_rgb(&config.pollen.color).unwrap_or(rgba), config.max_brightness / 3, config.pollen.pulse_speed_ms.max(200))
+    } else if let Some(rule) = date_override {
+        // Special-date colors replace the weather color until the rule stops matching
+        sequence(&rule.colors, if rule.interval_ms > 0 { rule.interval_ms } else { 1000 })
+    } else if weather.has_precipitation && config.pulse {
+        let (color, speed) = precip_pulse_style(weather, config, rgba, pulse_speed);
+        PulseState { effect: Effect::from_name(&config.pulse_effect), ..pulse(color, pulse_low, speed) }
+    } else if config.fog.is_triggered(weather) {
+        // Dim and steady, so it reads as murk rather than a warning
+        steady(hex_to_rgb(&config.fog.color).unwrap_or(rgba), config.fog.brightness.min(config.max_brightness))
+    } else {
+        steady(rgba, config.max_brightness)
+    }
+}
+
+// Weather colors for the next `wled.forecastHours` hours, the current one first
+fn forecast_colors(weather: &WeatherState, config: &AppConfig) -> Vec<(u8, u8, u8)> {
+    let from = Utc::now() - chrono::Duration::hours(1);
+    weather.debug_forecast.iter()
+        .filter(|h| h.time > from)
+        .take(config.wled.forecast_hours)
+        .filter_map(|h| {
+            let hour = WeatherState {
+                temperature: h.temp,
+                apparent_temperature: h.apparent_temp,
+                precip_probability: h.precip_prob,
+                ..weather.clone()
+            };
+            hex_to_rgb(&calculate_weather_color(&hour, config))
+        })
+        .collect()
+}
+
+// Applies the rain/snow style, if any, to the ordinary precipitation pulse
+fn precip_pulse_style(weather: &WeatherState, config: &AppConfig, rgba: (u8, u8, u8), pulse_speed: u64) -> ((u8, u8, u8), u64) {
+    match config.precip_styles.style_for(weather) {
+        Some(style) => (
+            hex_to_rgb(&style.color).unwrap_or(rgba),
+            if style.pulse_speed_ms > 0 { style.pulse_speed_ms.max(200) } else { pulse_speed },
+        ),
+        None => (rgba, pulse_speed),
+    }
+}
+
+fn rain_within_hour(weather: &WeatherState) -> bool {
+    weather.precip_starts_at
+        .map(|t| t - chrono::Utc::now() <= chrono::Duration::hours(1))
+        .unwrap_or(false)
+}
+
+// Scales pulse depth and speed with precipitation probability: around 40% gives a shallow,
+// slow breathe and 95%+ a deep, fast pulse. `pulse_speed` is the midpoint of the speed range.
+// Returns (pct_low, speed_ms).
+pub fn pulse_params(precip_probability: f64, config: &AppConfig) -> (u8, u64) {
+    let intensity = ((precip_probability - 40.0) / (95.0 - 40.0)).clamp(0.0, 1.0);
+
+    let depth = 0.2 + 0.6 * intensity;
+    let pct_low = (config.max_brightness as f64 * (1.0 - depth)).round() as u8;
+
+    let speed_ms = (config.pulse_speed as f64 * (1.5 - intensity)).round() as u64;
+
+    (pct_low, speed_ms.max(200))
+}
+
+// Headline on the first line, secondary metrics on their own lines below it.
+// Windows and macOS render the newlines; platforms without multi-line tooltips just show the headline.
+fn build_tooltip(weather: &WeatherState, config: &AppConfig, is_night_mode: bool) -> String {
+    let is_f = config.unit == "F";
+    let display = |t: f64| config.display_temp(t).round();
+    let display_temp = display(weather.temperature);
+
+    let short_location = weather.location_name.split(',').next().unwrap_or(&weather.location_name);
+    let mut lines = vec![format!("{}: {}°{}", short_location, display_temp, config.unit)];
+    if let (Some(high), Some(low)) = (weather.today_high, weather.today_low) {
+        lines[0].push_str(&format!(", H:{}° L:{}°", display(high), display(low)));
+    }
+    if let Some(feels) = weather.apparent_temperature.filter(|_| config.light_basis == "apparent") {
+        let display_feels = display(feels);
+        lines[0].push_str(&format!(", feels like {}°{}", display_feels, config.unit));
+    }
+    if let Some(normal) = weather.normal_high.filter(|_| config.light_basis == "anomaly") {
+        let high = weather.today_high.unwrap_or(weather.temperature);
+        // A difference scales by 9/5 without the offset
+        let delta = if is_f { (high - normal) * 9.0 / 5.0 } else { high - normal }.round();
+        let normal_label = format!("normal {}°{}", display(normal), config.unit);
+        lines.push(match delta {
+            d if d > 0.0 => format!("High {}° above {}", d, normal_label),
+            d if d < 0.0 => format!("High {}° below {}", -d, normal_label),
+            _ => format!("High at {}", normal_label),
+        });
+    }
+    if weather.precip_probability >= 1.0 {
+        let kind = if weather.precip_type.as_deref() == Some("Snow") { "snow" } else { "rain" };
+        lines[0].push_str(&format!(", {}% {}", weather.precip_probability.round(), kind));
+    } else if weather.has_precipitation {
+        lines[0].push_str(match weather.precip_type.as_deref() {
+            Some("Snow") => " (Snow)",
+            Some("Rain") => " (Rain)",
+            _ => " (Precip)",
+        });
+    }
+    if is_night_mode {
+        match weather.sun_times.moon_phase {
+            Some(phase) => lines[0].push_str(&format!(" (Night, {})", crate::sun::moon_phase_name(phase).to_lowercase())),
+            None => lines[0].push_str(" (Night)"),
+        }
+    }
+    if let Some(aq) = &weather.air_quality {
+        let mut line = format!("Air quality {} ({})", aq.us_aqi.round(), crate::color::aqi_band(aq.us_aqi).1);
+        if let Some(pm) = aq.pm2_5 {
+            line.push_str(&format!(", PM2.5 {} µg/m³", pm.round()));
+        }
+        lines.push(line);
+    }
+    if weather.stale {
+        lines.push(format!("Offline, data from {} ago", crate::weather_cache::age_label(weather, chrono::Utc::now())));
+    }
+
+    let mut metrics = Vec::new();
+    if let Some(h) = weather.humidity {
+        metrics.push(format!("Humidity {}%", h.round()));
+    }
+    if let Some(dp) = weather.dew_point {
+        let display_dp = display(dp);
+        metrics.push(format!("Dew point {}°{}", display_dp, config.unit));
+    }
+    if let Some(w) = weather.wind_speed {
+        let (speed, unit) = if is_f { (w / 1.609, "mph") } else { (w, "km/h") };
+        match weather.wind_gust.filter(|g| *g > w) {
+            Some(g) => {
+                let gust = if is_f { g / 1.609 } else { g };
+                metrics.push(format!("Wind {} {} (gusts {})", speed.round(), unit, gust.round()));
+            }
+            None => metrics.push(format!("Wind {} {}", speed.round(), unit)),
+        }
+    }
+    if let Some(p) = weather.pressure {
+        match weather.pressure_tendency {
+            Some(t) => metrics.push(format!("Pressure {} hPa ({:+.1}/3h)", p.round(), t)),
+            None => metrics.push(format!("Pressure {} hPa", p.round())),
+        }
+    }
+    if !metrics.is_empty() {
+        lines.push(metrics.join(", "));
+    }
+
+    // Say where the data came from once the primary provider has been skipped
+    let primary = config.provider.strip_prefix("plugin:")
+        .or_else(|| ProviderRegistry::builtin().get(&config.provider).map(|p| p.info().name));
+    if !config.fallback_providers.is_empty() && primary != Some(weather.provider.as_str()) {
+        lines.push(format!("Via {} (fallback)", weather.provider));
+    }
+
+    if config.heat_warning.is_triggered(&weather.debug_forecast, chrono::Utc::now()) {
+        let now = chrono::Utc::now();
+        let peak = weather.debug_forecast.iter()
+            .filter(|item| item.time < now + chrono::Duration::hours(24))
+            .map(|item| config.heat_warning.reading(item))
+            .reduce(f64::max);
+        match peak {
+            Some(p) => {
+                let display_peak = display(p);
+                lines.push(format!("Extreme heat warning ({} up to {}°{})", heat_basis_label(&config.heat_warning.basis), display_peak, config.unit));
+            }
+            None => lines.push("Extreme heat warning".to_string()),
+        }
+    }
+    if config.high_wind.is_triggered(weather) {
+        lines.push("High wind warning".to_string());
+    }
+    if weather.frost_warning {
+        match config.frost.forecast_low(&weather.debug_forecast, chrono::Utc::now()) {
+            Some(low) => {
+                let display_low = display(low);
+                lines.push(format!("Frost expected (low {}°{})", display_low, config.unit));
+            }
+            None => lines.push("Frost expected".to_string()),
+        }
+    }
+    if weather.has_thunder {
+        lines.push("Thunderstorms expected".to_string());
+    }
+    if weather.has_fog {
+        match weather.visibility {
+            Some(v) if v < 10.0 => {
+                let (distance, unit) = if is_f { (v / 1.609, "mi") } else { (v, "km") };
+                lines.push(format!("Fog (visibility {:.1} {})", distance, unit));
+            }
+            _ => lines.push("Fog expected".to_string()),
+        }
+    }
+    if config.storm_watch.is_triggered(weather) {
+        lines.push("Storm approaching (pressure falling fast)".to_string());
+    }
+    let high_pollen = config.pollen.high_counts(weather);
+    if !high_pollen.is_empty() {
+        lines.push(format!("High pollen: {}", high_pollen.join(", ")));
+    }
+    if config.aurora.is_triggered(weather) {
+        lines.push(format!("Aurora possible (Kp {:.1})", weather.kp_index.unwrap_or_default()));
+    }
+    // Official warnings can be long; two is about all a tooltip fits
+    lines.extend(weather.alerts.iter().take(2).cloned());
+
+    if let Some(starts) = weather.precip_starts_at {
+        let kind = match weather.debug_forecast.iter().find(|f| f.time == starts).map(|f| f.precip_type.as_str()) {
+            Some("Snow") => "Snow",
+            _ => "Rain",
+        };
+        if starts > chrono::Utc::now() {
+            lines.push(format!("{} ~{}", kind, starts.with_timezone(&chrono::Local).format("%H:%M")));
+        } else if !weather.has_precipitation {
+            lines.push(format!("{} now", kind));
+        }
+    }
+
+    lines.join("\n")
+}
+
+
 
@@ -1,24 +1,118 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use crate::busylight::{Calibration, DeviceStatus};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SunTimes {
     pub sunrise: Option<DateTime<Utc>>,
     pub sunset: Option<DateTime<Utc>>,
+    /// Start of morning civil twilight (sun 6° below the horizon)
+    #[serde(default)]
+    pub civil_dawn: Option<DateTime<Utc>>,
+    /// End of evening civil twilight
+    #[serde(default)]
+    pub civil_dusk: Option<DateTime<Utc>>,
+    /// Fraction of the lunar cycle: 0 new moon, 0.5 full moon
+    #[serde(default)]
+    pub moon_phase: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WeatherState {
     pub temperature: f64,
+    /// "Feels like" counterpart of `temperature`, over the same horizon, when the provider reports it
+    #[serde(default)]
+    pub apparent_temperature: Option<f64>,
+    /// Highest and lowest temperature over the local day, from the current reading and the forecast hours that fall on it
+    #[serde(default)]
+    pub today_high: Option<f64>,
+    #[serde(default)]
+    pub today_low: Option<f64>,
     pub has_precipitation: bool,
+    /// A thunderstorm is forecast within the precipitation horizon
+    #[serde(default)]
+    pub has_thunder: bool,
+    /// "Rain" or "Snow" for the first wet hour when `has_precipitation`; `None` when dry or unknown
+    #[serde(default)]
+    pub precip_type: Option<String>,
     pub location_name: String,
     pub sun_times: SunTimes,
     pub is_night: bool,
     pub provider: String,
     pub last_updated: DateTime<Utc>,
     pub debug_forecast: Vec<ForecastItem>,
+    /// Highest precipitation probability (0-100) within the configured precipitation horizon
+    pub precip_probability: f64,
+    /// Relative humidity in percent at the current hour
+    pub humidity: Option<f64>,
+    /// Dew point in °C at the current hour, derived from temperature and humidity when not reported
+    #[serde(default)]
+    pub dew_point: Option<f64>,
+    /// Wind speed in km/h at the current hour
+    pub wind_speed: Option<f64>,
+    /// Wind gusts in km/h at the current hour
+    #[serde(default)]
+    pub wind_gust: Option<f64>,
+    /// Sea-level pressure in hPa at the current hour
+    #[serde(default)]
+    pub pressure: Option<f64>,
+    /// Pressure change in hPa over the last 3 hours, from the recorded history
+    #[serde(default)]
+    pub pressure_tendency: Option<f64>,
+    /// Start of the next forecast block that crosses the precipitation threshold
+    pub precip_starts_at: Option<DateTime<Utc>>,
+    /// Headlines of official warnings in effect, for providers that publish them
+    pub alerts: Vec<String>,
+    /// Replayed from the last successful fetch because the current one failed
+    #[serde(default)]
+    pub stale: bool,
+    /// NOAA planetary Kp index (0-9), fetched on top of the provider's data when aurora alerts are on
+    #[serde(default)]
+    pub kp_index: Option<f64>,
+    /// Current air quality, fetched on top of the provider's data when the light follows AQI
+    #[serde(default)]
+    pub air_quality: Option<AirQuality>,
+    /// Today's peak pollen counts, fetched on top of the provider's data when allergy mode is on
+    #[serde(default)]
+    pub pollen: Option<Pollen>,
+    /// Current visibility in km, when the provider reports it
+    #[serde(default)]
+    pub visibility: Option<f64>,
+    /// Fog or visibility below `fog.visibilityKm`, now or within `fog.hours`
+    #[serde(default)]
+    pub has_fog: bool,
+    /// Average daily high (°C) for the date at this location, fetched when the light follows the anomaly
+    #[serde(default)]
+    pub normal_high: Option<f64>,
+    /// Tomorrow's forecast high (°C), fetched when a light has the "tomorrow" role
+    #[serde(default)]
+    pub tomorrow_high: Option<f64>,
+    /// Open-Meteo's 15-minute precipitation for the next couple of hours, with the "nowcast" horizon
+    #[serde(default)]
+    pub nowcast: Vec<NowcastItem>,
+    /// The forecast low within `frost.windowHours` is below `frost.threshold`
+    #[serde(default)]
+    pub frost_warning: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AirQuality {
+    /// US EPA air quality index (0-500)
+    pub us_aqi: f64,
+    /// Fine particulate matter in µg/m³
+    pub pm2_5: Option<f64>,
+}
+
+/// Pollen in grains/m³; `None` where the model has no coverage (it is Europe-only)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pollen {
+    pub birch: Option<f64>,
+    pub grass: Option<f64>,
+    pub ragweed: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,8 +120,39 @@ pub struct WeatherState {
 pub struct ForecastItem {
     pub time: DateTime<Utc>,
     pub temp: f64,
+    /// "Feels like" temperature, when the provider reports one
+    pub apparent_temp: Option<f64>,
     pub precip_prob: f64,
     pub precip_type: String,
+    #[serde(default)]
+    pub humidity: Option<f64>,
+    #[serde(default)]
+    pub dew_point: Option<f64>,
+}
+
+/// One 15-minute slot of the precipitation nowcast
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NowcastItem {
+    /// Start of the slot
+    pub time: DateTime<Utc>,
+    /// mm over the 15 minutes
+    pub precip_amount: f64,
+    pub is_snow: bool,
+}
+
+/// One day of the multi-day forecast returned by `get_forecast`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyForecast {
+    /// Local date at the forecast location
+    pub date: NaiveDate,
+    pub high: f64,
+    pub low: f64,
+    /// Highest hourly probability of precipitation over the day, 0-100
+    pub precip_probability: f64,
+    /// Short description of the day's dominant weather, e.g. "Rain showers"
+    pub condition: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,4 +177,137 @@ pub struct DeviceInfoResult {
     pub path: Option<String>,
     pub vendor_id: u16,
     pub product_id: u16,
+    /// False when the device was found but couldn't be opened
+    pub accessible: bool,
+}
+
+/// A connected Busylight for the settings window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BusylightListing {
+    /// `DeviceInfo::key()`, what `busylightRoles` refers to
+    pub key: String,
+    /// Name in `deviceLocations`: "busylight" for the first light, "busylight:<key>" for the others
+    pub name: String,
+    pub product: Option<String>,
+    pub role: String,
+    /// The light's entry in `busylightCalibrations`, or the defaults
+    pub calibration: Calibration,
+}
+
+/// The test color a Busylight shows while being calibrated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalibrationStep {
+    /// Index into `busylight::TEST_COLORS`
+    pub step: usize,
+    pub total: usize,
+    /// The color, and what to look for
+    pub label: String,
+}
+
+/// A Microsoft sign-in in progress: the user enters `user_code` at `verification_uri`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamsDeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Microsoft's own instructions, ready to show
+    pub message: String,
+    /// Seconds between checks for the user finishing
+    pub interval: u64,
+    pub expires_in: u64,
+}
+
+/// A Hue light or group (room, zone) on the paired bridge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HueTarget {
+    /// "light:<id>" or "group:<id>", what `hue.targets` holds
+    pub id: String,
+    pub name: String,
+    /// The bridge's own type, e.g. "Extended color light" or "Room"
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BusylightStatusResult {
+    pub connected: bool,
+    pub status: DeviceStatus,
+    /// Human-readable explanation for anything other than a working connection
+    pub message: Option<String>,
+}
+
+/// Payload of the `weather_updated` event, sent after each refresh that has weather to show
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherUpdated {
+    pub weather: WeatherState,
+    pub busylight: BusylightStatusResult,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaletteSwatch {
+    pub temp: f64,
+    pub color: String,
+    pub simulated: String,
+}
+
+/// What the light would show for a hypothetical reading (see `preview_color`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorPreview {
+    /// Weather color from the active theme, as drawn on the tray icon
+    pub color: String,
+    /// Color the Busylight shows, after precipitation styles and night mode
+    pub light_color: String,
+    pub brightness: u8,
+    pub pulse: bool,
+    pub pulse_low: u8,
+    pub speed_ms: u64,
+    pub night_mode: bool,
+}
+
+/// One recorded weather refresh
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub temperature: f64,
+    pub has_precipitation: bool,
+    pub precip_probability: f64,
+    pub color: String,
+    pub device_connected: bool,
+    pub location_name: String,
+    pub provider: String,
+    /// Sea-level pressure in hPa, when the provider reports it
+    #[serde(default)]
+    pub pressure: Option<f64>,
+}
+
+/// Circuit breaker state of a provider that has been failing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderHealth {
+    pub provider: String,
+    pub consecutive_failures: u32,
+    /// Set while the provider is skipped
+    pub open_until: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryStats {
+    pub count: u32,
+    pub min_temperature: Option<f64>,
+    pub max_temperature: Option<f64>,
+    pub avg_temperature: Option<f64>,
+    /// Fraction of refreshes that flagged precipitation
+    pub precipitation_share: f64,
+    /// Fraction of refreshes with the Busylight connected
+    pub device_uptime_share: f64,
 }
@@ -0,0 +1,59 @@
+use std::net::{IpAddr, UdpSocket};
+use std::process::Command;
+
+/// The local address the OS would use to reach the internet, or None without a usable route.
+/// Connecting a UDP socket only picks a route and sends nothing, so this is cheap enough to poll;
+/// a change means the machine came online or moved to another network.
+pub fn local_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("1.1.1.1:53").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip()).filter(|ip| !ip.is_unspecified())
+}
+
+// Current Wi-Fi network name, read from the stock OS tools. Blocking; returns None when
+// not on Wi-Fi or when the tool is unavailable.
+
+#[cfg(target_os = "windows")]
+pub fn current_ssid() -> Option<String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let output = Command::new("netsh")
+        .args(["wlan", "show", "interfaces"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // "    SSID                   : HomeNet" (skip the "BSSID" line)
+    text.lines()
+        .map(|l| l.trim())
+        .find(|l| l.starts_with("SSID"))
+        .and_then(|l| l.split_once(':'))
+        .map(|(_, v)| v.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(target_os = "macos")]
+pub fn current_ssid() -> Option<String> {
+    let output = Command::new("networksetup")
+        .args(["-getairportnetwork", "en0"])
+        .output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // "Current Wi-Fi Network: HomeNet"
+    text.trim()
+        .strip_prefix("Current Wi-Fi Network:")
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn current_ssid() -> Option<String> {
+    let output = Command::new("nmcli")
+        .args(["-t", "-f", "active,ssid", "dev", "wifi"])
+        .output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // "yes:HomeNet"
+    text.lines()
+        .find_map(|l| l.strip_prefix("yes:"))
+        .map(|s| s.to_string())
+        .filter(|s| !s.is_empty())
+}
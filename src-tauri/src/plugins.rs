@@ -0,0 +1,296 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use crate::models::{WeatherState, SunTimes};
+use crate::sink::{LightSink, SinkStatus};
+
+// Plugins are external executables described by a JSON manifest in `$APPDATA/WeatherLight/plugins`.
+// They speak newline-delimited JSON over stdio:
+//   provider: spawned per fetch, receives {"method":"fetch","lat","lon","locationName"}, replies with one weather object
+//   device:   kept running, receives {"method":"light","r","g","b","brightness","pulse","speedMs"} on every light change
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginManifest {
+    pub name: String,
+    /// "provider" or "device"
+    pub kind: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct PluginWeather {
+    temperature: f64,
+    has_precipitation: bool,
+    precip_probability: f64,
+    humidity: Option<f64>,
+    wind_speed: Option<f64>,
+    location_name: Option<String>,
+    sunrise: Option<DateTime<Utc>>,
+    sunset: Option<DateTime<Utc>>,
+    error: Option<String>,
+}
+
+// Light lines waiting for a device plugin; one that falls further behind skips frames
+const LINE_QUEUE: usize = 16;
+
+struct DevicePlugin {
+    manifest: PluginManifest,
+    child: Child,
+    // Lines for its writer thread, which owns the child's stdin so a plugin that stops reading
+    // blocks only that thread
+    lines: SyncSender<String>,
+}
+
+impl DevicePlugin {
+    // Kills and reaps the process, so it doesn't linger as a zombie
+    fn stop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// Drops the device plugins whose process has exited; `try_wait` has reaped them
+fn prune_exited(devices: &mut Vec<DevicePlugin>) {
+    devices.retain_mut(|dev| {
+        let exited = !matches!(dev.child.try_wait(), Ok(None));
+        if exited {
+            println!("Device plugin {} exited", dev.manifest.name);
+        }
+        !exited
+    });
+}
+
+pub struct PluginRegistry {
+    providers: Mutex<Vec<PluginManifest>>,
+    devices: Mutex<Vec<DevicePlugin>>,
+}
+
+pub fn get_plugins_dir() -> PathBuf {
+    let mut path = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("WeatherLight");
+    path.push("plugins");
+
+    if !path.exists() {
+        let _ = fs::create_dir_all(&path);
+    }
+
+    path
+}
+
+fn read_manifests() -> Vec<PluginManifest> {
+    let mut manifests = Vec::new();
+    if let Ok(entries) = fs::read_dir(get_plugins_dir()) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") { continue; }
+            match fs::read_to_string(&path).map_err(|e| e.to_string())
+                .and_then(|data| serde_json::from_str::<PluginManifest>(&data).map_err(|e| e.to_string())) {
+                Ok(m) => manifests.push(m),
+                Err(e) => println!("Skipping plugin manifest {:?}: {}", path, e),
+            }
+        }
+    }
+    manifests
+}
+
+impl PluginRegistry {
+    /// A registry with no plugins, without touching the plugins directory
+    pub fn empty() -> Self {
+        Self {
+            providers: Mutex::new(Vec::new()),
+            devices: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn load() -> Self {
+        let registry = Self::empty();
+        registry.reload();
+        registry
+    }
+
+    /// Re-scans the plugins directory, restarting any device plugins.
+    pub fn reload(&self) -> Vec<PluginManifest> {
+        let manifests = read_manifests();
+
+        if let Ok(mut devices) = self.devices.lock() {
+            for dev in devices.iter_mut() {
+                dev.stop();
+            }
+            devices.clear();
+
+            for m in manifests.iter().filter(|m| m.kind == "device") {
+                let spawned = Command::new(&m.command)
+                    .args(&m.args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn();
+                match spawned {
+                    Ok(mut child) => {
+                        if let Some(mut stdin) = child.stdin.take() {
+                            let (lines, queue) = mpsc::sync_channel::<String>(LINE_QUEUE);
+                            let name = m.name.clone();
+                            thread::spawn(move || {
+                                for line in queue {
+                                    if stdin.write_all(line.as_bytes()).and_then(|_| stdin.flush()).is_err() {
+                                        println!("Device plugin {} stopped responding", name);
+                                        break;
+                                    }
+                                }
+                            });
+                            devices.push(DevicePlugin { manifest: m.clone(), child, lines });
+                        }
+                    }
+                    Err(e) => println!("Failed to start device plugin {}: {}", m.name, e),
+                }
+            }
+        }
+
+        if let Ok(mut providers) = self.providers.lock() {
+            *providers = manifests.iter().filter(|m| m.kind == "provider").cloned().collect();
+        }
+
+        manifests
+    }
+
+    pub fn manifests(&self) -> Vec<PluginManifest> {
+        let mut all = self.providers.lock().map(|p| p.clone()).unwrap_or_default();
+        if let Ok(devices) = self.devices.lock() {
+            all.extend(devices.iter().map(|d| d.manifest.clone()));
+        }
+        all
+    }
+
+    pub fn provider(&self, name: &str) -> Option<PluginManifest> {
+        self.providers.lock().ok()?.iter().find(|m| m.name == name).cloned()
+    }
+
+    /// Names of the running device plugins
+    pub fn device_names(&self) -> Vec<String> {
+        self.devices.lock().map(|mut devices| {
+            prune_exited(&mut devices);
+            devices.iter().map(|dev| dev.manifest.name.clone()).collect()
+        }).unwrap_or_default()
+    }
+
+    /// Forwards the logical light state to every running device plugin, dropping ones that have exited.
+    pub fn send_light(&self, rgb: (u8, u8, u8), brightness: u8, pulse: bool, speed_ms: u64) {
+        self.send_light_where(|_| true, rgb, brightness, pulse, speed_ms);
+    }
+
+    /// Like `send_light`, limited to devices whose name passes `matches`.
+    pub fn send_light_where<F: Fn(&str) -> bool>(&self, matches: F, rgb: (u8, u8, u8), brightness: u8, pulse: bool, speed_ms: u64) {
+        if let Ok(mut devices) = self.devices.lock() {
+            let line = format!("{}\n", serde_json::json!({
+                "method": "light",
+                "r": rgb.0, "g": rgb.1, "b": rgb.2,
+                "brightness": brightness,
+                "pulse": pulse,
+                "speedMs": speed_ms,
+            }));
+            prune_exited(&mut devices);
+            devices.retain_mut(|dev| {
+                if !matches(&dev.manifest.name) {
+                    return true;
+                }
+                match dev.lines.try_send(line.clone()) {
+                    Ok(()) | Err(TrySendError::Full(_)) => true,
+                    // The writer thread gave up on the pipe
+                    Err(TrySendError::Disconnected(_)) => {
+                        dev.stop();
+                        false
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// One running device plugin as a light sink. The protocol has no pulse depth, so `low` is dropped.
+pub struct PluginSink {
+    registry: Arc<PluginRegistry>,
+    name: String,
+}
+
+impl PluginSink {
+    pub fn new(registry: Arc<PluginRegistry>, name: String) -> Self {
+        Self { registry, name }
+    }
+}
+
+impl LightSink for PluginSink {
+    fn kind(&self) -> &'static str {
+        "plugin"
+    }
+
+    fn set_color(&self, rgb: (u8, u8, u8), brightness: u8) {
+        self.registry.send_light_where(|name| name == self.name, rgb, brightness, false, 0);
+    }
+
+    fn pulse(&self, rgb: (u8, u8, u8), high: u8, _low: u8, speed_ms: u64) {
+        self.registry.send_light_where(|name| name == self.name, rgb, high, true, speed_ms);
+    }
+
+    fn off(&self) {
+        self.registry.send_light_where(|name| name == self.name, (0, 0, 0), 0, false, 0);
+    }
+
+    fn status(&self) -> SinkStatus {
+        if self.registry.device_names().contains(&self.name) { SinkStatus::Connected } else { SinkStatus::Disconnected }
+    }
+}
+
+pub async fn fetch_weather(manifest: &PluginManifest, lat: f64, lon: f64, location_name: String) -> Result<WeatherState, String> {
+    let mut child = tokio::process::Command::new(&manifest.command)
+        .args(&manifest.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start plugin {}: {}", manifest.name, e))?;
+
+    let request = serde_json::json!({ "method": "fetch", "lat": lat, "lon": lon, "locationName": location_name });
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(format!("{}\n", request).as_bytes()).await.map_err(|e| e.to_string())?;
+        // stdin is dropped here, signalling EOF to the plugin
+    }
+
+    let output = tokio::time::timeout(Duration::from_secs(15), child.wait_with_output()).await
+        .map_err(|_| format!("Plugin {} timed out", manifest.name))?
+        .map_err(|e| e.to_string())?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|l| !l.trim().is_empty())
+        .ok_or_else(|| format!("Plugin {} returned no data", manifest.name))?;
+    let resp: PluginWeather = serde_json::from_str(line).map_err(|e| e.to_string())?;
+    if let Some(err) = resp.error {
+        return Err(err);
+    }
+
+    Ok(WeatherState {
+        temperature: resp.temperature,
+        has_precipitation: resp.has_precipitation,
+        precip_probability: resp.precip_probability,
+        location_name: resp.location_name.unwrap_or(location_name),
+        sun_times: SunTimes { sunrise: resp.sunrise, sunset: resp.sunset, ..Default::default() },
+        provider: manifest.name.clone(),
+        last_updated: Utc::now(),
+        humidity: resp.humidity,
+        dew_point: resp.humidity.map(|rh| crate::weather::calculate_dew_point(resp.temperature, rh)),
+        wind_speed: resp.wind_speed,
+        ..Default::default()
+    })
+}
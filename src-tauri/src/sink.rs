@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use crate::busylight::{Effect, PulseState};
+use chrono::Utc;
+
+// Everything the pipeline can light up. The weather rules, roles and pulse parameters live in
+// `lib.rs` and end in a `PulseState` plan; a sink only has to show steady, breathing or off,
+// as well as its hardware allows. Busylights, device plugins, Hue and WLED all implement it, so
+// any number of them can be driven side by side.
+
+/// Whether a sink can take commands right now
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkStatus {
+    Connected,
+    /// Unplugged, exited or unreachable; commands are dropped until it comes back
+    Disconnected,
+}
+
+pub trait LightSink: Send + Sync {
+    /// "busylight", "plugin", "hue" or "wled"
+    fn kind(&self) -> &'static str;
+
+    /// Steady `rgb` at `brightness` percent
+    fn set_color(&self, rgb: (u8, u8, u8), brightness: u8);
+
+    /// Breathes `rgb` between `high` and `low` percent, one cycle per `speed_ms`. Sinks that
+    /// can't set the depth or speed approximate them.
+    fn pulse(&self, rgb: (u8, u8, u8), high: u8, low: u8, speed_ms: u64);
+
+    fn off(&self);
+
+    fn status(&self) -> SinkStatus;
+
+    /// Shows a whole plan. A color sequence becomes a pulse of its first color unless the sink
+    /// can step through colors itself, and a sunrise shows where it is right now.
+    fn show(&self, plan: &PulseState) {
+        if let Effect::Sunrise(ramp) = plan.effect {
+            let (color, perceived) = ramp.at(Utc::now(), plan.color_srgb, plan.pct_high);
+            self.set_color(color, (perceived * 100.0).round() as u8);
+        } else if plan.active {
+            self.pulse(plan.color_srgb, plan.pct_high, plan.pct_low, plan.speed_ms);
+        } else if plan.pct_high == 0 || plan.color_srgb == (0, 0, 0) {
+            self.off();
+        } else {
+            self.set_color(plan.color_srgb, plan.pct_high);
+        }
+    }
+
+    /// Spreads `colors` along the light, the first at its start. False when the sink shows one
+    /// color at a time (or isn't set up for gradients), and the caller falls back to `show()`.
+    fn show_gradient(&self, _colors: &[(u8, u8, u8)], _brightness: u8) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // Records what the default `show()` asked for
+    #[derive(Default)]
+    struct MockSink {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl LightSink for MockSink {
+        fn kind(&self) -> &'static str { "mock" }
+        fn set_color(&self, rgb: (u8, u8, u8), brightness: u8) {
+            self.calls.lock().unwrap().push(format!("color {:?} {}", rgb, brightness));
+        }
+        fn pulse(&self, rgb: (u8, u8, u8), high: u8, low: u8, speed_ms: u64) {
+            self.calls.lock().unwrap().push(format!("pulse {:?} {}-{} {}", rgb, low, high, speed_ms));
+        }
+        fn off(&self) {
+            self.calls.lock().unwrap().push("off".to_string());
+        }
+        fn status(&self) -> SinkStatus { SinkStatus::Connected }
+    }
+
+    fn plan(active: bool, color: (u8, u8, u8), pct_high: u8, sequence: Vec<(u8, u8, u8)>) -> PulseState {
+        PulseState { active, color_srgb: color, pct_high, pct_low: 10, speed_ms: 800, sequence, effect: Effect::Breathe }
+    }
+
+    #[test]
+    fn test_default_show() {
+        let sink = MockSink::default();
+        sink.show(&plan(false, (255, 128, 0), 60, Vec::new()));
+        sink.show(&plan(true, (0, 0, 255), 60, Vec::new()));
+        // A sequence pulses its first color
+        sink.show(&plan(true, (255, 0, 0), 60, vec![(255, 0, 0), (255, 255, 255)]));
+        sink.show(&plan(false, (255, 128, 0), 0, Vec::new()));
+        sink.show(&plan(false, (0, 0, 0), 60, Vec::new()));
+        assert_eq!(*sink.calls.lock().unwrap(), vec![
+            "color (255, 128, 0) 60",
+            "pulse (0, 0, 255) 10-60 800",
+            "pulse (255, 0, 0) 10-60 800",
+            "off",
+            "off",
+        ]);
+        assert!(!sink.show_gradient(&[(0, 0, 255), (255, 0, 0)], 60));
+    }
+}
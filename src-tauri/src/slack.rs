@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use chrono::Utc;
+use reqwest::Client;
+use serde_json::Value;
+use crate::config::SlackConfig;
+
+// Slack huddles and Do Not Disturb, read with the user's own token (`slack.token`, an `xoxp-`
+// token with the users.profile:read and dnd:read scopes). A poller asks every
+// `slack.pollSeconds`; while busy the arbiter shows `slack.busyColor`, like a Teams meeting.
+
+const API: &str = "https://slack.com/api";
+
+pub struct SlackPresence {
+    client: Client,
+    // Whether the last poll found a huddle or Do Not Disturb
+    busy: AtomicBool,
+}
+
+impl Default for SlackPresence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SlackPresence {
+    pub fn new() -> Self {
+        Self {
+            client: Client::builder().timeout(Duration::from_secs(10)).build().unwrap_or_default(),
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Relaxed)
+    }
+
+    /// Forgets the presence, when Slack is turned off or can't be reached; returns whether it was busy
+    pub fn reset(&self) -> bool {
+        self.busy.swap(false, Ordering::Relaxed)
+    }
+
+    /// Reads the huddle and Do Not Disturb state; returns whether busy changed
+    pub async fn poll(&self, config: &SlackConfig) -> Result<bool, String> {
+        let mut busy = false;
+        if config.on_huddle {
+            busy |= in_huddle(&self.call(config, "users.profile.get").await?);
+        }
+        if config.on_dnd && !busy {
+            busy |= dnd_active(&self.call(config, "dnd.info").await?, Utc::now().timestamp());
+        }
+        Ok(self.busy.swap(busy, Ordering::Relaxed) != busy)
+    }
+
+    async fn call(&self, config: &SlackConfig, method: &str) -> Result<Value, String> {
+        let body: Value = self.client.get(format!("{}/{}", API, method)).bearer_auth(config.token.trim())
+            .send().await.map_err(|e| e.to_string())?
+            .json().await.map_err(|e| e.to_string())?;
+        // Slack answers 200 with {"ok": false, "error": "invalid_auth"} and the like
+        if body.get("ok").and_then(|v| v.as_bool()) != Some(true) {
+            let error = body.get("error").and_then(|v| v.as_str()).unwrap_or("unexpected reply");
+            return Err(format!("Slack {}: {}", method, error));
+        }
+        Ok(body)
+    }
+}
+
+// `users.profile.get`: the profile carries the huddle while one is going on
+fn in_huddle(body: &Value) -> bool {
+    body.pointer("/profile/huddle_state").and_then(|v| v.as_str()) == Some("in_a_huddle")
+}
+
+// `dnd.info`: snoozed notifications by hand, or inside the Do Not Disturb schedule
+fn dnd_active(body: &Value, now: i64) -> bool {
+    let flag = |key: &str| body.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+    let ts = |key: &str| body.get(key).and_then(|v| v.as_i64()).unwrap_or(0);
+    flag("snooze_enabled") || (flag("dnd_enabled") && ts("next_dnd_start_ts") <= now && now < ts("next_dnd_end_ts"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_in_huddle() {
+        assert!(in_huddle(&json!({ "ok": true, "profile": { "huddle_state": "in_a_huddle" } })));
+        assert!(!in_huddle(&json!({ "ok": true, "profile": { "huddle_state": "default_unset" } })));
+        assert!(!in_huddle(&json!({ "ok": true, "profile": {} })));
+    }
+
+    #[test]
+    fn test_dnd_active() {
+        assert!(dnd_active(&json!({ "snooze_enabled": true }), 1000));
+        let scheduled = json!({ "dnd_enabled": true, "next_dnd_start_ts": 900, "next_dnd_end_ts": 1100, "snooze_enabled": false });
+        assert!(dnd_active(&scheduled, 1000));
+        assert!(!dnd_active(&scheduled, 1100));
+        assert!(!dnd_active(&json!({ "dnd_enabled": false, "next_dnd_start_ts": 900, "next_dnd_end_ts": 1100 }), 1000));
+    }
+}
@@ -0,0 +1,67 @@
+use std::process::Command;
+use std::thread;
+use crate::config::AppConfig;
+
+// Plays short alert sounds through the OS audio stack using the stock command-line players,
+// so we don't pull an audio backend into the binary. Windows only plays WAV files.
+
+pub enum SoundEvent {
+    RainSoon,
+    SevereAlert,
+    DeviceDisconnect,
+}
+
+pub fn play_event(config: &AppConfig, event: SoundEvent) {
+    let alerts = &config.sound_alerts;
+    if !alerts.enabled {
+        return;
+    }
+
+    let file = match event {
+        SoundEvent::RainSoon => &alerts.rain_soon,
+        SoundEvent::SevereAlert => &alerts.severe_alert,
+        SoundEvent::DeviceDisconnect => &alerts.device_disconnect,
+    };
+
+    if !file.is_empty() {
+        play_file(file.clone());
+    }
+}
+
+pub fn play_file(path: String) {
+    // Players block until the clip ends, keep them off the caller's thread
+    thread::spawn(move || {
+        let result = player_command(&path).status();
+        if let Err(e) = result {
+            println!("Failed to play sound {}: {}", path, e);
+        }
+    });
+}
+
+#[cfg(target_os = "windows")]
+fn player_command(path: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let script = format!("(New-Object Media.SoundPlayer '{}').PlaySync()", path.replace('\'', "''"));
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+#[cfg(target_os = "macos")]
+fn player_command(path: &str) -> Command {
+    let mut cmd = Command::new("afplay");
+    cmd.arg(path);
+    cmd
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn player_command(path: &str) -> Command {
+    // PulseAudio/PipeWire first, it handles more formats than plain ALSA
+    let player = if Command::new("paplay").arg("--version").output().is_ok() { "paplay" } else { "aplay" };
+    let mut cmd = Command::new(player);
+    cmd.arg(path);
+    cmd
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use crate::models::SunTimes;
+
+// Sunrise/sunset from the NOAA "Almanac for Computers" approximation (accurate to ~1 minute),
+// for providers that don't report sun times themselves. Civil twilight and the moon phase are
+// always computed here, no provider reports them.
+
+const OFFICIAL_ZENITH: f64 = 90.833;
+const CIVIL_ZENITH: f64 = 96.0;
+
+// Mean synodic month, and a reference new moon (2000-01-06 18:14 UTC)
+const SYNODIC_MONTH_DAYS: f64 = 29.530588853;
+const REFERENCE_NEW_MOON: i64 = 947_182_440;
+
+fn normalize(value: f64, max: f64) -> f64 {
+    value.rem_euclid(max)
+}
+
+fn event_utc(lat: f64, lon: f64, date: NaiveDate, zenith: f64, rising: bool) -> Option<DateTime<Utc>> {
+    let day_of_year = date.ordinal() as f64;
+    let lng_hour = lon / 15.0;
+    let t = day_of_year + ((if rising { 6.0 } else { 18.0 }) - lng_hour) / 24.0;
+
+    let mean_anomaly = 0.9856 * t - 3.289;
+    let true_long = normalize(
+        mean_anomaly + 1.916 * mean_anomaly.to_radians().sin() + 0.020 * (2.0 * mean_anomaly).to_radians().sin() + 282.634,
+        360.0,
+    );
+
+    let mut right_ascension = normalize((0.91764 * true_long.to_radians().tan()).atan().to_degrees(), 360.0);
+    // Same quadrant as the true longitude
+    right_ascension += (true_long / 90.0).floor() * 90.0 - (right_ascension / 90.0).floor() * 90.0;
+    right_ascension /= 15.0;
+
+    let sin_dec = 0.39782 * true_long.to_radians().sin();
+    let cos_dec = sin_dec.asin().cos();
+    let cos_h = (zenith.to_radians().cos() - sin_dec * lat.to_radians().sin()) / (cos_dec * lat.to_radians().cos());
+    if !(-1.0..=1.0).contains(&cos_h) {
+        // Polar day or night, the sun doesn't cross the horizon today
+        return None;
+    }
+
+    let hour_angle = if rising { 360.0 - cos_h.acos().to_degrees() } else { cos_h.acos().to_degrees() } / 15.0;
+    let local_mean_time = hour_angle + right_ascension - 0.06571 * t - 6.622;
+    let ut = normalize(local_mean_time - lng_hour, 24.0);
+
+    let midnight = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?);
+    Some(midnight + Duration::milliseconds((ut * 3_600_000.0) as i64))
+}
+
+pub fn sun_times(lat: f64, lon: f64, date: NaiveDate) -> SunTimes {
+    let mut times = SunTimes {
+        sunrise: event_utc(lat, lon, date, OFFICIAL_ZENITH, true),
+        sunset: event_utc(lat, lon, date, OFFICIAL_ZENITH, false),
+        ..Default::default()
+    };
+    fill_missing(&mut times, lat, lon, date);
+    times
+}
+
+/// Adds twilight and moon phase to provider-reported sunrise/sunset
+pub fn fill_missing(times: &mut SunTimes, lat: f64, lon: f64, date: NaiveDate) {
+    if times.civil_dawn.is_none() {
+        times.civil_dawn = event_utc(lat, lon, date, CIVIL_ZENITH, true);
+    }
+    if times.civil_dusk.is_none() {
+        times.civil_dusk = event_utc(lat, lon, date, CIVIL_ZENITH, false);
+    }
+    if times.moon_phase.is_none() {
+        times.moon_phase = date.and_hms_opt(12, 0, 0).map(|noon| moon_phase(Utc.from_utc_datetime(&noon)));
+    }
+}
+
+/// Mean lunar phase (0-1, 0.5 full), within about a day of the true one
+pub fn moon_phase(at: DateTime<Utc>) -> f64 {
+    let days = (at.timestamp() - REFERENCE_NEW_MOON) as f64 / 86_400.0;
+    (days / SYNODIC_MONTH_DAYS).rem_euclid(1.0)
+}
+
+pub fn moon_phase_name(phase: f64) -> &'static str {
+    const NAMES: [&str; 8] = [
+        "New moon", "Waxing crescent", "First quarter", "Waxing gibbous",
+        "Full moon", "Waning gibbous", "Last quarter", "Waning crescent",
+    ];
+    NAMES[((phase.rem_euclid(1.0) * 8.0).round() as usize) % 8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    #[test]
+    fn test_equinox_at_greenwich() {
+        let times = sun_times(51.48, 0.0, NaiveDate::from_ymd_opt(2026, 3, 20).unwrap());
+        let sunrise = times.sunrise.unwrap();
+        let sunset = times.sunset.unwrap();
+        // Roughly 06:00 and 18:10 UTC
+        assert_eq!(sunrise.hour(), 6);
+        assert_eq!(sunset.hour(), 18);
+        assert!(sun_times(78.2, 15.6, NaiveDate::from_ymd_opt(2026, 6, 21).unwrap()).sunset.is_none());
+
+        // Civil twilight adds roughly half an hour at this latitude
+        let dawn = times.civil_dawn.unwrap();
+        let dusk = times.civil_dusk.unwrap();
+        assert!((25..=40).contains(&(sunrise - dawn).num_minutes()));
+        assert!((25..=40).contains(&(dusk - sunset).num_minutes()));
+    }
+
+    #[test]
+    fn test_moon_phase() {
+        // Full moon 2026-03-03 11:38 UTC, new moon 2026-02-17 12:01 UTC
+        let full = moon_phase(Utc.with_ymd_and_hms(2026, 3, 3, 11, 38, 0).unwrap());
+        assert!((full - 0.5).abs() < 0.04);
+        assert_eq!(moon_phase_name(full), "Full moon");
+        let new = moon_phase(Utc.with_ymd_and_hms(2026, 2, 17, 12, 1, 0).unwrap());
+        assert!(!(0.04..0.96).contains(&new));
+        assert_eq!(moon_phase_name(new), "New moon");
+    }
+}
@@ -0,0 +1,174 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde_json::Value;
+use crate::config::TeamsConfig;
+use crate::models::TeamsDeviceCode;
+
+// Microsoft Teams presence, read from Microsoft Graph. Signing in is OAuth's device code flow:
+// the settings window shows a short code to enter at microsoft.com/devicelogin, and the refresh
+// token that comes back is kept in `teams.refreshToken`. A poller reads `/me/presence` every
+// `teams.pollSeconds`; during a call or meeting the arbiter shows `teams.busyColor`.
+
+const GRAPH_PRESENCE: &str = "https://graph.microsoft.com/v1.0/me/presence";
+const SCOPE: &str = "Presence.Read offline_access";
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+// Graph `activity` values that mean someone is talking to people
+const BUSY_ACTIVITIES: &[&str] = &["InACall", "InAConferenceCall", "InAMeeting", "Presenting"];
+
+pub struct TeamsPresence {
+    client: Client,
+    // Access token and when it runs out
+    token: Mutex<Option<(String, DateTime<Utc>)>>,
+    // Whether the last poll found a call or meeting
+    busy: AtomicBool,
+}
+
+impl Default for TeamsPresence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TeamsPresence {
+    pub fn new() -> Self {
+        Self {
+            client: Client::builder().timeout(Duration::from_secs(10)).build().unwrap_or_default(),
+            token: Mutex::new(None),
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Relaxed)
+    }
+
+    /// Forgets the token and presence, when Teams is turned off or signed out
+    pub fn reset(&self) -> bool {
+        *self.token.lock().unwrap() = None;
+        self.busy.swap(false, Ordering::Relaxed)
+    }
+
+    /// Asks Microsoft for a code for the user to enter
+    pub async fn start_sign_in(&self, config: &TeamsConfig) -> Result<TeamsDeviceCode, String> {
+        let body: Value = self.client.post(config.login_url("devicecode"))
+            .form(&[("client_id", config.client_id.trim()), ("scope", SCOPE)])
+            .send().await.map_err(|e| e.to_string())?
+            .json().await.map_err(|e| e.to_string())?;
+        if let Some(err) = login_error(&body) {
+            return Err(err);
+        }
+        let text = |key: &str| body.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+        let seconds = |key: &str, default: u64| body.get(key).and_then(|v| v.as_u64()).unwrap_or(default);
+        Ok(TeamsDeviceCode {
+            device_code: text("device_code"),
+            user_code: text("user_code"),
+            verification_uri: text("verification_uri"),
+            message: text("message"),
+            interval: seconds("interval", 5),
+            expires_in: seconds("expires_in", 900),
+        })
+    }
+
+    /// Waits for the user to enter `code`; returns the refresh token to store in `teams.refreshToken`
+    pub async fn finish_sign_in(&self, config: &TeamsConfig, code: &TeamsDeviceCode) -> Result<String, String> {
+        let deadline = Utc::now() + chrono::Duration::seconds(code.expires_in as i64);
+        let mut interval = code.interval.max(1);
+        while Utc::now() < deadline {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            let body = self.token_request(config, &[("grant_type", DEVICE_CODE_GRANT), ("device_code", code.device_code.as_str())]).await?;
+            match body.get("error").and_then(|v| v.as_str()) {
+                Some("authorization_pending") => continue,
+                Some("slow_down") => interval += 5,
+                Some(_) => return Err(login_error(&body).unwrap_or_default()),
+                None => return self.store_token(&body).ok_or_else(|| "Microsoft didn't return a refresh token".to_string()),
+            }
+        }
+        Err("The sign-in code expired, try again".to_string())
+    }
+
+    /// Reads the presence. Returns whether busy changed, and the refresh token when Microsoft
+    /// handed out a new one.
+    pub async fn poll(&self, config: &TeamsConfig) -> Result<(bool, Option<String>), String> {
+        let cached = self.token.lock().unwrap().clone().filter(|(_, expires)| *expires > Utc::now());
+        let (access_token, rotated) = match cached {
+            Some((token, _)) => (token, None),
+            None => {
+                *self.token.lock().unwrap() = None;
+                let body = self.token_request(config, &[("grant_type", "refresh_token"), ("refresh_token", config.refresh_token.as_str())]).await?;
+                if let Some(err) = login_error(&body) {
+                    return Err(err);
+                }
+                let rotated = self.store_token(&body).filter(|t| *t != config.refresh_token);
+                let token = self.token.lock().unwrap().clone().map(|(t, _)| t).ok_or_else(|| "Microsoft didn't return an access token".to_string())?;
+                (token, rotated)
+            }
+        };
+
+        let resp = self.client.get(GRAPH_PRESENCE).bearer_auth(&access_token).send().await.map_err(|e| e.to_string())?;
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+            // Revoked or expired early; the next poll signs in again with the refresh token
+            *self.token.lock().unwrap() = None;
+        }
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {} from Microsoft Graph", resp.status()));
+        }
+        let presence: Value = resp.json().await.map_err(|e| e.to_string())?;
+        let busy = is_busy_activity(presence.get("activity").and_then(|v| v.as_str()).unwrap_or(""));
+        Ok((self.busy.swap(busy, Ordering::Relaxed) != busy, rotated))
+    }
+
+    async fn token_request(&self, config: &TeamsConfig, grant: &[(&str, &str)]) -> Result<Value, String> {
+        let mut form = vec![("client_id", config.client_id.trim()), ("scope", SCOPE)];
+        form.extend_from_slice(grant);
+        self.client.post(config.login_url("token")).form(&form)
+            .send().await.map_err(|e| e.to_string())?
+            .json().await.map_err(|e| e.to_string())
+    }
+
+    // Keeps the access token from a token response; returns its refresh token
+    fn store_token(&self, body: &Value) -> Option<String> {
+        if let Some(token) = body.get("access_token").and_then(|v| v.as_str()) {
+            // A minute early, so a poll never goes out with a token about to lapse
+            let lifetime = body.get("expires_in").and_then(|v| v.as_i64()).unwrap_or(3600) - 60;
+            *self.token.lock().unwrap() = Some((token.to_string(), Utc::now() + chrono::Duration::seconds(lifetime)));
+        }
+        body.get("refresh_token").and_then(|v| v.as_str()).map(|t| t.to_string())
+    }
+}
+
+pub fn is_busy_activity(activity: &str) -> bool {
+    BUSY_ACTIVITIES.contains(&activity)
+}
+
+// The identity platform answers {"error", "error_description"} on failures
+fn login_error(body: &Value) -> Option<String> {
+    let error = body.get("error").and_then(|v| v.as_str())?;
+    let description = body.get("error_description").and_then(|v| v.as_str()).unwrap_or(error);
+    // Descriptions carry trace ids on further lines
+    Some(description.lines().next().unwrap_or(error).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_busy_activity() {
+        assert!(is_busy_activity("InAMeeting"));
+        assert!(is_busy_activity("Presenting"));
+        assert!(!is_busy_activity("Available"));
+        assert!(!is_busy_activity("Away"));
+    }
+
+    #[test]
+    fn test_login_error() {
+        assert_eq!(login_error(&json!({ "access_token": "x" })), None);
+        let body = json!({ "error": "invalid_grant", "error_description": "AADSTS70000: The grant is expired.\r\nTrace ID: 1234" });
+        assert_eq!(login_error(&body).as_deref(), Some("AADSTS70000: The grant is expired."));
+        assert_eq!(login_error(&json!({ "error": "access_denied" })).as_deref(), Some("access_denied"));
+    }
+}
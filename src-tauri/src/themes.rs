@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+use crate::color::{hex_to_rgb, ColorScale, Interpolation};
+use crate::config::AppConfig;
+
+// Named temperature palettes for the light and tray icon. Stops are in °C and sorted by
+// temperature; colors between two stops are interpolated linearly and the ends are clamped.
+// Built-in themes live here, imported ones in `custom_themes` in config.json.
+
+/// A single point on a palette
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorStop {
+    pub temp: f64,
+    pub color: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Theme {
+    pub name: String,
+    /// Unit of the stop temperatures, "C" (also when empty) or "F". Stored themes are always
+    /// °C; `in_unit` converts for the settings window and exports.
+    #[serde(default)]
+    pub unit: String,
+    pub stops: Vec<ColorStop>,
+}
+
+pub const DEFAULT_THEME: &str = "classic";
+
+// The original gradient, one stop per degree
+const CLASSIC: &[(f64, &str)] = &[
+    (-50.0, "#e1e1ff"), (-49.0, "#dfdfff"), (-48.0, "#dfdfff"), (-47.0, "#dcdcff"), (-46.0, "#dcdcff"),
+    (-45.0, "#dadaff"), (-44.0, "#dadaff"), (-43.0, "#d7d7ff"), (-42.0, "#d2d2ff"), (-41.0, "#cbcbff"),
+    (-40.0, "#c4c4ff"), (-39.0, "#bdbdff"), (-38.0, "#b6b6ff"), (-37.0, "#afafff"), (-36.0, "#a9a9ff"),
+    (-35.0, "#a4a4ff"), (-34.0, "#9e9eff"), (-33.0, "#9898ff"), (-32.0, "#9292ff"), (-31.0, "#8c8cff"),
+    (-30.0, "#8787ff"), (-29.0, "#8181ff"), (-28.0, "#7373f4"), (-27.0, "#6565e7"), (-26.0, "#5757da"),
+    (-25.0, "#4b4bcd"), (-24.0, "#4040c1"), (-23.0, "#3737b6"), (-22.0, "#2e2eab"), (-21.0, "#2626a0"),
+    (-20.0, "#1f1f96"), (-19.0, "#19198c"), (-18.0, "#141483"), (-17.0, "#11127e"), (-16.0, "#0e1078"),
+    (-15.0, "#0b0f73"), (-14.0, "#0a0d6e"), (-13.0, "#080d6b"), (-12.0, "#060b66"), (-11.0, "#050a62"),
+    (-10.0, "#04095d"), (-9.0,  "#030859"), (-8.0,  "#020856"), (-7.0,  "#010752"), (-6.0,  "#01064e"),
+    (-5.0,  "#01054a"), (-4.0,  "#000546"), (-3.0,  "#000443"), (-2.0,  "#000440"), (-1.0,  "#00033d"),
+    (0.0,   "#00033a"), (1.0,   "#000b57"), (2.0,   "#001d7c"), (3.0,   "#003bab"), (4.0,   "#0068e4"),
+    (5.0,   "#008cd7"), (6.0,   "#009e98"), (7.0,   "#00b466"), (8.0,   "#00cb40"), (9.0,   "#00e425"),
+    (10.0,  "#00ff13"), (11.0,  "#01ff0b"), (12.0,  "#07ff05"), (13.0,  "#17ff02"), (14.0,  "#33ff01"),
+    (15.0,  "#60ff00"), (16.0,  "#89f400"), (17.0,  "#9cda00"), (18.0,  "#b1c100"), (19.0,  "#c8ab00"),
+    (20.0,  "#e19600"), (21.0,  "#fc8300"), (22.0,  "#ff7300"), (23.0,  "#ff6600"), (24.0,  "#ff5900"),
+    (25.0,  "#ff4d00"), (26.0,  "#ff4300"), (27.0,  "#ff3900"), (28.0,  "#ff3000"), (29.0,  "#ff2800"),
+    (30.0,  "#ff2100"), (31.0,  "#ff1b00"), (32.0,  "#ff1500"), (33.0,  "#ff1000"), (34.0,  "#ff0c00"),
+    (35.0,  "#ff0900"), (36.0,  "#ff0600"), (37.0,  "#ff0400"), (38.0,  "#ff0300"), (39.0,  "#ff0100"),
+    (40.0,  "#ff0101"), (41.0,  "#ff0003"), (42.0,  "#ff0006"), (43.0,  "#ff000a"), (44.0,  "#f1000b"),
+    (45.0,  "#dc000a"), (46.0,  "#cb000a"), (47.0,  "#b80009"), (48.0,  "#a90008"), (49.0,  "#980008"),
+    (50.0,  "#8a0007"), (51.0,  "#7c0006"), (52.0,  "#6e0006"), (53.0,  "#630005"), (54.0,  "#570005"),
+    (55.0,  "#4e0004"), (56.0,  "#440004"), (57.0,  "#3c0003"), (58.0,  "#330003"), (59.0,  "#2d0003"),
+    (60.0,  "#260003"), (61.0,  "#200002"), (62.0,  "#1b0002"), (63.0,  "#160002"), (64.0,  "#120001"),
+    (65.0,  "#0e0001"), (66.0,  "#0d0001"), (67.0,  "#0d0001"), (68.0,  "#0d0001"), (69.0,  "#0d0001"),
+    (70.0,  "#0d0001")
+];
+
+// Ice blues through teal, never reaching red
+const ARCTIC: &[(f64, &str)] = &[
+    (-30.0, "#ffffff"), (-15.0, "#c6e6ff"), (0.0, "#6fb7ff"), (10.0, "#2f7fd6"),
+    (20.0, "#1fa8b0"), (30.0, "#5fe0c8"), (40.0, "#e0fff4"),
+];
+
+// Dusk purple through sand and terracotta
+const DESERT: &[(f64, &str)] = &[
+    (-20.0, "#3b2f5e"), (0.0, "#6b4f8a"), (10.0, "#c27c5b"), (20.0, "#e3a857"),
+    (30.0, "#f26b21"), (40.0, "#b3261e"), (50.0, "#5c0f0b"),
+];
+
+const PASTEL: &[(f64, &str)] = &[
+    (-20.0, "#d7d2ff"), (0.0, "#b5d8ff"), (10.0, "#b8f2d0"), (20.0, "#fff3b0"),
+    (30.0, "#ffc9a8"), (40.0, "#ffadb5"),
+];
+
+// Colorblind-safe gradients. The classic green -> orange -> red midsection collapses into one
+// olive under red-green deficiencies, so these run along the axis each deficiency keeps: blue to
+// yellow for protanopia and deuteranopia (protan reds darken, so the hot end stays yellow-brown),
+// teal to red for tritanopia. Adjacent stops stay apart under `vision::simulate`.
+const DEUTERANOPIA_SAFE: &[(f64, &str)] = &[
+    (-20.0, "#00204d"), (-10.0, "#1c3f8c"), (0.0, "#3b6fd1"), (8.0, "#8aa7e0"), (15.0, "#d8d4c8"),
+    (22.0, "#f0c94a"), (28.0, "#f39c1e"), (35.0, "#c45a00"), (45.0, "#6b2c00"),
+];
+
+const PROTANOPIA_SAFE: &[(f64, &str)] = &[
+    (-20.0, "#00204d"), (-10.0, "#1c3f8c"), (0.0, "#3b6fd1"), (8.0, "#8aa7e0"), (15.0, "#d8d4c8"),
+    (22.0, "#f0d64a"), (28.0, "#f5b31e"), (35.0, "#d98a00"), (45.0, "#8a5a00"),
+];
+
+const TRITANOPIA_SAFE: &[(f64, &str)] = &[
+    (-20.0, "#00363d"), (-10.0, "#006b78"), (0.0, "#00a6b8"), (8.0, "#8fdde6"), (15.0, "#e8e8e8"),
+    (22.0, "#ffb3c1"), (28.0, "#ff5c7a"), (35.0, "#d1003a"), (45.0, "#6b001c"),
+];
+
+const BUILTIN: &[(&str, &[(f64, &str)])] = &[
+    ("classic", CLASSIC),
+    ("arctic", ARCTIC),
+    ("desert", DESERT),
+    ("pastel", PASTEL),
+    ("deuteranopia-safe", DEUTERANOPIA_SAFE),
+    ("protanopia-safe", PROTANOPIA_SAFE),
+    ("tritanopia-safe", TRITANOPIA_SAFE),
+];
+
+// Departure of today's high from the normal, in °C: blue colder, near-white usual, red warmer
+const ANOMALY: &[(f64, &str)] = &[
+    (-10.0, "#2166ac"), (-5.0, "#67a9cf"), (-2.0, "#d1e5f0"), (0.0, "#f7f7f7"),
+    (2.0, "#fddbc7"), (5.0, "#ef8a62"), (10.0, "#b2182b"),
+];
+
+/// Color for a departure from the normal in °C, independent of the selected theme
+pub fn anomaly_color(delta_c: f64, mode: Interpolation) -> String {
+    Theme::from_table("anomaly", ANOMALY).color_at(delta_c, mode)
+}
+
+impl Theme {
+    fn from_table(name: &str, table: &[(f64, &str)]) -> Self {
+        Self {
+            name: name.to_string(),
+            unit: "C".to_string(),
+            stops: table.iter().map(|(temp, color)| ColorStop { temp: *temp, color: color.to_string() }).collect(),
+        }
+    }
+
+    /// A copy with the stops in `unit`, rounded to a tenth of a degree
+    pub fn in_unit(&self, unit: &str) -> Theme {
+        let fahrenheit = unit == "F";
+        let from_fahrenheit = self.unit == "F";
+        let convert = |t: f64| match (from_fahrenheit, fahrenheit) {
+            (false, true) => t * 9.0 / 5.0 + 32.0,
+            (true, false) => (t - 32.0) * 5.0 / 9.0,
+            _ => t,
+        };
+        Theme {
+            name: self.name.clone(),
+            unit: if fahrenheit { "F" } else { "C" }.to_string(),
+            stops: self.stops.iter()
+                .map(|s| ColorStop { temp: (convert(s.temp) * 10.0).round() / 10.0, color: s.color.clone() })
+                .collect(),
+        }
+    }
+
+    /// The stops as a `ColorScale` blending with `mode`
+    pub fn scale(&self, mode: Interpolation) -> Result<ColorScale, String> {
+        self.stops.iter()
+            .fold(ColorScale::builder().interpolation(mode), |b, s| b.stop(s.temp, &s.color))
+            .build()
+    }
+
+    /// Color for a temperature in °C, blending the surrounding stops with `mode`
+    pub fn color_at(&self, temp_c: f64, mode: Interpolation) -> String {
+        self.scale(mode).map(|s| s.hex_at(temp_c)).unwrap_or_else(|_| "#FFFFFF".to_string())
+    }
+}
+
+pub fn is_builtin(name: &str) -> bool {
+    BUILTIN.iter().any(|(n, _)| n.eq_ignore_ascii_case(name.trim()))
+}
+
+/// Built-in themes followed by the imported ones
+pub fn all(config: &AppConfig) -> Vec<Theme> {
+    BUILTIN.iter()
+        .map(|(name, table)| Theme::from_table(name, table))
+        .chain(config.custom_themes.iter().cloned())
+        .collect()
+}
+
+pub fn find(config: &AppConfig, name: &str) -> Option<Theme> {
+    all(config).into_iter().find(|t| t.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// The configured theme, or classic if it no longer exists
+pub fn active(config: &AppConfig) -> Theme {
+    find(config, &config.theme).unwrap_or_else(|| Theme::from_table(DEFAULT_THEME, CLASSIC))
+}
+
+/// Reads an exported theme, converting it to °C and sorting its stops. Rejects unusable names,
+/// colors and temperatures.
+pub fn parse(json: &str) -> Result<Theme, String> {
+    let mut theme: Theme = serde_json::from_str(json).map_err(|e| format!("Invalid theme: {}", e))?;
+    if !matches!(theme.unit.as_str(), "" | "C" | "F") {
+        return Err(format!("Unknown theme unit {} (expected C or F)", theme.unit));
+    }
+    theme.name = theme.name.trim().to_string();
+    if theme.name.is_empty() {
+        return Err("Theme needs a name".to_string());
+    }
+    if theme.stops.len() < 2 {
+        return Err("Theme needs at least two color stops".to_string());
+    }
+    for stop in &theme.stops {
+        if !stop.temp.is_finite() {
+            return Err("Theme has a stop without a temperature".to_string());
+        }
+        if hex_to_rgb(&stop.color).is_none() {
+            return Err(format!("Invalid color {} (expected #rrggbb)", stop.color));
+        }
+    }
+    if theme.unit == "F" {
+        for stop in &mut theme.stops {
+            stop.temp = (stop.temp - 32.0) * 5.0 / 9.0;
+        }
+    }
+    theme.unit = "C".to_string();
+    theme.stops.sort_by(|a, b| a.temp.total_cmp(&b.temp));
+    Ok(theme)
+}
+
+/// Adds an imported theme to the config, replacing an earlier import of the same name
+pub fn import(config: &mut AppConfig, json: &str) -> Result<Theme, String> {
+    let theme = parse(json)?;
+    if is_builtin(&theme.name) {
+        return Err(format!("{} is a built-in theme; rename it before importing", theme.name));
+    }
+    config.custom_themes.retain(|t| !t.name.eq_ignore_ascii_case(&theme.name));
+    config.custom_themes.push(theme.clone());
+    Ok(theme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_at_interpolates_and_clamps() {
+        let classic = Theme::from_table("classic", CLASSIC);
+        assert_eq!(classic.color_at(0.0, Interpolation::Rgb), "#00033a");
+        assert_eq!(classic.color_at(-80.0, Interpolation::Rgb), "#e1e1ff");
+        assert_eq!(classic.color_at(95.0, Interpolation::Rgb), "#0d0001");
+
+        let two = Theme::from_table("two", &[(0.0, "#000000"), (10.0, "#ff0080")]);
+        assert_eq!(two.color_at(5.0, Interpolation::Rgb), "#7f0040");
+    }
+
+    #[test]
+    fn test_colorblind_themes_stay_distinguishable() {
+        let distance = |a: (u8, u8, u8), b: (u8, u8, u8)| {
+            let d = |x: u8, y: u8| (x as f64 - y as f64).powi(2);
+            (d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)).sqrt()
+        };
+        for (name, vision) in [("deuteranopia-safe", "deuteranopia"), ("protanopia-safe", "protanopia"), ("tritanopia-safe", "tritanopia")] {
+            let theme = find(&AppConfig::default(), name).unwrap();
+            let seen: Vec<_> = theme.stops.iter()
+                .map(|s| crate::vision::simulate(hex_to_rgb(&s.color).unwrap(), vision).unwrap())
+                .collect();
+            for pair in seen.windows(2) {
+                assert!(distance(pair[0], pair[1]) > 40.0, "{} stops blend together: {:?}", name, pair);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fahrenheit_round_trip() {
+        let exported = Theme::from_table("two", &[(0.0, "#000000"), (37.0, "#ff0000")]).in_unit("F");
+        assert_eq!(exported.unit, "F");
+        assert_eq!((exported.stops[0].temp, exported.stops[1].temp), (32.0, 98.6));
+
+        let imported = parse(&serde_json::to_string(&exported).unwrap()).unwrap();
+        assert_eq!(imported.unit, "C");
+        assert!((imported.stops[1].temp - 37.0).abs() < 1e-9);
+        // Colors follow the °C reading whatever unit the theme was written in
+        assert_eq!(imported.color_at(37.0, Interpolation::Rgb), "#ff0000");
+        assert!(parse(r##"{"name": "x", "unit": "K", "stops": [{"temp": 0, "color": "#000000"}, {"temp": 1, "color": "#ffffff"}]}"##).is_err());
+    }
+
+    #[test]
+    fn test_anomaly_color() {
+        assert_eq!(anomaly_color(0.0, Interpolation::Rgb), "#f7f7f7");
+        assert_eq!(anomaly_color(-15.0, Interpolation::Rgb), "#2166ac");
+        assert_eq!(anomaly_color(12.0, Interpolation::Rgb), "#b2182b");
+    }
+
+    #[test]
+    fn test_active_falls_back_to_classic() {
+        let mut config = AppConfig { theme: "desert".into(), ..Default::default() };
+        assert_eq!(active(&config).name, "desert");
+        config.theme = "gone".into();
+        assert_eq!(active(&config).name, "classic");
+    }
+
+    #[test]
+    fn test_import() {
+        let mut config = AppConfig::default();
+        let json = r##"{"name": " Sunset ", "stops": [{"temp": 30, "color": "#ff0000"}, {"temp": 0, "color": "#0000ff"}]}"##;
+        let theme = import(&mut config, json).unwrap();
+        assert_eq!(theme.name, "Sunset");
+        assert_eq!(theme.stops[0].temp, 0.0);
+        // Importing again replaces rather than duplicates
+        import(&mut config, json).unwrap();
+        assert_eq!(config.custom_themes.len(), 1);
+        assert_eq!(find(&config, "sunset").unwrap().stops.len(), 2);
+
+        assert!(import(&mut config, r##"{"name": "Classic", "stops": [{"temp": 0, "color": "#000000"}, {"temp": 1, "color": "#ffffff"}]}"##).is_err());
+        assert!(parse(r##"{"name": "x", "stops": [{"temp": 0, "color": "#000000"}]}"##).is_err());
+        assert!(parse(r##"{"name": "x", "stops": [{"temp": 0, "color": "red"}, {"temp": 1, "color": "#ffffff"}]}"##).is_err());
+        assert!(parse("not json").is_err());
+    }
+}
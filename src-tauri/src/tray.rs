@@ -1,24 +1,271 @@
 use tauri::tray::{TrayIconBuilder, MouseButton, MouseButtonState, TrayIconEvent, TrayIcon};
 use tauri::{AppHandle, Manager, Emitter};
-use tauri::menu::{Menu, MenuItem, CheckMenuItem};
+use tauri::menu::{Menu, MenuItem, CheckMenuItem, Submenu};
+use chrono::{DateTime, Local, TimeZone, Utc};
 use tauri_plugin_autostart::ManagerExt;
-use image::{ImageBuffer, Rgba};
+use image::{ImageBuffer, Rgba, RgbaImage};
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::config::AppConfig;
+use crate::models::WeatherState;
+
+// Last values pushed to the OS. Some Linux trays flicker on every set_icon, so unchanged
+// tooltips and icons are skipped instead of being re-sent each refresh.
+struct TrayCache {
+    tooltip: Option<String>,
+    icon: Option<(IconSpec, bool)>,
+    // Lines of the Forecast submenu; the menu is rebuilt when they change
+    forecast: Vec<String>,
+    // The "Updated 14:32 · next in 9 min" line, changed in place rather than by rebuilding the
+    // menu every minute
+    status: String,
+    status_item: Option<MenuItem<tauri::Wry>>,
+    // `taskbar_is_dark()`, looked up once and again when the theme changes; on Windows it's a
+    // registry query in a child process
+    dark_theme: Option<bool>,
+}
+
+static TRAY_CACHE: Mutex<TrayCache> = Mutex::new(TrayCache { tooltip: None, icon: None, forecast: Vec::new(), status: String::new(), status_item: None, dark_theme: None });
 
 pub fn update_tray_tooltip(app_handle: &AppHandle, text: &str) {
+    if let Ok(mut cache) = TRAY_CACHE.lock() {
+        if cache.tooltip.as_deref() == Some(text) {
+            return;
+        }
+        cache.tooltip = Some(text.to_string());
+    }
     if let Some(tray) = app_handle.tray_by_id("main") {
         let _ = tray.set_tooltip(Some(text));
     }
 }
 
-pub fn create_tray(app_handle: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+/// The temperature drawn on the icon (`trayIcon` in the config)
+#[derive(Debug, Clone, PartialEq)]
+pub enum IconLabel {
+    None,
+    /// On top of the colored square
+    OnColor(String),
+    /// In place of it, underlined in the color
+    Alone(String),
+}
+
+impl IconLabel {
+    pub fn new(style: &str, text: String) -> Self {
+        match style {
+            "number" => Self::OnColor(text),
+            "numberOnly" => Self::Alone(text),
+            _ => Self::None,
+        }
+    }
+}
+
+/// Precipitation drawn along the bottom of the icon (`trayConditions`), like the stars at night
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    Rain,
+    Snow,
+    Thunder,
+}
+
+impl Condition {
+    pub fn of(weather: &WeatherState) -> Option<Self> {
+        if weather.has_thunder {
+            Some(Self::Thunder)
+        } else if weather.has_precipitation {
+            Some(if weather.precip_type.as_deref() == Some("Snow") { Self::Snow } else { Self::Rain })
+        } else {
+            None
+        }
+    }
+
+    // Rows of font pixels, '#' drawn
+    fn pattern(self) -> &'static [&'static str] {
+        match self {
+            Self::Rain => &[
+                "..#...#...#",
+                ".#...#...#.",
+                "...#...#...",
+                "..#...#....",
+            ],
+            Self::Snow => &[
+                ".#...#...#.",
+                "###.###.###",
+                ".#...#...#.",
+            ],
+            Self::Thunder => &[
+                "..##",
+                ".##.",
+                "###.",
+                ".#..",
+            ],
+        }
+    }
+}
+
+/// What the weather color is drawn as (`trayIconShape` in the config)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IconShape {
+    Square,
+    Circle,
+    /// A bar filled to the temperature
+    Thermometer,
+    /// The temperature color beside the chance of precipitation
+    Split,
+}
+
+impl IconShape {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "circle" => Self::Circle,
+            "thermometer" => Self::Thermometer,
+            "split" => Self::Split,
+            _ => Self::Square,
+        }
+    }
+}
+
+/// Everything the tray icon shows
+#[derive(Debug, Clone, PartialEq)]
+pub struct IconSpec {
+    pub hex_color: String,
+    pub is_night_mode: bool,
+    pub label: IconLabel,
+    pub condition: Option<Condition>,
+    pub shape: IconShape,
+    /// °C, for the thermometer
+    pub temperature: f64,
+    /// The rain or snow color and the chance of it (percent), for the split icon
+    pub precip_color: String,
+    pub precip_probability: f64,
+}
+
+/// Updates tooltip and icon together, touching only what changed.
+pub fn update_tray(app_handle: &AppHandle, tooltip: &str, icon: &IconSpec) {
+    update_tray_tooltip(app_handle, tooltip);
+    update_tray_icon(app_handle, icon);
+}
+
+/// Rebuilds the Forecast submenu when the coming hours read differently
+pub fn update_tray_forecast(app_handle: &AppHandle, weather: &WeatherState, config: &AppConfig) {
+    let lines = forecast_lines(weather, config, Utc::now());
+    if let Ok(mut cache) = TRAY_CACHE.lock() {
+        if cache.forecast == lines {
+            return;
+        }
+        cache.forecast = lines;
+    }
+    refresh_tray_menu(app_handle);
+}
+
+// "14:00  12°  20%" for the forecast blocks of the next 6 hours, the current one first
+fn forecast_lines(weather: &WeatherState, config: &AppConfig, now: DateTime<Utc>) -> Vec<String> {
+    weather.debug_forecast.iter()
+        .filter(|item| item.time + chrono::Duration::hours(1) > now && item.time < now + chrono::Duration::hours(6))
+        .take(6)
+        .map(|item| format!(
+            "{}  {}°  {}%",
+            item.time.with_timezone(&Local).format("%H:%M"),
+            config.display_temp(item.temp).round() as i64,
+            item.precip_prob.round() as i64,
+        ))
+        .collect()
+}
+
+/// Sets the menu's line on when the weather was last updated and the next refresh is due (None
+/// while updates are paused), if the text changed
+pub fn update_tray_status(updated: Option<DateTime<Utc>>, next_in: Option<Duration>) {
+    let line = status_line(updated.map(|t| t.with_timezone(&Local)), next_in);
+    let item = {
+        let Ok(mut cache) = TRAY_CACHE.lock() else { return };
+        if cache.status == line {
+            return;
+        }
+        cache.status = line.clone();
+        cache.status_item.clone()
+    };
+    if let Some(item) = item {
+        let _ = item.set_text(&line);
+    }
+}
+
+// "Updated 14:32 · next in 9 min"
+fn status_line(updated: Option<DateTime<Local>>, next_in: Option<Duration>) -> String {
+    let updated = updated.map_or_else(|| "Not updated yet".to_string(), |t| format!("Updated {}", t.format("%H:%M")));
+    match next_in {
+        None => format!("{} · paused", updated),
+        Some(wait) if wait.as_secs() < 60 => format!("{} · next in under a minute", updated),
+        Some(wait) => format!("{} · next in {} min", updated, wait.as_secs().div_ceil(60)),
+    }
+}
+
+// Rebuilt when the presets or the forecast change, so the submenus list the current ones
+fn build_menu(app_handle: &AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    // Informational, so disabled; `update_tray_status()` keeps its text current
+    let status = TRAY_CACHE.lock().map(|cache| cache.status.clone()).unwrap_or_default();
+    let status_i = MenuItem::with_id(app_handle, "status", if status.is_empty() { "Not updated yet" } else { &status }, false, None::<&str>)?;
+    if let Ok(mut cache) = TRAY_CACHE.lock() {
+        cache.status_item = Some(status_i.clone());
+    }
     let refresh_i = MenuItem::with_id(app_handle, "refresh", "Refresh", true, None::<&str>)?;
     let settings_i = MenuItem::with_id(app_handle, "settings", "Settings", true, None::<&str>)?;
+    let identify_i = MenuItem::with_id(app_handle, "identify", "Test Light", true, None::<&str>)?;
     
     let autostart_enabled = app_handle.autolaunch().is_enabled().unwrap_or(false);
     let autostart_i = CheckMenuItem::with_id(app_handle, "autostart", "Start with Windows", true, autostart_enabled, None::<&str>)?;
     let quit_i = MenuItem::with_id(app_handle, "quit", "Quit", true, None::<&str>)?;
 
-    let menu = Menu::with_items(app_handle, &[&refresh_i, &settings_i, &autostart_i, &quit_i])?;
+    // Rebuilt whenever either changes, so the checks follow the settings window too
+    let state = app_handle.state::<crate::AppState>();
+    let manual_mode = *state.busylight.manual_mode.lock().unwrap();
+    let pulse = crate::precip_pulse_enabled(&state.config.lock().unwrap());
+    let manual_mode_i = CheckMenuItem::with_id(app_handle, "manual_mode", "Manual mode", true, manual_mode, None::<&str>)?;
+    let pulse_i = CheckMenuItem::with_id(app_handle, "pulse", "Pulse on precipitation", true, pulse, None::<&str>)?;
+    let pause_i = CheckMenuItem::with_id(app_handle, "pause", "Pause updates", true, crate::is_paused(&state), None::<&str>)?;
+
+    let snooze_30_i = MenuItem::with_id(app_handle, "snooze_30", "30 minutes", true, None::<&str>)?;
+    let snooze_60_i = MenuItem::with_id(app_handle, "snooze_60", "1 hour", true, None::<&str>)?;
+    let snooze_tomorrow_i = MenuItem::with_id(app_handle, "snooze_tomorrow", "Until tomorrow", true, None::<&str>)?;
+    let snooze_resume_i = MenuItem::with_id(app_handle, "snooze_resume", "Resume now", true, None::<&str>)?;
+    let snooze_menu = Submenu::with_items(app_handle, "Snooze", true, &[&snooze_30_i, &snooze_60_i, &snooze_tomorrow_i, &snooze_resume_i])?;
+
+    // Presets by position; the click looks the name up in the config
+    let presets = state.config.lock().unwrap().manual_presets.clone();
+    let preset_menu = Submenu::new(app_handle, "Presets", true)?;
+    for (i, preset) in presets.iter().enumerate() {
+        preset_menu.append(&MenuItem::with_id(app_handle, format!("preset_{}", i), &preset.name, true, None::<&str>)?)?;
+    }
+    preset_menu.append(&MenuItem::with_id(app_handle, "preset_off", "Back to weather", true, None::<&str>)?)?;
+
+    // Only worth a submenu with places to choose between; the one in use is checked
+    let config = state.config.lock().unwrap().clone();
+    let location_menu = Submenu::new(app_handle, "Location", config.saved_locations.len() >= 2)?;
+    for (i, location) in config.saved_locations.iter().enumerate() {
+        let active = !config.auto_location && location.eq_ignore_ascii_case(config.location.trim());
+        location_menu.append(&CheckMenuItem::with_id(app_handle, format!("location_{}", i), location, true, active, None::<&str>)?)?;
+    }
+    location_menu.append(&CheckMenuItem::with_id(app_handle, "location_auto", "Detect automatically", true, config.auto_location, None::<&str>)?)?;
+
+    // Read-only: disabled items, nothing to click
+    let forecast = TRAY_CACHE.lock().map(|cache| cache.forecast.clone()).unwrap_or_default();
+    let forecast_menu = Submenu::new(app_handle, "Forecast", true)?;
+    if forecast.is_empty() {
+        forecast_menu.append(&MenuItem::new(app_handle, "No forecast yet", false, None::<&str>)?)?;
+    }
+    for line in &forecast {
+        forecast_menu.append(&MenuItem::new(app_handle, line, false, None::<&str>)?)?;
+    }
+
+    Menu::with_items(app_handle, &[&status_i, &refresh_i, &settings_i, &forecast_menu, &location_menu, &snooze_menu, &preset_menu, &manual_mode_i, &pulse_i, &pause_i, &identify_i, &autostart_i, &quit_i])
+}
+
+pub fn refresh_tray_menu(app_handle: &AppHandle) {
+    if let (Some(tray), Ok(menu)) = (app_handle.tray_by_id("main"), build_menu(app_handle)) {
+        let _ = tray.set_menu(Some(menu));
+    }
+}
+
+pub fn create_tray(app_handle: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let menu = build_menu(app_handle)?;
 
     // Default icon loaded via `image` crate and converted to Tauri Image
     let icon_bytes = include_bytes!("../icons/icon.png");
@@ -64,10 +311,54 @@ pub fn create_tray(app_handle: &AppHandle) -> Result<(), Box<dyn std::error::Err
                     *c = config;
                 }
             }
+            "identify" => {
+                if let Err(e) = crate::identify_busylights(&app.state::<crate::AppState>(), None) {
+                    println!("Light test failed: {}", e);
+                }
+            }
+            "snooze_30" => crate::set_snooze(app, Some(Utc::now() + chrono::Duration::minutes(30))),
+            "snooze_60" => crate::set_snooze(app, Some(Utc::now() + chrono::Duration::hours(1))),
+            "snooze_tomorrow" => crate::set_snooze(app, Some(start_of_tomorrow())),
+            "snooze_resume" => crate::set_snooze(app, None),
+            "preset_off" => crate::end_manual_mode(app),
+            "location_auto" => {
+                if let Err(e) = crate::switch_location(app, None) {
+                    println!("Location switch failed: {}", e);
+                }
+            }
+            "manual_mode" => {
+                if *app.state::<crate::AppState>().busylight.manual_mode.lock().unwrap() {
+                    crate::end_manual_mode(app);
+                } else {
+                    crate::start_manual_mode(app);
+                }
+            }
+            "pulse" => {
+                let enabled = crate::precip_pulse_enabled(&app.state::<crate::AppState>().config.lock().unwrap());
+                if let Err(e) = crate::set_precip_pulse(app, !enabled) {
+                    println!("Pulse toggle failed: {}", e);
+                }
+            }
+            "pause" => crate::set_paused(app, !crate::is_paused(&app.state::<crate::AppState>())),
             "quit" => {
                 std::process::exit(0);
             }
-            _ => {}
+            id => {
+                if let Some(index) = id.strip_prefix("location_").and_then(|i| i.parse::<usize>().ok()) {
+                    let location = app.state::<crate::AppState>().config.lock().unwrap().saved_locations.get(index).cloned();
+                    if let Err(e) = location.map_or(Ok(()), |location| crate::switch_location(app, Some(location))) {
+                        println!("Location switch failed: {}", e);
+                    }
+                    return;
+                }
+                let Some(index) = id.strip_prefix("preset_").and_then(|i| i.parse::<usize>().ok()) else { return };
+                let name = app.state::<crate::AppState>().config.lock().unwrap().manual_presets.get(index).map(|p| p.name.clone());
+                if let Some(name) = name {
+                    if let Err(e) = crate::apply_preset(app, &name, None) {
+                        println!("Preset failed: {}", e);
+                    }
+                }
+            }
         })
         .on_tray_icon_event(|tray: &TrayIcon, event| match event {
             TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } => {
@@ -84,76 +375,432 @@ pub fn create_tray(app_handle: &AppHandle) -> Result<(), Box<dyn std::error::Err
     Ok(())
 }
 
-fn hex_to_rgba(hex: &str) -> Option<Rgba<u8>> {
-    if hex.len() != 7 || !hex.starts_with('#') {
-        return None;
-    }
-    let r = u8::from_str_radix(&hex[1..3], 16).ok()?;
-    let g = u8::from_str_radix(&hex[3..5], 16).ok()?;
-    let b = u8::from_str_radix(&hex[5..7], 16).ok()?;
-    Some(Rgba([r, g, b, 255]))
+// Local midnight at the start of tomorrow
+fn start_of_tomorrow() -> DateTime<Utc> {
+    let tomorrow = Local::now().date_naive().succ_opt().unwrap_or_else(|| Local::now().date_naive());
+    let midnight = tomorrow.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local.from_local_datetime(&midnight).earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc::now() + chrono::Duration::hours(12))
 }
 
-pub fn update_tray_icon(app_handle: &AppHandle, hex_color: &str, is_night_mode: bool) {
+pub fn update_tray_icon(app_handle: &AppHandle, spec: &IconSpec) {
+    let cached = TRAY_CACHE.lock().ok().and_then(|cache| cache.dark_theme);
+    let dark_theme = cached.unwrap_or_else(|| refresh_theme(app_handle));
+    if let Ok(mut cache) = TRAY_CACHE.lock() {
+        let key = (IconSpec { hex_color: spec.hex_color.to_lowercase(), ..spec.clone() }, dark_theme);
+        if cache.icon.as_ref() == Some(&key) {
+            return;
+        }
+        cache.icon = Some(key);
+    }
     if let Some(tray) = app_handle.tray_by_id("main") {
-        if let Some(color) = hex_to_rgba(hex_color) {
-            let width = 16;
-            let height = 16;
-            let mut img: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(width, height);
-            
-            let bg_transparent = Rgba([0, 0, 0, 0]);
-
-            for (x, y, pixel) in img.enumerate_pixels_mut() {
-                // 2px rounded corner clipping mask
-                let r = 2.0_f32;
-                let mut clipped = false;
-
-                // Check 4 corners (top-left, top-right, bottom-left, bottom-right)
-                if (x as f32) < r && (y as f32) < r {
-                    let dx = x as f32 - r + 0.5;
-                    let dy = y as f32 - r + 0.5;
-                    if (dx * dx + dy * dy).sqrt() > r { clipped = true; }
-                } else if (x as f32) >= (width as f32 - r) && (y as f32) < r {
-                    let dx = x as f32 - (width as f32 - r) + 0.5;
-                    let dy = y as f32 - r + 0.5;
-                    if (dx * dx + dy * dy).sqrt() > r { clipped = true; }
-                } else if (x as f32) < r && (y as f32) >= (height as f32 - r) {
-                    let dx = x as f32 - r + 0.5;
-                    let dy = y as f32 - (height as f32 - r) + 0.5;
-                    if (dx * dx + dy * dy).sqrt() > r { clipped = true; }
-                } else if (x as f32) >= (width as f32 - r) && (y as f32) >= (height as f32 - r) {
-                    let dx = x as f32 - (width as f32 - r) + 0.5;
-                    let dy = y as f32 - (height as f32 - r) + 0.5;
-                    if (dx * dx + dy * dy).sqrt() > r { clipped = true; }
-                }
+        if let Some(rgb) = crate::color::hex_to_rgb(&spec.hex_color) {
+            let img = render_icon(rgb, spec, dark_theme);
+            // Convert image buffer to tauri valid icon format
+            let icon = tauri::image::Image::new_owned(img.into_raw(), ICON_SIZE, ICON_SIZE);
+            let _ = tray.set_icon(Some(icon));
+        }
+    }
+}
 
-                if clipped {
-                    *pixel = bg_transparent;
-                    continue;
+/// Draws the last icon again, when the system theme changes under it
+pub fn redraw_tray_icon(app_handle: &AppHandle) {
+    refresh_theme(app_handle);
+    let last = TRAY_CACHE.lock().ok().and_then(|cache| cache.icon.clone());
+    if let Some((spec, _)) = last {
+        update_tray_icon(app_handle, &spec);
+    }
+}
+
+// Looks the taskbar theme up again and keeps it for the next icons
+fn refresh_theme(app_handle: &AppHandle) -> bool {
+    let dark_theme = taskbar_is_dark(app_handle);
+    if let Ok(mut cache) = TRAY_CACHE.lock() {
+        cache.dark_theme = Some(dark_theme);
+    }
+    dark_theme
+}
+
+// Windows themes the taskbar apart from apps ("Choose your default Windows mode")
+#[cfg(target_os = "windows")]
+fn taskbar_is_dark(_app_handle: &AppHandle) -> bool {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    let output = std::process::Command::new("reg")
+        .args(["query", r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize", "/v", "SystemUsesLightTheme"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output();
+    // "    SystemUsesLightTheme    REG_DWORD    0x0"; missing means dark, the default
+    output.map(|o| !String::from_utf8_lossy(&o.stdout).split_whitespace().any(|word| word == "0x1")).unwrap_or(true)
+}
+
+// Elsewhere the menu bar or panel follows the system theme, as the settings window does
+#[cfg(not(target_os = "windows"))]
+fn taskbar_is_dark(app_handle: &AppHandle) -> bool {
+    app_handle.get_webview_window("main")
+        .and_then(|w| w.theme().ok())
+        .is_none_or(|theme| theme == tauri::Theme::Dark)
+}
+
+// Drawn at 32 px; trays that show 16 scale it down smoothly
+const ICON_SIZE: u32 = 32;
+
+// The thermometer is empty at the first and full at the second (°C)
+const THERMOMETER_RANGE: (f64, f64) = (-20.0, 40.0);
+
+fn render_icon((r, g, b): (u8, u8, u8), spec: &IconSpec, dark_theme: bool) -> RgbaImage {
+    let color = Rgba([r, g, b, 255]);
+    let ink = if dark_theme { Rgba([255, 255, 255, 255]) } else { Rgba([0, 0, 0, 255]) };
+    // Off the shape (the thermometer leaves most of the icon bare) the taskbar is underneath
+    let contrast = |under: Rgba<u8>| if under[3] < 128 {
+        ink
+    } else if luma(under) > 140 {
+        Rgba([0, 0, 0, 255])
+    } else {
+        Rgba([255, 255, 255, 255])
+    };
+    match &spec.label {
+        IconLabel::None | IconLabel::OnColor(_) => {
+            let mut img = match spec.shape {
+                IconShape::Square => color_square(color, spec.is_night_mode, dark_theme),
+                IconShape::Circle => color_circle(color, spec.is_night_mode, dark_theme),
+                IconShape::Thermometer => thermometer(color, spec.temperature, spec.is_night_mode, dark_theme),
+                IconShape::Split => {
+                    let (pr, pg, pb) = crate::color::hex_to_rgb(&spec.precip_color).unwrap_or((0, 80, 255));
+                    split_square(color, Rgba([pr, pg, pb, 255]), spec.precip_probability, spec.is_night_mode, dark_theme)
+                }
+            };
+            // A condition sits on the bottom rows of the shape, below where the digits go
+            if let Some(pattern) = spec.condition.map(Condition::pattern) {
+                stamp(&mut img, pattern, (15 - pattern.len() as u32) * 2, 2, contrast);
+            }
+            if let IconLabel::OnColor(text) = &spec.label {
+                draw_label(&mut img, text, 2, contrast);
+            }
+            img
+        }
+        IconLabel::Alone(text) => {
+            let mut img = RgbaImage::new(ICON_SIZE, ICON_SIZE);
+            let (left, right) = draw_label(&mut img, text, 2, |_| ink);
+            for x in left..right {
+                for y in 27..30 {
+                    img.put_pixel(x, y, color);
                 }
+            }
+            // The color bar takes the bottom, so the condition goes above the digits
+            if let Some(pattern) = spec.condition.map(Condition::pattern) {
+                stamp(&mut img, pattern, 2, 2, |_| ink);
+            }
+            img
+        }
+    }
+}
 
-                // Draw background color
-                *pixel = color;
-
-                // Night mode overlay
-                if is_night_mode {
-                    // Top half black
-                    if y < 8 {
-                        *pixel = Rgba([0, 0, 0, 255]);
-                        
-                        // White Stars: {x:2, y:2}, {x:8, y:1}, {x:13, y:3}, {x:5, y:5}, {x:11, y:6}
-                        if (x == 2 && y == 2) || (x == 8 && y == 1) || (x == 13 && y == 3) || 
-                           (x == 5 && y == 5) || (x == 11 && y == 6) {
-                            *pixel = Rgba([255, 255, 255, 255]);
-                        }
-                    }
+fn luma(Rgba([r, g, b, _]): Rgba<u8>) -> u32 {
+    (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000
+}
+
+// The rounded square in the weather color with anti-aliased corners and a faint edge that sets it
+// off from the taskbar, light on a dark one and dark on a light one. At night the top half is a
+// starry sky, navy rather than black on a dark taskbar so it doesn't vanish into it.
+fn color_square(color: Rgba<u8>, is_night_mode: bool, dark_theme: bool) -> RgbaImage {
+    shaded(rounded_square, night_sky(color, is_night_mode, dark_theme), dark_theme)
+}
+
+// The same, as a disc
+fn color_circle(color: Rgba<u8>, is_night_mode: bool, dark_theme: bool) -> RgbaImage {
+    let half = ICON_SIZE as f32 / 2.0;
+    shaded(|x, y| (x - half).hypot(y - half) - half, night_sky(color, is_night_mode, dark_theme), dark_theme)
+}
+
+// A bulb and tube in the weather color, the tube filled as far as `temperature` reaches within
+// `THERMOMETER_RANGE`; the empty rest of it is the night sky after dark
+fn thermometer(color: Rgba<u8>, temperature: f64, is_night_mode: bool, dark_theme: bool) -> RgbaImage {
+    let (low, high) = THERMOMETER_RANGE;
+    let full = ((temperature - low) / (high - low)).clamp(0.0, 1.0) as f32;
+    // The tube's inside runs from row 3 down into the bulb at row 21
+    let level = 3.0 + (1.0 - full) * 18.0;
+    let empty = if is_night_mode { sky(dark_theme) } else { well(dark_theme) };
+    let outline = |x: f32, y: f32| {
+        let tube = (x - 16.0).hypot(y - y.clamp(7.0, 20.0)) - 4.0;
+        let bulb = (x - 16.0).hypot(y - 24.5) - 7.0;
+        tube.min(bulb)
+    };
+    shaded(outline, |_, y| if y as f32 + 0.5 >= level { color } else { empty }, dark_theme)
+}
+
+// The rounded square cut down the middle: the weather color on the left (starry at night like the
+// plain square), and on the right `precip_color` rising from the bottom to the chance of it
+fn split_square(color: Rgba<u8>, precip_color: Rgba<u8>, precip_probability: f64, is_night_mode: bool, dark_theme: bool) -> RgbaImage {
+    let left = night_sky(color, is_night_mode, dark_theme);
+    let level = ICON_SIZE as f32 * (1.0 - (precip_probability / 100.0).clamp(0.0, 1.0) as f32);
+    let fill = |x: u32, y: u32| if x < ICON_SIZE / 2 {
+        left(x, y)
+    } else if y as f32 + 0.5 >= level {
+        precip_color
+    } else {
+        well(dark_theme)
+    };
+    shaded(rounded_square, fill, dark_theme)
+}
+
+// Signed distance from (x, y) to the outline of the icon-sized rounded square, negative inside
+fn rounded_square(x: f32, y: f32) -> f32 {
+    const RADIUS: f32 = 4.0;
+    let half = ICON_SIZE as f32 / 2.0;
+    let qx = (x - half).abs() - (half - RADIUS);
+    let qy = (y - half).abs() - (half - RADIUS);
+    qx.max(0.0).hypot(qy.max(0.0)) + qx.max(qy).min(0.0) - RADIUS
+}
+
+fn sky(dark_theme: bool) -> Rgba<u8> {
+    if dark_theme { Rgba([20, 28, 72, 255]) } else { Rgba([0, 0, 0, 255]) }
+}
+
+// The unfilled part of a gauge, a step off the taskbar
+fn well(dark_theme: bool) -> Rgba<u8> {
+    if dark_theme { Rgba([64, 64, 64, 255]) } else { Rgba([208, 208, 208, 255]) }
+}
+
+// `color`, or at night a starry sky over the top half of it
+fn night_sky(color: Rgba<u8>, is_night_mode: bool, dark_theme: bool) -> impl Fn(u32, u32) -> Rgba<u8> {
+    // Stars as 2x2 dots on the 16 px grid they were designed on
+    let stars = [(2, 2), (8, 1), (13, 3), (5, 5), (11, 6)];
+    move |x, y| if !is_night_mode || y >= ICON_SIZE / 2 {
+        color
+    } else if stars.contains(&(x / 2, y / 2)) {
+        Rgba([255, 255, 255, 255])
+    } else {
+        sky(dark_theme)
+    }
+}
+
+// Paints the shape whose signed distance (negative inside, from a pixel center) is `distance`,
+// each pixel in the color `fill` picks, with anti-aliased edges and a faint rim in the theme's
+// edge color
+fn shaded(distance: impl Fn(f32, f32) -> f32, fill: impl Fn(u32, u32) -> Rgba<u8>, dark_theme: bool) -> RgbaImage {
+    let edge = if dark_theme { [255.0, 255.0, 255.0] } else { [0.0, 0.0, 0.0] };
+    ImageBuffer::from_fn(ICON_SIZE, ICON_SIZE, |x, y| {
+        let distance = distance(x as f32 + 0.5, y as f32 + 0.5);
+        let coverage = (0.5 - distance).clamp(0.0, 1.0);
+        if coverage == 0.0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        let fill = fill(x, y);
+        // The outermost pixel, a third of the way toward the edge color
+        let rim = (1.5 + distance).clamp(0.0, 1.0) / 3.0;
+        let mix = |i: usize| (fill[i] as f32 * (1.0 - rim) + edge[i] * rim).round() as u8;
+        Rgba([mix(0), mix(1), mix(2), (coverage * 255.0).round() as u8])
+    })
+}
+
+// Draws `pattern` centered across `img` from row `top`, at `scale` pixels per '#'
+fn stamp(img: &mut RgbaImage, pattern: &[&str], top: u32, scale: u32, ink: impl Fn(Rgba<u8>) -> Rgba<u8>) {
+    let width = pattern.iter().map(|row| row.len() as u32).max().unwrap_or(0) * scale;
+    let left = img.width().saturating_sub(width) / 2;
+    for (row, line) in pattern.iter().enumerate() {
+        for (col, _) in line.chars().enumerate().filter(|(_, c)| *c == '#') {
+            fill(img, left + col as u32 * scale, top + row as u32 * scale, scale, &ink);
+        }
+    }
+}
+
+// A `scale`-sized block at (x0, y0), clipped to `img`
+fn fill(img: &mut RgbaImage, x0: u32, y0: u32, scale: u32, ink: &impl Fn(Rgba<u8>) -> Rgba<u8>) {
+    for (x, y) in (x0..x0 + scale).flat_map(|x| (y0..y0 + scale).map(move |y| (x, y))) {
+        if x < img.width() && y < img.height() {
+            let color = ink(*img.get_pixel(x, y));
+            img.put_pixel(x, y, color);
+        }
+    }
+}
+
+// 3x5 digits and a minus sign, a row per byte with the leftmost pixel in bit 2
+fn glyph(c: char) -> Option<[u8; 5]> {
+    Some(match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        _ => return None,
+    })
+}
+
+// Draws `text` centered on `img` at `scale` pixels per font pixel, each pixel in the color `ink`
+// picks for the one underneath. Returns the columns the text covers.
+fn draw_label(img: &mut RgbaImage, text: &str, scale: u32, ink: impl Fn(Rgba<u8>) -> Rgba<u8>) -> (u32, u32) {
+    let glyphs: Vec<[u8; 5]> = text.chars().filter_map(glyph).collect();
+    if glyphs.is_empty() {
+        return (0, 0);
+    }
+    // A font pixel between characters
+    let width = (glyphs.len() as u32 * 4 - 1) * scale;
+    let left = img.width().saturating_sub(width) / 2;
+    let top = img.height().saturating_sub(5 * scale) / 2;
+    for (i, rows) in glyphs.iter().enumerate() {
+        for (row, bits) in rows.iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
                 }
+                fill(img, left + (i as u32 * 4 + col) * scale, top + row as u32 * scale, scale, &ink);
             }
+        }
+    }
+    (left, (left + width).min(img.width()))
+}
 
-            // Convert image buffer to tauri valid icon format
-            let rgba_raw = img.into_raw();
-            let icon = tauri::image::Image::new_owned(rgba_raw, width, height);
-            let _ = tray.set_icon(Some(icon));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(hex: &str, label: IconLabel, condition: Option<Condition>) -> IconSpec {
+        IconSpec {
+            hex_color: hex.to_string(),
+            is_night_mode: false,
+            label,
+            condition,
+            shape: IconShape::Square,
+            temperature: 10.0,
+            precip_color: "#0050FF".to_string(),
+            precip_probability: 0.0,
         }
     }
+
+    #[test]
+    fn test_render_label() {
+        let plain = render_icon((255, 128, 0), &spec("#FF8000", IconLabel::None, None), true);
+        assert_eq!(plain.dimensions(), (32, 32));
+
+        // Dark digits on a light color, light ones on a dark color
+        let on_light = render_icon((255, 255, 0), &spec("#FFFF00", IconLabel::OnColor("21".into()), None), true);
+        assert_eq!(on_light.dimensions(), (32, 32));
+        assert!(on_light.pixels().any(|p| *p == Rgba([0, 0, 0, 255])));
+        let on_dark = render_icon((0, 0, 128), &spec("#000080", IconLabel::OnColor("-3".into()), None), true);
+        assert!(on_dark.pixels().any(|p| *p == Rgba([255, 255, 255, 255])));
+
+        // Alone: theme-colored digits over a color bar as wide as them, the rest transparent
+        let alone = render_icon((255, 0, 0), &spec("#FF0000", IconLabel::Alone("-12".into()), None), false);
+        assert_eq!(*alone.get_pixel(0, 0), Rgba([0, 0, 0, 0]));
+        assert!(alone.pixels().any(|p| *p == Rgba([0, 0, 0, 255])));
+        // "-12" is 11 font pixels, 22 px wide, centered
+        assert_eq!(*alone.get_pixel(5, 28), Rgba([255, 0, 0, 255]));
+        assert_eq!(*alone.get_pixel(26, 28), Rgba([255, 0, 0, 255]));
+        assert_eq!(*alone.get_pixel(27, 28), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_color_square() {
+        // Soft corners, a solid middle
+        let light = color_square(Rgba([255, 128, 0, 255]), false, false);
+        assert_eq!(light.get_pixel(0, 0)[3], 0);
+        assert!((1..255).contains(&light.get_pixel(1, 1)[3]));
+        assert_eq!(*light.get_pixel(16, 16), Rgba([255, 128, 0, 255]));
+        // The rim leans dark on a light taskbar and light on a dark one
+        assert!(light.get_pixel(16, 0)[1] < 128);
+        assert!(color_square(Rgba([255, 128, 0, 255]), false, true).get_pixel(16, 0)[1] > 128);
+
+        // Night: stars in a sky that follows the theme, the weather color below
+        let night = color_square(Rgba([255, 128, 0, 255]), true, true);
+        assert_eq!(*night.get_pixel(4, 4), Rgba([255, 255, 255, 255]));
+        assert_eq!(*night.get_pixel(12, 10), Rgba([20, 28, 72, 255]));
+        assert_eq!(*color_square(Rgba([255, 128, 0, 255]), true, false).get_pixel(12, 10), Rgba([0, 0, 0, 255]));
+        assert_eq!(*night.get_pixel(16, 24), Rgba([255, 128, 0, 255]));
+    }
+
+    #[test]
+    fn test_render_shapes() {
+        let shaped = |shape: IconShape, temperature: f64, precip_probability: f64| {
+            let spec = IconSpec { shape, temperature, precip_probability, ..spec("#FF8000", IconLabel::None, None) };
+            render_icon((255, 128, 0), &spec, true)
+        };
+        let orange = Rgba([255, 128, 0, 255]);
+
+        // The circle leaves more of the corners bare than the square
+        let circle = shaped(IconShape::Circle, 10.0, 0.0);
+        assert_eq!(circle.get_pixel(3, 3)[3], 0);
+        assert!(shaped(IconShape::Square, 10.0, 0.0).get_pixel(3, 3)[3] > 0);
+        assert_eq!(*circle.get_pixel(16, 16), orange);
+
+        // The tube fills with the temperature, the bulb always; the sides stay bare
+        let cold = shaped(IconShape::Thermometer, -20.0, 0.0);
+        let warm = shaped(IconShape::Thermometer, 40.0, 0.0);
+        assert_eq!(*cold.get_pixel(16, 26), orange);
+        assert_eq!(*cold.get_pixel(16, 8), Rgba([64, 64, 64, 255]));
+        assert_eq!(*warm.get_pixel(16, 8), orange);
+        assert_eq!(warm.get_pixel(2, 8)[3], 0);
+
+        // Weather color on the left, precipitation rising on the right
+        let split = shaped(IconShape::Split, 10.0, 50.0);
+        assert_eq!(*split.get_pixel(8, 8), orange);
+        assert_eq!(*split.get_pixel(24, 24), Rgba([0, 80, 255, 255]));
+        assert_eq!(*split.get_pixel(24, 8), Rgba([64, 64, 64, 255]));
+        assert_eq!(IconShape::from_name("thermometer"), IconShape::Thermometer);
+        assert_eq!(IconShape::from_name("anything"), IconShape::Square);
+    }
+
+    #[test]
+    fn test_forecast_lines() {
+        let now = Utc::now();
+        let item = |hours: i64, temp: f64, prob: f64| crate::models::ForecastItem {
+            time: now + chrono::Duration::hours(hours),
+            temp,
+            apparent_temp: None,
+            precip_prob: prob,
+            precip_type: "Rain".into(),
+            humidity: None,
+            dew_point: None,
+        };
+        let weather = WeatherState { debug_forecast: (-2..10).map(|h| item(h, 11.6, 20.0)).collect(), ..Default::default() };
+        let lines = forecast_lines(&weather, &AppConfig::default(), now);
+        // The hour under way and the next five
+        assert_eq!(lines.len(), 6);
+        let hour = now.with_timezone(&Local).format("%H:%M");
+        assert_eq!(lines[0], format!("{}  12°  20%", hour));
+        let fahrenheit = AppConfig { unit: "F".into(), ..Default::default() };
+        assert!(forecast_lines(&weather, &fahrenheit, now)[0].contains("  53°  "));
+        assert!(forecast_lines(&WeatherState::default(), &fahrenheit, now).is_empty());
+    }
+
+    #[test]
+    fn test_status_line() {
+        let updated = Local.with_ymd_and_hms(2026, 10, 16, 14, 32, 0).single();
+        assert_eq!(status_line(updated, Some(Duration::from_secs(9 * 60))), "Updated 14:32 · next in 9 min");
+        // Rounded up, so it never claims a refresh sooner than it comes
+        assert_eq!(status_line(updated, Some(Duration::from_secs(8 * 60 + 1))), "Updated 14:32 · next in 9 min");
+        assert_eq!(status_line(updated, Some(Duration::from_secs(20))), "Updated 14:32 · next in under a minute");
+        assert_eq!(status_line(updated, None), "Updated 14:32 · paused");
+        assert_eq!(status_line(None, Some(Duration::ZERO)), "Not updated yet · next in under a minute");
+    }
+
+    #[test]
+    fn test_render_condition() {
+        let weather = |precip: bool, kind: Option<&str>, thunder: bool| WeatherState {
+            has_precipitation: precip,
+            precip_type: kind.map(|k| k.to_string()),
+            has_thunder: thunder,
+            ..Default::default()
+        };
+        assert_eq!(Condition::of(&weather(false, None, false)), None);
+        assert_eq!(Condition::of(&weather(true, Some("Rain"), false)), Some(Condition::Rain));
+        assert_eq!(Condition::of(&weather(true, Some("Snow"), false)), Some(Condition::Snow));
+        assert_eq!(Condition::of(&weather(true, Some("Rain"), true)), Some(Condition::Thunder));
+
+        // Snowflakes on the bottom rows of a dark square, the rest of it left alone
+        let snow = render_icon((0, 0, 128), &spec("#000080", IconLabel::None, Some(Condition::Snow)), true);
+        assert_eq!(*snow.get_pixel(7, 24), Rgba([255, 255, 255, 255]));
+        assert!((2..22).all(|y| (2..30).all(|x| *snow.get_pixel(x, y) == Rgba([0, 0, 128, 255]))));
+
+        // Above the digits when the number stands alone
+        let alone = render_icon((255, 0, 0), &spec("#FF0000", IconLabel::Alone("5".into()), Some(Condition::Rain)), true);
+        assert!((2..10).any(|y| (0..32).any(|x| *alone.get_pixel(x, y) == Rgba([255, 255, 255, 255]))));
+    }
 }
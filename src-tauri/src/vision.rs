@@ -0,0 +1,56 @@
+// Color vision deficiency simulation (Machado, Oliveira & Fernandes 2009, full severity).
+// Matrices operate on linear RGB, so colors are decoded from sRGB first and re-encoded afterwards.
+
+use crate::color::{to_linear, to_srgb};
+
+const PROTANOPIA: [[f32; 3]; 3] = [
+    [0.152286, 1.052583, -0.204868],
+    [0.114503, 0.786281, 0.099216],
+    [-0.003882, -0.048116, 1.051998],
+];
+
+const DEUTERANOPIA: [[f32; 3]; 3] = [
+    [0.367322, 0.860646, -0.227968],
+    [0.280085, 0.672501, 0.047413],
+    [-0.011820, 0.042940, 0.968881],
+];
+
+const TRITANOPIA: [[f32; 3]; 3] = [
+    [1.255528, -0.076749, -0.178779],
+    [-0.078411, 0.930809, 0.147602],
+    [0.004733, 0.691367, 0.303900],
+];
+
+fn matrix_for(vision_type: &str) -> Option<&'static [[f32; 3]; 3]> {
+    match vision_type {
+        "protanopia" => Some(&PROTANOPIA),
+        "deuteranopia" => Some(&DEUTERANOPIA),
+        "tritanopia" => Some(&TRITANOPIA),
+        _ => None,
+    }
+}
+
+/// Returns how `rgb` appears under the given deficiency, or `None` for an unknown vision type.
+pub fn simulate(rgb: (u8, u8, u8), vision_type: &str) -> Option<(u8, u8, u8)> {
+    let m = matrix_for(vision_type)?;
+    let lin = [to_linear(rgb.0), to_linear(rgb.1), to_linear(rgb.2)];
+    let out: Vec<u8> = m.iter()
+        .map(|row| to_srgb(row[0] * lin[0] + row[1] * lin[1] + row[2] * lin[2]))
+        .collect();
+    Some((out[0], out[1], out[2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate_preserves_grey_and_rejects_unknown() {
+        // Neutral colors are (nearly) invariant under every simulation
+        for vt in ["protanopia", "deuteranopia", "tritanopia"] {
+            let (r, g, b) = simulate((128, 128, 128), vt).unwrap();
+            assert!(r.abs_diff(128) <= 2 && g.abs_diff(128) <= 2 && b.abs_diff(128) <= 2);
+        }
+        assert!(simulate((255, 0, 0), "monochrome").is_none());
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Worker loops call `beat()` every iteration. The watchdog thread restarts any worker whose
+// heartbeat goes stale (panicked on a poisoned lock, hung in a HID write, ...). Restarting bumps
+// the generation so a worker that later wakes up can notice it was replaced and exit.
+
+pub struct Heartbeat {
+    last_ms: AtomicU64,
+    generation: AtomicU64,
+}
+
+// Milliseconds since the first heartbeat, on the monotonic clock: a wall-clock step (NTP, the user
+// setting the time) doesn't look like a hang, and neither does a suspend where the clock stops
+// during sleep
+fn now_ms() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+impl Heartbeat {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            last_ms: AtomicU64::new(now_ms()),
+            generation: AtomicU64::new(0),
+        })
+    }
+
+    pub fn beat(&self) {
+        self.last_ms.store(now_ms(), Ordering::Relaxed);
+    }
+
+    pub fn age(&self) -> Duration {
+        Duration::from_millis(now_ms().saturating_sub(self.last_ms.load(Ordering::Relaxed)))
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Starts a new worker generation; older workers should stop once they see it.
+    pub fn next_generation(&self) -> u64 {
+        self.beat();
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+pub struct Watched {
+    pub name: &'static str,
+    pub heartbeat: Arc<Heartbeat>,
+    pub stale_after: Duration,
+    pub restart: Box<dyn Fn() + Send>,
+}
+
+pub fn spawn(watched: Vec<Watched>) {
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(5));
+        for w in &watched {
+            let age = w.heartbeat.age();
+            if age > w.stale_after {
+                println!("Watchdog: {} made no progress for {}s, restarting it", w.name, age.as_secs());
+                (w.restart)();
+                w.heartbeat.beat();
+            }
+        }
+    });
+}
@@ -1,26 +1,214 @@
 use reqwest::Client;
-use chrono::{DateTime, Utc, Local, TimeZone};
-use crate::models::{WeatherState, SunTimes, ForecastItem, LocationDetectResult, LocationValidationResult};
+use chrono::{DateTime, NaiveDate, Utc, Local, TimeZone};
+use crate::models::{WeatherState, SunTimes, ForecastItem, NowcastItem, LocationDetectResult, LocationValidationResult, ProviderHealth};
 use crate::config::AppConfig;
+use crate::plugins::PluginRegistry;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+mod air_quality;
+mod alerts;
+mod aurora;
+mod breaker;
+mod custom;
+mod daily;
+mod eccc;
+mod homeassistant;
+mod metar;
+mod mqtt;
+mod normals;
+mod providers;
+mod pws;
+
+pub use providers::{Capabilities, ProviderInfo, ProviderRegistry, WeatherProvider};
+
+/// Base URLs of the upstream APIs, overridable so tests can point them at a local server.
+#[derive(Debug, Clone)]
+pub struct ApiEndpoints {
+    pub ip_api: String,
+    pub open_meteo: String,
+    pub open_meteo_geocoding: String,
+    pub open_meteo_air_quality: String,
+    pub open_meteo_archive: String,
+    pub openweathermap: String,
+    pub nws: String,
+    pub met_no: String,
+    pub pirate_weather: String,
+    pub tomorrow_io: String,
+    pub accuweather: String,
+    pub eccc: String,
+    pub aviation_weather: String,
+    pub wunderground: String,
+    pub swpc: String,
+}
+
+impl Default for ApiEndpoints {
+    fn default() -> Self {
+        Self {
+            ip_api: "http://ip-api.com".to_string(),
+            open_meteo: "https://api.open-meteo.com".to_string(),
+            open_meteo_geocoding: "https://geocoding-api.open-meteo.com".to_string(),
+            open_meteo_air_quality: "https://air-quality-api.open-meteo.com".to_string(),
+            open_meteo_archive: "https://archive-api.open-meteo.com".to_string(),
+            openweathermap: "https://api.openweathermap.org".to_string(),
+            nws: "https://api.weather.gov".to_string(),
+            met_no: "https://api.met.no/weatherapi".to_string(),
+            pirate_weather: "https://api.pirateweather.net".to_string(),
+            tomorrow_io: "https://api.tomorrow.io".to_string(),
+            accuweather: "https://dataservice.accuweather.com".to_string(),
+            eccc: "https://api.weather.gc.ca".to_string(),
+            aviation_weather: "https://aviationweather.gov".to_string(),
+            wunderground: "https://api.weather.com".to_string(),
+            swpc: "https://services.swpc.noaa.gov".to_string(),
+        }
+    }
+}
+
+/// How often a failing fetch is retried. Only transient errors (see `is_transient_error`) are retried,
+/// waiting `base_delay` doubled per attempt plus up to 50% jitter.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub base_delay: std::time::Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { attempts: 3, base_delay: std::time::Duration::from_secs(1) }
+    }
+}
+
+impl RetryPolicy {
+    fn delay(&self, attempt: u32) -> std::time::Duration {
+        let backoff = self.base_delay * 2u32.saturating_pow(attempt);
+        backoff + jitter(backoff / 2)
+    }
+}
+
+/// A pseudo-random duration up to `max`. No rand dependency; the clock's sub-second noise is
+/// plenty to keep retries and polls from many installs out of lockstep.
+pub fn jitter(max: std::time::Duration) -> std::time::Duration {
+    let noise = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() % 1000).unwrap_or(0);
+    max * noise / 1000
+}
+
+// How long to leave an API alone after a 429 that didn't say when to come back
+const DEFAULT_RATE_LIMIT_BACKOFF: chrono::Duration = chrono::Duration::minutes(15);
+
+// `Retry-After` is either a number of seconds or an HTTP date
+fn retry_after(headers: &reqwest::header::HeaderMap, now: DateTime<Utc>) -> Option<chrono::Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<i64>() {
+        return Some(chrono::Duration::seconds(secs.max(0)));
+    }
+    DateTime::parse_from_rfc2822(value).ok().map(|at| (at.with_timezone(&Utc) - now).max(chrono::Duration::zero()))
+}
+
+/// Whether a fetch error might go away on its own: connection failures, timeouts, 408 and 5xx.
+/// Bad keys, other 4xx, unparseable data and missing settings fail the same way every time, and
+/// a 429 is left to the rate-limit back-off instead of being retried straight away.
+pub fn is_transient_error(err: &str) -> bool {
+    if err.contains("error sending request") || err.contains("response body error") || err.contains("timed out") {
+        return true;
+    }
+    match err.split("HTTP ").nth(1).and_then(|rest| rest.get(..3)).and_then(|code| code.parse::<u16>().ok()) {
+        Some(code) => code == 408 || code >= 500,
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ValidatedResponse {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: serde_json::Value,
+}
+
+// URLs carry the coordinates, so moving around adds entries; past this many the map starts over
+const MAX_VALIDATED_URLS: usize = 32;
+
+// AccuWeather icons for thunderstorms (day, mostly cloudy, partly sunny, night variants)
+const ACCUWEATHER_THUNDER_ICONS: [i64; 5] = [15, 16, 17, 41, 42];
+
+// AccuWeather's "Fog" icon
+const ACCUWEATHER_FOG_ICON: i64 = 11;
 
 pub struct WeatherService {
     client: Client,
+    retry: RetryPolicy,
+    plugins: Arc<PluginRegistry>,
+    endpoints: ApiEndpoints,
+    // AccuWeather location keys by rounded "lat,lon"; each lookup costs a request from a small daily quota
+    accuweather_keys: Mutex<HashMap<String, String>>,
+    // API hosts that answered 429, and when they may be asked again
+    rate_limits: Mutex<HashMap<String, DateTime<Utc>>>,
+    // Providers skipped after failing repeatedly
+    breakers: Mutex<breaker::Breakers>,
+    // Last response per URL that came with an ETag or Last-Modified, replayed on 304 Not Modified
+    validated: Mutex<HashMap<String, ValidatedResponse>>,
+    // Live subscription while `provider` is "mqtt"
+    mqtt: Mutex<Option<mqtt::MqttFeed>>,
+    mqtt_updates: Arc<tokio::sync::Notify>,
+    // Last climatological normal, keyed by rounded "lat,lon,date"
+    normals: Mutex<Option<(String, f64)>>,
 }
 
 impl WeatherService {
-    pub fn new() -> Self {
+    pub fn new(plugins: Arc<PluginRegistry>) -> Self {
+        Self::with_endpoints(plugins, ApiEndpoints::default())
+    }
+
+    pub fn with_endpoints(plugins: Arc<PluginRegistry>, endpoints: ApiEndpoints) -> Self {
         Self {
             client: Client::builder()
                 .timeout(std::time::Duration::from_secs(10))
+                // api.weather.gov and api.met.no reject requests without an identifying User-Agent
+                .user_agent(concat!("WeatherLight/", env!("CARGO_PKG_VERSION"), " (github.com/jonkt/weatherlight)"))
                 .build()
                 .unwrap_or_else(|_| Client::new()),
+            retry: RetryPolicy::default(),
+            plugins,
+            endpoints,
+            accuweather_keys: Mutex::new(HashMap::new()),
+            rate_limits: Mutex::new(HashMap::new()),
+            validated: Mutex::new(HashMap::new()),
+            breakers: Mutex::new(breaker::Breakers::default()),
+            mqtt: Mutex::new(None),
+            mqtt_updates: Arc::new(tokio::sync::Notify::new()),
+            normals: Mutex::new(None),
         }
     }
 
-    pub async fn fetch(&self, config: &AppConfig) -> Result<WeatherState, String> {
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    // Runs `attempt` until it succeeds, fails permanently or runs out of retries
+    async fn retrying<T, F, Fut>(&self, mut attempt: F) -> Result<T, String>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, String>>,
+    {
+        let mut tries = 0;
+        loop {
+            match attempt().await {
+                Err(e) if tries + 1 < self.retry.attempts && is_transient_error(&e) => {
+                    let delay = self.retry.delay(tries);
+                    println!("Transient error, retrying in {:?}: {}", delay, e);
+                    tokio::time::sleep(delay).await;
+                    tries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // Coordinates and display name for the configured location: auto-detected when enabled, geocoded otherwise
+    async fn resolve_location(&self, config: &AppConfig) -> Result<(f64, f64, String), String> {
         let (mut lat, mut lon, mut location_name) = (None, None, None);
 
-        // 1. Determine Location
         if config.auto_location {
             if let Ok(Some(detected)) = self.detect_location().await {
                 lat = Some(detected.lat);
@@ -30,36 +218,117 @@ impl WeatherService {
         }
 
         // Fallback to manual location
+        let provider = ProviderRegistry::builtin().resolve(&config.provider);
         if lat.is_none() && !config.location.is_empty() {
-            if config.provider == "openweathermap" && !config.api_key.is_empty() {
-                if let Ok(Some(geo)) = self.geocode_openweathermap(&config.location, &config.api_key).await {
-                    lat = Some(geo.lat);
-                    lon = Some(geo.lon);
-                    location_name = Some(geo.city);
-                }
-            } else {
-                if let Ok(Some(geo)) = self.geocode_openmeteo(&config.location).await {
-                    lat = Some(geo.lat);
-                    lon = Some(geo.lon);
-                    location_name = Some(geo.city);
-                }
+            if let Ok(Some(geo)) = self.retrying(|| provider.geocode(self, &config.location, config)).await {
+                lat = Some(geo.lat);
+                lon = Some(geo.lon);
+                location_name = Some(geo.city);
             }
         }
 
         let lat = lat.ok_or("No location set".to_string())?;
         let lon = lon.ok_or("No location set".to_string())?;
-        let loc_name = location_name.unwrap_or_else(|| "Unknown".to_string());
+        Ok((lat, lon, location_name.unwrap_or_else(|| "Unknown".to_string())))
+    }
 
-        // 2. Fetch Weather
-        if config.provider == "openweathermap" && !config.api_key.is_empty() {
-            self.fetch_openweathermap(lat, lon, loc_name, &config.api_key, config).await
-        } else {
-            self.fetch_openmeteo(lat, lon, loc_name, config).await
+    pub async fn fetch(&self, config: &AppConfig) -> Result<WeatherState, String> {
+        // 1. Determine Location
+        let (lat, lon, loc_name) = self.resolve_location(config).await?;
+
+        // 2. Fetch Weather, walking the fallback chain until a provider answers
+        let chain = config.provider_chain();
+        if !chain.contains(&"mqtt") {
+            self.stop_mqtt();
+        }
+
+        let mut primary_error = None;
+        for id in chain {
+            let open_until = self.breakers.lock().unwrap().open_until(id, Utc::now());
+            if let Some(until) = open_until {
+                let e = format!("{} skipped after repeated failures until {}", id, until.with_timezone(&Local).format("%H:%M"));
+                println!("{}", e);
+                primary_error.get_or_insert(e);
+                continue;
+            }
+
+            match self.retrying(|| self.fetch_from(id, lat, lon, loc_name.clone(), config)).await {
+                Ok(mut weather) => {
+                    self.breakers.lock().unwrap().record_success(id);
+                    config.apply_temp_offset(&mut weather);
+                    crate::sun::fill_missing(&mut weather.sun_times, lat, lon, Utc::now().date_naive());
+                    weather.is_night = self.is_night_for(&weather.sun_times, config);
+                    weather.frost_warning = config.frost.expects_frost(&weather.debug_forecast, Utc::now());
+                    if config.severe_alerts.enabled && weather.alerts.is_empty() {
+                        match self.fetch_nws_alerts(lat, lon).await {
+                            Ok(alerts) => weather.alerts = alerts,
+                            Err(e) => println!("NWS alerts unavailable: {}", e),
+                        }
+                    }
+                    if config.light_basis == "aqi" {
+                        match self.fetch_air_quality(lat, lon).await {
+                            Ok(aq) => weather.air_quality = Some(aq),
+                            Err(e) => println!("Air quality unavailable: {}", e),
+                        }
+                    }
+                    if config.light_basis == "anomaly" {
+                        match self.fetch_normal_high(lat, lon, Local::now().date_naive()).await {
+                            Ok(normal) => weather.normal_high = Some(normal),
+                            Err(e) => println!("Climate normal unavailable: {}", e),
+                        }
+                    }
+                    if config.pollen.enabled {
+                        match self.fetch_pollen(lat, lon).await {
+                            Ok(pollen) => weather.pollen = Some(pollen),
+                            Err(e) => println!("Pollen forecast unavailable: {}", e),
+                        }
+                    }
+                    return Ok(weather);
+                }
+                Err(e) => {
+                    println!("Provider {} failed: {}", id, e);
+                    self.breakers.lock().unwrap().record_failure(id, &e, Utc::now());
+                    primary_error.get_or_insert(e);
+                }
+            }
+        }
+        Err(primary_error.unwrap_or_else(|| "No provider configured".to_string()))
+    }
+
+    /// Providers that failed their last fetch, and whether they're currently skipped
+    pub fn provider_health(&self) -> Vec<ProviderHealth> {
+        self.breakers.lock().unwrap().health(Utc::now())
+    }
+
+    /// Gives every provider a fresh chance, e.g. after settings change or the network comes back
+    pub fn reset_provider_health(&self) {
+        self.breakers.lock().unwrap().clear();
+    }
+
+    /// Clears poison from every lock a fetch takes, for a restarted orchestrator
+    pub fn clear_poison(&self) {
+        self.accuweather_keys.clear_poison();
+        self.rate_limits.clear_poison();
+        self.breakers.clear_poison();
+        self.validated.clear_poison();
+        self.normals.clear_poison();
+        self.clear_mqtt_poison();
+    }
+
+    async fn fetch_from(&self, id: &str, lat: f64, lon: f64, location_name: String, config: &AppConfig) -> Result<WeatherState, String> {
+        if let Some(plugin_name) = id.strip_prefix("plugin:") {
+            let manifest = self.plugins.provider(plugin_name)
+                .ok_or_else(|| format!("Provider plugin '{}' is not installed", plugin_name))?;
+            let mut weather = crate::plugins::fetch_weather(&manifest, lat, lon, location_name).await?;
+            weather.is_night = self.check_is_night(&weather.sun_times);
+            return Ok(weather);
         }
+
+        ProviderRegistry::builtin().resolve(id).fetch(self, lat, lon, location_name, config).await
     }
 
     pub async fn detect_location(&self) -> Result<Option<LocationDetectResult>, String> {
-        let resp = self.client.get("http://ip-api.com/json/?fields=status,country,city,lat,lon")
+        let resp = self.client.get(format!("{}/json/?fields=status,country,city,lat,lon", self.endpoints.ip_api))
             .send()
             .await.map_err(|e| e.to_string())?;
 
@@ -87,7 +356,8 @@ impl WeatherService {
         };
 
         let url = format!(
-            "https://geocoding-api.open-meteo.com/v1/search?name={}&count=10&language=en&format=json",
+            "{}/v1/search?name={}&count=10&language=en&format=json",
+            self.endpoints.open_meteo_geocoding,
             urlencoding::encode(search_term)
         );
 
@@ -140,7 +410,8 @@ impl WeatherService {
         };
 
         let url = format!(
-            "https://api.openweathermap.org/geo/1.0/direct?q={}&limit=5&appid={}",
+            "{}/geo/1.0/direct?q={}&limit=5&appid={}",
+            self.endpoints.openweathermap,
             urlencoding::encode(search_term),
             api_key
         );
@@ -195,43 +466,31 @@ impl WeatherService {
         }
     }
 
-    fn check_is_night(&self, sun_times: &SunTimes) -> bool {
-        if let (Some(sunrise), Some(sunset)) = (sun_times.sunrise, sun_times.sunset) {
-            let now = Utc::now();
-            
-            // Extract HH:MM time components exclusively since Open-Meteo returns future days sequentially
-            let now_time = now.time();
-            let sr_time = sunrise.time();
-            let ss_time = sunset.time();
-
-            if sr_time < ss_time {
-                // Standard ordering (e.g. 06:00 Sunrise -> 18:00 Sunset)
-                now_time < sr_time || now_time > ss_time
-            } else {
-                // Wrapped ordering (e.g. 17:00 Sunrise -> 07:00 Sunset due to GMT shift in NZ/AUS)
-                // Night is the space *between* Sunset and Sunrise
-                now_time < sr_time && now_time > ss_time
-            }
-        } else {
-            false
+    pub fn check_is_night(&self, sun_times: &SunTimes) -> bool {
+        is_night_between(sun_times.sunrise, sun_times.sunset, Utc::now())
+    }
+
+    /// Night as `night_boundary` defines it: from sunset, or from the end of civil twilight
+    pub fn is_night_for(&self, sun_times: &SunTimes, config: &AppConfig) -> bool {
+        if config.night_boundary == "civil" && sun_times.civil_dawn.is_some() && sun_times.civil_dusk.is_some() {
+            return is_night_between(sun_times.civil_dawn, sun_times.civil_dusk, Utc::now());
         }
+        self.check_is_night(sun_times)
     }
 
     pub async fn fetch_openweathermap(&self, lat: f64, lon: f64, location_name: String, api_key: &str, config: &AppConfig) -> Result<WeatherState, String> {
-        let weather_url = format!("https://api.openweathermap.org/data/2.5/weather?lat={}&lon={}&appid={}&units=metric", lat, lon, api_key);
-        let current_resp = self.client.get(&weather_url).send().await.map_err(|e| e.to_string())?;
-        let current_data: serde_json::Value = current_resp.json().await.map_err(|e| e.to_string())?;
+        let weather_url = format!("{}/data/2.5/weather?lat={}&lon={}&appid={}&units=metric", self.endpoints.openweathermap, lat, lon, api_key);
+        let current_data = self.get_json(&weather_url).await.map_err(|e| e.replace(api_key, "***"))?;
 
         let sunrise = current_data.get("sys").and_then(|v| v.get("sunrise")).and_then(|v| v.as_i64())
             .map(|ts| Utc.timestamp_opt(ts, 0).unwrap());
         let sunset = current_data.get("sys").and_then(|v| v.get("sunset")).and_then(|v| v.as_i64())
             .map(|ts| Utc.timestamp_opt(ts, 0).unwrap());
         
-        let sun_times = SunTimes { sunrise, sunset };
+        let sun_times = SunTimes { sunrise, sunset, ..Default::default() };
 
-        let forecast_url = format!("https://api.openweathermap.org/data/2.5/forecast?lat={}&lon={}&appid={}&units=metric", lat, lon, api_key);
-        let forecast_resp = self.client.get(&forecast_url).send().await.map_err(|e| e.to_string())?;
-        let forecast_data: serde_json::Value = forecast_resp.json().await.map_err(|e| e.to_string())?;
+        let forecast_url = format!("{}/data/2.5/forecast?lat={}&lon={}&appid={}&units=metric", self.endpoints.openweathermap, lat, lon, api_key);
+        let forecast_data = self.get_json(&forecast_url).await.map_err(|e| e.replace(api_key, "***"))?;
 
         let list = forecast_data.get("list").and_then(|v| v.as_array()).ok_or_else(|| "No forecast data".to_string())?;
 
@@ -256,6 +515,16 @@ impl WeatherService {
 
         let mut temperature = current_data.get("main").and_then(|v| v.get("temp")).and_then(|v| v.as_f64()).unwrap_or(0.0);
         
+        let mut apparent_temperature = current_data.get("main").and_then(|v| v.get("feels_like")).and_then(|v| v.as_f64());
+
+        // 3-hour blocks carry their own min/max, so both ends of the range come from each block
+        let block_temps = list.iter().filter_map(|item| {
+            let time = item.get("dt").and_then(|v| v.as_i64()).and_then(|ts| Utc.timestamp_opt(ts, 0).single())?;
+            let main = item.get("main")?;
+            Some([main.get("temp_max"), main.get("temp_min")].into_iter().flatten().filter_map(|v| v.as_f64()).map(move |t| (time, t)))
+        }).flatten();
+        let (today_high, today_low) = today_range(Some(temperature), block_temps, now.date_naive());
+        
         if temp_blocks > 0 && !list.is_empty() {
             let limit = std::cmp::min(temp_blocks, list.len());
             for item in &list[0..limit] {
@@ -264,28 +533,71 @@ impl WeatherService {
                         temperature = t_max;
                     }
                 }
+                if let Some(feels) = item.get("main").and_then(|v| v.get("feels_like")).and_then(|v| v.as_f64()) {
+                    apparent_temperature = Some(apparent_temperature.map_or(feels, |a| a.max(feels)));
+                }
             }
         }
 
+        // Condition codes 2xx are the thunderstorm group
+        let is_thunder = |item: &serde_json::Value| item.get("weather").and_then(|v| v.as_array())
+            .is_some_and(|conditions| conditions.iter().any(|c| c.get("id").and_then(|v| v.as_i64()).is_some_and(|id| (200..300).contains(&id))));
+
         let mut has_precipitation = false;
+        let mut has_thunder = false;
+        let mut precip_type = None;
+        let mut precip_probability: f64 = 0.0;
         if precip_blocks > 0 && !list.is_empty() {
+            has_thunder = is_thunder(&current_data);
             let limit = std::cmp::min(precip_blocks, list.len());
             for item in &list[0..limit] {
                 let rain = item.get("rain").and_then(|v| v.get("3h")).and_then(|v| v.as_f64()).unwrap_or(0.0);
                 let snow = item.get("snow").and_then(|v| v.get("3h")).and_then(|v| v.as_f64()).unwrap_or(0.0);
                 let pop = item.get("pop").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                if pop >= 0.35 || rain >= 0.5 || snow >= 0.5 {
+                precip_probability = precip_probability.max(pop * 100.0);
+                if config.is_wet(pop * 100.0, rain.max(snow)) {
                     has_precipitation = true;
-                    break;
+                    precip_type.get_or_insert_with(|| if snow > 0.0 { "Snow" } else { "Rain" }.to_string());
                 }
+                has_thunder |= is_thunder(item);
             }
         }
 
+        let precip_starts_at = list.iter().find(|item| {
+            let rain = item.get("rain").and_then(|v| v.get("3h")).and_then(|v| v.as_f64()).unwrap_or(0.0);
+            let snow = item.get("snow").and_then(|v| v.get("3h")).and_then(|v| v.as_f64()).unwrap_or(0.0);
+            let pop = item.get("pop").and_then(|v| v.as_f64()).unwrap_or(0.0);
+            config.is_wet(pop * 100.0, rain.max(snow))
+        })
+            .and_then(|item| item.get("dt")).and_then(|v| v.as_i64())
+            .and_then(|ts| Utc.timestamp_opt(ts, 0).single());
+
+        let humidity = current_data.get("main").and_then(|v| v.get("humidity")).and_then(|v| v.as_f64());
+        let dew_point = current_data.get("main").and_then(|v| v.get("temp")).and_then(|v| v.as_f64()).zip(humidity)
+            .map(|(t, rh)| calculate_dew_point(t, rh));
+        // OWM reports m/s in metric mode, normalize to km/h like Open-Meteo
+        let wind_speed = current_data.get("wind").and_then(|v| v.get("speed")).and_then(|v| v.as_f64()).map(|ms| ms * 3.6);
+        let wind_gust = current_data.get("wind").and_then(|v| v.get("gust")).and_then(|v| v.as_f64()).map(|ms| ms * 3.6);
+        let pressure = current_data.get("main").and_then(|v| v.get("pressure")).and_then(|v| v.as_f64());
+
+        // Visibility is in metres; condition 741 is fog
+        let is_foggy = |item: &serde_json::Value| {
+            let visibility = item.get("visibility").and_then(|v| v.as_f64()).map(|m| m / 1000.0);
+            let fog_code = item.get("weather").and_then(|v| v.as_array())
+                .is_some_and(|conditions| conditions.iter().any(|c| c.get("id").and_then(|v| v.as_i64()) == Some(741)));
+            fog_code || config.fog.is_low(visibility)
+        };
+        let visibility = current_data.get("visibility").and_then(|v| v.as_f64()).map(|m| m / 1000.0);
+        let fog_blocks = (config.fog.hours.max(1) as f64 / 3.0).ceil() as usize;
+        let has_fog = is_foggy(&current_data) || list.iter().take(fog_blocks).any(is_foggy);
+
         let mut debug_forecast = Vec::new();
         let limit = std::cmp::min(16, list.len());
         for item in &list[0..limit] {
             let time_val = item.get("dt").and_then(|v| v.as_i64()).unwrap_or(0);
             let temp_val = item.get("main").and_then(|v| v.get("temp")).and_then(|v| v.as_f64()).unwrap_or(0.0);
+            let feels_val = item.get("main").and_then(|v| v.get("feels_like")).and_then(|v| v.as_f64());
+            let humidity_val = item.get("main").and_then(|v| v.get("humidity")).and_then(|v| v.as_f64());
             let pop_val = item.get("pop").and_then(|v| v.as_f64()).unwrap_or(0.0) * 100.0;
             let precip_type = if item.get("snow").is_some() { "Snow".to_string() }
                               else if item.get("rain").is_some() { "Rain".to_string() }
@@ -294,30 +606,585 @@ impl WeatherService {
             debug_forecast.push(ForecastItem {
                 time: Utc.timestamp_opt(time_val, 0).unwrap(),
                 temp: temp_val,
+                apparent_temp: feels_val,
                 precip_prob: pop_val,
                 precip_type,
+                humidity: humidity_val,
+                dew_point: humidity_val.map(|rh| calculate_dew_point(temp_val, rh)),
             });
         }
 
         Ok(WeatherState {
             temperature,
+            apparent_temperature,
+            today_high,
+            today_low,
             has_precipitation,
+            has_thunder,
+            precip_type,
             location_name,
             sun_times: sun_times.clone(),
             is_night: self.check_is_night(&sun_times),
             provider: "OpenWeatherMap".to_string(),
             last_updated: Utc::now(),
             debug_forecast,
+            precip_probability,
+            humidity,
+            dew_point,
+            wind_speed,
+            wind_gust,
+            pressure,
+            pressure_tendency: None,
+            precip_starts_at,
+            alerts: Vec::new(),
+            stale: false,
+            kp_index: None,
+            air_quality: None,
+            pollen: None,
+            nowcast: Vec::new(),
+            frost_warning: false,
+            visibility,
+            has_fog,
+            normal_high: None,
+            tomorrow_high: None,
         })
     }
 
-    pub async fn fetch_openmeteo(&self, lat: f64, lon: f64, location_name: String, config: &AppConfig) -> Result<WeatherState, String> {
+    async fn get_json(&self, url: &str) -> Result<serde_json::Value, String> {
+        // Skip the request entirely while the API has asked us to back off, so an exhausted
+        // free-tier quota isn't burned further (OWM locks out keys that keep hammering it)
+        let host = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(|h| h.to_string())).unwrap_or_default();
+        if let Some(until) = self.rate_limits.lock().unwrap().get(&host).filter(|until| **until > Utc::now()) {
+            return Err(format!("Rate limited by {} until {}, skipping", host, until.with_timezone(&Local).format("%H:%M")));
+        }
+
+        // Conditional request when we hold validators, so an unchanged forecast isn't downloaded again
+        let cached = self.validated.lock().unwrap().get(url).cloned();
+        let mut request = self.client.get(url);
+        if let Some(c) = &cached {
+            if let Some(etag) = &c.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(modified) = &c.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, modified);
+            }
+        }
+
+        let resp = request.send().await.map_err(|e| e.to_string())?;
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some(c) = cached {
+                return Ok(c.body);
+            }
+        }
+        if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let now = Utc::now();
+            let wait = retry_after(resp.headers(), now).unwrap_or(DEFAULT_RATE_LIMIT_BACKOFF);
+            self.rate_limits.lock().unwrap().insert(host, now + wait);
+            return Err(format!("HTTP {} from {} (retry after {}s)", resp.status(), url, wait.num_seconds()));
+        }
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {} from {}", resp.status(), url));
+        }
+
+        let header = |name: reqwest::header::HeaderName| resp.headers().get(name).and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+        let etag = header(reqwest::header::ETAG);
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
+        let body: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
+
+        if etag.is_some() || last_modified.is_some() {
+            let mut validated = self.validated.lock().unwrap();
+            if validated.len() >= MAX_VALIDATED_URLS && !validated.contains_key(url) {
+                validated.clear();
+            }
+            validated.insert(url.to_string(), ValidatedResponse { etag, last_modified, body: body.clone() });
+        }
+        Ok(body)
+    }
+
+    // Latest observation from the closest station listed for the forecast point
+    async fn fetch_nws_observation(&self, stations_url: &str) -> Result<Option<serde_json::Value>, String> {
+        let stations = self.get_json(stations_url).await?;
+        let station_id = stations.get("features").and_then(|v| v.as_array())
+            .and_then(|arr| arr.first())
+            .and_then(|f| f.get("id")).and_then(|v| v.as_str());
+
+        match station_id {
+            Some(id) => {
+                let latest = self.get_json(&format!("{}/observations/latest", id)).await?;
+                Ok(latest.get("properties").cloned())
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn fetch_nws(&self, lat: f64, lon: f64, location_name: String, config: &AppConfig) -> Result<WeatherState, String> {
+        let points = self.get_json(&format!("{}/points/{:.4},{:.4}", self.endpoints.nws, lat, lon)).await
+            .map_err(|e| format!("NWS only covers US locations ({})", e))?;
+        let props = points.get("properties").ok_or_else(|| "NWS returned no forecast point".to_string())?;
+
+        let hourly_url = props.get("forecastHourly").and_then(|v| v.as_str())
+            .ok_or_else(|| "NWS returned no hourly forecast".to_string())?;
+        let forecast = self.get_json(&format!("{}?units=si", hourly_url)).await?;
+        let periods = forecast.get("properties").and_then(|v| v.get("periods")).and_then(|v| v.as_array())
+            .ok_or_else(|| "No forecast data".to_string())?;
+
+        // Observations are optional, the first forecast hour stands in when a station is offline
+        let observation = match props.get("observationStations").and_then(|v| v.as_str()) {
+            Some(url) => self.fetch_nws_observation(url).await.ok().flatten(),
+            None => None,
+        };
+        let obs_value = |key: &str| observation.as_ref()
+            .and_then(|o| o.get(key)).and_then(|v| v.get("value")).and_then(|v| v.as_f64());
+
+        let hours: Vec<HourlyPoint> = periods.iter().filter_map(|p| {
+            let time = p.get("startTime").and_then(|v| v.as_str())
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .map(|dt| dt.with_timezone(&Utc))?;
+            Some(HourlyPoint {
+                time,
+                temp: p.get("temperature").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                // The hourly forecast endpoint doesn't carry heat index or wind chill
+                apparent_temp: None,
+                precip_prob: p.get("probabilityOfPrecipitation").and_then(|v| v.get("value")).and_then(|v| v.as_f64()).unwrap_or(0.0),
+                precip_amount: 0.0,
+                is_snow: p.get("shortForecast").and_then(|v| v.as_str())
+                    .map(|s| s.to_lowercase().contains("snow")).unwrap_or(false),
+                is_thunder: p.get("shortForecast").and_then(|v| v.as_str())
+                    .is_some_and(|s| s.to_lowercase().contains("thunder")),
+                humidity: p.get("relativeHumidity").and_then(|v| v.get("value")).and_then(|v| v.as_f64()),
+                dew_point: p.get("dewpoint").and_then(|v| v.get("value")).and_then(|v| v.as_f64()),
+                // Forecast wind comes as text like "15 km/h" (SI units requested above)
+                wind_speed: p.get("windSpeed").and_then(|v| v.as_str())
+                    .and_then(|s| s.split_whitespace().next()).and_then(|n| n.parse::<f64>().ok()),
+                wind_gust: None,
+                pressure: None,
+                visibility: None,
+                is_fog: p.get("shortForecast").and_then(|v| v.as_str())
+                    .is_some_and(|s| s.to_lowercase().contains("fog")),
+            })
+        }).collect();
+
+        let summary = summarize_hourly(&hours, obs_value("temperature"), config);
+
+        let humidity = obs_value("relativeHumidity").or(summary.humidity);
+        let dew_point = obs_value("dewpoint").or(summary.dew_point);
+        let wind_speed = obs_value("windSpeed").or(summary.wind_speed);
+        let wind_gust = obs_value("windGust");
+        // Observations are in Pa
+        let pressure = obs_value("seaLevelPressure").map(|pa| pa / 100.0);
+        let visibility = obs_value("visibility").map(|m| m / 1000.0);
+
+        let sun_times = crate::sun::sun_times(lat, lon, Utc::now().date_naive());
+
+        Ok(WeatherState {
+            temperature: summary.temperature,
+            apparent_temperature: summary.apparent_temperature,
+            today_high: summary.today_high,
+            today_low: summary.today_low,
+            has_precipitation: summary.has_precipitation,
+            has_thunder: summary.has_thunder,
+            precip_type: summary.precip_type,
+            location_name,
+            sun_times: sun_times.clone(),
+            is_night: self.check_is_night(&sun_times),
+            provider: "NWS".to_string(),
+            last_updated: Utc::now(),
+            debug_forecast: summary.debug_forecast,
+            precip_probability: summary.precip_probability,
+            humidity,
+            dew_point,
+            wind_speed,
+            wind_gust,
+            pressure,
+            pressure_tendency: None,
+            precip_starts_at: summary.precip_starts_at,
+            alerts: Vec::new(),
+            stale: false,
+            kp_index: None,
+            air_quality: None,
+            pollen: None,
+            nowcast: Vec::new(),
+            frost_warning: false,
+            visibility: visibility.or(summary.visibility),
+            has_fog: summary.has_fog || config.fog.is_low(visibility),
+            normal_high: None,
+            tomorrow_high: None,
+        })
+    }
+
+    pub async fn fetch_metno(&self, lat: f64, lon: f64, location_name: String, config: &AppConfig) -> Result<WeatherState, String> {
+        // MET Norway's terms ask for at most 4 decimals so responses cache well
+        let data = self.get_json(&format!(
+            "{}/locationforecast/2.0/complete?lat={:.4}&lon={:.4}",
+            self.endpoints.met_no, lat, lon
+        )).await?;
+        let series = data.get("properties").and_then(|v| v.get("timeseries")).and_then(|v| v.as_array())
+            .ok_or_else(|| "No forecast data".to_string())?;
+
+        // The series turns 6-hourly after ~2.5 days; only entries with a 1h block are hourly
+        let hours: Vec<HourlyPoint> = series.iter()
+            .take_while(|entry| entry.get("data").and_then(|d| d.get("next_1_hours")).is_some())
+            .filter_map(|entry| {
+                let time = entry.get("time").and_then(|v| v.as_str())
+                    .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                    .map(|dt| dt.with_timezone(&Utc))?;
+                let instant = entry.get("data").and_then(|d| d.get("instant")).and_then(|d| d.get("details"));
+                let next_hour = entry.get("data").and_then(|d| d.get("next_1_hours"));
+                let details = next_hour.and_then(|n| n.get("details"));
+                let amount = details.and_then(|d| d.get("precipitation_amount")).and_then(|v| v.as_f64()).unwrap_or(0.0);
+                Some(HourlyPoint {
+                    time,
+                    temp: instant.and_then(|d| d.get("air_temperature")).and_then(|v| v.as_f64()).unwrap_or(0.0),
+                    apparent_temp: None,
+                    // Probability is only published for the Nordic area; elsewhere any amount counts as certain
+                    precip_prob: details.and_then(|d| d.get("probability_of_precipitation")).and_then(|v| v.as_f64())
+                        .unwrap_or(if amount > 0.0 { 100.0 } else { 0.0 }),
+                    precip_amount: amount,
+                    is_snow: next_hour.and_then(|n| n.get("summary")).and_then(|s| s.get("symbol_code")).and_then(|v| v.as_str())
+                        .map(|c| c.contains("snow")).unwrap_or(false),
+                    is_thunder: next_hour.and_then(|n| n.get("summary")).and_then(|s| s.get("symbol_code")).and_then(|v| v.as_str())
+                        .is_some_and(|c| c.contains("thunder")),
+                    humidity: instant.and_then(|d| d.get("relative_humidity")).and_then(|v| v.as_f64()),
+                    dew_point: instant.and_then(|d| d.get("dew_point_temperature")).and_then(|v| v.as_f64()),
+                    // MET reports m/s, normalize to km/h like Open-Meteo
+                    wind_speed: instant.and_then(|d| d.get("wind_speed")).and_then(|v| v.as_f64()).map(|ms| ms * 3.6),
+                    wind_gust: instant.and_then(|d| d.get("wind_speed_of_gust")).and_then(|v| v.as_f64()).map(|ms| ms * 3.6),
+                    pressure: instant.and_then(|d| d.get("air_pressure_at_sea_level")).and_then(|v| v.as_f64()),
+                    visibility: None,
+                    is_fog: next_hour.and_then(|n| n.get("summary")).and_then(|s| s.get("symbol_code")).and_then(|v| v.as_str())
+                        .is_some_and(|c| c == "fog"),
+                })
+            })
+            .collect();
+
+        let summary = summarize_hourly(&hours, None, config);
+
+        let sun_times = crate::sun::sun_times(lat, lon, Utc::now().date_naive());
+
+        Ok(WeatherState {
+            temperature: summary.temperature,
+            apparent_temperature: summary.apparent_temperature,
+            today_high: summary.today_high,
+            today_low: summary.today_low,
+            has_precipitation: summary.has_precipitation,
+            has_thunder: summary.has_thunder,
+            precip_type: summary.precip_type,
+            location_name,
+            sun_times: sun_times.clone(),
+            is_night: self.check_is_night(&sun_times),
+            provider: "MET Norway".to_string(),
+            last_updated: Utc::now(),
+            debug_forecast: summary.debug_forecast,
+            precip_probability: summary.precip_probability,
+            humidity: summary.humidity,
+            dew_point: summary.dew_point,
+            wind_speed: summary.wind_speed,
+            wind_gust: summary.wind_gust,
+            pressure: summary.pressure,
+            pressure_tendency: None,
+            precip_starts_at: summary.precip_starts_at,
+            alerts: Vec::new(),
+            stale: false,
+            kp_index: None,
+            air_quality: None,
+            pollen: None,
+            nowcast: Vec::new(),
+            frost_warning: false,
+            visibility: summary.visibility,
+            has_fog: summary.has_fog,
+            normal_high: None,
+            tomorrow_high: None,
+        })
+    }
+
+    pub async fn fetch_pirateweather(&self, lat: f64, lon: f64, location_name: String, api_key: &str, config: &AppConfig) -> Result<WeatherState, String> {
+        let data = self.get_json(&format!(
+            "{}/forecast/{}/{},{}?units=si&exclude=alerts",
+            self.endpoints.pirate_weather, api_key, lat, lon
+        )).await.map_err(|e| e.replace(api_key, "***"))?;
+
+        let unix_time = |v: &serde_json::Value| v.as_i64().and_then(|ts| Utc.timestamp_opt(ts, 0).single());
+        let hourly = data.get("hourly").and_then(|v| v.get("data")).and_then(|v| v.as_array())
+            .ok_or_else(|| "No forecast data".to_string())?;
+
+        // Dark Sky format: probabilities and humidity are 0-1, intensity is mm/h, wind is m/s with units=si
+        let hours: Vec<HourlyPoint> = hourly.iter().filter_map(|h| {
+            Some(HourlyPoint {
+                time: h.get("time").and_then(unix_time)?,
+                temp: h.get("temperature").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                apparent_temp: h.get("apparentTemperature").and_then(|v| v.as_f64()),
+                precip_prob: h.get("precipProbability").and_then(|v| v.as_f64()).unwrap_or(0.0) * 100.0,
+                precip_amount: h.get("precipIntensity").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                is_snow: h.get("precipType").and_then(|v| v.as_str()) == Some("snow"),
+                // No thunderstorm icon in the Dark Sky set, only the summary text says so
+                is_thunder: h.get("summary").and_then(|v| v.as_str()).is_some_and(|s| s.to_lowercase().contains("thunder")),
+                humidity: h.get("humidity").and_then(|v| v.as_f64()).map(|f| f * 100.0),
+                dew_point: h.get("dewPoint").and_then(|v| v.as_f64()),
+                wind_speed: h.get("windSpeed").and_then(|v| v.as_f64()).map(|ms| ms * 3.6),
+                wind_gust: h.get("windGust").and_then(|v| v.as_f64()).map(|ms| ms * 3.6),
+                pressure: h.get("pressure").and_then(|v| v.as_f64()),
+                visibility: h.get("visibility").and_then(|v| v.as_f64()),
+                is_fog: h.get("icon").and_then(|v| v.as_str()) == Some("fog"),
+            })
+        }).collect();
+
+        let current = data.get("currently");
+        let mut summary = summarize_hourly(&hours, current.and_then(|c| c.get("temperature")).and_then(|v| v.as_f64()), config);
+
+        // Minutely data pins down when precipitation starts within the next hour
+        if let Some(minutes) = data.get("minutely").and_then(|v| v.get("data")).and_then(|v| v.as_array()) {
+            let now = Utc::now();
+            let wet_minute = minutes.iter().find(|m| {
+                let prob = m.get("precipProbability").and_then(|v| v.as_f64()).unwrap_or(0.0);
+                let intensity = m.get("precipIntensity").and_then(|v| v.as_f64()).unwrap_or(0.0);
+                prob * 100.0 >= config.precip_probability_threshold && intensity > 0.0
+            });
+            if let Some(minute) = wet_minute {
+                let start = minute.get("time").and_then(unix_time);
+                if start.is_some_and(|t| summary.precip_starts_at.is_none_or(|h| t < h)) {
+                    summary.precip_starts_at = start;
+                }
+                if config.precip_horizon != "none" && start.is_some_and(|t| t - now <= chrono::Duration::hours(1)) {
+                    summary.has_precipitation = true;
+                    let kind = if minute.get("precipType").and_then(|v| v.as_str()) == Some("snow") { "Snow" } else { "Rain" };
+                    summary.precip_type.get_or_insert_with(|| kind.to_string());
+                    let prob = minute.get("precipProbability").and_then(|v| v.as_f64()).unwrap_or(0.0) * 100.0;
+                    summary.precip_probability = summary.precip_probability.max(prob);
+                }
+            }
+        }
+
+        let humidity = current.and_then(|c| c.get("humidity")).and_then(|v| v.as_f64()).map(|f| f * 100.0).or(summary.humidity);
+        let dew_point = current.and_then(|c| c.get("dewPoint")).and_then(|v| v.as_f64()).or(summary.dew_point);
+        let wind_speed = current.and_then(|c| c.get("windSpeed")).and_then(|v| v.as_f64()).map(|ms| ms * 3.6).or(summary.wind_speed);
+        let wind_gust = current.and_then(|c| c.get("windGust")).and_then(|v| v.as_f64()).map(|ms| ms * 3.6).or(summary.wind_gust);
+        let pressure = current.and_then(|c| c.get("pressure")).and_then(|v| v.as_f64()).or(summary.pressure);
+
+        let today = data.get("daily").and_then(|v| v.get("data")).and_then(|v| v.as_array()).and_then(|arr| arr.first());
+        let sun_times = match today {
+            Some(day) => SunTimes {
+                sunrise: day.get("sunriseTime").and_then(unix_time),
+                sunset: day.get("sunsetTime").and_then(unix_time),
+                moon_phase: day.get("moonPhase").and_then(|v| v.as_f64()),
+                ..Default::default()
+            },
+            None => crate::sun::sun_times(lat, lon, Utc::now().date_naive()),
+        };
+
+        Ok(WeatherState {
+            temperature: summary.temperature,
+            apparent_temperature: summary.apparent_temperature,
+            today_high: summary.today_high,
+            today_low: summary.today_low,
+            has_precipitation: summary.has_precipitation,
+            has_thunder: summary.has_thunder,
+            precip_type: summary.precip_type,
+            location_name,
+            sun_times: sun_times.clone(),
+            is_night: self.check_is_night(&sun_times),
+            provider: "Pirate Weather".to_string(),
+            last_updated: Utc::now(),
+            debug_forecast: summary.debug_forecast,
+            precip_probability: summary.precip_probability,
+            humidity,
+            dew_point,
+            wind_speed,
+            wind_gust,
+            pressure,
+            pressure_tendency: None,
+            precip_starts_at: summary.precip_starts_at,
+            alerts: Vec::new(),
+            stale: false,
+            kp_index: None,
+            air_quality: None,
+            pollen: None,
+            nowcast: Vec::new(),
+            frost_warning: false,
+            visibility: current.and_then(|c| c.get("visibility")).and_then(|v| v.as_f64()).or(summary.visibility),
+            has_fog: summary.has_fog,
+            normal_high: None,
+            tomorrow_high: None,
+        })
+    }
+
+    pub async fn fetch_tomorrowio(&self, lat: f64, lon: f64, location_name: String, api_key: &str, config: &AppConfig) -> Result<WeatherState, String> {
         let url = format!(
-            "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}&hourly=temperature_2m,precipitation_probability,rain,showers,snowfall&daily=sunrise,sunset&timezone=GMT&forecast_days=2",
-            lat, lon
+            "{}/v4/timelines?location={},{}&fields=temperature,temperatureApparent,precipitationIntensity,precipitationProbability,precipitationType,weatherCode,humidity,dewPoint,windSpeed,windGust,pressureSeaLevel,visibility&timesteps=1h&units=metric&apikey={}",
+            self.endpoints.tomorrow_io, lat, lon, api_key
         );
-        let resp = self.client.get(&url).send().await.map_err(|e| e.to_string())?;
-        let data: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
+        let data = self.get_json(&url).await.map_err(|e| e.replace(api_key, "***"))?;
+
+        let intervals = data.get("data").and_then(|v| v.get("timelines")).and_then(|v| v.as_array())
+            .and_then(|timelines| timelines.iter().find(|t| t.get("timestep").and_then(|v| v.as_str()) == Some("1h")))
+            .and_then(|t| t.get("intervals")).and_then(|v| v.as_array())
+            .ok_or_else(|| "No forecast data".to_string())?;
+
+        // Metric units: intensity in mm/h, wind in m/s. precipitationType 2 is snow, 4 ice pellets; weatherCode 8000 is a thunderstorm.
+        let hours: Vec<HourlyPoint> = intervals.iter().filter_map(|interval| {
+            let time = interval.get("startTime").and_then(|v| v.as_str())
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .map(|dt| dt.with_timezone(&Utc))?;
+            let values = interval.get("values")?;
+            let value = |key: &str| values.get(key).and_then(|v| v.as_f64());
+            Some(HourlyPoint {
+                time,
+                temp: value("temperature").unwrap_or(0.0),
+                apparent_temp: value("temperatureApparent"),
+                precip_prob: value("precipitationProbability").unwrap_or(0.0),
+                precip_amount: value("precipitationIntensity").unwrap_or(0.0),
+                is_snow: matches!(values.get("precipitationType").and_then(|v| v.as_i64()), Some(2) | Some(4)),
+                is_thunder: values.get("weatherCode").and_then(|v| v.as_i64()) == Some(8000),
+                humidity: value("humidity"),
+                dew_point: value("dewPoint"),
+                wind_speed: value("windSpeed").map(|ms| ms * 3.6),
+                wind_gust: value("windGust").map(|ms| ms * 3.6),
+                pressure: value("pressureSeaLevel"),
+                visibility: value("visibility"),
+                // 2000 fog, 2100 light fog
+                is_fog: matches!(values.get("weatherCode").and_then(|v| v.as_i64()), Some(2000) | Some(2100)),
+            })
+        }).collect();
+
+        let summary = summarize_hourly(&hours, None, config);
+        let sun_times = crate::sun::sun_times(lat, lon, Utc::now().date_naive());
+
+        Ok(WeatherState {
+            temperature: summary.temperature,
+            apparent_temperature: summary.apparent_temperature,
+            today_high: summary.today_high,
+            today_low: summary.today_low,
+            has_precipitation: summary.has_precipitation,
+            has_thunder: summary.has_thunder,
+            precip_type: summary.precip_type,
+            location_name,
+            sun_times: sun_times.clone(),
+            is_night: self.check_is_night(&sun_times),
+            provider: "Tomorrow.io".to_string(),
+            last_updated: Utc::now(),
+            debug_forecast: summary.debug_forecast,
+            precip_probability: summary.precip_probability,
+            humidity: summary.humidity,
+            dew_point: summary.dew_point,
+            wind_speed: summary.wind_speed,
+            wind_gust: summary.wind_gust,
+            pressure: summary.pressure,
+            pressure_tendency: None,
+            precip_starts_at: summary.precip_starts_at,
+            alerts: Vec::new(),
+            stale: false,
+            kp_index: None,
+            air_quality: None,
+            pollen: None,
+            nowcast: Vec::new(),
+            frost_warning: false,
+            visibility: summary.visibility,
+            has_fog: summary.has_fog,
+            normal_high: None,
+            tomorrow_high: None,
+        })
+    }
+
+    // Resolves (and caches) the AccuWeather location key for a coordinate, ~1 km granularity
+    async fn accuweather_location_key(&self, lat: f64, lon: f64, api_key: &str) -> Result<String, String> {
+        let cache_key = format!("{:.2},{:.2}", lat, lon);
+        if let Some(key) = self.accuweather_keys.lock().unwrap().get(&cache_key) {
+            return Ok(key.clone());
+        }
+
+        let url = format!(
+            "{}/locations/v1/cities/geoposition/search?apikey={}&q={},{}",
+            self.endpoints.accuweather, api_key, lat, lon
+        );
+        let data = self.get_json(&url).await.map_err(|e| e.replace(api_key, "***"))?;
+        let key = data.get("Key").and_then(|v| v.as_str())
+            .ok_or_else(|| "AccuWeather returned no location key".to_string())?
+            .to_string();
+
+        self.accuweather_keys.lock().unwrap().insert(cache_key, key.clone());
+        Ok(key)
+    }
+
+    pub async fn fetch_accuweather(&self, lat: f64, lon: f64, location_name: String, api_key: &str, config: &AppConfig) -> Result<WeatherState, String> {
+        let location_key = self.accuweather_location_key(lat, lon, api_key).await?;
+
+        // One call per refresh: the 12-hour forecast's first entry stands in for current conditions
+        let url = format!(
+            "{}/forecasts/v1/hourly/12hour/{}?apikey={}&metric=true&details=true",
+            self.endpoints.accuweather, location_key, api_key
+        );
+        let data = self.get_json(&url).await.map_err(|e| e.replace(api_key, "***"))?;
+        let hourly = data.as_array().ok_or_else(|| "No forecast data".to_string())?;
+
+        let hours: Vec<HourlyPoint> = hourly.iter().filter_map(|h| {
+            let metric = |key: &str| h.get(key).and_then(|v| v.get("Value")).and_then(|v| v.as_f64());
+            Some(HourlyPoint {
+                time: h.get("EpochDateTime").and_then(|v| v.as_i64()).and_then(|ts| Utc.timestamp_opt(ts, 0).single())?,
+                temp: metric("Temperature").unwrap_or(0.0),
+                apparent_temp: metric("RealFeelTemperature"),
+                precip_prob: h.get("PrecipitationProbability").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                precip_amount: metric("TotalLiquid").unwrap_or(0.0),
+                is_snow: h.get("PrecipitationType").and_then(|v| v.as_str()) == Some("Snow"),
+                is_thunder: h.get("WeatherIcon").and_then(|v| v.as_i64()).is_some_and(|icon| ACCUWEATHER_THUNDER_ICONS.contains(&icon)),
+                humidity: h.get("RelativeHumidity").and_then(|v| v.as_f64()),
+                dew_point: metric("DewPoint"),
+                wind_speed: h.get("Wind").and_then(|w| w.get("Speed")).and_then(|v| v.get("Value")).and_then(|v| v.as_f64()),
+                wind_gust: h.get("WindGust").and_then(|w| w.get("Speed")).and_then(|v| v.get("Value")).and_then(|v| v.as_f64()),
+                // Not part of the hourly forecast
+                pressure: None,
+                visibility: metric("Visibility"),
+                is_fog: h.get("WeatherIcon").and_then(|v| v.as_i64()) == Some(ACCUWEATHER_FOG_ICON),
+            })
+        }).collect();
+
+        let summary = summarize_hourly(&hours, None, config);
+        let sun_times = crate::sun::sun_times(lat, lon, Utc::now().date_naive());
+
+        Ok(WeatherState {
+            temperature: summary.temperature,
+            apparent_temperature: summary.apparent_temperature,
+            today_high: summary.today_high,
+            today_low: summary.today_low,
+            has_precipitation: summary.has_precipitation,
+            has_thunder: summary.has_thunder,
+            precip_type: summary.precip_type,
+            location_name,
+            sun_times: sun_times.clone(),
+            is_night: self.check_is_night(&sun_times),
+            provider: "AccuWeather".to_string(),
+            last_updated: Utc::now(),
+            debug_forecast: summary.debug_forecast,
+            precip_probability: summary.precip_probability,
+            humidity: summary.humidity,
+            dew_point: summary.dew_point,
+            wind_speed: summary.wind_speed,
+            wind_gust: summary.wind_gust,
+            pressure: summary.pressure,
+            pressure_tendency: None,
+            precip_starts_at: summary.precip_starts_at,
+            alerts: Vec::new(),
+            stale: false,
+            kp_index: None,
+            air_quality: None,
+            pollen: None,
+            nowcast: Vec::new(),
+            frost_warning: false,
+            visibility: summary.visibility,
+            has_fog: summary.has_fog,
+            normal_high: None,
+            tomorrow_high: None,
+        })
+    }
+
+    pub async fn fetch_openmeteo(&self, lat: f64, lon: f64, location_name: String, config: &AppConfig) -> Result<WeatherState, String> {
+        let mut url = format!(
+            "{}/v1/forecast?latitude={}&longitude={}&hourly=temperature_2m,precipitation_probability,rain,showers,snowfall,relative_humidity_2m,wind_speed_10m,wind_gusts_10m,apparent_temperature,dew_point_2m,weather_code,pressure_msl,visibility&daily=sunrise,sunset&timezone=GMT&forecast_days=2",
+            self.endpoints.open_meteo, lat, lon
+        );
+        if config.precip_horizon == "nowcast" {
+            // Two hours of 15-minute slots; outside Europe and North America they're interpolated from hourly models
+            url.push_str("&minutely_15=precipitation,snowfall&forecast_minutely_15=8");
+        }
+        let data = self.get_json(&url).await?;
 
         let sunrise = data.get("daily").and_then(|v| v.get("sunrise")).and_then(|v| v.as_array())
             .and_then(|arr| arr.first()).and_then(|v| v.as_str())
@@ -335,7 +1202,7 @@ impl WeatherService {
                 DateTime::parse_from_rfc3339(&format!("{}:00Z", s)).ok().map(|dt| dt.with_timezone(&Utc))
             });
 
-        let sun_times = SunTimes { sunrise, sunset };
+        let sun_times = SunTimes { sunrise, sunset, ..Default::default() };
 
         // Dynamically find the array index for the exact CURRENT hour in GMT
         let now_utc = Utc::now();
@@ -368,6 +1235,16 @@ impl WeatherService {
         };
 
         let mut temperature = hourly_temps.and_then(|arr| arr.get(current_hour_index)).and_then(|v| v.as_f64()).unwrap_or(0.0);
+        let hourly_apparent = data.get("hourly").and_then(|v| v.get("apparent_temperature")).and_then(|v| v.as_array());
+        let mut apparent_temperature = hourly_apparent.and_then(|arr| arr.get(current_hour_index)).and_then(|v| v.as_f64());
+
+        let hour_temps = hourly_times.zip(hourly_temps).map(|(times, temps)| {
+            times.iter().zip(temps.iter()).filter_map(|(t, v)| {
+                let time = DateTime::parse_from_rfc3339(&format!("{}:00Z", t.as_str()?)).ok()?.with_timezone(&Utc);
+                Some((time, v.as_f64()?))
+            }).collect::<Vec<_>>()
+        }).unwrap_or_default();
+        let (today_high, today_low) = today_range(Some(temperature), hour_temps, now_local.date_naive());
 
         if temp_hours > 0 {
             if let Some(arr) = hourly_temps {
@@ -380,9 +1257,21 @@ impl WeatherService {
                 }
                 temperature = max_t;
             }
+            if let Some(arr) = hourly_apparent {
+                let limit = std::cmp::min(arr.len(), current_hour_index + temp_hours);
+                if let Some(max_a) = arr.get(current_hour_index..limit).unwrap_or(&[]).iter().filter_map(|v| v.as_f64()).reduce(f64::max) {
+                    apparent_temperature = Some(max_a);
+                }
+            }
         }
 
         let mut has_precipitation = false;
+        let mut precip_type = None;
+        let mut precip_probability: f64 = 0.0;
+        // WMO codes 95-99: thunderstorm, optionally with hail
+        let has_thunder = precip_hours > 0 && data.get("hourly").and_then(|v| v.get("weather_code")).and_then(|v| v.as_array())
+            .and_then(|codes| codes.get(current_hour_index..std::cmp::min(codes.len(), current_hour_index + precip_hours)))
+            .is_some_and(|codes| codes.iter().filter_map(|v| v.as_i64()).any(|code| (95..=99).contains(&code)));
         if precip_hours > 0 {
             let probs = data.get("hourly").and_then(|v| v.get("precipitation_probability")).and_then(|v| v.as_array());
             let rain = data.get("hourly").and_then(|v| v.get("rain")).and_then(|v| v.as_array());
@@ -397,14 +1286,63 @@ impl WeatherService {
                     let show_val = showers.and_then(|arr| arr.get(i)).and_then(|v| v.as_f64()).unwrap_or(0.0);
                     let snow_val = snow.and_then(|arr| arr.get(i)).and_then(|v| v.as_f64()).unwrap_or(0.0);
 
-                    if prob_val >= 35.0 || rain_val >= 0.5 || show_val >= 0.5 || snow_val >= 0.5 {
+                    precip_probability = precip_probability.max(prob_val);
+                    if config.is_wet(prob_val, rain_val.max(show_val).max(snow_val)) {
                         has_precipitation = true;
-                        break;
+                        precip_type.get_or_insert_with(|| if snow_val > 0.0 { "Snow" } else { "Rain" }.to_string());
                     }
                 }
             }
         }
 
+        let precip_starts_at = {
+            let probs = data.get("hourly").and_then(|v| v.get("precipitation_probability")).and_then(|v| v.as_array());
+            let rain = data.get("hourly").and_then(|v| v.get("rain")).and_then(|v| v.as_array());
+            let showers = data.get("hourly").and_then(|v| v.get("showers")).and_then(|v| v.as_array());
+            let snow = data.get("hourly").and_then(|v| v.get("snowfall")).and_then(|v| v.as_array());
+
+            hourly_times.and_then(|t_arr| {
+                (current_hour_index..t_arr.len()).find(|&i| {
+                    let prob_val = probs.and_then(|a| a.get(i)).and_then(|v| v.as_f64()).unwrap_or(0.0);
+                    let rain_val = rain.and_then(|a| a.get(i)).and_then(|v| v.as_f64()).unwrap_or(0.0);
+                    let show_val = showers.and_then(|a| a.get(i)).and_then(|v| v.as_f64()).unwrap_or(0.0);
+                    let snow_val = snow.and_then(|a| a.get(i)).and_then(|v| v.as_f64()).unwrap_or(0.0);
+                    config.is_wet(prob_val, rain_val.max(show_val).max(snow_val))
+                })
+                .and_then(|i| t_arr[i].as_str())
+                .and_then(|t_str| DateTime::parse_from_rfc3339(&format!("{}:00Z", t_str)).ok())
+                .map(|dt| dt.with_timezone(&Utc))
+            })
+        };
+
+        // With the "nowcast" horizon the 15-minute slots decide, falling back to the current hour without them
+        let nowcast = parse_minutely_15(&data);
+        let (has_precipitation, precip_type, precip_starts_at) = match summarize_nowcast(&nowcast, config) {
+            Some((wet, kind, starts)) if config.precip_horizon == "nowcast" => (wet, kind, starts.or(precip_starts_at)),
+            _ => (has_precipitation, precip_type, precip_starts_at),
+        };
+
+        let humidity = data.get("hourly").and_then(|v| v.get("relative_humidity_2m")).and_then(|v| v.as_array())
+            .and_then(|arr| arr.get(current_hour_index)).and_then(|v| v.as_f64());
+        let hourly_dew_points = data.get("hourly").and_then(|v| v.get("dew_point_2m")).and_then(|v| v.as_array());
+        let dew_point = hourly_dew_points.and_then(|arr| arr.get(current_hour_index)).and_then(|v| v.as_f64());
+        let wind_speed = data.get("hourly").and_then(|v| v.get("wind_speed_10m")).and_then(|v| v.as_array())
+            .and_then(|arr| arr.get(current_hour_index)).and_then(|v| v.as_f64());
+        let wind_gust = data.get("hourly").and_then(|v| v.get("wind_gusts_10m")).and_then(|v| v.as_array())
+            .and_then(|arr| arr.get(current_hour_index)).and_then(|v| v.as_f64());
+        let pressure = data.get("hourly").and_then(|v| v.get("pressure_msl")).and_then(|v| v.as_array())
+            .and_then(|arr| arr.get(current_hour_index)).and_then(|v| v.as_f64());
+
+        // Visibility is in metres; WMO codes 45 and 48 are fog and depositing rime fog
+        let hourly_visibility = data.get("hourly").and_then(|v| v.get("visibility")).and_then(|v| v.as_array());
+        let hourly_codes = data.get("hourly").and_then(|v| v.get("weather_code")).and_then(|v| v.as_array());
+        let visibility = hourly_visibility.and_then(|arr| arr.get(current_hour_index)).and_then(|v| v.as_f64()).map(|m| m / 1000.0);
+        let has_fog = (current_hour_index..current_hour_index + config.fog.hours.max(1) as usize).any(|i| {
+            let code = hourly_codes.and_then(|a| a.get(i)).and_then(|v| v.as_i64());
+            let vis = hourly_visibility.and_then(|a| a.get(i)).and_then(|v| v.as_f64()).map(|m| m / 1000.0);
+            matches!(code, Some(45) | Some(48)) || config.fog.is_low(vis)
+        });
+
         let mut debug_forecast = Vec::new();
         let times = data.get("hourly").and_then(|v| v.get("time")).and_then(|v| v.as_array());
         if let (Some(t_arr), Some(temp_arr)) = (times, hourly_temps) {
@@ -413,6 +1351,11 @@ impl WeatherService {
                 if let Some(t_str) = t_arr[i].as_str() {
                     let dt = DateTime::parse_from_rfc3339(&format!("{}:00Z", t_str)).ok().map(|dt| dt.with_timezone(&Utc)).unwrap_or_else(Utc::now);
                     let t_val = temp_arr.get(i).and_then(|v| v.as_f64()).unwrap_or(0.0);
+                    let feels_val = data.get("hourly").and_then(|v| v.get("apparent_temperature")).and_then(|v| v.as_array())
+                        .and_then(|a| a.get(i)).and_then(|v| v.as_f64());
+                    let humidity_val = data.get("hourly").and_then(|v| v.get("relative_humidity_2m")).and_then(|v| v.as_array())
+                        .and_then(|a| a.get(i)).and_then(|v| v.as_f64());
+                    let dew_val = hourly_dew_points.and_then(|a| a.get(i)).and_then(|v| v.as_f64());
                     
                     let probs = data.get("hourly").and_then(|v| v.get("precipitation_probability")).and_then(|v| v.as_array());
                     let rain = data.get("hourly").and_then(|v| v.get("rain")).and_then(|v| v.as_array());
@@ -431,8 +1374,11 @@ impl WeatherService {
                     debug_forecast.push(ForecastItem {
                         time: dt,
                         temp: t_val,
+                        apparent_temp: feels_val,
                         precip_prob: prob_val,
                         precip_type,
+                        humidity: humidity_val,
+                        dew_point: dew_val,
                     });
                 }
             }
@@ -440,14 +1386,270 @@ impl WeatherService {
 
         Ok(WeatherState {
             temperature,
+            apparent_temperature,
+            today_high,
+            today_low,
             has_precipitation,
+            has_thunder,
+            precip_type,
             location_name,
             sun_times: sun_times.clone(),
             is_night: self.check_is_night(&sun_times),
             provider: "Open-Meteo".to_string(),
             last_updated: Utc::now(),
             debug_forecast,
+            precip_probability,
+            humidity,
+            dew_point,
+            wind_speed,
+            wind_gust,
+            pressure,
+            pressure_tendency: None,
+            precip_starts_at,
+            alerts: Vec::new(),
+            stale: false,
+            kp_index: None,
+            air_quality: None,
+            pollen: None,
+            nowcast,
+            frost_warning: false,
+            visibility,
+            has_fog,
+            normal_high: None,
+            tomorrow_high: None,
+        })
+    }
+}
+
+fn is_night_between(morning: Option<DateTime<Utc>>, evening: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    if let (Some(morning), Some(evening)) = (morning, evening) {
+        // Extract HH:MM time components exclusively since Open-Meteo returns future days sequentially
+        let now_time = now.time();
+        let sr_time = morning.time();
+        let ss_time = evening.time();
+
+        if sr_time < ss_time {
+            // Standard ordering (e.g. 06:00 Sunrise -> 18:00 Sunset)
+            now_time < sr_time || now_time > ss_time
+        } else {
+            // Wrapped ordering (e.g. 17:00 Sunrise -> 07:00 Sunset due to GMT shift in NZ/AUS)
+            // Night is the space *between* Sunset and Sunrise
+            now_time < sr_time && now_time > ss_time
+        }
+    } else {
+        false
+    }
+}
+
+/// One hour of a provider's forecast, normalized for `summarize_hourly`
+struct HourlyPoint {
+    time: DateTime<Utc>,
+    temp: f64,
+    apparent_temp: Option<f64>,
+    /// 0-100
+    precip_prob: f64,
+    /// mm over the hour, 0 when the provider only reports probability
+    precip_amount: f64,
+    is_snow: bool,
+    is_thunder: bool,
+    humidity: Option<f64>,
+    /// °C, when the provider reports it
+    dew_point: Option<f64>,
+    /// km/h
+    wind_speed: Option<f64>,
+    wind_gust: Option<f64>,
+    /// hPa, sea level
+    pressure: Option<f64>,
+    /// km
+    visibility: Option<f64>,
+    is_fog: bool,
+}
+
+struct HourlySummary {
+    temperature: f64,
+    apparent_temperature: Option<f64>,
+    today_high: Option<f64>,
+    today_low: Option<f64>,
+    has_precipitation: bool,
+    has_thunder: bool,
+    precip_type: Option<String>,
+    precip_probability: f64,
+    precip_starts_at: Option<DateTime<Utc>>,
+    debug_forecast: Vec<ForecastItem>,
+    /// Current hour's humidity, dew point and wind
+    humidity: Option<f64>,
+    dew_point: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_gust: Option<f64>,
+    pressure: Option<f64>,
+    visibility: Option<f64>,
+    /// Fog or low visibility within the fog window
+    has_fog: bool,
+}
+
+/// Dew point in °C from air temperature and relative humidity (Magnus formula)
+pub fn calculate_dew_point(temp_c: f64, humidity: f64) -> f64 {
+    let gamma = (humidity.clamp(1.0, 100.0) / 100.0).ln() + 17.625 * temp_c / (243.04 + temp_c);
+    243.04 * gamma / (17.625 - gamma)
+}
+
+// Open-Meteo's `minutely_15` block, when it was requested
+fn parse_minutely_15(data: &serde_json::Value) -> Vec<NowcastItem> {
+    let Some(minutely) = data.get("minutely_15") else { return Vec::new() };
+    let series = |key: &str| minutely.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    let (precipitation, snowfall) = (series("precipitation"), series("snowfall"));
+    series("time").iter().enumerate().filter_map(|(i, t)| {
+        let time = DateTime::parse_from_rfc3339(&format!("{}:00Z", t.as_str()?)).ok()?.with_timezone(&Utc);
+        Some(NowcastItem {
+            time,
+            precip_amount: precipitation.get(i).and_then(|v| v.as_f64()).unwrap_or(0.0),
+            is_snow: snowfall.get(i).and_then(|v| v.as_f64()).is_some_and(|cm| cm > 0.0),
         })
+    }).collect()
+}
+
+// Quarter-hour slots in the nowcast's "next hour"
+const NOWCAST_SLOTS: usize = 4;
+
+// (has_precipitation, precip_type, precip_starts_at) from the nowcast, or None without one.
+// Slot amounts are scaled to an hourly rate before the amount threshold applies.
+fn summarize_nowcast(slots: &[NowcastItem], config: &AppConfig) -> Option<(bool, Option<String>, Option<DateTime<Utc>>)> {
+    if slots.is_empty() {
+        return None;
+    }
+    let now = Utc::now();
+    // Fall back to the first slot when the whole series is in the past
+    let current = slots.iter().position(|s| s.time + chrono::Duration::minutes(15) > now).unwrap_or(0);
+    let is_wet = |s: &&NowcastItem| config.is_wet(0.0, s.precip_amount * 4.0);
+
+    let next_hour = slots[current..].iter().take(NOWCAST_SLOTS).find(is_wet);
+    let starts = slots[current..].iter().find(is_wet).map(|s| s.time);
+    Some((next_hour.is_some(), next_hour.map(|s| if s.is_snow { "Snow" } else { "Rain" }.to_string()), starts))
+}
+
+/// NWS heat index in °C from air temperature and relative humidity (Rothfusz regression, with
+/// Steadman's simple formula below about 27°C where the regression doesn't hold)
+pub fn heat_index(temp_c: f64, humidity: f64) -> f64 {
+    let t = temp_c * 9.0 / 5.0 + 32.0;
+    let rh = humidity.clamp(0.0, 100.0);
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    let hi = if (simple + t) / 2.0 < 80.0 {
+        simple
+    } else {
+        let mut hi = -42.379 + 2.04901523 * t + 10.14333127 * rh - 0.22475541 * t * rh - 0.00683783 * t * t
+            - 0.05481717 * rh * rh + 0.00122874 * t * t * rh + 0.00085282 * t * rh * rh - 0.00000199 * t * t * rh * rh;
+        if rh < 13.0 && (80.0..=112.0).contains(&t) {
+            hi -= (13.0 - rh) / 4.0 * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+        } else if rh > 85.0 && (80.0..=87.0).contains(&t) {
+            hi += (rh - 85.0) / 10.0 * (87.0 - t) / 5.0;
+        }
+        hi
+    };
+    (hi - 32.0) * 5.0 / 9.0
+}
+
+// Highest and lowest of the current reading and the hourly temperatures that fall on `today` (local date)
+fn today_range(current: Option<f64>, hours: impl IntoIterator<Item = (DateTime<Utc>, f64)>, today: NaiveDate) -> (Option<f64>, Option<f64>) {
+    let temps: Vec<f64> = hours.into_iter()
+        .filter(|(time, _)| time.with_timezone(&Local).date_naive() == today)
+        .map(|(_, t)| t)
+        .chain(current)
+        .collect();
+    (temps.iter().copied().reduce(f64::max), temps.iter().copied().reduce(f64::min))
+}
+
+// Applies the temperature and precipitation horizons to a plain hourly series.
+// `current_temp` (e.g. a live observation) replaces the current hour's forecast temperature.
+fn summarize_hourly(hours: &[HourlyPoint], current_temp: Option<f64>, config: &AppConfig) -> HourlySummary {
+    let now = Utc::now();
+    // Fall back to the first entry when the whole series is in the past
+    let current_index = hours.iter().position(|h| h.time + chrono::Duration::hours(1) > now).unwrap_or(0);
+    let upcoming = hours.get(current_index..).unwrap_or(&[]);
+
+    let now_local = Local::now();
+    let hours_left = 24 - now_local.time().format("%H").to_string().parse::<usize>().unwrap_or(0);
+
+    let precip_hours = match config.precip_horizon.as_str() {
+        "none" => 0,
+        "short" => 6,
+        "today" => hours_left,
+        "day" => 24,
+        _ => 1,
+    };
+
+    let temp_hours = match config.temp_horizon.as_str() {
+        "short_high" => 6,
+        "today_high" => hours_left,
+        "day_high" => 24,
+        _ => 0,
+    };
+
+    let is_wet = |h: &HourlyPoint| config.is_wet(h.precip_prob, h.precip_amount);
+
+    let mut temperature = current_temp.or_else(|| upcoming.first().map(|h| h.temp)).unwrap_or(0.0);
+    let mut apparent_temperature = upcoming.first().and_then(|h| h.apparent_temp);
+    let (today_high, today_low) = today_range(Some(temperature), hours.iter().map(|h| (h.time, h.temp)), now_local.date_naive());
+    if temp_hours > 0 {
+        let limit = std::cmp::min(upcoming.len(), temp_hours);
+        if let Some(max_t) = upcoming[..limit].iter().map(|h| h.temp).reduce(f64::max) {
+            temperature = max_t;
+        }
+        if let Some(max_a) = upcoming[..limit].iter().filter_map(|h| h.apparent_temp).reduce(f64::max) {
+            apparent_temperature = Some(max_a);
+        }
+    }
+
+    let mut has_precipitation = false;
+    let mut precip_probability: f64 = 0.0;
+    let limit = std::cmp::min(upcoming.len(), precip_hours);
+    for h in &upcoming[..limit] {
+        precip_probability = precip_probability.max(h.precip_prob);
+        if is_wet(h) {
+            has_precipitation = true;
+        }
+    }
+    let has_thunder = upcoming[..limit].iter().any(|h| h.is_thunder);
+    let precip_type = upcoming[..limit].iter().find(|h| is_wet(h))
+        .map(|h| if h.is_snow { "Snow" } else { "Rain" }.to_string());
+
+    let precip_starts_at = upcoming.iter().find(|h| is_wet(h)).map(|h| h.time);
+
+    let fog_limit = std::cmp::min(upcoming.len(), config.fog.hours.max(1) as usize);
+    let has_fog = upcoming[..fog_limit].iter().any(|h| h.is_fog || config.fog.is_low(h.visibility));
+
+    let hour_dew_point = |h: &HourlyPoint| h.dew_point.or_else(|| h.humidity.map(|rh| calculate_dew_point(h.temp, rh)));
+
+    let debug_forecast = upcoming.iter().take(24).map(|h| {
+        let precip_type = if !is_wet(h) && h.precip_amount <= 0.0 { "None" } else if h.is_snow { "Snow" } else { "Rain" };
+        ForecastItem {
+            time: h.time,
+            temp: h.temp,
+            apparent_temp: h.apparent_temp,
+            precip_prob: h.precip_prob,
+            precip_type: precip_type.to_string(),
+            humidity: h.humidity,
+            dew_point: hour_dew_point(h),
+        }
+    }).collect();
+
+    HourlySummary {
+        temperature,
+        apparent_temperature,
+        today_high,
+        today_low,
+        has_precipitation,
+        has_thunder,
+        precip_type,
+        precip_probability,
+        precip_starts_at,
+        debug_forecast,
+        humidity: upcoming.first().and_then(|h| h.humidity),
+        dew_point: upcoming.first().and_then(hour_dew_point),
+        wind_speed: upcoming.first().and_then(|h| h.wind_speed),
+        wind_gust: upcoming.first().and_then(|h| h.wind_gust),
+        pressure: upcoming.first().and_then(|h| h.pressure),
+        visibility: upcoming.first().and_then(|h| h.visibility),
+        has_fog,
     }
 }
 
@@ -462,4 +1664,73 @@ mod tests {
         println!("Parse result: {:?}", dt);
         assert!(dt.is_ok());
     }
+
+    #[test]
+    fn test_heat_index() {
+        // NWS table: 95°F at 50% is 105°F, 90°F at 70% is 105°F
+        assert!((heat_index(35.0, 50.0) - 40.9).abs() < 0.5);
+        assert!((heat_index(32.2, 70.0) - 40.6).abs() < 0.5);
+        // Mild air stays close to the thermometer
+        assert!((heat_index(20.0, 50.0) - 20.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_today_range() {
+        let today = Local::now().date_naive();
+        let at = |days: i64, h: u32| today.and_hms_opt(h, 0, 0).unwrap().and_local_timezone(Local).earliest().unwrap().with_timezone(&Utc) + chrono::Duration::days(days);
+        let hours = vec![(at(0, 6), 4.0), (at(0, 14), 17.0), (at(0, 23), 8.0), (at(1, 3), 1.0), (at(-1, 13), 25.0)];
+
+        assert_eq!(today_range(Some(12.0), hours.clone(), today), (Some(17.0), Some(4.0)));
+        // The current reading counts even when it's outside the forecast hours
+        assert_eq!(today_range(Some(2.5), hours, today), (Some(17.0), Some(2.5)));
+        assert_eq!(today_range(None, Vec::new(), today), (None, None));
+    }
+
+    #[test]
+    fn test_night_between() {
+        let at = |h, m| Utc.with_ymd_and_hms(2026, 3, 20, h, m, 0).unwrap();
+        // Past sunset but still inside civil twilight
+        assert!(is_night_between(Some(at(6, 0)), Some(at(18, 0)), at(18, 20)));
+        assert!(!is_night_between(Some(at(5, 30)), Some(at(18, 30)), at(18, 20)));
+        // Wrapped ordering from UTC times in NZ
+        assert!(is_night_between(Some(at(17, 0)), Some(at(7, 0)), at(12, 0)));
+        assert!(!is_night_between(None, Some(at(7, 0)), at(12, 0)));
+    }
+
+    #[test]
+    fn test_dew_point() {
+        assert!((calculate_dew_point(20.0, 50.0) - 9.3).abs() < 0.1);
+        assert!((calculate_dew_point(-5.0, 100.0) + 5.0).abs() < 1e-9);
+        // Bone-dry readings stay finite
+        assert!(calculate_dew_point(25.0, 0.0).is_finite());
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        assert!(is_transient_error("HTTP 503 Service Unavailable from https://api.met.no/x"));
+        assert!(is_transient_error("NWS only covers US locations (HTTP 502 Bad Gateway from https://api.weather.gov/x)"));
+        assert!(is_transient_error("error sending request for url (https://api.open-meteo.com/v1/forecast)"));
+        assert!(!is_transient_error("HTTP 401 Unauthorized from https://api.weather.com/x"));
+        assert!(!is_transient_error("HTTP 429 Too Many Requests from https://api.tomorrow.io/x (retry after 900s)"));
+        assert!(!is_transient_error("HTTP 404 Not Found from https://api.weather.gov/x"));
+        assert!(!is_transient_error("Pirate Weather requires an API key"));
+        assert!(!is_transient_error("No forecast data"));
+
+        let policy = RetryPolicy::default();
+        assert!(policy.delay(0) >= std::time::Duration::from_secs(1) && policy.delay(0) < std::time::Duration::from_millis(1500));
+        assert!(policy.delay(2) >= std::time::Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(retry_after(&headers, now), None);
+
+        headers.insert(reqwest::header::RETRY_AFTER, "120".parse().unwrap());
+        assert_eq!(retry_after(&headers, now), Some(chrono::Duration::seconds(120)));
+
+        headers.insert(reqwest::header::RETRY_AFTER, "Sun, 01 Mar 2026 12:10:00 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers, now), Some(chrono::Duration::minutes(10)));
+    }
 }
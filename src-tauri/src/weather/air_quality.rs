@@ -0,0 +1,43 @@
+use serde_json::Value;
+use crate::models::{AirQuality, Pollen};
+use super::WeatherService;
+
+// Open-Meteo's air quality API (CAMS model data, global, no key), fetched alongside the weather
+// when `light_basis` is "aqi". Pollen comes from the same API for allergy mode, but CAMS only
+// models it over Europe; elsewhere the series are null.
+
+impl WeatherService {
+    pub async fn fetch_air_quality(&self, lat: f64, lon: f64) -> Result<AirQuality, String> {
+        let url = format!(
+            "{}/v1/air-quality?latitude={}&longitude={}&current=us_aqi,pm2_5",
+            self.endpoints.open_meteo_air_quality, lat, lon
+        );
+        let data = self.get_json(&url).await?;
+        let current = data.get("current").ok_or_else(|| "No current air quality".to_string())?;
+
+        Ok(AirQuality {
+            us_aqi: current.get("us_aqi").and_then(|v| v.as_f64()).ok_or_else(|| "No AQI in air quality response".to_string())?,
+            pm2_5: current.get("pm2_5").and_then(|v| v.as_f64()),
+        })
+    }
+
+    /// Peak counts over today's hourly pollen forecast
+    pub async fn fetch_pollen(&self, lat: f64, lon: f64) -> Result<Pollen, String> {
+        let url = format!(
+            "{}/v1/air-quality?latitude={}&longitude={}&hourly=birch_pollen,grass_pollen,ragweed_pollen&forecast_days=1",
+            self.endpoints.open_meteo_air_quality, lat, lon
+        );
+        let data = self.get_json(&url).await?;
+        let hourly = data.get("hourly").ok_or_else(|| "No pollen forecast".to_string())?;
+
+        Ok(Pollen {
+            birch: peak(hourly, "birch_pollen"),
+            grass: peak(hourly, "grass_pollen"),
+            ragweed: peak(hourly, "ragweed_pollen"),
+        })
+    }
+}
+
+fn peak(hourly: &Value, key: &str) -> Option<f64> {
+    hourly.get(key).and_then(|v| v.as_array())?.iter().filter_map(|v| v.as_f64()).reduce(f64::max)
+}
@@ -0,0 +1,27 @@
+use serde_json::Value;
+use super::WeatherService;
+
+// Official warnings for providers that don't publish their own, from the NWS alerts feed. It only
+// covers the US; elsewhere the feed is simply empty (or refuses the point) and nothing is added.
+
+// CAP severities that count as a warning worth overriding the light for
+const SEVERE: [&str; 2] = ["Extreme", "Severe"];
+
+impl WeatherService {
+    /// Headlines of severe or extreme alerts in effect at the point
+    pub async fn fetch_nws_alerts(&self, lat: f64, lon: f64) -> Result<Vec<String>, String> {
+        let url = format!("{}/alerts/active?point={:.4},{:.4}", self.endpoints.nws, lat, lon);
+        let data = self.get_json(&url).await?;
+        Ok(severe_headlines(&data))
+    }
+}
+
+fn severe_headlines(data: &Value) -> Vec<String> {
+    let features = data.get("features").and_then(|v| v.as_array()).map(|f| f.as_slice()).unwrap_or_default();
+    features.iter()
+        .filter_map(|f| f.get("properties"))
+        .filter(|p| p.get("severity").and_then(|v| v.as_str()).is_some_and(|s| SEVERE.contains(&s)))
+        .filter_map(|p| p.get("headline").or_else(|| p.get("event")).and_then(|v| v.as_str()))
+        .map(|s| s.to_string())
+        .collect()
+}
@@ -0,0 +1,23 @@
+use serde_json::Value;
+use super::WeatherService;
+
+// Planetary Kp index from NOAA's Space Weather Prediction Center, layered onto whichever provider
+// answered when aurora alerts are on. The 1-minute estimate follows a storm as it builds, where the
+// official 3-hourly index lags by hours.
+
+impl WeatherService {
+    /// Most recent estimated Kp (0-9)
+    pub async fn fetch_kp_index(&self) -> Result<f64, String> {
+        let url = format!("{}/json/planetary_k_index_1m.json", self.endpoints.swpc);
+        let data = self.get_json(&url).await?;
+        latest_kp(&data).ok_or_else(|| "No Kp index in SWPC response".to_string())
+    }
+}
+
+// Entries are oldest first; `estimated_kp` has decimals, `kp_index` is the rounded fallback
+fn latest_kp(data: &Value) -> Option<f64> {
+    data.as_array()?.iter().rev().find_map(|entry| {
+        entry.get("estimated_kp").and_then(|v| v.as_f64())
+            .or_else(|| entry.get("kp_index").and_then(|v| v.as_f64()))
+    })
+}
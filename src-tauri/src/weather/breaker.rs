@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use crate::models::ProviderHealth;
+
+// Per-provider circuit breaker. After `FAILURE_THRESHOLD` failed fetches in a row (each one already
+// retried) the provider is skipped for a cooldown that doubles with every further failure. When the
+// cooldown runs out a single attempt is let through; a success closes the circuit again.
+
+const FAILURE_THRESHOLD: u32 = 3;
+const BASE_COOLDOWN_MINUTES: i64 = 30;
+const MAX_COOLDOWN_MINUTES: i64 = 240;
+
+#[derive(Debug, Default)]
+struct Breaker {
+    failures: u32,
+    open_until: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Breakers {
+    providers: HashMap<String, Breaker>,
+}
+
+impl Breakers {
+    /// End of the cooldown, while `id` is being skipped
+    pub(super) fn open_until(&self, id: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.providers.get(id).and_then(|b| b.open_until).filter(|until| *until > now)
+    }
+
+    pub(super) fn record_success(&mut self, id: &str) {
+        self.providers.remove(id);
+    }
+
+    pub(super) fn record_failure(&mut self, id: &str, error: &str, now: DateTime<Utc>) {
+        let breaker = self.providers.entry(id.to_string()).or_default();
+        breaker.failures += 1;
+        breaker.last_error = Some(error.to_string());
+        if breaker.failures >= FAILURE_THRESHOLD {
+            let doublings = (breaker.failures - FAILURE_THRESHOLD).min(8);
+            let minutes = (BASE_COOLDOWN_MINUTES << doublings).min(MAX_COOLDOWN_MINUTES);
+            breaker.open_until = Some(now + chrono::Duration::minutes(minutes));
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.providers.clear();
+    }
+
+    pub(super) fn health(&self, now: DateTime<Utc>) -> Vec<ProviderHealth> {
+        let mut health: Vec<ProviderHealth> = self.providers.iter().map(|(id, b)| ProviderHealth {
+            provider: id.clone(),
+            consecutive_failures: b.failures,
+            open_until: b.open_until.filter(|until| *until > now),
+            last_error: b.last_error.clone(),
+        }).collect();
+        health.sort_by(|a, b| a.provider.cmp(&b.provider));
+        health
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldown_doubles() {
+        let now = Utc::now();
+        let mut breakers = Breakers::default();
+
+        breakers.record_failure("metno", "HTTP 503", now);
+        breakers.record_failure("metno", "HTTP 503", now);
+        assert_eq!(breakers.open_until("metno", now), None);
+
+        breakers.record_failure("metno", "HTTP 503", now);
+        assert_eq!(breakers.open_until("metno", now), Some(now + chrono::Duration::minutes(30)));
+        // Half-open once the cooldown is over; failing again doubles it
+        let later = now + chrono::Duration::minutes(31);
+        assert_eq!(breakers.open_until("metno", later), None);
+        breakers.record_failure("metno", "HTTP 503", later);
+        assert_eq!(breakers.open_until("metno", later), Some(later + chrono::Duration::minutes(60)));
+
+        breakers.record_success("metno");
+        assert!(breakers.health(later).is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::Utc;
+use serde_json::Value;
+use crate::config::AppConfig;
+use crate::models::WeatherState;
+use super::WeatherService;
+
+// User-defined JSON endpoint. The response is a single current reading, so the forecast
+// horizons don't apply; the endpoint decides what "precipitation" means.
+
+impl WeatherService {
+    pub async fn fetch_custom(&self, lat: f64, lon: f64, location_name: String, config: &AppConfig) -> Result<WeatherState, String> {
+        let custom = &config.custom_provider;
+        if custom.url.trim().is_empty() {
+            return Err("Custom provider has no URL".to_string());
+        }
+
+        let url = custom.url
+            .replace("{lat}", &lat.to_string())
+            .replace("{lon}", &lon.to_string())
+            .replace("{apiKey}", &config.api_key);
+        let data = self.get_json(&url).await
+            .map_err(|e| if config.api_key.is_empty() { e } else { e.replace(&config.api_key, "***") })?;
+
+        let raw_temp = lookup(&data, &custom.temperature_path)
+            .and_then(as_number)
+            .ok_or_else(|| format!("No temperature at '{}'", custom.temperature_path))?;
+        let temperature = to_celsius(raw_temp, &custom.temperature_unit);
+
+        let (has_precipitation, precip_probability) = match lookup(&data, &custom.precipitation_path) {
+            Some(v) => precipitation(v, &custom.precipitation_unit, config),
+            None => (false, 0.0),
+        };
+
+        let humidity = lookup(&data, &custom.humidity_path).and_then(as_number);
+        let dew_point = humidity.map(|rh| super::calculate_dew_point(temperature, rh));
+        let sun_times = crate::sun::sun_times(lat, lon, Utc::now().date_naive());
+
+        Ok(WeatherState {
+            temperature,
+            has_precipitation,
+            location_name,
+            sun_times: sun_times.clone(),
+            is_night: self.check_is_night(&sun_times),
+            provider: "Custom".to_string(),
+            last_updated: Utc::now(),
+            precip_probability,
+            humidity,
+            dew_point,
+            ..Default::default()
+        })
+    }
+}
+
+/// Resolves a JSON pointer ("/current/temp") or the dotted shorthand ("current.temp", "hourly.0.pop").
+/// An empty path resolves to nothing.
+pub fn lookup<'a>(data: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim();
+    if path.is_empty() {
+        None
+    } else if path.starts_with('/') {
+        data.pointer(path)
+    } else {
+        data.pointer(&format!("/{}", path.replace('.', "/")))
+    }
+}
+
+pub(super) fn as_number(v: &Value) -> Option<f64> {
+    v.as_f64().or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+pub(super) fn to_celsius(value: f64, unit: &str) -> f64 {
+    match unit {
+        "F" => (value - 32.0) * 5.0 / 9.0,
+        "K" => value - 273.15,
+        _ => value,
+    }
+}
+
+// Returns (has_precipitation, probability 0-100) using the same thresholds as the built-in providers
+pub(super) fn precipitation(v: &Value, unit: &str, config: &AppConfig) -> (bool, f64) {
+    match unit {
+        "bool" => {
+            let wet = v.as_bool().unwrap_or_else(|| as_number(v).is_some_and(|n| n != 0.0));
+            (wet, if wet { 100.0 } else { 0.0 })
+        }
+        "mm" => {
+            let wet = config.is_wet(0.0, as_number(v).unwrap_or(0.0));
+            (wet, if wet { 100.0 } else { 0.0 })
+        }
+        "fraction" => {
+            let prob = as_number(v).unwrap_or(0.0) * 100.0;
+            (config.is_wet(prob, 0.0), prob)
+        }
+        _ => {
+            let prob = as_number(v).unwrap_or(0.0);
+            (config.is_wet(prob, 0.0), prob)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lookup_paths() {
+        let data = json!({"current": {"temp": 291.15, "rain": "true"}, "hourly": [{"pop": 0.4}]});
+        assert_eq!(lookup(&data, "/current/temp"), Some(&json!(291.15)));
+        assert_eq!(lookup(&data, "hourly.0.pop"), Some(&json!(0.4)));
+        assert_eq!(lookup(&data, ""), None);
+        assert_eq!(lookup(&data, "current.missing"), None);
+
+        assert!((to_celsius(291.15, "K") - 18.0).abs() < 1e-9);
+        assert!((to_celsius(64.4, "F") - 18.0).abs() < 1e-9);
+
+        let config = AppConfig::default();
+        assert_eq!(precipitation(&json!(0.4), "fraction", &config), (true, 40.0));
+        assert_eq!(precipitation(&json!("0.2"), "mm", &config), (false, 0.0));
+
+        let strict = AppConfig { precip_probability_threshold: 60.0, precip_amount_threshold: 0.1, ..Default::default() };
+        assert_eq!(precipitation(&json!(0.4), "fraction", &strict), (false, 40.0));
+        assert_eq!(precipitation(&json!("0.2"), "mm", &strict), (true, 100.0));
+    }
+}
@@ -0,0 +1,158 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, Timelike, Utc};
+use serde_json::Value;
+use crate::config::AppConfig;
+use crate::models::DailyForecast;
+use super::{ProviderRegistry, WeatherService};
+
+// Multi-day forecast for the settings window, separate from the 24h `debug_forecast` that drives
+// the light. Providers without a daily series of their own use Open-Meteo's, which needs no key.
+
+const FORECAST_DAYS: usize = 7;
+
+impl WeatherService {
+    /// Daily summaries for the configured location, starting today
+    pub async fn fetch_forecast(&self, config: &AppConfig) -> Result<Vec<DailyForecast>, String> {
+        let (lat, lon, _) = self.resolve_location(config).await?;
+        let provider = ProviderRegistry::builtin().resolve(&config.provider);
+        let mut days = self.retrying(|| provider.fetch_daily(self, lat, lon, config)).await?;
+        for day in &mut days {
+            day.high += config.temp_offset;
+            day.low += config.temp_offset;
+        }
+        Ok(days)
+    }
+
+    pub async fn fetch_daily_openmeteo(&self, lat: f64, lon: f64) -> Result<Vec<DailyForecast>, String> {
+        // timezone=auto so the days break at the location's midnight rather than GMT's
+        let url = format!(
+            "{}/v1/forecast?latitude={}&longitude={}&daily=temperature_2m_max,temperature_2m_min,precipitation_probability_max,weather_code&timezone=auto&forecast_days={}",
+            self.endpoints.open_meteo, lat, lon, FORECAST_DAYS
+        );
+        let data = self.get_json(&url).await?;
+        let daily = data.get("daily").ok_or_else(|| "No daily forecast".to_string())?;
+        let series = |key: &str| daily.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+        let (highs, lows, probs, codes) = (series("temperature_2m_max"), series("temperature_2m_min"), series("precipitation_probability_max"), series("weather_code"));
+
+        let days: Vec<DailyForecast> = series("time").iter().enumerate().filter_map(|(i, t)| {
+            Some(DailyForecast {
+                date: NaiveDate::parse_from_str(t.as_str()?, "%Y-%m-%d").ok()?,
+                high: highs.get(i)?.as_f64()?,
+                low: lows.get(i)?.as_f64()?,
+                precip_probability: probs.get(i).and_then(|v| v.as_f64()).unwrap_or(0.0),
+                condition: codes.get(i).and_then(|v| v.as_i64()).map(wmo_condition).unwrap_or("Unknown").to_string(),
+            })
+        }).collect();
+
+        if days.is_empty() {
+            return Err("No daily forecast".to_string());
+        }
+        Ok(days)
+    }
+
+    // OWM's free tier has no daily endpoint; its 5-day/3-hour forecast is grouped by the city's local date
+    pub async fn fetch_daily_openweathermap(&self, lat: f64, lon: f64, api_key: &str) -> Result<Vec<DailyForecast>, String> {
+        let url = format!("{}/data/2.5/forecast?lat={}&lon={}&appid={}&units=metric", self.endpoints.openweathermap, lat, lon, api_key);
+        let data = self.get_json(&url).await.map_err(|e| e.replace(api_key, "***"))?;
+        let list = data.get("list").and_then(|v| v.as_array()).ok_or_else(|| "No forecast data".to_string())?;
+
+        let offset = data.get("city").and_then(|c| c.get("timezone")).and_then(|v| v.as_i64())
+            .and_then(|secs| FixedOffset::east_opt(secs as i32))
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+
+        let blocks: Vec<(DateTime<FixedOffset>, &Value)> = list.iter().filter_map(|item| {
+            let time = item.get("dt").and_then(|v| v.as_i64()).and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0))?;
+            Some((time.with_timezone(&offset), item))
+        }).collect();
+
+        let days = group_blocks(&blocks);
+        if days.is_empty() {
+            return Err("No forecast data".to_string());
+        }
+        Ok(days)
+    }
+}
+
+// Folds 3-hour blocks into one summary per local date; the condition is the block nearest midday
+fn group_blocks(blocks: &[(DateTime<FixedOffset>, &Value)]) -> Vec<DailyForecast> {
+    let mut days: Vec<DailyForecast> = Vec::new();
+    let mut midday_distance = Vec::new();
+
+    for (time, item) in blocks {
+        let main = item.get("main");
+        let temp = |key: &str| main.and_then(|m| m.get(key).or_else(|| m.get("temp"))).and_then(|v| v.as_f64());
+        let (Some(high), Some(low)) = (temp("temp_max"), temp("temp_min")) else { continue };
+        let pop = item.get("pop").and_then(|v| v.as_f64()).unwrap_or(0.0) * 100.0;
+        let condition = item.get("weather").and_then(|v| v.as_array()).and_then(|w| w.first())
+            .and_then(|c| c.get("main")).and_then(|v| v.as_str()).unwrap_or("Unknown");
+        let distance = (time.hour() as i32 - 12).abs();
+
+        match days.last_mut().filter(|d| d.date == time.date_naive()) {
+            Some(day) => {
+                day.high = day.high.max(high);
+                day.low = day.low.min(low);
+                day.precip_probability = day.precip_probability.max(pop);
+                let nearest = midday_distance.last_mut().unwrap();
+                if distance < *nearest {
+                    *nearest = distance;
+                    day.condition = condition.to_string();
+                }
+            }
+            None => {
+                days.push(DailyForecast { date: time.date_naive(), high, low, precip_probability: pop, condition: condition.to_string() });
+                midday_distance.push(distance);
+            }
+        }
+    }
+    days
+}
+
+// Plain-language name for a WMO weather interpretation code
+fn wmo_condition(code: i64) -> &'static str {
+    match code {
+        0 => "Clear",
+        1 | 2 => "Partly cloudy",
+        3 => "Overcast",
+        45 | 48 => "Fog",
+        51..=57 => "Drizzle",
+        61..=67 => "Rain",
+        71..=77 => "Snow",
+        80..=82 => "Rain showers",
+        85 | 86 => "Snow showers",
+        95..=99 => "Thunderstorm",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_group_blocks() {
+        let offset = FixedOffset::east_opt(13 * 3600).unwrap();
+        let at = |ts: i64| DateTime::<Utc>::from_timestamp(ts, 0).unwrap().with_timezone(&offset);
+        let items = [
+            json!({"main": {"temp_max": 14.0, "temp_min": 12.0}, "pop": 0.1, "weather": [{"main": "Clouds"}]}),
+            json!({"main": {"temp_max": 19.5, "temp_min": 17.0}, "pop": 0.6, "weather": [{"main": "Rain"}]}),
+            json!({"main": {"temp_max": 9.0, "temp_min": 8.0}, "pop": 0.0, "weather": [{"main": "Clear"}]}),
+        ];
+        // 09:00 and 12:00 on the 24th in Auckland, then 00:00 on the 25th
+        let blocks = vec![(at(1771876800), &items[0]), (at(1771887600), &items[1]), (at(1771930800), &items[2])];
+
+        let days = group_blocks(&blocks);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].date, NaiveDate::from_ymd_opt(2026, 2, 24).unwrap());
+        assert_eq!((days[0].high, days[0].low, days[0].precip_probability), (19.5, 12.0, 60.0));
+        assert_eq!(days[0].condition, "Rain");
+        assert_eq!(days[1].condition, "Clear");
+    }
+
+    #[test]
+    fn test_wmo_condition() {
+        assert_eq!(wmo_condition(0), "Clear");
+        assert_eq!(wmo_condition(81), "Rain showers");
+        assert_eq!(wmo_condition(96), "Thunderstorm");
+        assert_eq!(wmo_condition(42), "Unknown");
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use crate::config::AppConfig;
+use crate::models::WeatherState;
+use super::{summarize_hourly, HourlyPoint, WeatherService};
+
+// Environment and Climate Change Canada through MSC GeoMet's OGC API. Unlike the other providers
+// there is no point forecast: the city page features near the coordinate are searched by bounding
+// box and the closest one is used. Most values are wrapped as {"value": {"en": ..., "fr": ...}}.
+
+const SEARCH_RADIUS_DEG: f64 = 0.75;
+
+impl WeatherService {
+    pub async fn fetch_eccc(&self, lat: f64, lon: f64, location_name: String, config: &AppConfig) -> Result<WeatherState, String> {
+        let url = format!(
+            "{}/collections/citypageweather-realtime/items?f=json&lang=en&limit=50&bbox={:.4},{:.4},{:.4},{:.4}",
+            self.endpoints.eccc,
+            lon - SEARCH_RADIUS_DEG, lat - SEARCH_RADIUS_DEG, lon + SEARCH_RADIUS_DEG, lat + SEARCH_RADIUS_DEG
+        );
+        let data = self.get_json(&url).await?;
+        let features = data.get("features").and_then(|v| v.as_array()).map(|f| f.as_slice()).unwrap_or(&[]);
+
+        let city = nearest_feature(features, lat, lon)
+            .and_then(|f| f.get("properties"))
+            .ok_or_else(|| "Environment Canada only covers Canadian locations".to_string())?;
+
+        let current = city.get("currentConditions");
+        let hourly = city.get("hourlyForecastGroup").and_then(|g| g.get("hourlyForecasts"))
+            .and_then(|v| v.as_array()).map(|h| h.as_slice()).unwrap_or(&[]);
+
+        let hours: Vec<HourlyPoint> = hourly.iter().filter_map(|h| {
+            let time = h.get("timestamp").and_then(text)
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&Utc))?;
+            let condition = h.get("condition").and_then(text).unwrap_or_default().to_lowercase();
+            Some(HourlyPoint {
+                time,
+                temp: h.get("temperature").and_then(number).unwrap_or(0.0),
+                // Only one of these is published, depending on the season
+                apparent_temp: h.get("humidex").and_then(number).or_else(|| h.get("windChill").and_then(number)),
+                precip_prob: h.get("lop").and_then(number).unwrap_or(0.0),
+                precip_amount: 0.0,
+                is_snow: condition.contains("snow") || condition.contains("flurr"),
+                is_thunder: condition.contains("thunder"),
+                humidity: None,
+                dew_point: None,
+                wind_speed: h.get("wind").and_then(|w| w.get("speed")).and_then(number),
+                wind_gust: h.get("wind").and_then(|w| w.get("gust")).and_then(number),
+                pressure: None,
+                visibility: None,
+                is_fog: condition.contains("fog"),
+            })
+        }).collect();
+
+        let summary = summarize_hourly(&hours, current.and_then(|c| c.get("temperature")).and_then(number), config);
+
+        let humidity = current.and_then(|c| c.get("relativeHumidity")).and_then(number);
+        let dew_point = current.and_then(|c| c.get("dewpoint")).and_then(number).or(summary.dew_point);
+        let wind_speed = current.and_then(|c| c.get("wind")).and_then(|w| w.get("speed")).and_then(number)
+            .or(summary.wind_speed);
+        let wind_gust = current.and_then(|c| c.get("wind")).and_then(|w| w.get("gust")).and_then(number)
+            .or(summary.wind_gust);
+
+        // Reported in kPa
+        let pressure = current.and_then(|c| c.get("pressure")).and_then(number).map(|kpa| kpa * 10.0);
+        // Reported in km
+        let visibility = current.and_then(|c| c.get("visibility")).and_then(number);
+
+        let alerts = city.get("warnings").and_then(|v| v.as_array())
+            .map(|warnings| warnings.iter().filter_map(|w| w.get("description").and_then(text)).collect())
+            .unwrap_or_default();
+
+        let sun_times = crate::sun::sun_times(lat, lon, Utc::now().date_naive());
+
+        Ok(WeatherState {
+            temperature: summary.temperature,
+            apparent_temperature: summary.apparent_temperature,
+            today_high: summary.today_high,
+            today_low: summary.today_low,
+            has_precipitation: summary.has_precipitation,
+            has_thunder: summary.has_thunder,
+            precip_type: summary.precip_type,
+            location_name,
+            sun_times: sun_times.clone(),
+            is_night: self.check_is_night(&sun_times),
+            provider: "Environment Canada".to_string(),
+            last_updated: Utc::now(),
+            debug_forecast: summary.debug_forecast,
+            precip_probability: summary.precip_probability,
+            humidity,
+            dew_point,
+            wind_speed,
+            wind_gust,
+            pressure,
+            pressure_tendency: None,
+            precip_starts_at: summary.precip_starts_at,
+            alerts,
+            stale: false,
+            kp_index: None,
+            air_quality: None,
+            pollen: None,
+            nowcast: Vec::new(),
+            frost_warning: false,
+            visibility: visibility.or(summary.visibility),
+            has_fog: summary.has_fog || config.fog.is_low(visibility),
+            normal_high: None,
+            tomorrow_high: None,
+        })
+    }
+}
+
+fn nearest_feature(features: &[Value], lat: f64, lon: f64) -> Option<&Value> {
+    let distance = |f: &Value| {
+        let coords = f.get("geometry")?.get("coordinates")?.as_array()?;
+        let (f_lon, f_lat) = (coords.first()?.as_f64()?, coords.get(1)?.as_f64()?);
+        // Equirectangular is plenty for ranking cities within a degree or so
+        let dx = (f_lon - lon) * lat.to_radians().cos();
+        let dy = f_lat - lat;
+        Some(dx * dx + dy * dy)
+    };
+    features.iter()
+        .filter_map(|f| distance(f).map(|d| (f, d)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(f, _)| f)
+}
+
+// Strips the {"value": ...} and {"en": ...} wrappers GeoMet puts around most fields
+fn unwrap_value(v: &Value) -> &Value {
+    let v = v.get("value").unwrap_or(v);
+    v.get("en").unwrap_or(v)
+}
+
+fn number(v: &Value) -> Option<f64> {
+    let v = unwrap_value(v);
+    v.as_f64().or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+fn text(v: &Value) -> Option<String> {
+    unwrap_value(v).as_str().map(|s| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_bilingual_values() {
+        assert_eq!(number(&json!({"value": {"en": -3.5, "fr": -3.5}})), Some(-3.5));
+        assert_eq!(number(&json!({"value": {"en": "40"}})), Some(40.0));
+        assert_eq!(text(&json!({"en": "Light Snow", "fr": "Faible neige"})).as_deref(), Some("Light Snow"));
+
+        let features = vec![
+            json!({"geometry": {"coordinates": [-79.4, 43.7]}, "id": "toronto"}),
+            json!({"geometry": {"coordinates": [-75.7, 45.4]}, "id": "ottawa"}),
+        ];
+        let nearest = nearest_feature(&features, 45.3, -75.9).unwrap();
+        assert_eq!(nearest["id"], "ottawa");
+    }
+}
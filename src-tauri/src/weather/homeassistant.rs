@@ -0,0 +1,184 @@
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use crate::config::AppConfig;
+use crate::models::WeatherState;
+use super::{summarize_hourly, HourlyPoint, WeatherService};
+
+// A `weather.*` entity from the user's own Home Assistant, read through the REST API with a
+// long-lived access token. Values come in the units HA is configured to display, named in the
+// entity's `*_unit` attributes.
+
+// HA conditions that mean something is falling
+const WET_CONDITIONS: [&str; 6] = ["rainy", "pouring", "snowy", "snowy-rainy", "lightning-rainy", "hail"];
+
+struct Units {
+    fahrenheit: bool,
+    inches: bool,
+    wind_factor: f64,
+    pressure_factor: f64,
+    visibility_factor: f64,
+}
+
+impl Units {
+    fn from_attributes(attrs: &Value) -> Self {
+        let unit = |key: &str| attrs.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+        Self {
+            fahrenheit: unit("temperature_unit").contains('F'),
+            inches: unit("precipitation_unit") == "in",
+            // Factor to km/h
+            wind_factor: match unit("wind_speed_unit").as_str() {
+                "m/s" => 3.6,
+                "mph" => 1.609,
+                "kn" => 1.852,
+                "ft/s" => 1.097,
+                _ => 1.0,
+            },
+            // Factor to hPa
+            pressure_factor: match unit("pressure_unit").as_str() {
+                "inHg" => 33.8639,
+                "mmHg" => 1.33322,
+                "kPa" => 10.0,
+                "psi" => 68.9476,
+                "Pa" => 0.01,
+                _ => 1.0,
+            },
+            // Factor to km
+            visibility_factor: match unit("visibility_unit").as_str() {
+                "mi" => 1.609,
+                "m" => 0.001,
+                _ => 1.0,
+            },
+        }
+    }
+
+    fn celsius(&self, t: f64) -> f64 {
+        if self.fahrenheit { (t - 32.0) * 5.0 / 9.0 } else { t }
+    }
+}
+
+impl WeatherService {
+    pub async fn fetch_homeassistant(&self, lat: f64, lon: f64, location_name: String, config: &AppConfig) -> Result<WeatherState, String> {
+        let ha = &config.home_assistant;
+        let base = ha.url.trim().trim_end_matches('/');
+        let entity = ha.entity_id.trim();
+        if base.is_empty() || ha.token.is_empty() || entity.is_empty() {
+            return Err("Home Assistant needs a URL, access token and weather entity".to_string());
+        }
+
+        let state_url = format!("{}/api/states/{}", base, entity);
+        let state = self.ha_json(self.client.get(&state_url), &ha.token, &state_url).await?;
+        let attrs = state.get("attributes").ok_or_else(|| format!("{} has no attributes", entity))?;
+        let units = Units::from_attributes(attrs);
+
+        // Since HA 2023.9 forecasts only come from the get_forecasts service; older versions keep them in the attributes
+        let service_url = format!("{}/api/services/weather/get_forecasts?return_response", base);
+        let request = self.client.post(&service_url).json(&json!({ "entity_id": entity, "type": "hourly" }));
+        let forecast = match self.ha_json(request, &ha.token, &service_url).await {
+            Ok(resp) => resp.get("service_response").and_then(|r| r.get(entity)).and_then(|e| e.get("forecast")).cloned(),
+            Err(_) => None,
+        }.or_else(|| attrs.get("forecast").cloned());
+
+        let hours: Vec<HourlyPoint> = forecast.as_ref().and_then(|f| f.as_array()).map(|entries| {
+            entries.iter().filter_map(|f| parse_forecast_entry(f, &units)).collect()
+        }).unwrap_or_default();
+
+        let current_temp = attrs.get("temperature").and_then(|v| v.as_f64()).map(|t| units.celsius(t));
+        let condition = state.get("state").and_then(|v| v.as_str()).unwrap_or("");
+        let wet_now = WET_CONDITIONS.contains(&condition);
+
+        let mut summary = summarize_hourly(&hours, current_temp, config);
+        // The entity's own condition is the closest thing to an observation
+        if wet_now && config.precip_horizon != "none" {
+            summary.has_precipitation = true;
+            summary.precip_probability = 100.0;
+            summary.precip_type = Some(if condition.starts_with("snowy") { "Snow" } else { "Rain" }.to_string());
+        }
+        if condition.starts_with("lightning") && config.precip_horizon != "none" {
+            summary.has_thunder = true;
+        }
+
+        let humidity = attrs.get("humidity").and_then(|v| v.as_f64()).or(summary.humidity);
+        let dew_point = attrs.get("dew_point").and_then(|v| v.as_f64()).map(|t| units.celsius(t)).or(summary.dew_point);
+        let wind_speed = attrs.get("wind_speed").and_then(|v| v.as_f64()).map(|w| w * units.wind_factor).or(summary.wind_speed);
+        let wind_gust = attrs.get("wind_gust_speed").and_then(|v| v.as_f64()).map(|w| w * units.wind_factor).or(summary.wind_gust);
+        let pressure = attrs.get("pressure").and_then(|v| v.as_f64()).map(|p| p * units.pressure_factor).or(summary.pressure);
+        let visibility = attrs.get("visibility").and_then(|v| v.as_f64()).map(|v| v * units.visibility_factor);
+        let has_fog = summary.has_fog || condition == "fog" || config.fog.is_low(visibility);
+        let sun_times = crate::sun::sun_times(lat, lon, Utc::now().date_naive());
+
+        Ok(WeatherState {
+            temperature: summary.temperature,
+            apparent_temperature: summary.apparent_temperature,
+            today_high: summary.today_high,
+            today_low: summary.today_low,
+            has_precipitation: summary.has_precipitation,
+            has_thunder: summary.has_thunder,
+            precip_type: summary.precip_type,
+            location_name,
+            sun_times: sun_times.clone(),
+            is_night: self.check_is_night(&sun_times),
+            provider: "Home Assistant".to_string(),
+            last_updated: Utc::now(),
+            debug_forecast: summary.debug_forecast,
+            precip_probability: summary.precip_probability,
+            humidity,
+            dew_point,
+            wind_speed,
+            wind_gust,
+            pressure,
+            pressure_tendency: None,
+            precip_starts_at: summary.precip_starts_at,
+            alerts: Vec::new(),
+            stale: false,
+            kp_index: None,
+            air_quality: None,
+            pollen: None,
+            nowcast: Vec::new(),
+            frost_warning: false,
+            visibility,
+            has_fog,
+            normal_high: None,
+            tomorrow_high: None,
+        })
+    }
+
+    async fn ha_json(&self, request: reqwest::RequestBuilder, token: &str, url: &str) -> Result<Value, String> {
+        let resp = request.bearer_auth(token).send().await.map_err(|e| e.to_string())?;
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+            return Err("Home Assistant rejected the access token".to_string());
+        }
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {} from {}", resp.status(), url));
+        }
+        resp.json().await.map_err(|e| e.to_string())
+    }
+}
+
+fn parse_forecast_entry(f: &Value, units: &Units) -> Option<HourlyPoint> {
+    let time = f.get("datetime").and_then(|v| v.as_str())
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| dt.with_timezone(&Utc))?;
+    let condition = f.get("condition").and_then(|v| v.as_str()).unwrap_or("");
+    let amount = f.get("precipitation").and_then(|v| v.as_f64())
+        .map(|p| if units.inches { p * 25.4 } else { p })
+        .unwrap_or(0.0);
+
+    Some(HourlyPoint {
+        time,
+        temp: units.celsius(f.get("temperature").and_then(|v| v.as_f64()).unwrap_or(0.0)),
+        apparent_temp: f.get("apparent_temperature").and_then(|v| v.as_f64()).map(|t| units.celsius(t)),
+        // Integrations without a probability (met.no) still name a wet condition
+        precip_prob: f.get("precipitation_probability").and_then(|v| v.as_f64())
+            .unwrap_or(if WET_CONDITIONS.contains(&condition) { 100.0 } else { 0.0 }),
+        precip_amount: amount,
+        is_snow: condition.starts_with("snowy"),
+        is_thunder: condition.starts_with("lightning"),
+        humidity: f.get("humidity").and_then(|v| v.as_f64()),
+        dew_point: f.get("dew_point").and_then(|v| v.as_f64()).map(|t| units.celsius(t)),
+        wind_speed: f.get("wind_speed").and_then(|v| v.as_f64()).map(|w| w * units.wind_factor),
+        wind_gust: f.get("wind_gust_speed").and_then(|v| v.as_f64()).map(|w| w * units.wind_factor),
+        pressure: f.get("pressure").and_then(|v| v.as_f64()).map(|p| p * units.pressure_factor),
+        visibility: None,
+        is_fog: condition == "fog",
+    })
+}
@@ -0,0 +1,310 @@
+use chrono::Utc;
+use serde_json::Value;
+use crate::config::AppConfig;
+use crate::models::WeatherState;
+use super::WeatherService;
+
+// Station observations from aviationweather.gov. The bbox query returns every reporting station
+// nearby with its coordinates, so picking the nearest one costs a single request. Only the raw
+// report is trusted; the decoded JSON fields vary between stations.
+
+const SEARCH_RADIUS_DEG: f64 = 1.0;
+
+const PRECIPITATION: &[&str] = &["DZ", "RA", "SN", "SG", "IC", "PL", "GR", "GS", "UP"];
+const DESCRIPTORS: &[&str] = &["MI", "PR", "BC", "DR", "BL", "SH", "TS", "FZ"];
+const PHENOMENA: &[&str] = &[
+    "DZ", "RA", "SN", "SG", "IC", "PL", "GR", "GS", "UP",
+    "BR", "FG", "FU", "VA", "DU", "SA", "HZ", "PY",
+    "PO", "SQ", "FC", "SS", "DS",
+];
+
+#[derive(Debug, Default, PartialEq)]
+pub struct MetarReport {
+    pub station: String,
+    pub temperature: Option<f64>,
+    pub dew_point: Option<f64>,
+    /// km/h
+    pub wind_speed: Option<f64>,
+    /// km/h, from the "G" part of the wind group
+    pub wind_gust: Option<f64>,
+    /// hPa, from the Q (hPa) or A (inHg) altimeter group
+    pub pressure: Option<f64>,
+    /// km, from a metre ("0800") or statute-mile ("1/2SM") visibility group
+    pub visibility: Option<f64>,
+    /// Present-weather groups at the station, e.g. "-RA", "TSRA", "BR"
+    pub weather: Vec<String>,
+}
+
+impl MetarReport {
+    pub fn has_precipitation(&self) -> bool {
+        self.weather.iter().any(|w| PRECIPITATION.iter().any(|p| w.contains(p)))
+    }
+
+    pub fn is_snow(&self) -> bool {
+        self.weather.iter().any(|w| w.contains("SN") || w.contains("SG"))
+    }
+
+    pub fn has_thunder(&self) -> bool {
+        self.weather.iter().any(|w| w.contains("TS"))
+    }
+
+    /// Fog at the station, including shallow (MIFG) and patchy (BCFG) fog but not mist (BR)
+    pub fn has_fog(&self) -> bool {
+        self.weather.iter().any(|w| w.contains("FG"))
+    }
+
+    /// Relative humidity from temperature and dew point (Magnus formula)
+    pub fn humidity(&self) -> Option<f64> {
+        let (t, td) = (self.temperature?, self.dew_point?);
+        let magnus = |x: f64| (17.625 * x / (243.04 + x)).exp();
+        Some((100.0 * magnus(td) / magnus(t)).clamp(0.0, 100.0))
+    }
+}
+
+impl WeatherService {
+    pub async fn fetch_metar(&self, lat: f64, lon: f64, location_name: String, config: &AppConfig) -> Result<WeatherState, String> {
+        let url = format!(
+            "{}/api/data/metar?format=json&bbox={:.3},{:.3},{:.3},{:.3}",
+            self.endpoints.aviation_weather,
+            lat - SEARCH_RADIUS_DEG, lon - SEARCH_RADIUS_DEG, lat + SEARCH_RADIUS_DEG, lon + SEARCH_RADIUS_DEG
+        );
+        let data = self.get_json(&url).await?;
+        let stations = data.as_array().map(|a| a.as_slice()).unwrap_or(&[]);
+
+        let raw = nearest_station(stations, lat, lon)
+            .and_then(|s| s.get("rawOb")).and_then(|v| v.as_str())
+            .ok_or_else(|| "No METAR station within ~100 km".to_string())?;
+        let report = parse_metar(raw);
+        let temperature = report.temperature.ok_or_else(|| format!("METAR without temperature: {}", raw))?;
+
+        let short_location = location_name.split(',').next().unwrap_or(&location_name).to_string();
+        let sun_times = crate::sun::sun_times(lat, lon, Utc::now().date_naive());
+
+        Ok(WeatherState {
+            temperature,
+            has_precipitation: report.has_precipitation(),
+            has_thunder: report.has_thunder(),
+            precip_type: report.has_precipitation().then(|| if report.is_snow() { "Snow" } else { "Rain" }.to_string()),
+            location_name: format!("{} ({})", short_location, report.station),
+            sun_times: sun_times.clone(),
+            is_night: self.check_is_night(&sun_times),
+            provider: "METAR".to_string(),
+            last_updated: Utc::now(),
+            precip_probability: if report.has_precipitation() { 100.0 } else { 0.0 },
+            humidity: report.humidity(),
+            dew_point: report.dew_point,
+            wind_speed: report.wind_speed,
+            wind_gust: report.wind_gust,
+            pressure: report.pressure,
+            visibility: report.visibility,
+            has_fog: report.has_fog() || config.fog.is_low(report.visibility),
+            ..Default::default()
+        })
+    }
+}
+
+fn nearest_station(stations: &[Value], lat: f64, lon: f64) -> Option<&Value> {
+    let distance = |s: &Value| {
+        let (s_lat, s_lon) = (s.get("lat")?.as_f64()?, s.get("lon")?.as_f64()?);
+        let dx = (s_lon - lon) * lat.to_radians().cos();
+        let dy = s_lat - lat;
+        Some(dx * dx + dy * dy)
+    };
+    stations.iter()
+        .filter(|s| s.get("rawOb").is_some())
+        .filter_map(|s| distance(s).map(|d| (s, d)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(s, _)| s)
+}
+
+fn parse_temp(s: &str) -> Option<f64> {
+    let (negative, digits) = match s.strip_prefix('M') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    if digits.len() != 2 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let value: f64 = digits.parse().ok()?;
+    Some(if negative { -value } else { value })
+}
+
+// "RMK ... T01720083" carries temperature and dew point in tenths
+fn parse_precise_temps(group: &str) -> Option<(f64, f64)> {
+    let digits = group.strip_prefix('T')?;
+    if digits.len() != 8 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let tenths = |sign: &str, value: &str| -> Option<f64> {
+        let v = value.parse::<f64>().ok()? / 10.0;
+        Some(if sign == "1" { -v } else { v })
+    };
+    Some((tenths(&digits[0..1], &digits[1..4])?, tenths(&digits[4..5], &digits[5..8])?))
+}
+
+// (speed, gust) in km/h from a group like "31015G25KT"
+fn parse_wind(group: &str) -> Option<(f64, Option<f64>)> {
+    let (body, to_kmh) = if let Some(b) = group.strip_suffix("KT") {
+        (b, 1.852)
+    } else if let Some(b) = group.strip_suffix("MPS") {
+        (b, 3.6)
+    } else {
+        return None;
+    };
+    let mut parts = body.get(3..)?.split('G');
+    let speed = parts.next()?;
+    if body.len() < 5 || !speed.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let gust = parts.next().and_then(|g| g.parse::<f64>().ok()).map(|g| g * to_kmh);
+    Some((speed.parse::<f64>().ok()? * to_kmh, gust))
+}
+
+// "Q1012" is hPa, "A3012" inches of mercury times 100
+fn parse_altimeter(group: &str) -> Option<f64> {
+    let (kind, digits) = group.split_at_checked(1)?;
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let value: f64 = digits.parse().ok()?;
+    match kind {
+        "Q" => Some(value),
+        "A" => Some(value / 100.0 * 33.8639),
+        _ => None,
+    }
+}
+
+// "9999" (and CAVOK) is 10 km or more in metres; "10SM", "1/2SM" and "M1/4SM" (less than) are statute miles
+fn parse_visibility(group: &str) -> Option<f64> {
+    if group == "CAVOK" {
+        return Some(10.0);
+    }
+    if let Some(miles) = group.strip_suffix("SM") {
+        let miles = miles.trim_start_matches(['M', 'P']);
+        let value = match miles.split_once('/') {
+            Some((num, den)) => num.parse::<f64>().ok()? / den.parse::<f64>().ok().filter(|d| *d > 0.0)?,
+            None => miles.parse::<f64>().ok()?,
+        };
+        return Some(value * 1.609);
+    }
+    if group.len() == 4 && group.bytes().all(|b| b.is_ascii_digit()) {
+        return Some(group.parse::<f64>().ok()? / 1000.0);
+    }
+    None
+}
+
+fn is_weather_group(group: &str) -> bool {
+    let mut rest = group.trim_start_matches(['-', '+']);
+    rest = rest.strip_prefix("VC").unwrap_or(rest);
+    let mut matched_any = false;
+    while !rest.is_empty() {
+        let code = rest.get(..2).unwrap_or("");
+        if DESCRIPTORS.contains(&code) || PHENOMENA.contains(&code) {
+            matched_any = true;
+            rest = &rest[2..];
+        } else {
+            return false;
+        }
+    }
+    matched_any
+}
+
+pub fn parse_metar(raw: &str) -> MetarReport {
+    let mut report = MetarReport::default();
+    let mut groups = raw.split_whitespace().peekable();
+
+    if groups.peek() == Some(&"METAR") || groups.peek() == Some(&"SPECI") {
+        groups.next();
+    }
+    report.station = groups.next().unwrap_or_default().to_string();
+
+    let mut in_remarks = false;
+    for group in groups {
+        if in_remarks {
+            if let Some((t, td)) = parse_precise_temps(group) {
+                report.temperature = Some(t);
+                report.dew_point = Some(td);
+            }
+            continue;
+        }
+        match group {
+            "RMK" => in_remarks = true,
+            // Trend forecasts describe later conditions, not the observation
+            "TEMPO" | "BECMG" | "NOSIG" => break,
+            _ => {
+                if report.wind_speed.is_none() {
+                    if let Some((speed, gust)) = parse_wind(group) {
+                        report.wind_speed = Some(speed);
+                        report.wind_gust = gust;
+                        continue;
+                    }
+                }
+                if let Some((t, td)) = group.split_once('/') {
+                    if let Some(temp) = parse_temp(t) {
+                        report.temperature = Some(temp);
+                        report.dew_point = parse_temp(td);
+                        continue;
+                    }
+                }
+                // Visibility directly follows the wind group
+                if report.wind_speed.is_some() && report.visibility.is_none() && report.temperature.is_none() {
+                    if let Some(v) = parse_visibility(group) {
+                        report.visibility = Some(v);
+                        continue;
+                    }
+                }
+                if report.pressure.is_none() {
+                    if let Some(p) = parse_altimeter(group) {
+                        report.pressure = Some(p);
+                        continue;
+                    }
+                }
+                // Vicinity groups (VCSH) aren't at the station
+                if is_weather_group(group) && !group.starts_with("VC") {
+                    report.weather.push(group.to_string());
+                }
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metar() {
+        let r = parse_metar("METAR KJFK 231651Z 31015G25KT 10SM -RA BR BKN050 05/M03 A3012 RMK AO2 SLP201 T00501028");
+        assert_eq!(r.station, "KJFK");
+        assert_eq!(r.temperature, Some(5.0));
+        assert_eq!(r.dew_point, Some(-2.8));
+        assert!((r.wind_speed.unwrap() - 27.78).abs() < 0.01);
+        assert!((r.wind_gust.unwrap() - 46.3).abs() < 0.01);
+        assert!((r.pressure.unwrap() - 1019.9).abs() < 0.1);
+        assert_eq!(r.weather, vec!["-RA", "BR"]);
+        assert!(r.has_precipitation());
+        assert!(!r.is_snow());
+        assert!((r.visibility.unwrap() - 16.09).abs() < 0.01);
+        // Mist is not fog
+        assert!(!r.has_fog());
+
+        let r = parse_metar("NZAA 230000Z 24008KT 9999 VCSH TS FEW020CB 18/12 Q1012 TEMPO 3000 +TSRA");
+        assert_eq!(r.temperature, Some(18.0));
+        assert_eq!(r.pressure, Some(1012.0));
+        assert_eq!(r.weather, vec!["TS"]);
+        assert!(r.has_thunder());
+        assert!(!r.has_precipitation());
+
+        let r = parse_metar("ENGM 230050Z 01005KT 3000 -SN M04/M06 Q1020");
+        assert_eq!(r.temperature, Some(-4.0));
+        assert!(r.is_snow());
+        assert!((r.humidity().unwrap() - 86.0).abs() < 1.0);
+        assert_eq!(r.visibility, Some(3.0));
+
+        let r = parse_metar("EGLL 230620Z 00000KT 0150 R27L/0300 FG VV001 03/03 Q1025");
+        assert_eq!(r.visibility, Some(0.15));
+        assert!(r.has_fog());
+        assert_eq!(r.weather, vec!["FG"]);
+    }
+}
@@ -0,0 +1,229 @@
+use chrono::{DateTime, Utc};
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use crate::config::{AppConfig, MqttConfig};
+use crate::models::WeatherState;
+use super::custom::{as_number, lookup, precipitation, to_celsius};
+use super::WeatherService;
+
+// Readings pushed to an MQTT broker (Home Assistant, rtl_433, ...). Unlike the HTTP providers the
+// data arrives on its own schedule: a long-lived subscription keeps the latest value per topic and
+// pokes `mqtt_updates` so the pipeline can refresh, while `fetch` just reads what has arrived so far.
+
+#[derive(Debug, Default, Clone)]
+struct Reading {
+    value: Option<f64>,
+    received_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct Readings {
+    temperature: Reading,
+    // Kept raw: whether it counts as wet depends on the thresholds at fetch time
+    precipitation: Option<(Value, DateTime<Utc>)>,
+    humidity: Reading,
+}
+
+/// A running subscription, restarted whenever the MQTT settings change
+pub struct MqttFeed {
+    config: MqttConfig,
+    readings: Arc<Mutex<Readings>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for MqttFeed {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl MqttFeed {
+    fn start(config: &MqttConfig, updates: Arc<Notify>) -> Self {
+        let readings = Arc::new(Mutex::new(Readings::default()));
+
+        let client_id = format!("weatherlight-{}", std::process::id());
+        let mut options = MqttOptions::new(client_id, config.host.trim(), config.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if !config.username.is_empty() {
+            options.set_credentials(config.username.clone(), config.password.clone());
+        }
+        let (client, mut eventloop) = AsyncClient::new(options, 10);
+
+        let task_config = config.clone();
+        let task_readings = readings.clone();
+        let task = tokio::spawn(async move {
+            let topics: Vec<&str> = [&task_config.temperature_topic, &task_config.precipitation_topic, &task_config.humidity_topic]
+                .into_iter()
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .collect();
+
+            loop {
+                match eventloop.poll().await {
+                    // Sessions are clean, so subscribe again after every (re)connect
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        for topic in &topics {
+                            let _ = client.subscribe(*topic, QoS::AtMostOnce).await;
+                        }
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        let payload = String::from_utf8_lossy(&publish.payload);
+                        let changed = {
+                            let mut r = task_readings.lock().unwrap();
+                            apply_message(&mut r, &task_config, &publish.topic, &payload, Utc::now())
+                        };
+                        if changed {
+                            updates.notify_one();
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        // The next poll reconnects
+                        println!("MQTT connection to {} failed: {}", task_config.host, e);
+                        tokio::time::sleep(Duration::from_secs(10)).await;
+                    }
+                }
+            }
+        });
+
+        Self { config: config.clone(), readings, task }
+    }
+}
+
+impl WeatherService {
+    /// Signalled whenever a subscribed MQTT topic delivers a new reading
+    pub fn mqtt_updates(&self) -> Arc<Notify> {
+        self.mqtt_updates.clone()
+    }
+
+    /// Ends the MQTT subscription, if one is running
+    pub fn stop_mqtt(&self) {
+        self.mqtt.lock().unwrap().take();
+    }
+
+    pub(super) fn clear_mqtt_poison(&self) {
+        self.mqtt.clear_poison();
+        if let Some(feed) = self.mqtt.lock().unwrap().as_ref() {
+            feed.readings.clear_poison();
+        }
+    }
+
+    pub async fn fetch_mqtt(&self, lat: f64, lon: f64, location_name: String, config: &AppConfig) -> Result<WeatherState, String> {
+        let mqtt = &config.mqtt;
+        if mqtt.host.trim().is_empty() || mqtt.temperature_topic.trim().is_empty() {
+            return Err("MQTT needs a broker address and a temperature topic".to_string());
+        }
+
+        let (temperature, precip, humidity) = {
+            let mut feed = self.mqtt.lock().unwrap();
+            if feed.as_ref().is_none_or(|f| f.config != *mqtt) {
+                *feed = Some(MqttFeed::start(mqtt, self.mqtt_updates.clone()));
+            }
+            let feed = feed.as_ref().unwrap();
+            let r = feed.readings.lock().unwrap();
+            (r.temperature.clone(), r.precipitation.clone(), r.humidity.clone())
+        };
+
+        let now = Utc::now();
+        let max_age = chrono::Duration::minutes(mqtt.max_age_minutes.max(1) as i64);
+        let fresh = |at: Option<DateTime<Utc>>| at.is_some_and(|t| now - t <= max_age);
+
+        let temp = temperature.value.filter(|_| fresh(temperature.received_at))
+            .ok_or_else(|| format!("No recent reading on {}", mqtt.temperature_topic.trim()))?;
+        let (has_precipitation, precip_probability) = precip
+            .filter(|(_, at)| fresh(Some(*at)))
+            .map(|(v, _)| precipitation(&v, &mqtt.precipitation_unit, config))
+            .unwrap_or((false, 0.0));
+
+        let humidity = humidity.value.filter(|_| fresh(humidity.received_at));
+        let sun_times = crate::sun::sun_times(lat, lon, now.date_naive());
+
+        Ok(WeatherState {
+            temperature: temp,
+            has_precipitation,
+            location_name,
+            sun_times: sun_times.clone(),
+            is_night: self.check_is_night(&sun_times),
+            provider: "MQTT".to_string(),
+            last_updated: temperature.received_at.unwrap_or(now),
+            precip_probability,
+            humidity,
+            dew_point: humidity.map(|rh| super::calculate_dew_point(temp, rh)),
+            ..Default::default()
+        })
+    }
+}
+
+// Plain payloads are taken as-is; "on"/"off" style states count as booleans for "bool" precipitation
+fn payload_value(payload: &str, path: &str) -> Option<Value> {
+    let text = payload.trim();
+    if !path.trim().is_empty() {
+        let json: Value = serde_json::from_str(text).ok()?;
+        return lookup(&json, path).cloned();
+    }
+    match text.to_lowercase().as_str() {
+        "on" | "yes" | "true" => Some(Value::Bool(true)),
+        "off" | "no" | "false" => Some(Value::Bool(false)),
+        _ => Some(serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))),
+    }
+}
+
+// Stores a message if it's on one of the configured topics; returns whether anything was recorded
+fn apply_message(readings: &mut Readings, config: &MqttConfig, topic: &str, payload: &str, at: DateTime<Utc>) -> bool {
+    let mut changed = false;
+
+    if topic == config.temperature_topic.trim() {
+        if let Some(t) = payload_value(payload, &config.temperature_path).as_ref().and_then(as_number) {
+            readings.temperature = Reading { value: Some(to_celsius(t, &config.temperature_unit)), received_at: Some(at) };
+            changed = true;
+        }
+    }
+    if topic == config.precipitation_topic.trim() {
+        if let Some(v) = payload_value(payload, &config.precipitation_path) {
+            readings.precipitation = Some((v, at));
+            changed = true;
+        }
+    }
+    if topic == config.humidity_topic.trim() {
+        if let Some(h) = payload_value(payload, &config.humidity_path).as_ref().and_then(as_number) {
+            readings.humidity = Reading { value: Some(h), received_at: Some(at) };
+            changed = true;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_messages() {
+        let config = MqttConfig {
+            temperature_topic: "rtl_433/events".into(),
+            temperature_path: "temperature_F".into(),
+            temperature_unit: "F".into(),
+            precipitation_topic: "homeassistant/binary_sensor/rain/state".into(),
+            humidity_topic: "rtl_433/events".into(),
+            humidity_path: "humidity".into(),
+            ..Default::default()
+        };
+        let mut readings = Readings::default();
+        let now = Utc::now();
+
+        assert!(apply_message(&mut readings, &config, "rtl_433/events", r#"{"temperature_F": 68.0, "humidity": 55}"#, now));
+        assert!((readings.temperature.value.unwrap() - 20.0).abs() < 1e-9);
+        assert_eq!(readings.humidity.value, Some(55.0));
+
+        assert!(apply_message(&mut readings, &config, "homeassistant/binary_sensor/rain/state", "on", now));
+        assert_eq!(readings.precipitation, Some((Value::Bool(true), now)));
+
+        assert!(!apply_message(&mut readings, &config, "other/topic", "12", now));
+        // JSON without the configured field leaves the previous reading alone
+        assert!(!apply_message(&mut readings, &config, "rtl_433/events", r#"{"battery_ok": 1}"#, now));
+        assert!((readings.temperature.value.unwrap() - 20.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,86 @@
+use chrono::{Datelike, NaiveDate};
+use super::WeatherService;
+
+// Climatological normal for `light_basis: "anomaly"`: the average daily high around the same
+// calendar date over the past `NORMAL_YEARS` years, from Open-Meteo's ERA5 historical archive (no
+// key). The archive lags a few days behind, so the window ends with last year. The result only
+// changes with the date and place, so it's fetched once per day and location.
+
+const NORMAL_YEARS: i32 = 10;
+// Days either side of the date that count, to smooth out single odd days
+const NORMAL_WINDOW_DAYS: i64 = 3;
+
+impl WeatherService {
+    /// Average daily high (°C) around `date` at the location over the past ten years
+    pub async fn fetch_normal_high(&self, lat: f64, lon: f64, date: NaiveDate) -> Result<f64, String> {
+        let key = format!("{:.2},{:.2},{}", lat, lon, date);
+        if let Some((cached_key, normal)) = self.normals.lock().unwrap().as_ref() {
+            if *cached_key == key {
+                return Ok(*normal);
+            }
+        }
+
+        let url = format!(
+            "{}/v1/archive?latitude={}&longitude={}&start_date={}-01-01&end_date={}-12-31&daily=temperature_2m_max&timezone=auto",
+            self.endpoints.open_meteo_archive, lat, lon, date.year() - NORMAL_YEARS, date.year() - 1
+        );
+        let data = self.get_json(&url).await?;
+        let daily = data.get("daily").ok_or_else(|| "No archive data".to_string())?;
+        let series = |key: &str| daily.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+        let (times, highs) = (series("time"), series("temperature_2m_max"));
+
+        let days: Vec<(NaiveDate, f64)> = times.iter().zip(highs.iter()).filter_map(|(t, v)| {
+            Some((NaiveDate::parse_from_str(t.as_str()?, "%Y-%m-%d").ok()?, v.as_f64()?))
+        }).collect();
+
+        let normal = normal_for(&days, date).ok_or_else(|| "No archive data around this date".to_string())?;
+        *self.normals.lock().unwrap() = Some((key, normal));
+        Ok(normal)
+    }
+}
+
+// Mean of the values within the window around `date`'s month and day in any year
+fn normal_for(days: &[(NaiveDate, f64)], date: NaiveDate) -> Option<f64> {
+    let near: Vec<f64> = days.iter()
+        .filter(|(day, _)| {
+            // Compare against the anniversary in the neighbouring years too, so late December counts for early January
+            (day.year() - 1..=day.year() + 1).any(|year| {
+                anniversary(date, year).is_some_and(|a| (*day - a).num_days().abs() <= NORMAL_WINDOW_DAYS)
+            })
+        })
+        .map(|(_, v)| *v)
+        .collect();
+    if near.is_empty() {
+        return None;
+    }
+    Some(near.iter().sum::<f64>() / near.len() as f64)
+}
+
+// `date`'s month and day in `year`; 29 February becomes the 28th in common years
+fn anniversary(date: NaiveDate, year: i32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, date.month(), date.day())
+        .or_else(|| NaiveDate::from_ymd_opt(year, date.month(), date.day() - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_for() {
+        let d = |y: i32, m: u32, day: u32| NaiveDate::from_ymd_opt(y, m, day).unwrap();
+        let days = vec![
+            (d(2024, 7, 10), 14.0),
+            (d(2024, 7, 13), 16.0),
+            (d(2024, 7, 17), 30.0), // outside the window
+            (d(2025, 7, 9), 12.0),
+            (d(2024, 12, 30), 25.0),
+            (d(2025, 1, 2), 27.0),
+        ];
+        assert_eq!(normal_for(&days, d(2026, 7, 12)), Some(14.0));
+        // The window wraps the new year
+        assert_eq!(normal_for(&days, d(2026, 1, 1)), Some(26.0));
+        assert_eq!(normal_for(&days, d(2026, 4, 1)), None);
+        assert_eq!(anniversary(d(2024, 2, 29), 2025), Some(d(2025, 2, 28)));
+    }
+}
@@ -0,0 +1,415 @@
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use crate::config::AppConfig;
+use crate::models::{DailyForecast, LocationDetectResult, WeatherState};
+use super::WeatherService;
+
+// Every built-in backend implements `WeatherProvider` and is listed once in `ProviderRegistry::builtin`.
+// The fetch logic itself stays on `WeatherService`, which owns the HTTP client, endpoints and caches;
+// a provider only decides how to geocode, what it needs from the config, and which fetch to run.
+
+pub type ProviderFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send + 'a>>;
+
+/// What a provider can supply beyond current temperature and precipitation
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    /// Hourly forecast, so the temperature and precipitation horizons apply
+    pub forecast: bool,
+    /// Official warnings in `WeatherState::alerts`
+    pub alerts: bool,
+    /// Has its own geocoder instead of Open-Meteo's
+    pub geocoding: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderInfo {
+    /// Value of `provider` in the config
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Text for the settings provider list
+    pub label: &'static str,
+    pub requires_api_key: bool,
+    /// Shortest refresh interval that stays inside the free tier's quota
+    pub min_refresh_minutes: u32,
+    pub capabilities: Capabilities,
+}
+
+pub trait WeatherProvider: Send + Sync {
+    fn info(&self) -> ProviderInfo;
+
+    /// Whether `config` can't fetch anything without `api_key`
+    fn requires_api_key(&self, _config: &AppConfig) -> bool {
+        self.info().requires_api_key
+    }
+
+    fn geocode<'a>(&'a self, svc: &'a WeatherService, location: &'a str, _config: &'a AppConfig) -> ProviderFuture<'a, Option<LocationDetectResult>> {
+        Box::pin(svc.geocode_openmeteo(location))
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState>;
+
+    /// Several days of daily summaries for the settings forecast panel
+    fn fetch_daily<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, _config: &'a AppConfig) -> ProviderFuture<'a, Vec<DailyForecast>> {
+        Box::pin(svc.fetch_daily_openmeteo(lat, lon))
+    }
+}
+
+pub struct ProviderRegistry {
+    providers: Vec<Box<dyn WeatherProvider>>,
+}
+
+impl ProviderRegistry {
+    /// The built-in providers, in settings list order
+    pub fn builtin() -> &'static ProviderRegistry {
+        static BUILTIN: OnceLock<ProviderRegistry> = OnceLock::new();
+        BUILTIN.get_or_init(|| ProviderRegistry {
+            providers: vec![
+                Box::new(OpenMeteo),
+                Box::new(OpenWeatherMap),
+                Box::new(Nws),
+                Box::new(MetNo),
+                Box::new(PirateWeather),
+                Box::new(TomorrowIo),
+                Box::new(AccuWeather),
+                Box::new(Eccc),
+                Box::new(Metar),
+                Box::new(Pws),
+                Box::new(HomeAssistant),
+                Box::new(Mqtt),
+                Box::new(Custom),
+            ],
+        })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&dyn WeatherProvider> {
+        self.providers.iter().find(|p| p.info().id == id).map(|p| p.as_ref())
+    }
+
+    /// The provider for `id`, or Open-Meteo (the default) for anything unknown
+    pub fn resolve(&self, id: &str) -> &dyn WeatherProvider {
+        self.get(id).unwrap_or_else(|| self.providers[0].as_ref())
+    }
+
+    pub fn list(&self) -> Vec<ProviderInfo> {
+        self.providers.iter().map(|p| p.info()).collect()
+    }
+}
+
+fn api_key<'a>(config: &'a AppConfig, name: &str) -> Result<&'a str, String> {
+    if config.api_key.is_empty() {
+        Err(format!("{} requires an API key", name))
+    } else {
+        Ok(&config.api_key)
+    }
+}
+
+const FORECAST: Capabilities = Capabilities { forecast: true, alerts: false, geocoding: false };
+const CURRENT_ONLY: Capabilities = Capabilities { forecast: false, alerts: false, geocoding: false };
+
+struct OpenMeteo;
+
+impl WeatherProvider for OpenMeteo {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "open-meteo",
+            name: "Open-Meteo",
+            label: "Open-Meteo",
+            requires_api_key: false,
+            min_refresh_minutes: 5,
+            capabilities: FORECAST,
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(svc.fetch_openmeteo(lat, lon, location_name, config))
+    }
+}
+
+struct OpenWeatherMap;
+
+impl WeatherProvider for OpenWeatherMap {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "openweathermap",
+            name: "OpenWeatherMap",
+            label: "OpenWeatherMap (Free API Key Required)",
+            requires_api_key: true,
+            min_refresh_minutes: 5,
+            capabilities: Capabilities { geocoding: true, ..FORECAST },
+        }
+    }
+
+    fn geocode<'a>(&'a self, svc: &'a WeatherService, location: &'a str, config: &'a AppConfig) -> ProviderFuture<'a, Option<LocationDetectResult>> {
+        if config.api_key.is_empty() {
+            Box::pin(svc.geocode_openmeteo(location))
+        } else {
+            Box::pin(svc.geocode_openweathermap(location, &config.api_key))
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(async move {
+            let key = api_key(config, "OpenWeatherMap")?;
+            svc.fetch_openweathermap(lat, lon, location_name, key, config).await
+        })
+    }
+
+    fn fetch_daily<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, config: &'a AppConfig) -> ProviderFuture<'a, Vec<DailyForecast>> {
+        if config.api_key.is_empty() {
+            Box::pin(svc.fetch_daily_openmeteo(lat, lon))
+        } else {
+            Box::pin(svc.fetch_daily_openweathermap(lat, lon, &config.api_key))
+        }
+    }
+}
+
+struct Nws;
+
+impl WeatherProvider for Nws {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "nws",
+            name: "NWS",
+            label: "National Weather Service (US only)",
+            requires_api_key: false,
+            min_refresh_minutes: 5,
+            capabilities: FORECAST,
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(svc.fetch_nws(lat, lon, location_name, config))
+    }
+}
+
+struct MetNo;
+
+impl WeatherProvider for MetNo {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "metno",
+            name: "MET Norway",
+            label: "MET Norway (Yr)",
+            requires_api_key: false,
+            min_refresh_minutes: 5,
+            capabilities: FORECAST,
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(svc.fetch_metno(lat, lon, location_name, config))
+    }
+}
+
+struct PirateWeather;
+
+impl WeatherProvider for PirateWeather {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "pirateweather",
+            name: "Pirate Weather",
+            label: "Pirate Weather (Free API Key Required)",
+            requires_api_key: true,
+            min_refresh_minutes: 5,
+            capabilities: FORECAST,
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(async move {
+            let key = api_key(config, "Pirate Weather")?;
+            svc.fetch_pirateweather(lat, lon, location_name, key, config).await
+        })
+    }
+}
+
+struct TomorrowIo;
+
+impl WeatherProvider for TomorrowIo {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "tomorrowio",
+            name: "Tomorrow.io",
+            label: "Tomorrow.io (Free API Key Required)",
+            requires_api_key: true,
+            min_refresh_minutes: 5,
+            capabilities: FORECAST,
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(async move {
+            let key = api_key(config, "Tomorrow.io")?;
+            svc.fetch_tomorrowio(lat, lon, location_name, key, config).await
+        })
+    }
+}
+
+struct AccuWeather;
+
+impl WeatherProvider for AccuWeather {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "accuweather",
+            name: "AccuWeather",
+            label: "AccuWeather (Free API Key Required)",
+            requires_api_key: true,
+            min_refresh_minutes: 30,
+            capabilities: FORECAST,
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(async move {
+            let key = api_key(config, "AccuWeather")?;
+            svc.fetch_accuweather(lat, lon, location_name, key, config).await
+        })
+    }
+}
+
+struct Eccc;
+
+impl WeatherProvider for Eccc {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "eccc",
+            name: "Environment Canada",
+            label: "Environment Canada (Canada only)",
+            requires_api_key: false,
+            min_refresh_minutes: 5,
+            capabilities: Capabilities { alerts: true, ..FORECAST },
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(svc.fetch_eccc(lat, lon, location_name, config))
+    }
+}
+
+struct Metar;
+
+impl WeatherProvider for Metar {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "metar",
+            name: "METAR",
+            label: "METAR (Nearest Airport)",
+            requires_api_key: false,
+            min_refresh_minutes: 5,
+            capabilities: CURRENT_ONLY,
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(svc.fetch_metar(lat, lon, location_name, config))
+    }
+}
+
+struct Pws;
+
+impl WeatherProvider for Pws {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "pws",
+            name: "Weather Station",
+            label: "Personal Weather Station",
+            requires_api_key: false,
+            min_refresh_minutes: 5,
+            capabilities: CURRENT_ONLY,
+        }
+    }
+
+    // Only the Weather Underground source goes through a keyed API
+    fn requires_api_key(&self, config: &AppConfig) -> bool {
+        config.pws.source == "wunderground"
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(svc.fetch_pws(lat, lon, location_name, config))
+    }
+}
+
+struct HomeAssistant;
+
+impl WeatherProvider for HomeAssistant {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "homeassistant",
+            name: "Home Assistant",
+            label: "Home Assistant Weather Entity",
+            requires_api_key: false,
+            min_refresh_minutes: 1,
+            capabilities: FORECAST,
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(svc.fetch_homeassistant(lat, lon, location_name, config))
+    }
+}
+
+struct Mqtt;
+
+impl WeatherProvider for Mqtt {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "mqtt",
+            name: "MQTT",
+            label: "MQTT Broker (Home Assistant, rtl_433)",
+            requires_api_key: false,
+            min_refresh_minutes: 1,
+            capabilities: CURRENT_ONLY,
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(svc.fetch_mqtt(lat, lon, location_name, config))
+    }
+}
+
+struct Custom;
+
+impl WeatherProvider for Custom {
+    fn info(&self) -> ProviderInfo {
+        ProviderInfo {
+            id: "custom",
+            name: "Custom",
+            label: "Custom JSON Endpoint",
+            requires_api_key: false,
+            min_refresh_minutes: 1,
+            capabilities: CURRENT_ONLY,
+        }
+    }
+
+    fn fetch<'a>(&'a self, svc: &'a WeatherService, lat: f64, lon: f64, location_name: String, config: &'a AppConfig) -> ProviderFuture<'a, WeatherState> {
+        Box::pin(svc.fetch_custom(lat, lon, location_name, config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_lookup() {
+        let registry = ProviderRegistry::builtin();
+        let ids: Vec<&str> = registry.list().iter().map(|p| p.id).collect();
+        assert_eq!(ids.first(), Some(&"open-meteo"));
+        // Ids are unique
+        assert!(ids.iter().enumerate().all(|(i, id)| !ids[i + 1..].contains(id)));
+
+        assert_eq!(registry.resolve("nws").info().name, "NWS");
+        assert_eq!(registry.resolve("no-such-provider").info().id, "open-meteo");
+        assert!(registry.get("no-such-provider").is_none());
+
+        let pws = registry.resolve("pws");
+        let mut config = AppConfig::default();
+        assert!(pws.requires_api_key(&config));
+        config.pws.source = "ecowitt".into();
+        assert!(!pws.requires_api_key(&config));
+    }
+}
@@ -0,0 +1,143 @@
+use chrono::Utc;
+use serde_json::Value;
+use crate::config::AppConfig;
+use crate::models::WeatherState;
+use super::WeatherService;
+
+// A personal weather station's live readings, either from Weather Underground's PWS API or
+// straight from an Ecowitt gateway on the LAN. Rain is measured, not forecast, so any
+// non-zero rain rate counts as precipitation.
+
+// Ecowitt live data ids (get_livedata_info)
+const ECOWITT_OUTDOOR_TEMP: &str = "0x02";
+const ECOWITT_OUTDOOR_HUMIDITY: &str = "0x07";
+const ECOWITT_WIND_SPEED: &str = "0x0B";
+const ECOWITT_RAIN_RATE: &str = "0x0E";
+
+struct StationReading {
+    name: Option<String>,
+    temperature: f64,
+    humidity: Option<f64>,
+    /// km/h
+    wind_speed: Option<f64>,
+    /// mm/h
+    rain_rate: f64,
+}
+
+impl WeatherService {
+    pub async fn fetch_pws(&self, lat: f64, lon: f64, location_name: String, config: &AppConfig) -> Result<WeatherState, String> {
+        let reading = match config.pws.source.as_str() {
+            "ecowitt" => self.read_ecowitt(&config.pws.gateway_url).await?,
+            _ => self.read_wunderground(&config.pws.station_id, &config.api_key).await?,
+        };
+
+        let sun_times = crate::sun::sun_times(lat, lon, Utc::now().date_naive());
+        let raining = reading.rain_rate > 0.0;
+
+        Ok(WeatherState {
+            temperature: reading.temperature,
+            has_precipitation: raining,
+            // Stations report a rate, not a type
+            precip_type: raining.then(|| "Rain".to_string()),
+            location_name: reading.name.unwrap_or(location_name),
+            sun_times: sun_times.clone(),
+            is_night: self.check_is_night(&sun_times),
+            provider: "Weather Station".to_string(),
+            last_updated: Utc::now(),
+            precip_probability: if raining { 100.0 } else { 0.0 },
+            humidity: reading.humidity,
+            dew_point: reading.humidity.map(|rh| super::calculate_dew_point(reading.temperature, rh)),
+            wind_speed: reading.wind_speed,
+            ..Default::default()
+        })
+    }
+
+    async fn read_wunderground(&self, station_id: &str, api_key: &str) -> Result<StationReading, String> {
+        if station_id.trim().is_empty() || api_key.is_empty() {
+            return Err("Weather Underground needs a station ID and API key".to_string());
+        }
+        let url = format!(
+            "{}/v2/pws/observations/current?stationId={}&format=json&units=m&numericPrecision=decimal&apiKey={}",
+            self.endpoints.wunderground, urlencoding::encode(station_id.trim()), api_key
+        );
+        let data = self.get_json(&url).await.map_err(|e| e.replace(api_key, "***"))?;
+        let obs = data.get("observations").and_then(|v| v.as_array()).and_then(|a| a.first())
+            .ok_or_else(|| format!("Station {} has no current observation", station_id))?;
+        let metric = |key: &str| obs.get("metric").and_then(|m| m.get(key)).and_then(|v| v.as_f64());
+
+        Ok(StationReading {
+            name: obs.get("neighborhood").and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(|s| s.to_string()),
+            temperature: metric("temp").ok_or_else(|| format!("Station {} reports no temperature", station_id))?,
+            humidity: obs.get("humidity").and_then(|v| v.as_f64()),
+            wind_speed: metric("windSpeed"),
+            rain_rate: metric("precipRate").unwrap_or(0.0),
+        })
+    }
+
+    async fn read_ecowitt(&self, gateway_url: &str) -> Result<StationReading, String> {
+        let base = gateway_url.trim().trim_end_matches('/');
+        if base.is_empty() {
+            return Err("No Ecowitt gateway address set".to_string());
+        }
+        let base = if base.starts_with("http") { base.to_string() } else { format!("http://{}", base) };
+        let data = self.get_json(&format!("{}/get_livedata_info", base)).await?;
+        parse_ecowitt(&data)
+    }
+}
+
+// Values come as strings with the unit appended ("21.5", "65%", "5.4 km/h", "0.0 mm/Hr"),
+// in whatever units the gateway is set to display
+fn parse_ecowitt(data: &Value) -> Result<StationReading, String> {
+    let entries: Vec<&Value> = ["common_list", "rain", "piezoRain"].iter()
+        .filter_map(|k| data.get(*k).and_then(|v| v.as_array()))
+        .flatten()
+        .collect();
+    let find = |id: &str| entries.iter().find(|e| e.get("id").and_then(|v| v.as_str()) == Some(id));
+    let value = |entry: &Value| -> Option<(f64, String)> {
+        let text = entry.get("val").and_then(|v| v.as_str())?;
+        let number: String = text.chars().take_while(|c| c.is_ascii_digit() || *c == '.' || *c == '-').collect();
+        let unit = entry.get("unit").and_then(|v| v.as_str()).unwrap_or(&text[number.len()..]).trim().to_lowercase();
+        Some((number.parse().ok()?, unit))
+    };
+
+    let temperature = find(ECOWITT_OUTDOOR_TEMP).and_then(|e| value(e))
+        .map(|(t, unit)| if unit.contains('f') { (t - 32.0) * 5.0 / 9.0 } else { t })
+        .ok_or_else(|| "Gateway reports no outdoor temperature".to_string())?;
+    let humidity = find(ECOWITT_OUTDOOR_HUMIDITY).and_then(|e| value(e)).map(|(h, _)| h);
+    let wind_speed = find(ECOWITT_WIND_SPEED).and_then(|e| value(e)).map(|(w, unit)| match unit.as_str() {
+        "mph" => w * 1.609,
+        "m/s" => w * 3.6,
+        "knots" => w * 1.852,
+        _ => w,
+    });
+    let rain_rate = find(ECOWITT_RAIN_RATE).and_then(|e| value(e))
+        .map(|(r, unit)| if unit.starts_with("in") { r * 25.4 } else { r })
+        .unwrap_or(0.0);
+
+    Ok(StationReading { name: None, temperature, humidity, wind_speed, rain_rate })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_ecowitt_units() {
+        let data = json!({
+            "common_list": [
+                {"id": "0x02", "val": "68.0", "unit": "F"},
+                {"id": "0x07", "val": "65%"},
+                {"id": "0x0B", "val": "3.1 mph"}
+            ],
+            "rain": [{"id": "0x0E", "val": "0.02 in/Hr"}]
+        });
+        let r = parse_ecowitt(&data).unwrap();
+        assert!((r.temperature - 20.0).abs() < 1e-9);
+        assert_eq!(r.humidity, Some(65.0));
+        assert!((r.wind_speed.unwrap() - 4.99).abs() < 0.01);
+        assert!((r.rain_rate - 0.508).abs() < 1e-9);
+
+        assert!(parse_ecowitt(&json!({"common_list": []})).is_err());
+    }
+}
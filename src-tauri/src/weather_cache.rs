@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::models::WeatherState;
+
+// The last successful fetch, rewritten after every refresh. When a fetch fails (or the app starts
+// offline) the pipeline replays it as stale data instead of turning the light off.
+
+/// Cached weather older than this is no longer worth showing
+pub const MAX_STALE_HOURS: i64 = 12;
+
+pub fn get_cache_path() -> PathBuf {
+    let mut path = crate::config::get_config_path();
+    path.set_file_name("last_weather.json");
+    path
+}
+
+pub fn save(path: &Path, weather: &WeatherState) -> Result<(), String> {
+    let json = serde_json::to_string(weather).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())
+}
+
+/// The cached weather, if there is one younger than `MAX_STALE_HOURS`
+pub fn load(path: &Path, now: DateTime<Utc>) -> Option<WeatherState> {
+    let data = fs::read_to_string(path).ok()?;
+    let weather: WeatherState = serde_json::from_str(&data).ok()?;
+    is_usable(&weather, now).then_some(weather)
+}
+
+pub fn is_usable(weather: &WeatherState, now: DateTime<Utc>) -> bool {
+    now - weather.last_updated <= chrono::Duration::hours(MAX_STALE_HOURS)
+}
+
+/// "25 min" / "3 h" since the data was fetched
+pub fn age_label(weather: &WeatherState, now: DateTime<Utc>) -> String {
+    let minutes = (now - weather.last_updated).num_minutes().max(0);
+    if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{} h", minutes / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_expiry() {
+        let path = std::env::temp_dir().join(format!("weatherlight-cache-{}.json", std::process::id()));
+        let now = Utc::now();
+        let weather = WeatherState {
+            temperature: 14.5,
+            location_name: "Wellington".into(),
+            last_updated: now - chrono::Duration::minutes(90),
+            ..Default::default()
+        };
+
+        save(&path, &weather).unwrap();
+        let cached = load(&path, now).unwrap();
+        assert_eq!(cached.temperature, 14.5);
+        assert_eq!(age_label(&cached, now), "1 h");
+
+        assert!(load(&path, now + chrono::Duration::hours(MAX_STALE_HOURS)).is_none());
+        let _ = fs::remove_file(&path);
+        assert!(load(&path, now).is_none());
+    }
+}
@@ -0,0 +1,261 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use reqwest::Client;
+use serde_json::{json, Value};
+use tokio::net::UdpSocket;
+use crate::color::{mix, to_hex, Interpolation};
+use crate::config::WledConfig;
+use crate::sink::{LightSink, SinkStatus};
+
+// An addressable LED strip running WLED, on the local network. The JSON API (`/json/state`)
+// can run WLED's own Breathe effect for a pulse and set each LED for the forecast gradient;
+// UDP realtime (DRGB on port 21324) only streams raw colors, so a pulse shows as a steady color
+// and the strip stays in "live" mode until something else takes over.
+
+/// Device name used in `deviceLocations` for the WLED output
+pub const WLED_DEVICE: &str = "wled";
+
+const UDP_PORT: u16 = 21324;
+// DRGB protocol byte, then the seconds WLED waits before leaving realtime mode (255: never)
+const DRGB: u8 = 2;
+const REALTIME_HOLD: u8 = 255;
+// DRGB packets carry at most this many LEDs
+const DRGB_MAX_LEDS: usize = 490;
+// WLED effect ids
+const FX_SOLID: u8 = 0;
+const FX_BREATHE: u8 = 2;
+
+/// What to show on the strip
+#[derive(Debug, Clone, PartialEq)]
+pub enum WledFrame {
+    Solid { rgb: (u8, u8, u8), brightness: u8, pulse: bool, speed_ms: u64 },
+    /// Colors spread evenly from the first LED to the last
+    Gradient { colors: Vec<(u8, u8, u8)>, brightness: u8, interpolation: Interpolation },
+}
+
+pub struct WledOutput {
+    client: Client,
+    // Host and LED count last read from `/json/info`
+    led_count: Mutex<Option<(String, usize)>>,
+    // Whether the last frame got through
+    reachable: AtomicBool,
+}
+
+impl Default for WledOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WledOutput {
+    pub fn new() -> Self {
+        Self {
+            client: Client::builder().timeout(Duration::from_secs(5)).build().unwrap_or_default(),
+            led_count: Mutex::new(None),
+            reachable: AtomicBool::new(true),
+        }
+    }
+
+    pub async fn apply(&self, config: &WledConfig, frame: WledFrame) -> Result<(), String> {
+        if !config.is_ready() {
+            return Ok(());
+        }
+        let result = self.send_frame(config, frame).await;
+        self.reachable.store(result.is_ok(), Ordering::Relaxed);
+        result
+    }
+
+    async fn send_frame(&self, config: &WledConfig, frame: WledFrame) -> Result<(), String> {
+        // A solid color over JSON is the only frame that doesn't need the strip's length
+        let leds = match (&frame, config.protocol.as_str()) {
+            (WledFrame::Solid { .. }, "json") => 0,
+            _ => self.led_count(config).await?,
+        };
+
+        if config.protocol == "udp" {
+            let socket = UdpSocket::bind("0.0.0.0:0").await.map_err(|e| e.to_string())?;
+            socket.send_to(&drgb_packet(&pixels(&frame, leds)), (config.host_name(), UDP_PORT)).await.map_err(|e| e.to_string())?;
+            return Ok(());
+        }
+
+        let url = format!("{}/json/state", config.base_url());
+        let resp = self.client.post(&url).json(&state_body(&frame, leds)).send().await.map_err(|e| e.to_string())?;
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {} from {}", resp.status(), url));
+        }
+        Ok(())
+    }
+
+    /// The strip's LED count, asked once per host
+    async fn led_count(&self, config: &WledConfig) -> Result<usize, String> {
+        let host = config.host_name().to_string();
+        if let Some((known, count)) = self.led_count.lock().unwrap().as_ref() {
+            if *known == host {
+                return Ok(*count);
+            }
+        }
+        let url = format!("{}/json/info", config.base_url());
+        let info: Value = self.client.get(&url).send().await.map_err(|e| e.to_string())?
+            .json().await.map_err(|e| e.to_string())?;
+        let count = info.pointer("/leds/count").and_then(|v| v.as_u64())
+            .filter(|&n| n > 0)
+            .ok_or_else(|| format!("{} didn't report an LED count", host))? as usize;
+        *self.led_count.lock().unwrap() = Some((host, count));
+        Ok(count)
+    }
+}
+
+/// The WLED output with the settings it was built from, as a light sink
+pub struct WledSink {
+    output: Arc<WledOutput>,
+    config: WledConfig,
+    interpolation: Interpolation,
+}
+
+impl WledSink {
+    pub fn new(output: Arc<WledOutput>, config: WledConfig, interpolation: Interpolation) -> Self {
+        Self { output, config, interpolation }
+    }
+
+    fn send(&self, frame: WledFrame) {
+        let (output, config) = (self.output.clone(), self.config.clone());
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = output.apply(&config, frame).await {
+                println!("WLED update failed: {}", e);
+            }
+        });
+    }
+}
+
+impl LightSink for WledSink {
+    fn kind(&self) -> &'static str {
+        "wled"
+    }
+
+    fn set_color(&self, rgb: (u8, u8, u8), brightness: u8) {
+        self.send(WledFrame::Solid { rgb, brightness, pulse: false, speed_ms: 0 });
+    }
+
+    // Breathe has no depth setting
+    fn pulse(&self, rgb: (u8, u8, u8), high: u8, _low: u8, speed_ms: u64) {
+        self.send(WledFrame::Solid { rgb, brightness: high, pulse: true, speed_ms });
+    }
+
+    fn off(&self) {
+        self.send(WledFrame::Solid { rgb: (0, 0, 0), brightness: 0, pulse: false, speed_ms: 0 });
+    }
+
+    fn status(&self) -> SinkStatus {
+        if self.output.reachable.load(Ordering::Relaxed) { SinkStatus::Connected } else { SinkStatus::Disconnected }
+    }
+
+    // Only with `forecastHours` set; otherwise the strip shows one color like everything else
+    fn show_gradient(&self, colors: &[(u8, u8, u8)], brightness: u8) -> bool {
+        if self.config.forecast_hours == 0 || colors.is_empty() {
+            return false;
+        }
+        self.send(WledFrame::Gradient { colors: colors.to_vec(), brightness, interpolation: self.interpolation });
+        true
+    }
+}
+
+fn state_body(frame: &WledFrame, leds: usize) -> Value {
+    match frame {
+        WledFrame::Solid { rgb, brightness, .. } if *rgb == (0, 0, 0) || *brightness == 0 => json!({ "on": false }),
+        WledFrame::Solid { rgb, brightness, pulse, speed_ms } => json!({
+            "on": true,
+            "bri": wled_brightness(*brightness),
+            "seg": [{
+                "id": 0,
+                "col": [[rgb.0, rgb.1, rgb.2]],
+                "fx": if *pulse { FX_BREATHE } else { FX_SOLID },
+                "sx": effect_speed(*speed_ms),
+            }],
+        }),
+        WledFrame::Gradient { colors, brightness, interpolation } => {
+            let hex: Vec<String> = spread(colors, leds, *interpolation).into_iter()
+                .map(|c| to_hex(c).trim_start_matches('#').to_string())
+                .collect();
+            json!({
+                "on": *brightness > 0,
+                "bri": wled_brightness(*brightness),
+                // Consecutive colors without indices fill the segment from its first LED
+                "seg": [{ "id": 0, "i": hex }],
+            })
+        }
+    }
+}
+
+// Raw LED colors for UDP, with the brightness folded in since DRGB has no brightness of its own
+fn pixels(frame: &WledFrame, leds: usize) -> Vec<(u8, u8, u8)> {
+    let leds = leds.min(DRGB_MAX_LEDS);
+    let (colors, brightness) = match frame {
+        WledFrame::Solid { rgb, brightness, .. } => (vec![*rgb; leds], *brightness),
+        WledFrame::Gradient { colors, brightness, interpolation } => (spread(colors, leds, *interpolation), *brightness),
+    };
+    let scale = |c: u8| (c as u32 * brightness.min(100) as u32 / 100) as u8;
+    colors.into_iter().map(|(r, g, b)| (scale(r), scale(g), scale(b))).collect()
+}
+
+fn drgb_packet(pixels: &[(u8, u8, u8)]) -> Vec<u8> {
+    let mut packet = vec![DRGB, REALTIME_HOLD];
+    for (r, g, b) in pixels {
+        packet.extend_from_slice(&[*r, *g, *b]);
+    }
+    packet
+}
+
+/// Stretches `colors` over `leds` LEDs, blending between neighbours
+pub fn spread(colors: &[(u8, u8, u8)], leds: usize, mode: Interpolation) -> Vec<(u8, u8, u8)> {
+    match colors.len() {
+        0 => Vec::new(),
+        1 => vec![colors[0]; leds],
+        n => (0..leds).map(|i| {
+            let pos = if leds > 1 { i as f32 * (n - 1) as f32 / (leds - 1) as f32 } else { 0.0 };
+            let idx = (pos as usize).min(n - 2);
+            mix(colors[idx], colors[idx + 1], pos - idx as f32, mode)
+        }).collect(),
+    }
+}
+
+// Percent to WLED's 0-255
+fn wled_brightness(pct: u8) -> u8 {
+    (pct.min(100) as u32 * 255 / 100) as u8
+}
+
+// Roughly matches the Breathe period to the Busylight's pulse: 200ms is fastest, 10s slowest
+fn effect_speed(speed_ms: u64) -> u8 {
+    (255 * (10_000 - speed_ms.clamp(200, 10_000)) / 9_800) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spread() {
+        let colors = [(0, 0, 0), (200, 100, 0)];
+        let strip = spread(&colors, 5, Interpolation::Rgb);
+        assert_eq!(strip, vec![(0, 0, 0), (50, 25, 0), (100, 50, 0), (150, 75, 0), (200, 100, 0)]);
+        assert_eq!(spread(&colors[..1], 3, Interpolation::Rgb), vec![(0, 0, 0); 3]);
+        assert!(spread(&[], 3, Interpolation::Rgb).is_empty());
+    }
+
+    #[test]
+    fn test_drgb_packet() {
+        let frame = WledFrame::Solid { rgb: (200, 100, 0), brightness: 50, pulse: true, speed_ms: 5000 };
+        assert_eq!(drgb_packet(&pixels(&frame, 2)), vec![2, 255, 100, 50, 0, 100, 50, 0]);
+    }
+
+    #[test]
+    fn test_state_body() {
+        let frame = WledFrame::Solid { rgb: (255, 0, 0), brightness: 60, pulse: true, speed_ms: 200 };
+        let body = state_body(&frame, 0);
+        assert_eq!(body["bri"], 153);
+        assert_eq!(body["seg"][0]["fx"], FX_BREATHE);
+        assert_eq!(body["seg"][0]["sx"], 255);
+        let off = WledFrame::Solid { rgb: (0, 0, 0), brightness: 60, pulse: false, speed_ms: 0 };
+        assert_eq!(state_body(&off, 0), json!({ "on": false }));
+    }
+}
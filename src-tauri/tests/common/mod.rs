@@ -0,0 +1,93 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Minimal HTTP server answering canned responses by path prefix.
+/// `{{base}}` in a body is replaced with the server's own URL, for APIs that return follow-up links.
+pub struct MockServer {
+    pub url: String,
+    // Requests answered per route path
+    hits: Arc<Mutex<Vec<(&'static str, usize)>>>,
+}
+
+pub struct Route {
+    pub path: &'static str,
+    pub status: u16,
+    pub body: String,
+    /// Sent as `ETag`; a request presenting it in `If-None-Match` gets 304 Not Modified
+    pub etag: Option<&'static str>,
+}
+
+impl Route {
+    pub fn with_etag(self, etag: &'static str) -> Self {
+        Route { etag: Some(etag), ..self }
+    }
+}
+
+pub fn route(path: &'static str, fixture: &str) -> Route {
+    Route { path, status: 200, body: load_fixture(fixture), etag: None }
+}
+
+pub fn error_route(path: &'static str, status: u16) -> Route {
+    Route { path, status, body: "Internal Server Error".to_string(), etag: None }
+}
+
+pub fn load_fixture(name: &str) -> String {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("fixtures");
+    path.push(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("missing fixture {:?}: {}", path, e))
+}
+
+impl MockServer {
+    pub fn start(routes: Vec<Route>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let addr = listener.local_addr().unwrap();
+        let url = format!("http://{}", addr);
+        let routes: Vec<Route> = routes.into_iter()
+            .map(|r| Route { body: r.body.replace("{{base}}", &url), ..r })
+            .collect();
+        let hits = Arc::new(Mutex::new(routes.iter().map(|r| (r.path, 0)).collect::<Vec<_>>()));
+        let counter = hits.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut buf = [0u8; 8192];
+                let n = stream.read(&mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+
+                let matched = routes.iter().position(|r| path.starts_with(r.path));
+                if let Some(i) = matched {
+                    counter.lock().unwrap()[i].1 += 1;
+                }
+                let etag = matched.and_then(|i| routes[i].etag);
+                let not_modified = etag.is_some_and(|tag| {
+                    request.lines().any(|l| l.to_lowercase().starts_with("if-none-match:") && l.contains(tag))
+                });
+                let (status, body) = match matched {
+                    _ if not_modified => (304, ""),
+                    Some(i) => (routes[i].status, routes[i].body.as_str()),
+                    None => (404, "{}"),
+                };
+                let etag_header = etag.map(|tag| format!("ETag: {}\r\n", tag)).unwrap_or_default();
+
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, etag_header, body.len(), body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        Self { url, hits }
+    }
+
+    pub fn hits(&self, path: &str) -> usize {
+        self.hits.lock().unwrap().iter().find(|(p, _)| *p == path).map(|(_, n)| *n).unwrap_or(0)
+    }
+}
@@ -0,0 +1,878 @@
+mod common;
+
+use std::sync::Arc;
+use chrono::{NaiveDate, TimeZone, Utc};
+use common::{error_route, route, MockServer};
+use weatherlight_lib::config::{AppConfig, CustomProviderConfig, FogConfig, HomeAssistantConfig, HueConfig, PwsConfig, ThunderConfig, WledConfig};
+use weatherlight_lib::hue::HueOutput;
+use weatherlight_lib::wled::{WledFrame, WledOutput};
+use weatherlight_lib::plugins::PluginRegistry;
+use weatherlight_lib::weather::{ApiEndpoints, RetryPolicy, WeatherService};
+
+fn service(server: &MockServer) -> WeatherService {
+    WeatherService::with_endpoints(Arc::new(PluginRegistry::empty()), ApiEndpoints {
+        ip_api: server.url.clone(),
+        open_meteo: server.url.clone(),
+        open_meteo_geocoding: server.url.clone(),
+        open_meteo_air_quality: server.url.clone(),
+        open_meteo_archive: server.url.clone(),
+        openweathermap: server.url.clone(),
+        nws: server.url.clone(),
+        met_no: server.url.clone(),
+        pirate_weather: server.url.clone(),
+        tomorrow_io: server.url.clone(),
+        accuweather: server.url.clone(),
+        eccc: server.url.clone(),
+        aviation_weather: server.url.clone(),
+        wunderground: server.url.clone(),
+        swpc: server.url.clone(),
+    }).with_retry(RetryPolicy { attempts: 3, base_delay: std::time::Duration::from_millis(1) })
+}
+
+fn config(provider: &str, location: &str) -> AppConfig {
+    AppConfig {
+        provider: provider.to_string(),
+        auto_location: false,
+        location: location.to_string(),
+        api_key: "test".to_string(),
+        ..Default::default()
+    }
+}
+
+// Fixture timestamps are in the past, so providers fall back to the first hourly entry as "now".
+
+#[tokio::test]
+async fn open_meteo_current_conditions() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+
+    let weather = service(&server).fetch(&config("open-meteo", "Auckland, New Zealand")).await.unwrap();
+
+    assert_eq!(weather.provider, "Open-Meteo");
+    assert_eq!(weather.location_name, "Auckland, Auckland, New Zealand");
+    assert_eq!(weather.temperature, 18.5);
+    assert!(!weather.has_precipitation);
+    assert_eq!(weather.precip_probability, 10.0);
+    assert_eq!(weather.humidity, Some(70.0));
+    assert_eq!(weather.wind_speed, Some(12.0));
+    assert_eq!(weather.wind_gust, Some(31.0));
+    assert_eq!(weather.dew_point, Some(12.9));
+    assert_eq!(weather.pressure, Some(1012.4));
+    // Only the app's own history knows the tendency
+    assert_eq!(weather.pressure_tendency, None);
+    assert_eq!(weather.debug_forecast[2].humidity, Some(85.0));
+    assert_eq!(weather.debug_forecast[2].dew_point, Some(17.5));
+    assert_eq!(weather.precip_starts_at, Some(Utc.with_ymd_and_hms(2026, 2, 23, 2, 0, 0).unwrap()));
+    assert!(weather.sun_times.sunrise.is_some());
+    // Twilight and moon phase are filled in locally
+    assert!(weather.sun_times.civil_dusk.is_some());
+    assert!(weather.sun_times.moon_phase.is_some());
+    assert_eq!(weather.debug_forecast.len(), 6);
+}
+
+#[tokio::test]
+async fn open_meteo_nowcast_horizon() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+
+    // The hourly data is dry for the current hour, but the quarter hours from 00:30 are wet
+    let cfg = AppConfig { precip_horizon: "nowcast".to_string(), ..config("open-meteo", "Auckland") };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert!(weather.has_precipitation);
+    assert_eq!(weather.precip_type.as_deref(), Some("Rain"));
+    assert_eq!(weather.precip_starts_at, Some(Utc.with_ymd_and_hms(2026, 2, 23, 0, 30, 0).unwrap()));
+    assert_eq!(weather.nowcast.len(), 8);
+    assert_eq!(weather.precip_probability, 10.0);
+}
+
+#[tokio::test]
+async fn open_meteo_fog_window() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+    let svc = service(&server);
+
+    // 600 m of visibility two hours out falls inside the default 3-hour window
+    let weather = svc.fetch(&config("open-meteo", "Auckland")).await.unwrap();
+    assert_eq!(weather.visibility, Some(24.0));
+    assert!(weather.has_fog);
+
+    let cfg = AppConfig { fog: FogConfig { hours: 2, ..Default::default() }, ..config("open-meteo", "Auckland") };
+    assert!(!svc.fetch(&cfg).await.unwrap().has_fog);
+}
+
+#[tokio::test]
+async fn open_meteo_horizons() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+
+    let cfg = AppConfig {
+        temp_horizon: "short_high".to_string(),
+        precip_horizon: "short".to_string(),
+        ..config("open-meteo", "Auckland")
+    };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert_eq!(weather.temperature, 23.0);
+    assert!(weather.has_precipitation);
+    assert_eq!(weather.precip_probability, 90.0);
+}
+
+#[tokio::test]
+async fn open_meteo_custom_precip_thresholds() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+
+    let cfg = AppConfig {
+        precip_horizon: "short".to_string(),
+        precip_probability_threshold: 95.0,
+        precip_amount_threshold: 5.0,
+        ..config("open-meteo", "Auckland")
+    };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert!(!weather.has_precipitation);
+    assert_eq!(weather.precip_probability, 90.0);
+}
+
+#[tokio::test]
+async fn open_meteo_missing_sunrise() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast_no_daily.json"),
+    ]);
+
+    let weather = service(&server).fetch(&config("open-meteo", "Auckland")).await.unwrap();
+
+    assert!(weather.sun_times.sunrise.is_none());
+    assert!(weather.sun_times.sunset.is_none());
+    assert!(!weather.is_night);
+    assert_eq!(weather.temperature, -3.0);
+    assert!(weather.precip_starts_at.is_none());
+}
+
+#[tokio::test]
+async fn open_meteo_server_error() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        error_route("/v1/forecast", 500),
+    ]);
+
+    assert!(service(&server).fetch(&config("open-meteo", "Auckland")).await.is_err());
+    // 5xx is transient, so every attempt was used
+    assert_eq!(server.hits("/v1/forecast"), 3);
+}
+
+#[tokio::test]
+async fn client_errors_are_not_retried() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        error_route("/locationforecast", 403),
+    ]);
+
+    let err = service(&server).fetch(&config("metno", "Auckland")).await.unwrap_err();
+    assert!(err.starts_with("HTTP 403"));
+    assert_eq!(server.hits("/locationforecast"), 1);
+}
+
+#[tokio::test]
+async fn rate_limited_api_is_left_alone() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        error_route("/v1/forecast", 429),
+    ]);
+    let svc = service(&server);
+
+    let err = svc.fetch(&config("open-meteo", "Auckland")).await.unwrap_err();
+    assert!(err.starts_with("HTTP 429"));
+    assert_eq!(server.hits("/v1/forecast"), 1);
+
+    // The next cycle is skipped without touching the API
+    assert!(svc.fetch(&config("open-meteo", "Auckland")).await.is_err());
+    assert_eq!(server.hits("/v1/forecast"), 1);
+}
+
+#[tokio::test]
+async fn falls_back_when_primary_fails() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        error_route("/points", 500),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+
+    let mut cfg = config("nws", "Auckland");
+    cfg.fallback_providers = vec!["nws".into(), "open-meteo".into()];
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert_eq!(weather.provider, "Open-Meteo");
+    assert_eq!(weather.temperature, 18.5);
+    // A server error is retried before falling back; "nws" appears twice but is only tried once
+    assert_eq!(server.hits("/points"), 3);
+
+    // With every provider failing, the primary's error is reported
+    cfg.fallback_providers = vec!["plugin:missing".into()];
+    let err = service(&server).fetch(&cfg).await.unwrap_err();
+    assert!(err.starts_with("NWS only covers US locations"));
+}
+
+#[tokio::test]
+async fn falls_back_at_once_on_client_errors() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        error_route("/points", 404),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+
+    let mut cfg = config("nws", "Auckland");
+    cfg.fallback_providers = vec!["open-meteo".into()];
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert_eq!(weather.provider, "Open-Meteo");
+    // A 404 won't go away on a retry
+    assert_eq!(server.hits("/points"), 1);
+}
+
+#[tokio::test]
+async fn circuit_opens_after_repeated_failures() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        error_route("/locationforecast", 403),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+    let svc = service(&server);
+    let mut cfg = config("metno", "Auckland");
+    cfg.fallback_providers = vec!["open-meteo".into()];
+
+    for _ in 0..4 {
+        assert_eq!(svc.fetch(&cfg).await.unwrap().provider, "Open-Meteo");
+    }
+    // The fourth cycle went straight to the fallback
+    assert_eq!(server.hits("/locationforecast"), 3);
+
+    let health = svc.provider_health();
+    assert_eq!(health.len(), 1);
+    assert_eq!(health[0].provider, "metno");
+    assert!(health[0].open_until.is_some());
+    assert!(health[0].last_error.as_deref().unwrap().starts_with("HTTP 403"));
+
+    svc.reset_provider_health();
+    svc.fetch(&cfg).await.unwrap();
+    assert_eq!(server.hits("/locationforecast"), 4);
+}
+
+#[tokio::test]
+async fn unknown_location_is_an_error() {
+    let server = MockServer::start(vec![error_route("/v1/search", 500)]);
+
+    let err = service(&server).fetch(&config("open-meteo", "Atlantis")).await.unwrap_err();
+    assert_eq!(err, "No location set");
+}
+
+#[tokio::test]
+async fn openweathermap_snow_in_forecast() {
+    let server = MockServer::start(vec![
+        route("/geo/1.0/direct", "owm_geocode.json"),
+        route("/data/2.5/weather", "owm_weather.json"),
+        route("/data/2.5/forecast", "owm_forecast.json"),
+    ]);
+
+    let cfg = AppConfig { precip_horizon: "short".to_string(), ..config("openweathermap", "Oslo, NO") };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert_eq!(weather.provider, "OpenWeatherMap");
+    assert_eq!(weather.location_name, "Oslo, Oslo, NO");
+    assert_eq!(weather.temperature, -2.4);
+    assert!(weather.has_precipitation);
+    assert_eq!(weather.precip_type.as_deref(), Some("Snow"));
+    assert_eq!(weather.precip_probability, 60.0);
+    assert_eq!(weather.humidity, Some(81.0));
+    assert_eq!(weather.wind_speed, Some(18.0));
+    assert_eq!(weather.precip_starts_at, Utc.timestamp_opt(1771848000, 0).single());
+    assert_eq!(weather.debug_forecast[1].precip_type, "Snow");
+}
+
+#[tokio::test]
+async fn open_meteo_daily_forecast() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_daily.json"),
+    ]);
+
+    let days = service(&server).fetch_forecast(&config("open-meteo", "Auckland")).await.unwrap();
+
+    assert_eq!(days.len(), 7);
+    assert_eq!(days[0].date, NaiveDate::from_ymd_opt(2026, 2, 23).unwrap());
+    assert_eq!((days[0].high, days[0].low), (23.0, 15.2));
+    assert_eq!(days[0].precip_probability, 90.0);
+    assert_eq!(days[0].condition, "Rain");
+    assert_eq!(days[5].condition, "Thunderstorm");
+    assert_eq!(days[6].precip_probability, 0.0);
+}
+
+#[tokio::test]
+async fn openweathermap_daily_forecast_from_blocks() {
+    let server = MockServer::start(vec![
+        route("/geo/1.0/direct", "owm_geocode.json"),
+        route("/data/2.5/forecast", "owm_forecast.json"),
+    ]);
+
+    let days = service(&server).fetch_forecast(&config("openweathermap", "Oslo, NO")).await.unwrap();
+
+    // Three blocks on one day; without temp_min the block temperature stands in for it
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0].date, NaiveDate::from_ymd_opt(2026, 2, 23).unwrap());
+    assert_eq!((days[0].high, days[0].low), (1.0, -2.0));
+    assert_eq!(days[0].precip_probability, 60.0);
+}
+
+#[tokio::test]
+async fn openweathermap_empty_forecast() {
+    let server = MockServer::start(vec![
+        route("/geo/1.0/direct", "owm_geocode.json"),
+        route("/data/2.5/weather", "owm_weather.json"),
+        route("/data/2.5/forecast", "owm_forecast_empty.json"),
+    ]);
+
+    let weather = service(&server).fetch(&config("openweathermap", "Oslo")).await.unwrap();
+
+    assert_eq!(weather.temperature, -2.4);
+    assert!(!weather.has_precipitation);
+    assert!(weather.debug_forecast.is_empty());
+    assert!(weather.precip_starts_at.is_none());
+}
+
+#[tokio::test]
+async fn nws_observation_and_hourly_forecast() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/points/", "nws_points.json"),
+        route("/gridpoints/OKX/33,35/forecast/hourly", "nws_forecast_hourly.json"),
+        route("/gridpoints/OKX/33,35/stations", "nws_stations.json"),
+        route("/stations/KNYC/observations/latest", "nws_observation.json"),
+    ]);
+
+    let weather = service(&server).fetch(&config("nws", "New York")).await.unwrap();
+
+    assert_eq!(weather.provider, "NWS");
+    assert_eq!(weather.temperature, 3.3);
+    assert!(!weather.has_precipitation);
+    assert_eq!(weather.precip_probability, 20.0);
+    assert_eq!(weather.humidity, Some(65.2));
+    assert_eq!(weather.wind_speed, Some(18.36));
+    assert_eq!(weather.precip_starts_at, Some(Utc.with_ymd_and_hms(2026, 2, 23, 16, 0, 0).unwrap()));
+    assert_eq!(weather.debug_forecast[1].precip_type, "Snow");
+    assert!(weather.sun_times.sunrise.is_some() && weather.sun_times.sunset.is_some());
+}
+
+#[tokio::test]
+async fn nws_outside_us() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        error_route("/points/", 404),
+    ]);
+
+    let err = service(&server).fetch(&config("nws", "Auckland")).await.unwrap_err();
+    assert!(err.starts_with("NWS only covers US locations"));
+}
+
+#[tokio::test]
+async fn metno_hourly_timeseries() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/locationforecast/2.0/complete", "metno_complete.json"),
+    ]);
+
+    let cfg = AppConfig { precip_horizon: "short".into(), temp_horizon: "short_high".into(), ..config("metno", "Oslo") };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert_eq!(weather.provider, "MET Norway");
+    assert_eq!(weather.temperature, 0.6);
+    assert!(weather.has_precipitation);
+    assert_eq!(weather.precip_probability, 80.0);
+    assert_eq!(weather.humidity, Some(81.0));
+    assert_eq!(weather.wind_speed, Some(9.0));
+    assert_eq!(weather.precip_starts_at, Some(Utc.with_ymd_and_hms(2026, 2, 23, 1, 0, 0).unwrap()));
+    // The 6-hourly tail isn't part of the hourly forecast
+    assert_eq!(weather.debug_forecast.len(), 4);
+    assert_eq!(weather.debug_forecast[1].precip_type, "Snow");
+    assert_eq!(weather.debug_forecast[3].precip_type, "Rain");
+}
+
+#[tokio::test]
+async fn unchanged_forecast_is_not_downloaded_again() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/locationforecast/2.0/complete", "metno_complete.json").with_etag("\"v1\""),
+    ]);
+    let svc = service(&server);
+
+    let first = svc.fetch(&config("metno", "Oslo")).await.unwrap();
+    // The second answer is an empty 304, so this only works if the first body was kept
+    let second = svc.fetch(&config("metno", "Oslo")).await.unwrap();
+    assert_eq!(server.hits("/locationforecast/2.0/complete"), 2);
+    assert_eq!(second.temperature, first.temperature);
+    assert_eq!(second.debug_forecast.len(), first.debug_forecast.len());
+}
+
+#[tokio::test]
+async fn pirateweather_minutely_start() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/forecast/test/", "pirateweather_forecast.json"),
+    ]);
+
+    let weather = service(&server).fetch(&config("pirateweather", "Auckland")).await.unwrap();
+
+    assert_eq!(weather.provider, "Pirate Weather");
+    assert_eq!(weather.temperature, 14.6);
+    // Hourly says 01:00, minutely narrows it down to 00:20
+    assert_eq!(weather.precip_starts_at, Some(Utc.with_ymd_and_hms(2026, 2, 23, 0, 20, 0).unwrap()));
+    assert!(weather.has_precipitation);
+    assert_eq!(weather.precip_probability, 50.0);
+    assert_eq!(weather.humidity, Some(70.0));
+    assert_eq!(weather.debug_forecast.len(), 4);
+    assert_eq!(weather.debug_forecast[1].precip_prob, 60.0);
+    assert_eq!(weather.debug_forecast[1].apparent_temp, Some(12.5));
+    assert!(weather.sun_times.sunset.is_some());
+
+    let missing_key = AppConfig { api_key: String::new(), ..config("pirateweather", "Auckland") };
+    assert!(service(&server).fetch(&missing_key).await.is_err());
+}
+
+#[tokio::test]
+async fn tomorrowio_hourly_timeline() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v4/timelines", "tomorrowio_timelines.json"),
+    ]);
+
+    let cfg = AppConfig { precip_horizon: "short".into(), temp_horizon: "short_high".into(), ..config("tomorrowio", "Auckland") };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert_eq!(weather.provider, "Tomorrow.io");
+    assert_eq!(weather.temperature, 22.4);
+    assert!(weather.has_precipitation);
+    assert_eq!(weather.precip_probability, 75.0);
+    assert_eq!(weather.humidity, Some(64.0));
+    assert_eq!(weather.wind_speed, Some(12.6));
+    assert_eq!(weather.precip_starts_at, Some(Utc.with_ymd_and_hms(2026, 2, 23, 2, 0, 0).unwrap()));
+    assert_eq!(weather.debug_forecast.len(), 5);
+    assert_eq!(weather.debug_forecast[1].precip_type, "Rain");
+    assert_eq!(weather.debug_forecast[4].precip_type, "None");
+}
+
+#[tokio::test]
+async fn accuweather_caches_location_key() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/locations/v1/cities/geoposition/search", "accuweather_geoposition.json"),
+        route("/forecasts/v1/hourly/12hour/2515397", "accuweather_hourly.json"),
+    ]);
+    let svc = service(&server);
+    let cfg = config("accuweather", "Oslo");
+
+    let weather = svc.fetch(&cfg).await.unwrap();
+    assert_eq!(weather.provider, "AccuWeather");
+    assert_eq!(weather.temperature, -1.0);
+    assert_eq!(weather.precip_probability, 20.0);
+    assert_eq!(weather.precip_starts_at, Some(Utc.with_ymd_and_hms(2026, 2, 23, 1, 0, 0).unwrap()));
+    assert_eq!(weather.debug_forecast[1].precip_type, "Snow");
+    assert_eq!(weather.debug_forecast[1].apparent_temp, Some(-6.0));
+    assert_eq!(weather.wind_speed, Some(14.8));
+
+    svc.fetch(&cfg).await.unwrap();
+    assert_eq!(server.hits("/locations/v1/cities/geoposition/search"), 1);
+    assert_eq!(server.hits("/forecasts/v1/hourly/12hour/2515397"), 2);
+}
+
+#[tokio::test]
+async fn eccc_nearest_city_page() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode_ottawa.json"),
+        route("/collections/citypageweather-realtime/items", "eccc_citypage.json"),
+    ]);
+
+    let cfg = AppConfig { precip_horizon: "short".into(), ..config("eccc", "Ottawa") };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert_eq!(weather.provider, "Environment Canada");
+    assert_eq!(weather.temperature, -6.4);
+    assert!(weather.has_precipitation);
+    assert_eq!(weather.precip_probability, 70.0);
+    assert_eq!(weather.humidity, Some(78.0));
+    assert_eq!(weather.precip_starts_at, Some(Utc.with_ymd_and_hms(2026, 2, 23, 16, 0, 0).unwrap()));
+    assert_eq!(weather.debug_forecast[1].precip_type, "Snow");
+    assert_eq!(weather.debug_forecast[0].apparent_temp, Some(-13.0));
+    assert_eq!(weather.alerts, vec!["Snowfall warning in effect".to_string()]);
+}
+
+#[tokio::test]
+async fn custom_endpoint_field_mapping() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/station/latest", "custom_station.json"),
+    ]);
+
+    let mut cfg = config("custom", "Auckland");
+    cfg.custom_provider = CustomProviderConfig {
+        url: format!("{}/station/latest?lat={{lat}}&lon={{lon}}", server.url),
+        temperature_path: "/readings/outdoor/tempF".into(),
+        temperature_unit: "F".into(),
+        precipitation_path: "readings.rainChance".into(),
+        precipitation_unit: "fraction".into(),
+        humidity_path: "readings.outdoor.humidity".into(),
+    };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert_eq!(weather.provider, "Custom");
+    assert_eq!(weather.temperature, 20.0);
+    assert!(weather.has_precipitation);
+    assert_eq!(weather.precip_probability, 50.0);
+    assert_eq!(weather.humidity, Some(55.0));
+
+    cfg.custom_provider.temperature_path = "readings.indoor.temp".into();
+    let err = service(&server).fetch(&cfg).await.unwrap_err();
+    assert_eq!(err, "No temperature at 'readings.indoor.temp'");
+}
+
+#[tokio::test]
+async fn metar_nearest_station() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/api/data/metar", "metar_bbox.json"),
+    ]);
+
+    let weather = service(&server).fetch(&config("metar", "Auckland")).await.unwrap();
+
+    assert_eq!(weather.provider, "METAR");
+    assert_eq!(weather.location_name, "Auckland (NZWP)");
+    assert_eq!(weather.temperature, 17.0);
+    assert!(weather.has_precipitation);
+    assert!(weather.debug_forecast.is_empty());
+}
+
+#[tokio::test]
+async fn personal_weather_station_sources() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v2/pws/observations/current", "wu_pws_current.json"),
+        route("/get_livedata_info", "ecowitt_livedata.json"),
+    ]);
+    let svc = service(&server);
+
+    let mut cfg = config("pws", "Auckland");
+    cfg.pws = PwsConfig { source: "wunderground".into(), station_id: "IAUCKL123".into(), ..Default::default() };
+    let weather = svc.fetch(&cfg).await.unwrap();
+    assert_eq!(weather.location_name, "Grey Lynn");
+    assert_eq!(weather.temperature, 16.4);
+    assert!(weather.has_precipitation);
+    assert_eq!(weather.humidity, Some(88.0));
+
+    cfg.pws = PwsConfig { source: "ecowitt".into(), gateway_url: server.url.clone(), ..Default::default() };
+    let weather = svc.fetch(&cfg).await.unwrap();
+    assert_eq!(weather.provider, "Weather Station");
+    assert_eq!(weather.temperature, 21.5);
+    assert!(!weather.has_precipitation);
+    assert_eq!(weather.wind_speed, Some(7.2));
+}
+
+#[tokio::test]
+async fn home_assistant_weather_entity() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/api/states/weather.home", "ha_weather_state.json"),
+        route("/api/services/weather/get_forecasts", "ha_get_forecasts.json"),
+    ]);
+
+    let mut cfg = config("homeassistant", "Auckland");
+    cfg.home_assistant = HomeAssistantConfig { url: format!("{}/", server.url), token: "token".into(), entity_id: "weather.home".into() };
+    cfg.temp_horizon = "short_high".into();
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert_eq!(weather.provider, "Home Assistant");
+    // Highest of the next six forecast hours, converted from °F
+    assert!((weather.temperature - 18.0).abs() < 1e-9);
+    assert!(weather.has_precipitation);
+    assert_eq!(weather.humidity, Some(82.0));
+    assert_eq!(weather.wind_speed, Some(18.0));
+    assert_eq!(weather.pressure, Some(1009.1));
+    assert_eq!(weather.debug_forecast.len(), 4);
+    assert_eq!(weather.debug_forecast[2].precip_type, "None");
+    assert_eq!(server.hits("/api/services/weather/get_forecasts"), 1);
+
+    cfg.home_assistant.token.clear();
+    let err = service(&server).fetch(&cfg).await.unwrap_err();
+    assert_eq!(err, "Home Assistant needs a URL, access token and weather entity");
+}
+
+#[tokio::test]
+async fn home_assistant_without_forecast_service() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/api/states/weather.home", "ha_weather_state.json"),
+        error_route("/api/services/weather/get_forecasts", 400),
+    ]);
+
+    let mut cfg = config("homeassistant", "Auckland");
+    cfg.home_assistant = HomeAssistantConfig { url: server.url.clone(), token: "token".into(), entity_id: "weather.home".into() };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    assert!((weather.temperature - 15.0).abs() < 1e-9);
+    // The entity's "rainy" state alone flags precipitation
+    assert!(weather.has_precipitation);
+    assert_eq!(weather.precip_probability, 100.0);
+    assert!(weather.debug_forecast.is_empty());
+}
+
+#[tokio::test]
+async fn severe_alerts_from_nws() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+        route("/alerts/active", "nws_alerts.json"),
+    ]);
+
+    let mut cfg = config("open-meteo", "Auckland");
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+    assert!(weather.alerts.is_empty());
+    assert_eq!(server.hits("/alerts/active"), 0);
+
+    cfg.severe_alerts.enabled = true;
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+    // The advisory is left out
+    assert_eq!(weather.alerts.len(), 1);
+    assert!(weather.alerts[0].starts_with("Severe Thunderstorm Warning"));
+}
+
+#[tokio::test]
+async fn kp_index_latest_estimate() {
+    let server = MockServer::start(vec![
+        route("/json/planetary_k_index_1m.json", "swpc_kp_1m.json"),
+    ]);
+
+    assert_eq!(service(&server).fetch_kp_index().await.unwrap(), 6.0);
+}
+
+#[tokio::test]
+async fn color_and_pulse_decisions() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+
+    let cfg = AppConfig { precip_horizon: "short".to_string(), ..config("open-meteo", "Auckland") };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    // 18.5°C sits halfway between the 18° and 19° stops
+    assert_eq!(weatherlight_lib::calculate_weather_color(&weather, &cfg), "#bcb600");
+
+    // 90% chance: deeper and faster than the configured baseline
+    let (pct_low, speed_ms) = weatherlight_lib::pulse_params(weather.precip_probability, &cfg);
+    assert!(pct_low < cfg.max_brightness / 2);
+    assert!(speed_ms < cfg.pulse_speed);
+
+    // A marginal chance only breathes gently
+    let (pct_low, speed_ms) = weatherlight_lib::pulse_params(40.0, &cfg);
+    assert!(pct_low > cfg.max_brightness / 2);
+    assert!(speed_ms > cfg.pulse_speed);
+}
+
+#[tokio::test]
+async fn air_quality_light_basis() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+        route("/v1/air-quality", "open_meteo_air_quality.json"),
+    ]);
+
+    let cfg = AppConfig { light_basis: "aqi".to_string(), ..config("open-meteo", "Auckland") };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    let aq = weather.air_quality.as_ref().unwrap();
+    assert_eq!(aq.us_aqi, 112.0);
+    assert_eq!(aq.pm2_5, Some(40.3));
+    // 101-150 is the orange "unhealthy for sensitive groups" band
+    assert_eq!(weatherlight_lib::calculate_weather_color(&weather, &cfg), "#ff7e00");
+
+    // Same data on the temperature basis
+    let temp_cfg = config("open-meteo", "Auckland");
+    assert_eq!(weatherlight_lib::calculate_weather_color(&weather, &temp_cfg), "#bcb600");
+}
+
+#[tokio::test]
+async fn anomaly_light_basis() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+        route("/v1/archive", "open_meteo_archive.json"),
+    ]);
+    let svc = service(&server);
+
+    // Highs within three days of 24 February: 22.5, 23.0, 24.0, 23.5 and 25.0 (null and far days skipped)
+    let date = NaiveDate::from_ymd_opt(2026, 2, 24).unwrap();
+    let normal = svc.fetch_normal_high(-36.85, 174.76, date).await.unwrap();
+    assert!((normal - 23.6).abs() < 1e-9);
+    // Same place and day comes from memory
+    svc.fetch_normal_high(-36.85, 174.76, date).await.unwrap();
+    assert_eq!(server.hits("/v1/archive"), 1);
+
+    let cfg = AppConfig { light_basis: "anomaly".to_string(), ..config("open-meteo", "Auckland") };
+    let mut weather = svc.fetch(&config("open-meteo", "Auckland")).await.unwrap();
+    let temp_color = weatherlight_lib::calculate_weather_color(&weather, &config("open-meteo", "Auckland"));
+    // No normal yet: same as the temperature basis
+    assert_eq!(weatherlight_lib::calculate_weather_color(&weather, &cfg), temp_color);
+
+    weather.today_high = Some(normal);
+    weather.normal_high = Some(normal);
+    assert_eq!(weatherlight_lib::calculate_weather_color(&weather, &cfg), "#f7f7f7");
+    weather.today_high = Some(normal + 10.0);
+    assert_eq!(weatherlight_lib::calculate_weather_color(&weather, &cfg), "#b2182b");
+}
+
+#[tokio::test]
+async fn thunderstorm_within_precip_horizon() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+
+    // The storm is three hours out: beyond the default one-hour horizon, inside the six-hour one
+    let weather = service(&server).fetch(&config("open-meteo", "Auckland")).await.unwrap();
+    assert!(!weather.has_thunder);
+
+    let cfg = AppConfig { precip_horizon: "short".to_string(), ..config("open-meteo", "Auckland") };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+    assert!(weather.has_thunder);
+    assert!(!cfg.thunder.is_triggered(&weather));
+    let storm_cfg = AppConfig { thunder: ThunderConfig { enabled: true, ..Default::default() }, ..cfg };
+    assert!(storm_cfg.thunder.is_triggered(&weather));
+}
+
+#[tokio::test]
+async fn pollen_allergy_tint() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+        route("/v1/air-quality", "open_meteo_pollen.json"),
+    ]);
+
+    let mut cfg = config("open-meteo", "Auckland");
+    cfg.pollen.enabled = true;
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+
+    let pollen = weather.pollen.as_ref().unwrap();
+    assert_eq!(pollen.birch, Some(164.5));
+    assert_eq!(pollen.grass, Some(4.0));
+    assert_eq!(pollen.ragweed, None);
+    // Birch is over its threshold, so #bcb600 blends halfway toward #FFD700
+    assert_eq!(weatherlight_lib::calculate_weather_color(&weather, &cfg), "#ddc600");
+
+    // Disabled, nothing is fetched
+    let plain = service(&server).fetch(&config("open-meteo", "Auckland")).await.unwrap();
+    assert!(plain.pollen.is_none());
+    assert_eq!(server.hits("/v1/air-quality"), 1);
+}
+
+#[tokio::test]
+async fn apparent_temperature_light_basis() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+
+    let cfg = AppConfig { light_basis: "apparent".to_string(), ..config("open-meteo", "Auckland") };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+    assert_eq!(weather.apparent_temperature, Some(16.0));
+    // Colored as 16°C rather than the 18.5°C air temperature
+    let feels_like = weatherlight_lib::models::WeatherState { temperature: 16.0, ..weather.clone() };
+    assert_eq!(weatherlight_lib::calculate_weather_color(&weather, &cfg), weatherlight_lib::calculate_weather_color(&feels_like, &config("open-meteo", "Auckland")));
+
+    // Providers without a feels-like value fall back to the air temperature
+    let no_apparent = weatherlight_lib::models::WeatherState { apparent_temperature: None, ..weather.clone() };
+    assert_eq!(weatherlight_lib::calculate_weather_color(&no_apparent, &cfg), "#bcb600");
+}
+
+#[tokio::test]
+async fn precipitation_washes_out_color() {
+    let server = MockServer::start(vec![
+        route("/v1/search", "open_meteo_geocode.json"),
+        route("/v1/forecast", "open_meteo_forecast.json"),
+    ]);
+
+    let cfg = AppConfig { precip_saturation: true, ..config("open-meteo", "Auckland") };
+    let weather = service(&server).fetch(&cfg).await.unwrap();
+    // A dry day keeps the full temperature color ...
+    let dry = weatherlight_lib::models::WeatherState { precip_probability: 0.0, ..weather.clone() };
+    assert_eq!(weatherlight_lib::calculate_weather_color(&dry, &cfg), "#bcb600");
+    // ... and a certain one greys it out while it still leans yellow
+    let wet = weatherlight_lib::models::WeatherState { precip_probability: 100.0, ..weather.clone() };
+    assert_eq!(weatherlight_lib::calculate_weather_color(&wet, &cfg), "#adac88");
+}
+
+#[tokio::test]
+async fn hue_pairing_and_output() {
+    let unpressed = MockServer::start(vec![route("/api", "hue_link_button.json")]);
+    let err = HueOutput::new().pair(&unpressed.url).await.unwrap_err();
+    assert!(err.contains("link button"), "{}", err);
+
+    let key = "83b7780291a6ceffbe0bd049104df";
+    let server = MockServer::start(vec![
+        route("/api/83b7780291a6ceffbe0bd049104df/lights/3/state", "hue_state.json"),
+        route("/api/83b7780291a6ceffbe0bd049104df/groups/1/action", "hue_state.json"),
+        route("/api/83b7780291a6ceffbe0bd049104df/lights", "hue_lights.json"),
+        route("/api/83b7780291a6ceffbe0bd049104df/groups", "hue_groups.json"),
+        route("/api", "hue_pair.json"),
+    ]);
+    let hue = HueOutput::new();
+    assert_eq!(hue.pair(&server.url).await.unwrap(), key);
+
+    let mut cfg = HueConfig { enabled: true, bridge: server.url.clone(), app_key: key.to_string(), targets: Vec::new() };
+    let targets = hue.list_targets(&cfg).await.unwrap();
+    let ids: Vec<&str> = targets.iter().map(|t| t.id.as_str()).collect();
+    // Lights by name, then rooms
+    assert_eq!(ids, ["light:3", "light:1", "group:1"]);
+    assert_eq!(targets[2].kind, "Room");
+
+    cfg.targets = vec!["light:3".to_string(), "group:1".to_string()];
+    hue.apply(&cfg, (255, 0, 0), 60, false).await.unwrap();
+    assert_eq!(server.hits("/api/83b7780291a6ceffbe0bd049104df/lights/3/state"), 1);
+    assert_eq!(server.hits("/api/83b7780291a6ceffbe0bd049104df/groups/1/action"), 1);
+    // The same steady state isn't sent twice, but a pulse is always renewed
+    hue.apply(&cfg, (255, 0, 0), 60, false).await.unwrap();
+    assert_eq!(server.hits("/api/83b7780291a6ceffbe0bd049104df/lights/3/state"), 1);
+    hue.apply(&cfg, (255, 0, 0), 60, true).await.unwrap();
+    hue.apply(&cfg, (255, 0, 0), 60, true).await.unwrap();
+    assert_eq!(server.hits("/api/83b7780291a6ceffbe0bd049104df/lights/3/state"), 3);
+}
+
+#[tokio::test]
+async fn wled_output() {
+    let server = MockServer::start(vec![
+        route("/json/info", "wled_info.json"),
+        route("/json/state", "wled_state.json"),
+    ]);
+    let cfg = WledConfig { enabled: true, host: server.url.clone(), ..Default::default() };
+    let wled = WledOutput::new();
+
+    // A single color doesn't need the strip's length
+    wled.apply(&cfg, WledFrame::Solid { rgb: (255, 0, 0), brightness: 60, pulse: true, speed_ms: 2000 }).await.unwrap();
+    assert_eq!(server.hits("/json/info"), 0);
+    assert_eq!(server.hits("/json/state"), 1);
+
+    // The gradient asks for it once
+    let gradient = WledFrame::Gradient { colors: vec![(0, 0, 255), (255, 0, 0)], brightness: 60, interpolation: weatherlight_lib::color::Interpolation::Rgb };
+    wled.apply(&cfg, gradient.clone()).await.unwrap();
+    wled.apply(&cfg, gradient).await.unwrap();
+    assert_eq!(server.hits("/json/info"), 1);
+    assert_eq!(server.hits("/json/state"), 3);
+}
@@ -12,23 +12,98 @@ window.api = {
     sendIconData: (dataURL) => { },
 
     getWeatherState: () => invoke('get_weather_state'),
+    // After each refresh: { weather, busylight }, the same as getWeatherState and getBusylightStatus
+    onWeatherUpdated: (callback) => {
+        listen('weather_updated', (event) => callback(event.payload));
+    },
+    // Coming days: [{ date, high, low, precipProbability, condition }]
+    getForecast: () => invoke('get_forecast'),
+    // Failing providers: [{ provider, consecutiveFailures, openUntil, lastError }]
+    getProviderHealth: () => invoke('get_provider_health'),
     openExternal: (url) => invoke('open_external', { url }),
     resizeSettings: (height) => invoke('resize_settings', { height }),
 
     // Diagnostics & Status
     getDeviceInfo: () => invoke('get_device_info'),
     getBusylightStatus: () => invoke('get_busylight_status'),
+    busylightUdevRule: (install) => invoke('busylight_udev_rule', { install }),
     onBusylightStatus: (callback) => {
         listen('busylight-status', (event) => {
             callback(event.payload);
         });
     },
-    setManualMode: (enabled) => invoke('set_manual_mode', { enabled }),
+    // Connected Busylights: [{ key, name, product, role, calibration }], the diagnostics device first
+    listBusylights: () => invoke('list_busylights'),
+    // Shows test color `step` through an unsaved { gamma, colorGamma, red, green, blue }; resolves to
+    // { step, total, label }. A null step restores the saved calibration and the weather.
+    calibrateBusylight: (key, step, calibration) => invoke('calibrate_busylight', { key, step, calibration }),
+    // Press the bridge's link button first; resolves to the app key
+    pairHueBridge: (bridge) => invoke('pair_hue_bridge', { bridge }),
+    // [{ id: 'light:<id>' | 'group:<id>', name, kind }]
+    listHueTargets: (bridge, appKey) => invoke('list_hue_targets', { bridge, appKey }),
+    // Microsoft sign-in for Teams presence: start resolves to { deviceCode, userCode, verificationUri,
+    // message, interval, expiresIn }; finish waits for the user and resolves to the refresh token
+    teamsSignInStart: (clientId, tenant) => invoke('teams_sign_in_start', { clientId, tenant }),
+    teamsSignInFinish: (clientId, tenant, code) => invoke('teams_sign_in_finish', { clientId, tenant, code }),
+    // minutes (optional) ends manual mode by itself; resolves to when, or null
+    setManualMode: (enabled, minutes) => invoke('set_manual_mode', { enabled, minutes: minutes || null }),
+    onManualModeExpired: (callback) => {
+        listen('manual-mode-expired', () => callback());
+    },
+    // The tray's Manual mode check, which holds whatever the light shows
+    onManualModeStarted: (callback) => {
+        listen('manual-mode-started', () => callback());
+    },
+    // Turns manual mode on with a saved preset, like setManualMode; the tray's Presets menu does the same
+    applyManualPreset: (name, minutes) => invoke('apply_manual_preset', { name, minutes: minutes || null }),
+    onManualPresetApplied: (callback) => {
+        listen('manual-preset-applied', (event) => callback(event.payload));
+    },
+    // The tray's Location submenu: { autoLocation, location }
+    onLocationChanged: (callback) => {
+        listen('location-changed', (event) => callback(event.payload));
+    },
+    // The tray's Pulse on precipitation check; the payload is the precipHorizon to show ('none' when off)
+    onPulseChanged: (callback) => {
+        listen('pulse-changed', (event) => callback(event.payload));
+    },
+    // { name, color, brightness, pulse, pulseSpeedMs, effect }; both resolve to the saved presets
+    saveManualPreset: (preset) => invoke('save_manual_preset', { preset }),
+    deleteManualPreset: (name) => invoke('delete_manual_preset', { name }),
 
-    // Note: applyManualState doesn't exist in lib.rs yet, we need to map this if used heavily,
-    // or just rely on setManualMode and a new color command.
-    // For now we'll add a dummy or send it to a non-existent command that we'll add next.
+    // Only while manual mode is on: { temp, pulse, pulseSpeed, maxBrightness, effect, color?, pulseLow? }.
+    // With color ('#RRGGBB') the light shows exactly that, otherwise the color for temp.
     applyManualState: (state) => invoke('apply_manual_state', { statePayload: state }),
+    // What the light would show for a °C reading: { color, lightColor, brightness, pulse, pulseLow, speedMs, nightMode }.
+    // flash shows it on the Busylight for 2 seconds.
+    previewColor: (temp, precip, night, flash) => invoke('preview_color', { temp, precip, night, flash }),
+
+    detectLocation: () => invoke('detect_location'),
+
+    // Returns the color scale as seen with 'protanopia' | 'deuteranopia' | 'tritanopia'
+    simulatePalette: (visionType) => invoke('simulate_palette', { visionType }),
+
+    // Color themes: [{ name, unit, stops: [{ temp, color }] }], built-in ones first, stops in the configured unit
+    listThemes: () => invoke('list_themes'),
+    applyTheme: (name) => invoke('apply_theme', { name }),
+    // Both take/return the theme as a JSON string
+    importTheme: (json) => invoke('import_theme', { json }),
+    exportTheme: (name) => invoke('export_theme', { name }),
+
+    // Built-in weather providers: [{ id, name, label, requiresApiKey, capabilities }]
+    listProviders: () => invoke('list_providers'),
+
+    // External provider/device plugins from the plugins folder
+    listPlugins: () => invoke('list_plugins'),
+    reloadPlugins: () => invoke('reload_plugins'),
+
+    // Turn the light off for a while without touching settings; 0 resumes
+    snooze: (minutes) => invoke('snooze', { minutes }),
+
+    // [ssid, profileName] for the active Wi-Fi network profile
+    getNetworkProfile: () => invoke('get_network_profile'),
 
-    detectLocation: () => invoke('detect_location')
+    // Recorded refreshes and aggregates over the last N hours
+    getHistory: (hours) => invoke('get_history', { hours }),
+    getHistoryStats: (hours) => invoke('get_history_stats', { hours })
 };
@@ -13,6 +13,9 @@ let providerSelect, apiKeyContainer, apiKeyInput,
     tempHorizonSelect, precipHorizonSelect,
     saveButton, closeButton;
 
+// Last settings loaded from the backend; keeps fields the form doesn't edit (e.g. dateOverrides)
+let loadedSettings = {};
+
 // Move initialization into a function
 function initializeApp() {
     providerSelect = document.getElementById('provider');
@@ -77,10 +80,32 @@ async function detectAndPopulateLocation() {
     }
 }
 
+// Providers that need an API key, and where to sign up for one
+const KEYED_PROVIDERS = {
+    openweathermap: { name: 'OpenWeatherMap', site: 'openweathermap.org', url: 'https://openweathermap.org', step: 'Navigate to "My API Keys".' },
+    pirateweather: { name: 'Pirate Weather', site: 'pirateweather.net', url: 'https://pirateweather.net', step: 'Subscribe to the free tier in the API portal.' },
+    tomorrowio: { name: 'Tomorrow.io', site: 'tomorrow.io', url: 'https://www.tomorrow.io/weather-api/', step: 'Open "API Management" in your dashboard.' },
+    wunderground: { name: 'Weather Underground', site: 'wunderground.com', url: 'https://www.wunderground.com/member/api-keys', step: 'Register your station, then open "API Keys" in your profile.' },
+    accuweather: { name: 'AccuWeather', site: 'developer.accuweather.com', url: 'https://developer.accuweather.com', step: 'Create an app under "My Apps" to get a key.' },
+};
+
 async function updateUIState() {
     // 1. Provider logic
-    const isOWM = providerSelect.value === 'openweathermap';
-    apiKeyContainer.style.display = isOWM ? 'block' : 'none';
+    const isPws = providerSelect.value === 'pws';
+    const pwsSource = document.getElementById('pwsSource').value;
+    const keyed = KEYED_PROVIDERS[isPws && pwsSource === 'wunderground' ? 'wunderground' : providerSelect.value];
+    apiKeyContainer.style.display = keyed ? 'block' : 'none';
+    document.getElementById('customProviderContainer').style.display = providerSelect.value === 'custom' ? 'block' : 'none';
+    document.getElementById('pwsContainer').style.display = isPws ? 'block' : 'none';
+    document.getElementById('homeAssistantContainer').style.display = providerSelect.value === 'homeassistant' ? 'block' : 'none';
+    document.getElementById('mqttContainer').style.display = providerSelect.value === 'mqtt' ? 'block' : 'none';
+    document.getElementById('pwsStationId').style.display = pwsSource === 'wunderground' ? 'block' : 'none';
+    document.getElementById('pwsGatewayUrl').style.display = pwsSource === 'ecowitt' ? 'block' : 'none';
+    if (keyed) {
+        document.getElementById('apiKeyLabel').textContent = `${keyed.name} API Key`;
+        document.getElementById('owmLink').textContent = keyed.site;
+        document.getElementById('apiKeyStep').textContent = keyed.step;
+    }
 
     // 2. Pulse logic
     const precipHorizonVal = precipHorizonSelect.value || 'none';
@@ -166,6 +191,9 @@ async function loadDiagnostics() {
                         <strong>Provider:</strong> ${weather.provider || 'Unknown'}<br>
                         <strong>Location:</strong> ${weather.locationName || 'Unknown'}<br>
                         <strong>Temperature:</strong> ${weather.temperature}°C<br>
+                        <strong>Today:</strong> ${weather.todayHigh != null ? `H:${Math.round(weather.todayHigh)}° L:${Math.round(weather.todayLow)}°` : 'n/a'}<br>
+                        <strong>Humidity:</strong> ${weather.humidity != null ? `${Math.round(weather.humidity)}%` : 'n/a'}<br>
+                        <strong>Dew Point:</strong> ${weather.dewPoint != null ? `${weather.dewPoint.toFixed(1)}°C` : 'n/a'}<br>
                         <strong>Precipitation:</strong> ${precip}<br>
                         <strong>Night Mode:</strong> ${night}<br>
                         <strong>Last Updated:</strong> ${updated}
@@ -178,6 +206,8 @@ async function loadDiagnostics() {
                             <tr style="border-bottom: 1px solid #eee;">
                                 <td style="padding: 4px;">${new Date(item.time).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}</td>
                                 <td style="padding: 4px;">${item.temp.toFixed(1)}°</td>
+                                <td style="padding: 4px;">${item.humidity != null ? `${Math.round(item.humidity)}%` : '-'}</td>
+                                <td style="padding: 4px;">${item.dewPoint != null ? `${item.dewPoint.toFixed(1)}°` : '-'}</td>
                                 <td style="padding: 4px;">${Math.round(item.precipProb)}%</td>
                                 <td style="padding: 4px;">${item.precipType}</td>
                             </tr>
@@ -196,15 +226,20 @@ async function loadDiagnostics() {
     // Resize after weather data (and table) loads
     updateWindowSize();
 }
-function updateHardwareStatus(connected) {
+function updateHardwareStatus(status) {
     const statusDiv = document.getElementById('connection-status');
+    // Accepts the full status object or a bare boolean
+    const connected = typeof status === 'object' && status !== null ? status.connected : !!status;
 
     // Update footer text/icon
     if (connected) {
         statusDiv.innerHTML = '<span class="connection-indicator connected"></span> Hardware Connected';
+    } else if (status && status.status === 'permission_denied') {
+        statusDiv.innerHTML = '<span class="connection-indicator disconnected"></span> Hardware Found (Permission Denied)';
     } else {
         statusDiv.innerHTML = '<span class="connection-indicator disconnected"></span> Hardware Disconnected';
     }
+    statusDiv.title = (status && status.message) || '';
 
     // Disable/Enable Busylight-specific controls
     // tempHorizon remains active (affects Tray Icon)
@@ -298,6 +333,7 @@ async function validateLocation() {
 
 function attachListeners() {
     providerSelect.addEventListener('change', updateUIState);
+    document.getElementById('pwsSource').addEventListener('change', updateUIState);
 
     autoLocationInput.addEventListener('change', (e) => {
         updateUIState();
@@ -324,23 +360,252 @@ function attachListeners() {
 
     saveButton.addEventListener('click', () => {
         const settings = {
+            ...loadedSettings,
             provider: providerSelect.value,
+            // Only the first fallback is editable here; any further ones from config.json are kept
+            fallbackProviders: [
+                document.getElementById('fallbackProvider').value,
+                ...(loadedSettings.fallbackProviders || []).slice(1)
+            ].filter(Boolean),
+            refreshIntervalMinutes: parseInt(document.getElementById('refreshInterval').value, 10),
+            lightBasis: document.getElementById('lightBasis').value,
+            theme: document.getElementById('theme').value,
+            interpolation: document.getElementById('interpolation').value,
+            bandSize: parseFloat(document.getElementById('bandSize').value) || 0,
+            precipSaturation: document.getElementById('precipSaturation').checked,
+            trayIcon: document.getElementById('trayIcon').value,
+            trayIconShape: document.getElementById('trayIconShape').value,
+            trayConditions: document.getElementById('trayConditions').checked,
             unit: document.querySelector('input[name="unit"]:checked').value,
+            // Entered in the display unit, stored in °C (a difference, so no 32° offset)
+            tempOffset: (parseFloat(document.getElementById('tempOffset').value) || 0)
+                / (document.querySelector('input[name="unit"]:checked').value === 'F' ? 1.8 : 1),
             autoLocation: autoLocationInput.checked,
             location: locationInput.value.trim(),
+            savedLocations: document.getElementById('savedLocations').value.split('\n').map((line) => line.trim()).filter(Boolean),
             apiKey: apiKeyInput.value.trim(),
             // Pulse is enabled if precipHorizon is NOT 'none'
             pulse: precipHorizonSelect.value !== 'none',
             pulseSpeed: Math.round(parseFloat(pulseSpeedInput.value) * 1000),
+            pulseEffect: document.getElementById('pulseEffect').value,
             maxBrightness: parseInt(maxBrightnessInput.value, 10),
+            crossfadeMs: parseInt(document.getElementById('crossfadeMs').value, 10),
             sunsetSunrise: sunsetSunriseInput.checked,
+            nightBoundary: document.getElementById('nightBoundary').value,
+            statusLight: {
+                ...(loadedSettings.statusLight || {}),
+                enabled: document.getElementById('statusLight').checked,
+                standby: document.getElementById('statusStandby').checked
+            },
+            // Roles of lights that aren't plugged in right now are kept
+            busylightRoles: [
+                ...(loadedSettings.busylightRoles || []).filter(r =>
+                    !document.querySelector(`#busylightRoles select[data-key="${CSS.escape(r.device)}"]`)),
+                ...Array.from(document.querySelectorAll('#busylightRoles select'))
+                    .filter(select => select.value !== 'weather')
+                    .map(select => ({ device: select.dataset.key, role: select.value }))
+            ],
+            busylightCalibrations: [
+                ...(loadedSettings.busylightCalibrations || []).filter(c =>
+                    !document.querySelector(`.busylight-calibration[data-key="${CSS.escape(c.device)}"]`)),
+                ...Array.from(document.querySelectorAll('.busylight-calibration'))
+                    .map(panel => ({
+                        ...readCalibration(panel, (loadedSettings.busylightCalibrations || []).find(c => c.device === panel.dataset.key) || {}),
+                        device: panel.dataset.key
+                    }))
+                    .filter(c => c.gamma !== 2.8 || c.red !== 1 || c.green !== 1 || c.blue !== 1 || c.colorGamma)
+            ],
+            hue: {
+                enabled: document.getElementById('hueEnabled').checked,
+                bridge: document.getElementById('hueBridge').value.trim(),
+                appKey: hueAppKey,
+                targets: Array.from(document.querySelectorAll('#hueTargets input:checked')).map(box => box.dataset.target)
+            },
+            wled: {
+                enabled: document.getElementById('wledEnabled').checked,
+                host: document.getElementById('wledHost').value.trim(),
+                protocol: document.getElementById('wledProtocol').value,
+                // A custom length from config.json is kept
+                forecastHours: document.getElementById('wledForecast').checked ? ((loadedSettings.wled || {}).forecastHours || 12) : 0
+            },
+            teams: {
+                ...(loadedSettings.teams || {}),
+                enabled: document.getElementById('teamsEnabled').checked,
+                clientId: document.getElementById('teamsClientId').value.trim(),
+                refreshToken: teamsRefreshToken,
+                busyColor: document.getElementById('teamsBusyColor').value
+            },
+            slack: {
+                ...(loadedSettings.slack || {}),
+                enabled: document.getElementById('slackEnabled').checked,
+                token: document.getElementById('slackToken').value.trim(),
+                onHuddle: document.getElementById('slackOnHuddle').checked,
+                onDnd: document.getElementById('slackOnDnd').checked,
+                busyColor: document.getElementById('slackBusyColor').value
+            },
+            calendar: {
+                ...(loadedSettings.calendar || {}),
+                enabled: document.getElementById('calendarEnabled').checked,
+                url: document.getElementById('calendarUrl').value.trim(),
+                meetingColor: document.getElementById('calendarColor').value,
+                includeAllDay: document.getElementById('calendarAllDay').checked
+            },
+            capture: {
+                ...(loadedSettings.capture || {}),
+                enabled: document.getElementById('captureEnabled').checked,
+                camera: document.getElementById('captureCamera').checked,
+                microphone: document.getElementById('captureMicrophone').checked,
+                busyColor: document.getElementById('captureBusyColor').value
+            },
+            jingle: {
+                ...(loadedSettings.jingle || {}),
+                enabled: document.getElementById('jingle').checked,
+                tone: document.getElementById('jingleTone').value
+            },
+            wakeUp: {
+                ...(loadedSettings.wakeUp || {}),
+                enabled: document.getElementById('wakeUp').checked,
+                time: document.getElementById('wakeUpSunrise').checked ? '' : (document.getElementById('wakeUpTime').value || '07:00'),
+                minutes: parseInt(document.getElementById('wakeUpMinutes').value, 10) || 30
+            },
+            quietHours: {
+                ...(loadedSettings.quietHours || {}),
+                enabled: document.getElementById('quietHours').checked,
+                days: Array.from(document.querySelectorAll('.quiet-day:checked')).map(box => box.value),
+                start: document.getElementById('quietHoursStart').value || '22:00',
+                end: document.getElementById('quietHoursEnd').value || '07:00',
+                brightness: Math.min(100, Math.max(0, parseInt(document.getElementById('quietHoursBrightness').value, 10) || 0))
+            },
+            workHours: {
+                ...(loadedSettings.workHours || {}),
+                enabled: document.getElementById('workHours').checked,
+                days: Array.from(document.querySelectorAll('.work-day:checked')).map(box => box.value),
+                start: document.getElementById('workHoursStart').value || '08:00',
+                end: document.getElementById('workHoursEnd').value || '18:00'
+            },
+            nightDim: {
+                enabled: document.getElementById('nightDim').checked,
+                brightness: parseInt(document.getElementById('nightDimBrightness').value, 10) || 10,
+                // Candlelight; a custom tint from config.json is kept
+                tint: document.getElementById('nightDimWarm').checked ? ((loadedSettings.nightDim || {}).tint || '#FF9329') : ''
+            },
             tempHorizon: tempHorizonSelect.value,
-            precipHorizon: precipHorizonSelect.value
+            precipHorizon: precipHorizonSelect.value,
+            customProvider: {
+                ...(loadedSettings.customProvider || {}),
+                url: document.getElementById('customUrl').value.trim(),
+                temperaturePath: document.getElementById('customTempPath').value.trim(),
+                temperatureUnit: document.getElementById('customTempUnit').value,
+                precipitationPath: document.getElementById('customPrecipPath').value.trim(),
+                precipitationUnit: document.getElementById('customPrecipUnit').value
+            },
+            pws: {
+                source: document.getElementById('pwsSource').value,
+                stationId: document.getElementById('pwsStationId').value.trim(),
+                gatewayUrl: document.getElementById('pwsGatewayUrl').value.trim()
+            },
+            homeAssistant: {
+                url: document.getElementById('haUrl').value.trim(),
+                token: document.getElementById('haToken').value.trim(),
+                entityId: document.getElementById('haEntityId').value.trim()
+            },
+            mqtt: {
+                ...(loadedSettings.mqtt || {}),
+                host: document.getElementById('mqttHost').value.trim(),
+                port: parseInt(document.getElementById('mqttPort').value, 10) || 1883,
+                username: document.getElementById('mqttUsername').value.trim(),
+                password: document.getElementById('mqttPassword').value,
+                temperatureTopic: document.getElementById('mqttTempTopic').value.trim(),
+                temperaturePath: document.getElementById('mqttTempPath').value.trim(),
+                temperatureUnit: document.getElementById('mqttTempUnit').value,
+                precipitationTopic: document.getElementById('mqttPrecipTopic').value.trim(),
+                precipitationPath: document.getElementById('mqttPrecipPath').value.trim(),
+                precipitationUnit: document.getElementById('mqttPrecipUnit').value
+            }
         };
         window.api.saveSettings(settings);
         window.api.closeSettings();
     });
 
+    // Themes apply right away so the light can be compared; imports are stored by the backend
+    document.getElementById('theme').addEventListener('change', async (e) => {
+        await window.api.applyTheme(e.target.value);
+        loadedSettings.theme = e.target.value;
+    });
+
+    document.getElementById('hueEnabled').addEventListener('change', async (e) => {
+        document.getElementById('hueSetting').style.display = e.target.checked ? 'block' : 'none';
+        if (e.target.checked) {
+            await loadHueTargets((loadedSettings.hue || {}).targets || []);
+        }
+    });
+
+    document.getElementById('wledEnabled').addEventListener('change', (e) => {
+        document.getElementById('wledSetting').style.display = e.target.checked ? 'block' : 'none';
+    });
+
+    document.getElementById('teamsEnabled').addEventListener('change', (e) => {
+        document.getElementById('teamsSetting').style.display = e.target.checked ? 'block' : 'none';
+    });
+
+    document.getElementById('slackEnabled').addEventListener('change', (e) => {
+        document.getElementById('slackSetting').style.display = e.target.checked ? 'block' : 'none';
+    });
+
+    document.getElementById('calendarEnabled').addEventListener('change', (e) => {
+        document.getElementById('calendarSetting').style.display = e.target.checked ? 'block' : 'none';
+    });
+
+    document.getElementById('captureEnabled').addEventListener('change', (e) => {
+        document.getElementById('captureSetting').style.display = e.target.checked ? 'block' : 'none';
+    });
+
+    document.getElementById('teamsSignIn').addEventListener('click', async () => {
+        const status = document.getElementById('teams-status');
+        const clientId = document.getElementById('teamsClientId').value.trim();
+        const tenant = (loadedSettings.teams || {}).tenant || 'organizations';
+        status.textContent = 'Contacting Microsoft...';
+        try {
+            const code = await window.api.teamsSignInStart(clientId, tenant);
+            status.textContent = `Enter ${code.userCode} at ${code.verificationUri}`;
+            window.api.openExternal(code.verificationUri);
+            teamsRefreshToken = await window.api.teamsSignInFinish(clientId, tenant, code);
+            status.textContent = 'Signed in. Save to start watching your presence.';
+        } catch (err) {
+            status.textContent = String(err);
+        }
+    });
+
+    document.getElementById('huePair').addEventListener('click', async () => {
+        const status = document.getElementById('hue-status');
+        status.textContent = 'Pairing...';
+        try {
+            hueAppKey = await window.api.pairHueBridge(document.getElementById('hueBridge').value.trim());
+            await loadHueTargets([]);
+        } catch (err) {
+            status.textContent = String(err);
+        }
+    });
+
+    document.getElementById('themeExport').addEventListener('click', async () => {
+        const json = await window.api.exportTheme(document.getElementById('theme').value);
+        document.getElementById('themeJson').value = json;
+        navigator.clipboard?.writeText(json).catch(() => { });
+        document.getElementById('theme-status').textContent = 'Copied to clipboard';
+    });
+
+    document.getElementById('themeImport').addEventListener('click', async () => {
+        const status = document.getElementById('theme-status');
+        try {
+            const theme = await window.api.importTheme(document.getElementById('themeJson').value);
+            loadedSettings.customThemes = (await window.api.getSettings()).customThemes;
+            await loadThemes(theme.name);
+            status.textContent = `Imported ${theme.name}`;
+        } catch (err) {
+            status.textContent = String(err);
+        }
+    });
+
     closeButton.addEventListener('click', () => {
         window.api.closeSettings();
     });
@@ -348,27 +613,277 @@ function attachListeners() {
 
 // ...
 
+async function loadThemes(selected) {
+    const themeSelect = document.getElementById('theme');
+    themeSelect.innerHTML = '';
+    for (const t of await window.api.listThemes()) {
+        themeSelect.add(new Option(t.name.charAt(0).toUpperCase() + t.name.slice(1), t.name));
+    }
+    themeSelect.value = selected;
+}
+
+const BUSYLIGHT_ROLES = [
+    ['weather', 'Weather (everything)'],
+    ['temperature', 'Temperature only'],
+    ['precipitation', 'Chance of rain'],
+    ['tomorrow', "Tomorrow's high"]
+];
+
+// One role menu per connected Busylight, keyed by its serial number or USB path
+async function loadBusylights() {
+    const container = document.getElementById('busylightRoles');
+    const lights = await window.api.listBusylights();
+    container.innerHTML = '';
+    lights.forEach((light, i) => {
+        const row = document.createElement('div');
+        row.style.cssText = 'display: flex; gap: 10px; align-items: center; margin-bottom: 6px;';
+        const label = document.createElement('span');
+        label.style.flex = '1';
+        label.textContent = `${i + 1}. ${light.product || 'Busylight'} (${light.key})`;
+        const select = document.createElement('select');
+        select.dataset.key = light.key;
+        for (const [value, text] of BUSYLIGHT_ROLES) {
+            select.add(new Option(text, value));
+        }
+        select.value = light.role;
+        const calibrate = document.createElement('button');
+        calibrate.type = 'button';
+        calibrate.textContent = 'Calibrate';
+        row.append(label, select, calibrate);
+        container.appendChild(row);
+
+        const panel = calibrationPanel(light);
+        calibrate.addEventListener('click', () => {
+            panel.style.display = panel.style.display === 'none' ? 'block' : 'none';
+        });
+        container.appendChild(panel);
+    });
+    document.getElementById('busylightRolesSetting').style.display = lights.length ? 'block' : 'none';
+}
+
+const CALIBRATION_FIELDS = [
+    ['gamma', 'Brightness gamma', 1, 4, 0.1],
+    ['red', 'Red', 0, 1, 0.05],
+    ['green', 'Green', 0, 1, 0.05],
+    ['blue', 'Blue', 0, 1, 0.05]
+];
+
+// Gamma and white balance for one light, tried out on it live against a series of test colors
+function calibrationPanel(light) {
+    const panel = document.createElement('div');
+    panel.className = 'busylight-calibration';
+    panel.dataset.key = light.key;
+    panel.style.cssText = 'display: none; margin: 0 0 10px 16px;';
+
+    const fields = document.createElement('div');
+    fields.style.cssText = 'display: flex; gap: 10px;';
+    for (const [name, text, min, max, step] of CALIBRATION_FIELDS) {
+        const field = document.createElement('label');
+        field.style.flex = '1';
+        field.textContent = text;
+        const input = document.createElement('input');
+        input.type = 'number';
+        input.dataset.field = name;
+        Object.assign(input, { min, max, step, value: light.calibration[name] });
+        field.appendChild(input);
+        fields.appendChild(field);
+    }
+
+    const status = document.createElement('span');
+    status.className = 'status';
+    const next = document.createElement('button');
+    next.type = 'button';
+    next.textContent = 'Next Test Color';
+    const done = document.createElement('button');
+    done.type = 'button';
+    done.textContent = 'Done';
+
+    let step = -1;
+    const show = async () => {
+        try {
+            const shown = await window.api.calibrateBusylight(light.key, step, readCalibration(panel, light.calibration));
+            status.textContent = `${shown.step + 1}/${shown.total}: ${shown.label}`;
+        } catch (err) {
+            status.textContent = String(err);
+        }
+    };
+    next.addEventListener('click', () => { step += 1; show(); });
+    fields.addEventListener('change', () => { if (step >= 0) show(); });
+    done.addEventListener('click', async () => {
+        step = -1;
+        status.textContent = '';
+        await window.api.calibrateBusylight(light.key, null, light.calibration);
+    });
+
+    panel.append(fields, next, done, status);
+    return panel;
+}
+
+function readCalibration(panel, saved) {
+    const calibration = { ...saved };
+    panel.querySelectorAll('input[data-field]').forEach(input => {
+        const value = parseFloat(input.value);
+        if (!Number.isNaN(value)) calibration[input.dataset.field] = value;
+    });
+    return calibration;
+}
+
+// App key from pairing with the Hue bridge
+let hueAppKey = '';
+
+// Refresh token from signing in to Microsoft for Teams presence
+let teamsRefreshToken = '';
+
+// One checkbox per Hue light and room once the bridge is paired
+async function loadHueTargets(selected) {
+    const container = document.getElementById('hueTargets');
+    const status = document.getElementById('hue-status');
+    container.innerHTML = '';
+    if (!hueAppKey) {
+        status.textContent = 'Press the link button on the bridge, then Pair';
+        return;
+    }
+    try {
+        const targets = await window.api.listHueTargets(document.getElementById('hueBridge').value.trim(), hueAppKey);
+        for (const target of targets) {
+            const row = document.createElement('div');
+            row.className = 'checkbox-container';
+            const box = document.createElement('input');
+            box.type = 'checkbox';
+            box.id = `hue-${target.id}`;
+            box.dataset.target = target.id;
+            box.checked = selected.includes(target.id);
+            const label = document.createElement('label');
+            label.htmlFor = box.id;
+            label.textContent = `${target.name} (${target.kind})`;
+            row.append(box, label);
+            container.appendChild(row);
+        }
+        status.textContent = 'Paired';
+    } catch (err) {
+        status.textContent = String(err);
+    }
+}
+
 async function startAsyncLogic() {
     const settings = await window.api.getSettings();
     const weather = await window.api.getWeatherState();
+    loadedSettings = settings;
 
     // Start background weather UI poller
     updateSunTimes();
     setInterval(updateSunTimes, 10000);
 
+    // Provider list comes from the backend registry
+    const providers = await window.api.listProviders();
+    providerSelect.innerHTML = '';
+    const fallbackSelect = document.getElementById('fallbackProvider');
+    for (const p of providers) {
+        providerSelect.add(new Option(p.label, p.id));
+        fallbackSelect.add(new Option(p.label, p.id));
+    }
+
     // Set values
     providerSelect.value = settings.provider || 'open-meteo';
+    fallbackSelect.value = (settings.fallbackProviders || [])[0] || '';
+    document.getElementById('refreshInterval').value = String(settings.refreshIntervalMinutes || 15);
+    document.getElementById('lightBasis').value = settings.lightBasis || 'temperature';
+    await loadThemes(settings.theme || 'classic');
+    await loadBusylights();
+    const hue = settings.hue || {};
+    hueAppKey = hue.appKey || '';
+    document.getElementById('hueEnabled').checked = hue.enabled || false;
+    document.getElementById('hueBridge').value = hue.bridge || '';
+    document.getElementById('hueSetting').style.display = hue.enabled ? 'block' : 'none';
+    if (hue.enabled) {
+        await loadHueTargets(hue.targets || []);
+    }
+    const teams = settings.teams || {};
+    teamsRefreshToken = teams.refreshToken || '';
+    document.getElementById('teamsEnabled').checked = teams.enabled || false;
+    document.getElementById('teamsClientId').value = teams.clientId || '';
+    document.getElementById('teamsBusyColor').value = teams.busyColor || '#FF0000';
+    document.getElementById('teamsSetting').style.display = teams.enabled ? 'block' : 'none';
+    document.getElementById('teams-status').textContent = teamsRefreshToken ? 'Signed in' : '';
+    const slack = settings.slack || {};
+    document.getElementById('slackEnabled').checked = slack.enabled || false;
+    document.getElementById('slackToken').value = slack.token || '';
+    document.getElementById('slackOnHuddle').checked = slack.onHuddle !== false;
+    document.getElementById('slackOnDnd').checked = slack.onDnd !== false;
+    document.getElementById('slackBusyColor').value = slack.busyColor || '#FF0000';
+    document.getElementById('slackSetting').style.display = slack.enabled ? 'block' : 'none';
+    const calendar = settings.calendar || {};
+    document.getElementById('calendarEnabled').checked = calendar.enabled || false;
+    document.getElementById('calendarUrl').value = calendar.url || '';
+    document.getElementById('calendarColor').value = calendar.meetingColor || '#FF0000';
+    document.getElementById('calendarAllDay').checked = calendar.includeAllDay || false;
+    document.getElementById('calendarSetting').style.display = calendar.enabled ? 'block' : 'none';
+
+    const capture = settings.capture || {};
+    document.getElementById('captureEnabled').checked = capture.enabled || false;
+    document.getElementById('captureCamera').checked = capture.camera !== false;
+    document.getElementById('captureMicrophone').checked = capture.microphone !== false;
+    document.getElementById('captureBusyColor').value = capture.busyColor || '#FF0000';
+    document.getElementById('captureSetting').style.display = capture.enabled ? 'block' : 'none';
+    const wled = settings.wled || {};
+    document.getElementById('wledEnabled').checked = wled.enabled || false;
+    document.getElementById('wledHost').value = wled.host || '';
+    document.getElementById('wledProtocol').value = wled.protocol || 'json';
+    document.getElementById('wledForecast').checked = (wled.forecastHours || 0) > 0;
+    document.getElementById('wledSetting').style.display = wled.enabled ? 'block' : 'none';
+    document.getElementById('interpolation').value = settings.interpolation || 'rgb';
+    document.getElementById('bandSize').value = String(settings.bandSize || 0);
+    document.getElementById('precipSaturation').checked = !!settings.precipSaturation;
+    document.getElementById('trayIcon').value = settings.trayIcon || 'color';
+    document.getElementById('trayIconShape').value = settings.trayIconShape || 'square';
+    document.getElementById('trayConditions').checked = settings.trayConditions !== false;
 
     // Set Unit Radio
     const unitVal = settings.unit || 'C';
     const unitRadio = document.querySelector(`input[name="unit"][value="${unitVal}"]`);
     if (unitRadio) unitRadio.checked = true;
+    const offsetScale = unitVal === 'F' ? 1.8 : 1;
+    document.getElementById('tempOffset').value = Math.round((settings.tempOffset || 0) * offsetScale * 10) / 10;
+    document.getElementById('tempOffsetUnit').textContent = `°${unitVal}`;
 
     autoLocationInput.checked = settings.autoLocation || false;
 
     // API Key
     apiKeyInput.value = settings.apiKey || '';
 
+    // Custom endpoint
+    const custom = settings.customProvider || {};
+    document.getElementById('customUrl').value = custom.url || '';
+    document.getElementById('customTempPath').value = custom.temperaturePath || '';
+    document.getElementById('customTempUnit').value = custom.temperatureUnit || 'C';
+    document.getElementById('customPrecipPath').value = custom.precipitationPath || '';
+    document.getElementById('customPrecipUnit').value = custom.precipitationUnit || 'percent';
+
+    // Personal weather station
+    const pws = settings.pws || {};
+    document.getElementById('pwsSource').value = pws.source || 'wunderground';
+    document.getElementById('pwsStationId').value = pws.stationId || '';
+    document.getElementById('pwsGatewayUrl').value = pws.gatewayUrl || '';
+
+    // Home Assistant
+    const ha = settings.homeAssistant || {};
+    document.getElementById('haUrl').value = ha.url || '';
+    document.getElementById('haToken').value = ha.token || '';
+    document.getElementById('haEntityId').value = ha.entityId || '';
+
+    // MQTT broker
+    const mqtt = settings.mqtt || {};
+    document.getElementById('mqttHost').value = mqtt.host || '';
+    document.getElementById('mqttPort').value = mqtt.port || 1883;
+    document.getElementById('mqttUsername').value = mqtt.username || '';
+    document.getElementById('mqttPassword').value = mqtt.password || '';
+    document.getElementById('mqttTempTopic').value = mqtt.temperatureTopic || '';
+    document.getElementById('mqttTempPath').value = mqtt.temperaturePath || '';
+    document.getElementById('mqttTempUnit').value = mqtt.temperatureUnit || 'C';
+    document.getElementById('mqttPrecipTopic').value = mqtt.precipitationTopic || '';
+    document.getElementById('mqttPrecipPath').value = mqtt.precipitationPath || '';
+    document.getElementById('mqttPrecipUnit').value = mqtt.precipitationUnit || 'bool';
+
     // Location
     if (settings.autoLocation) {
         if (weather && weather.locationName) {
@@ -379,21 +894,55 @@ async function startAsyncLogic() {
     } else {
         locationInput.value = settings.location || '';
     }
+    document.getElementById('savedLocations').value = (settings.savedLocations || []).join('\n');
 
     // Pulse state is now derived from horizon
     pulseSpeedContainer.style.display = settings.pulse ? 'block' : 'none';
 
     pulseSpeedInput.value = (settings.pulseSpeed || 5000) / 1000;
+    document.getElementById('pulseEffect').value = settings.pulseEffect || 'breathe';
     pulseSpeedValue.textContent = `${parseFloat(pulseSpeedInput.value).toFixed(1)}s`;
 
     maxBrightnessInput.value = settings.maxBrightness || 60;
     maxBrightnessValue.textContent = `${maxBrightnessInput.value}%`;
+    document.getElementById('crossfadeMs').value = String(settings.crossfadeMs ?? 2000);
 
     sunsetSunriseInput.checked = settings.sunsetSunrise || false;
+    document.getElementById('nightBoundary').value = settings.nightBoundary || 'sunset';
+    document.getElementById('statusLight').checked = (settings.statusLight || {}).enabled || false;
+    document.getElementById('statusStandby').checked = (settings.statusLight || {}).standby || false;
+    document.getElementById('jingle').checked = (settings.jingle || {}).enabled || false;
+    document.getElementById('jingleTone').value = (settings.jingle || {}).tone || 'funky';
+    const wakeUp = settings.wakeUp || {};
+    document.getElementById('wakeUp').checked = wakeUp.enabled || false;
+    document.getElementById('wakeUpSunrise').checked = wakeUp.time === '';
+    document.getElementById('wakeUpTime').value = wakeUp.time || '07:00';
+    document.getElementById('wakeUpMinutes').value = wakeUp.minutes || 30;
+
+    const quietHours = settings.quietHours || {};
+    const quietDays = quietHours.days || ['mon', 'tue', 'wed', 'thu', 'fri', 'sat', 'sun'];
+    document.getElementById('quietHours').checked = quietHours.enabled || false;
+    document.getElementById('quietHoursStart').value = quietHours.start || '22:00';
+    document.getElementById('quietHoursEnd').value = quietHours.end || '07:00';
+    document.getElementById('quietHoursBrightness').value = quietHours.brightness || 0;
+    document.querySelectorAll('.quiet-day').forEach(box => { box.checked = quietDays.includes(box.value); });
+
+    const workHours = settings.workHours || {};
+    const workDays = workHours.days || ['mon', 'tue', 'wed', 'thu', 'fri'];
+    document.getElementById('workHours').checked = workHours.enabled || false;
+    document.getElementById('workHoursStart').value = workHours.start || '08:00';
+    document.getElementById('workHoursEnd').value = workHours.end || '18:00';
+    document.querySelectorAll('.work-day').forEach(box => { box.checked = workDays.includes(box.value); });
+
+    const nightDim = settings.nightDim || {};
+    document.getElementById('nightDim').checked = nightDim.enabled || false;
+    document.getElementById('nightDimBrightness').value = nightDim.brightness || 10;
+    document.getElementById('nightDimWarm').checked = !!nightDim.tint;
 
     // Set Select Values
     tempHorizonSelect.value = settings.tempHorizon || 'current';
-    precipHorizonSelect.value = settings.precipHorizon || 'immediate';
+    // The tray can turn the pulse off without touching the horizon
+    precipHorizonSelect.value = settings.pulse === false ? 'none' : (settings.precipHorizon || 'immediate');
 
     // ...
 
@@ -414,7 +963,8 @@ async function startAsyncLogic() {
 
     owmLink.addEventListener('click', (e) => {
         e.preventDefault();
-        window.api.openExternal('https://openweathermap.org');
+        const keyed = KEYED_PROVIDERS[providerSelect.value === 'pws' ? 'wunderground' : providerSelect.value] || KEYED_PROVIDERS.openweathermap;
+        window.api.openExternal(keyed.url);
     });
 
     if (openMeteoLink) {
@@ -434,14 +984,22 @@ async function startAsyncLogic() {
     updateUIState();
 
     // Initial Hardware Status
-    const isConnected = await window.api.getBusylightStatus();
-    updateHardwareStatus(isConnected);
+    const hardwareStatus = await window.api.getBusylightStatus();
+    updateHardwareStatus(hardwareStatus);
 
     // Listen for hardware changes
     window.api.onBusylightStatus((connected) => {
         updateHardwareStatus(connected);
     });
 
+    // Flipped in the tray; saved already, so a later Save keeps it
+    window.api.onPulseChanged((horizon) => {
+        loadedSettings.pulse = horizon !== 'none';
+        if (horizon !== 'none') loadedSettings.precipHorizon = horizon;
+        precipHorizonSelect.value = horizon;
+        updateUIState();
+    });
+
     // --- Diagnostics Mode Logic ---
 
     const mainSettings = document.getElementById('mainSettings');
@@ -465,13 +1023,41 @@ async function startAsyncLogic() {
         } else {
             diagnosticsView.style.display = 'none';
             mainSettings.style.display = 'block';
-            // Disable manual mode when closing
+            // Closing keeps a timed override running; an open-ended one ends here
+            if (diagManualMode.checked && diagManualDuration.value !== '0') return;
             diagManualMode.checked = false;
             updateManualModeUI();
             window.api.setManualMode(false);
         }
     }
 
+    // Linux only: the device enumerates but its hidraw node isn't writable by the user
+    async function showUdevHelp() {
+        const rule = await window.api.busylightUdevRule(false);
+        const help = document.createElement('div');
+        help.style.marginTop = '8px';
+        help.innerHTML = `
+                <strong>Access denied.</strong> Add this udev rule, then replug the device:
+                <pre style="white-space: pre-wrap; font-size: 0.75rem;"></pre>
+                <button type="button">Install rule (requires admin)</button>
+                <span class="status"></span>
+            `;
+        help.querySelector('pre').textContent = rule;
+        const result = help.querySelector('.status');
+        help.querySelector('button').addEventListener('click', async () => {
+            result.textContent = 'Installing...';
+            try {
+                await window.api.busylightUdevRule(true);
+                result.textContent = 'Installed.';
+                updateHardwareStatus(await window.api.getBusylightStatus());
+                loadDiagnostics();
+            } catch (e) {
+                result.textContent = e;
+            }
+        });
+        diagDeviceInfo.appendChild(help);
+    }
+
     async function loadDiagnostics() {
         diagDeviceInfo.textContent = 'Loading...';
         try {
@@ -483,6 +1069,9 @@ async function startAsyncLogic() {
                         <strong>VendorID:</strong> ${info.vendorId} (0x${info.vendorId.toString(16)})<br>
                         <strong>ProductID:</strong> ${info.productId} (0x${info.productId.toString(16)})
                     `;
+                if (!info.accessible) {
+                    showUdevHelp();
+                }
             } else {
                 diagDeviceInfo.textContent = 'No Busylight device connected.';
             }
@@ -506,11 +1095,27 @@ async function startAsyncLogic() {
                         <strong>Provider:</strong> ${weather.provider || 'Unknown'}<br>
                         <strong>Location:</strong> ${weather.locationName || 'Unknown'}<br>
                         <strong>Temperature:</strong> ${weather.temperature}°C<br>
+                        <strong>Today:</strong> ${weather.todayHigh != null ? `H:${Math.round(weather.todayHigh)}° L:${Math.round(weather.todayLow)}°` : 'n/a'}<br>
+                        <strong>Humidity:</strong> ${weather.humidity != null ? `${Math.round(weather.humidity)}%` : 'n/a'}<br>
+                        <strong>Dew Point:</strong> ${weather.dewPoint != null ? `${weather.dewPoint.toFixed(1)}°C` : 'n/a'}<br>
                         <strong>Precipitation:</strong> ${precip}<br>
                         <strong>Night Mode:</strong> ${night}<br>
                         <strong>Last Updated:</strong> ${updated}
                     `;
 
+                    // Explain why a provider is being skipped
+                    const health = await window.api.getProviderHealth();
+                    for (const h of health) {
+                        const line = document.createElement('div');
+                        line.style.marginTop = '6px';
+                        line.style.color = h.openUntil ? '#a94442' : '#856404';
+                        const status = h.openUntil
+                            ? `skipped until ${new Date(h.openUntil).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}`
+                            : `${h.consecutiveFailures} failure(s) in a row`;
+                        line.textContent = `${h.provider}: ${status} (${h.lastError || 'unknown error'})`;
+                        weatherDiv.appendChild(line);
+                    }
+
                     // Populate Table
                     const tableBody = document.getElementById('diag-forecast-table');
                     if (tableBody && weather.debugForecast) {
@@ -518,6 +1123,8 @@ async function startAsyncLogic() {
                             <tr style="border-bottom: 1px solid #eee;">
                                 <td style="padding: 4px;">${new Date(item.time).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}</td>
                                 <td style="padding: 4px;">${item.temp.toFixed(1)}°</td>
+                                <td style="padding: 4px;">${item.humidity != null ? `${Math.round(item.humidity)}%` : '-'}</td>
+                                <td style="padding: 4px;">${item.dewPoint != null ? `${item.dewPoint.toFixed(1)}°` : '-'}</td>
                                 <td style="padding: 4px;">${Math.round(item.precipProb)}%</td>
                                 <td style="padding: 4px;">${item.precipType}</td>
                             </tr>
@@ -554,8 +1161,11 @@ async function startAsyncLogic() {
         window.api.applyManualState({
             temp: temp,
             pulse: pulse,
-            maxBrightness: 100, // Full brightness to guarantee visibility during test
-            pulseSpeed: 1500    // Quick 1.5s pulse
+            maxBrightness: parseInt(document.getElementById('diag-brightness').value, 10),
+            pulseSpeed: 1500,   // Quick 1.5s pulse
+            effect: document.getElementById('diag-effect').value,
+            // A fixed color skips the weather rules, so the light works as a plain busy light
+            color: document.getElementById('diag-use-color').checked ? document.getElementById('diag-color').value : null
         });
     }
 
@@ -568,19 +1178,131 @@ async function startAsyncLogic() {
         toggleDiagnostics(false);
     });
 
+    const diagManualDuration = document.getElementById('diag-manual-duration');
+    const diagManualUntil = document.getElementById('diag-manual-until');
+
+    function showManualUntil(until) {
+        diagManualUntil.textContent = until
+            ? `Returns to the weather at ${new Date(until).toLocaleTimeString([], { hour: 'numeric', minute: '2-digit' })}`
+            : '';
+    }
+
+    async function setManualMode(enabled) {
+        showManualUntil(await window.api.setManualMode(enabled, parseInt(diagManualDuration.value, 10) || null));
+    }
+
     diagManualMode.addEventListener('change', () => {
-        window.api.setManualMode(diagManualMode.checked);
+        setManualMode(diagManualMode.checked);
+        updateManualModeUI();
+    });
+
+    // Restarts the countdown from now
+    diagManualDuration.addEventListener('change', () => {
+        if (diagManualMode.checked) setManualMode(true);
+    });
+
+    window.api.onManualModeExpired(() => {
+        diagManualMode.checked = false;
+        diagManualUntil.textContent = '';
         updateManualModeUI();
     });
 
+    // Not updateManualModeUI, which would replace what the light holds with the test state
+    window.api.onManualModeStarted(() => {
+        diagManualMode.checked = true;
+        diagManualUntil.textContent = '';
+        diagControls.style.opacity = '1';
+        diagControls.style.pointerEvents = 'auto';
+    });
+
+    // Switched in the tray; saved already, so a later Save keeps it
+    window.api.onLocationChanged(({ autoLocation, location }) => {
+        loadedSettings.autoLocation = autoLocation;
+        loadedSettings.location = location;
+        autoLocationInput.checked = autoLocation;
+        if (!autoLocation) locationInput.value = location;
+        updateUIState();
+    });
+
+    // Keep an open window current as the background loop refreshes
+    window.api.onWeatherUpdated(({ weather, busylight }) => {
+        updateHardwareStatus(busylight);
+        updateSunTimes();
+        if (autoLocationInput.checked && weather.locationName) {
+            locationInput.value = weather.locationName;
+        }
+        if (diagnosticsView.style.display === 'block') {
+            loadDiagnostics();
+        }
+    });
+
+    // Presets: saved scenes for manual mode, also in the tray menu
+    const diagPreset = document.getElementById('diag-preset');
+
+    function fillPresets(presets) {
+        loadedSettings.manualPresets = presets;
+        diagPreset.innerHTML = '';
+        presets.forEach((preset) => {
+            const option = document.createElement('option');
+            option.value = preset.name;
+            option.textContent = preset.name;
+            diagPreset.appendChild(option);
+        });
+    }
+    fillPresets(loadedSettings.manualPresets || []);
+
+    document.getElementById('diag-preset-apply').addEventListener('click', async () => {
+        if (!diagPreset.value) return;
+        showManualUntil(await window.api.applyManualPreset(diagPreset.value, parseInt(diagManualDuration.value, 10) || null));
+    });
+
+    document.getElementById('diag-preset-delete').addEventListener('click', async () => {
+        if (!diagPreset.value) return;
+        fillPresets(await window.api.deleteManualPreset(diagPreset.value));
+    });
+
+    document.getElementById('diag-preset-save').addEventListener('click', async () => {
+        const name = document.getElementById('diag-preset-name').value.trim();
+        if (!name) return;
+        const pulse = diagPulse.checked;
+        fillPresets(await window.api.saveManualPreset({
+            name,
+            color: document.getElementById('diag-color').value,
+            brightness: parseInt(document.getElementById('diag-brightness').value, 10),
+            pulse,
+            pulseSpeedMs: 1500,
+            effect: document.getElementById('diag-effect').value
+        }));
+        diagPreset.value = name;
+    });
+
+    // Applied here or from the tray: manual mode is on, but the sliders only take over once touched
+    window.api.onManualPresetApplied((name) => {
+        diagManualMode.checked = true;
+        diagControls.style.opacity = '1';
+        diagControls.style.pointerEvents = 'auto';
+        diagPreset.value = name;
+    });
+
     diagTemp.addEventListener('input', () => {
         const cVal = parseInt(diagTemp.value, 10);
         const fVal = Math.round((cVal * 1.8) + 32);
         diagTempValue.textContent = `${cVal}°C / ${fVal}°F`;
+        // Same rules as the real pipeline, so the swatch matches what the light would do
+        window.api.previewColor(cVal, diagPulse.checked, false, false).then((preview) => {
+            document.getElementById('diag-temp-swatch').style.background = preview.color;
+        });
         updateManualState();
     });
 
     diagPulse.addEventListener('change', updateManualState);
+    document.getElementById('diag-effect').addEventListener('change', updateManualState);
+    document.getElementById('diag-use-color').addEventListener('change', updateManualState);
+    document.getElementById('diag-color').addEventListener('input', updateManualState);
+    document.getElementById('diag-brightness').addEventListener('input', (e) => {
+        document.getElementById('diag-brightness-value').textContent = `${e.target.value}%`;
+        updateManualState();
+    });
 }
 
 async function updateSunTimes() {
@@ -594,7 +1316,16 @@ async function updateSunTimes() {
         };
         const nextSunset = fmt(weather.sunTimes.sunset);
         const nextSunrise = fmt(weather.sunTimes.sunrise);
+        const civil = document.getElementById('nightBoundary').value === 'civil';
 
-        sunTimesDiv.innerHTML = `<span>Next sunset: <strong>${nextSunset}</strong></span><span>Next sunrise: <strong>${nextSunrise}</strong></span>`;
+        let html = `<span>Next sunset: <strong>${nextSunset}</strong></span><span>Next sunrise: <strong>${nextSunrise}</strong></span>`;
+        if (civil && weather.sunTimes.civilDusk) {
+            html = `<span>Dusk: <strong>${fmt(weather.sunTimes.civilDusk)}</strong></span><span>Dawn: <strong>${fmt(weather.sunTimes.civilDawn)}</strong></span>`;
+        }
+        if (weather.sunTimes.moonPhase != null) {
+            const phases = ['New moon', 'Waxing crescent', 'First quarter', 'Waxing gibbous', 'Full moon', 'Waning gibbous', 'Last quarter', 'Waning crescent'];
+            html += `<span>${phases[Math.round(weather.sunTimes.moonPhase * 8) % 8]}</span>`;
+        }
+        sunTimesDiv.innerHTML = html;
     }
 }